@@ -0,0 +1,115 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::{Entities, NoEntitiesSchema, TCComputation};
+use cedar_policy_core::extensions::Extensions;
+use cedar_policy_generators::{
+    abac::{ABACPolicy, ABACRequest},
+    err::Error,
+    hierarchy::HierarchyGenerator,
+    schema::Schema,
+    settings::ABACSettings,
+};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::{debug, info};
+use serde::Serialize;
+
+/// Input expected by this fuzz target:
+/// An ABAC hierarchy, action entities with attributes, a policy reading an
+/// action attribute, and 4 associated requests
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// generated schema
+    #[serde(skip)]
+    pub schema: Schema,
+    /// generated entity slice
+    #[serde(skip)]
+    pub entities: Entities,
+    /// generated policy
+    pub policy: ABACPolicy,
+    /// the requests to try for this hierarchy and policy
+    #[serde(skip)]
+    pub requests: [ABACRequest; 4],
+}
+
+/// settings for this fuzz target
+const SETTINGS: ABACSettings = ABACSettings::builder().build();
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let schema = Schema::arbitrary(SETTINGS.clone(), u)?;
+        let hierarchy = schema.arbitrary_hierarchy(u)?;
+        let action_entities = schema.arbitrary_action_entities(u)?;
+        let policy = schema.arbitrary_action_attr_policy(&hierarchy, u)?;
+        let requests = [
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+        ];
+        let entities = Entities::from_entities(
+            hierarchy.into_entities().chain(action_entities),
+            None::<&NoEntitiesSchema>,
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        )
+        .map_err(|e| Error::EntitiesError(e.to_string()))?;
+        Ok(Self {
+            schema,
+            entities,
+            policy,
+            requests,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_size_hint(depth),
+            HierarchyGenerator::size_hint(depth),
+            Schema::arbitrary_policy_size_hint(&SETTINGS, depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+        ])
+    }
+}
+
+// Diff test validation and authorization of policies reading action
+// attributes, which may be absent from some or all actions.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    let mut policyset = ast::PolicySet::new();
+    let policy: ast::StaticPolicy = input.policy.into();
+    policyset.add_static(policy).unwrap();
+    debug!("Schema: {}\n", input.schema.schemafile_string());
+    debug!("Policies: {policyset}\n");
+    debug!("Entities: {}\n", input.entities);
+
+    if let Ok(schema) = ValidatorSchema::try_from(input.schema) {
+        run_val_test(&def_impl, schema, &policyset, ValidationMode::Strict);
+    }
+    for request in input.requests {
+        let (_, total_dur) =
+            time_function(|| run_auth_test(&def_impl, request.into(), &policyset, &input.entities));
+        info!("{}{}", TOTAL_MSG, total_dur.as_nanos());
+    }
+});
@@ -0,0 +1,124 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::Entities;
+use cedar_policy_generators::{
+    abac::{ABACPolicy, ABACRequest},
+    err::Error,
+    hierarchy::HierarchyGenerator,
+    schema::Schema,
+    settings::ABACSettings,
+};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::{debug, info};
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Input expected by this fuzz target:
+/// An ABAC hierarchy, one policy per combination of boolean operators over a
+/// `context`, a `principal`, and a `resource` attribute, and 8 associated
+/// requests
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// generated schema
+    #[serde(skip)]
+    pub schema: Schema,
+    /// generated entity slice
+    #[serde(skip)]
+    pub entities: Entities,
+    /// generated policies, one per operator combination
+    pub policies: Vec<ABACPolicy>,
+    /// the requests to try for this hierarchy and policies. We try 8 requests
+    /// per policy set/hierarchy
+    #[serde(skip)]
+    pub requests: [ABACRequest; 8],
+}
+
+/// settings for this fuzz target
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_extensions(false)
+    .with_like(false)
+    .with_arbitrary_func_call(false)
+    .with_unspecified_apply_spec(false)
+    .with_action_in_constraints(false)
+    .build();
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let schema = Schema::arbitrary(SETTINGS.clone(), u)?;
+        let hierarchy = schema.arbitrary_hierarchy(u)?;
+        let policies = schema.arbitrary_bool_operator_matrix(u)?;
+        let requests = [
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+        ];
+        let entities = Entities::try_from(hierarchy).map_err(|_| Error::NotEnoughData)?;
+        Ok(Self {
+            schema,
+            entities,
+            policies,
+            requests,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_size_hint(depth),
+            HierarchyGenerator::size_hint(depth),
+            (1, None), // one effect per operator combination
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+        ])
+    }
+}
+
+// Diff test authorization on every `&&`/`||`/`!` combination of three boolean
+// attributes at once, so that each response covers the whole matrix.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    let mut policyset = ast::PolicySet::new();
+    for policy in input.policies {
+        policyset.add_static(policy.into()).unwrap();
+    }
+    debug!("Schema: {}\n", input.schema.schemafile_string());
+    debug!("Policies: {policyset}\n");
+    debug!("Entities: {}\n", input.entities);
+
+    for request in input.requests {
+        let request: ast::Request = request.into();
+        debug!("Request : {request}");
+        let (_, total_dur) =
+            time_function(|| run_auth_test(&def_impl, request, &policyset, &input.entities));
+        info!("{}{}", TOTAL_MSG, total_dur.as_nanos());
+    }
+});
@@ -0,0 +1,135 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::Entities;
+use cedar_policy_generators::{
+    abac::{ABACPolicy, ABACRequest},
+    err::Error,
+    hierarchy::HierarchyGenerator,
+    policy::ScopeWeights,
+    schema::Schema,
+    settings::ABACSettings,
+};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::{debug, info};
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Input expected by this fuzz target:
+/// An ABAC hierarchy, a permit and a forbid policy with the same scope, and 8
+/// associated requests
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// generated schema
+    #[serde(skip)]
+    pub schema: Schema,
+    /// generated entity slice
+    #[serde(skip)]
+    pub entities: Entities,
+    /// the permit policy, whose condition is `true`
+    pub permit: ABACPolicy,
+    /// the forbid policy, with the same scope as `permit`
+    pub forbid: ABACPolicy,
+    /// the requests to try for this hierarchy and policies
+    #[serde(skip)]
+    pub requests: [ABACRequest; 8],
+}
+
+/// settings for this fuzz target. Scopes are mostly unconstrained or `is`
+/// constraints, so that many requests are in the scope of both policies.
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_scope_weights(ScopeWeights {
+        no_constraint: 4,
+        eq: 1,
+        is_in: 1,
+        is_type: 3,
+        is_type_in: 1,
+    })
+    .build();
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let schema = Schema::arbitrary(SETTINGS.clone(), u)?;
+        let hierarchy = schema.arbitrary_hierarchy(u)?;
+        let (permit, forbid) = schema.arbitrary_overlapping_policies(&hierarchy, u)?;
+        let requests = [
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+        ];
+        let all_entities = Entities::try_from(hierarchy).map_err(|_| Error::NotEnoughData)?;
+        let entities = drop_some_entities(all_entities, u)?;
+        Ok(Self {
+            schema,
+            entities,
+            permit,
+            forbid,
+            requests,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_size_hint(depth),
+            HierarchyGenerator::size_hint(depth),
+            Schema::arbitrary_overlapping_policies_size_hint(&SETTINGS, depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+        ])
+    }
+}
+
+// Type-directed fuzzing of forbid-overrides-permit: a permit and a forbid
+// policy with the same scope. If the condition of the forbid policy errors,
+// both engines must agree that it doesn't apply.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    let forbid_condition = input.forbid.0.abac_constraints().clone();
+    let forbid: ast::StaticPolicy = input.forbid.into();
+    let forbid_id = forbid.id().clone();
+    let mut policyset = ast::PolicySet::new();
+    policyset.add_static(input.permit.into()).unwrap();
+    policyset.add_static(forbid).unwrap();
+    debug!("Schema: {}\n", input.schema.schemafile_string());
+    debug!("Policies: {policyset}\n");
+    debug!("Entities: {}\n", input.entities);
+
+    for request in input.requests {
+        let request: ast::Request = request.into();
+        debug!("Request : {request}");
+        let (response, total_dur) = time_function(|| {
+            run_auth_test(&def_impl, request.clone(), &policyset, &input.entities)
+        });
+        info!("{}{}", TOTAL_MSG, total_dur.as_nanos());
+        check_forbid_overrides_permit(&response, &forbid_id, &forbid_condition);
+    }
+});
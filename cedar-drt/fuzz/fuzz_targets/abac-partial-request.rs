@@ -0,0 +1,115 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::Entities;
+use cedar_policy_generators::{
+    abac::ABACPolicy, err::Error, hierarchy::HierarchyGenerator, request::PartialRequest,
+    schema::Schema, settings::ABACSettings,
+};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::{debug, info};
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Input expected by this fuzz target:
+/// An ABAC hierarchy, a policy, and 8 associated partial requests, whose
+/// principal, resource, or context may be unknown
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// generated schema
+    #[serde(skip)]
+    pub schema: Schema,
+    /// generated entity slice
+    #[serde(skip)]
+    pub entities: Entities,
+    /// generated policy
+    pub policy: ABACPolicy,
+    /// the requests to try for this hierarchy and policy. We try 8 requests per
+    /// policy/hierarchy
+    #[serde(skip)]
+    pub requests: [PartialRequest; 8],
+}
+
+/// settings for this fuzz target
+const SETTINGS: ABACSettings = ABACSettings::builder().build();
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let schema = Schema::arbitrary(SETTINGS.clone(), u)?;
+        let hierarchy = schema.arbitrary_hierarchy(u)?;
+        let policy = schema.arbitrary_policy(&hierarchy, u)?;
+
+        let requests = [
+            schema.arbitrary_partial_request(&hierarchy, u)?,
+            schema.arbitrary_partial_request(&hierarchy, u)?,
+            schema.arbitrary_partial_request(&hierarchy, u)?,
+            schema.arbitrary_partial_request(&hierarchy, u)?,
+            schema.arbitrary_partial_request(&hierarchy, u)?,
+            schema.arbitrary_partial_request(&hierarchy, u)?,
+            schema.arbitrary_partial_request(&hierarchy, u)?,
+            schema.arbitrary_partial_request(&hierarchy, u)?,
+        ];
+        let all_entities = Entities::try_from(hierarchy).map_err(|_| Error::NotEnoughData)?;
+        let entities = drop_some_entities(all_entities, u)?;
+        Ok(Self {
+            schema,
+            entities,
+            policy,
+            requests,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_size_hint(depth),
+            HierarchyGenerator::size_hint(depth),
+            Schema::arbitrary_policy_size_hint(&SETTINGS, depth),
+            Schema::arbitrary_partial_request_size_hint(depth),
+            Schema::arbitrary_partial_request_size_hint(depth),
+            Schema::arbitrary_partial_request_size_hint(depth),
+            Schema::arbitrary_partial_request_size_hint(depth),
+            Schema::arbitrary_partial_request_size_hint(depth),
+            Schema::arbitrary_partial_request_size_hint(depth),
+            Schema::arbitrary_partial_request_size_hint(depth),
+            Schema::arbitrary_partial_request_size_hint(depth),
+        ])
+    }
+}
+
+// Type-directed fuzzing of partial authorization, with unknowns in the request.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    let mut policyset = ast::PolicySet::new();
+    let policy: ast::StaticPolicy = input.policy.into();
+    policyset.add_static(policy).unwrap();
+    debug!("Schema: {}\n", input.schema.schemafile_string());
+    debug!("Policies: {policyset}\n");
+    debug!("Entities: {}\n", input.entities);
+
+    for request in input.requests {
+        debug!("Request : {request}");
+        let request = request.into();
+        let (_, total_dur) = time_function(|| {
+            run_partial_auth_test(&def_impl, request, &policyset, &input.entities)
+        });
+        info!("{}{}", TOTAL_MSG, total_dur.as_nanos());
+    }
+});
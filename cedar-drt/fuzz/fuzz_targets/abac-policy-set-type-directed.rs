@@ -0,0 +1,167 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::authorizer::{Authorizer, Decision};
+use cedar_policy_core::entities::Entities;
+use cedar_policy_generators::{
+    abac::{ABACPolicy, ABACRequest},
+    err::Error,
+    hierarchy::HierarchyGenerator,
+    schema::Schema,
+    settings::ABACSettings,
+};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::{debug, info};
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Input expected by this fuzz target:
+/// An ABAC hierarchy, a set of permit and forbid policies, and 8 associated
+/// requests
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// generated schema
+    #[serde(skip)]
+    pub schema: Schema,
+    /// generated entity slice
+    #[serde(skip)]
+    pub entities: Entities,
+    /// generated policies, with arbitrary effects. We generate up to 4
+    /// policies.
+    pub policies: Vec<ABACPolicy>,
+    /// the requests to try for this hierarchy and policies. We try 8 requests
+    /// per policy set/hierarchy
+    #[serde(skip)]
+    pub requests: [ABACRequest; 8],
+}
+
+/// settings for this fuzz target
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_ownership_patterns(true)
+    .build();
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let schema = Schema::arbitrary(SETTINGS.clone(), u)?;
+        let hierarchy = schema.arbitrary_hierarchy(u)?;
+        let mut policies = Vec::new();
+        u.arbitrary_loop(Some(1), Some(4), |u| {
+            policies.push(schema.arbitrary_policy(&hierarchy, u)?);
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        let requests = [
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+        ];
+        let all_entities = Entities::try_from(hierarchy).map_err(|_| Error::NotEnoughData)?;
+        let entities = drop_some_entities(all_entities, u)?;
+        Ok(Self {
+            schema,
+            entities,
+            policies,
+            requests,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_size_hint(depth),
+            HierarchyGenerator::size_hint(depth),
+            Schema::arbitrary_policy_size_hint(&SETTINGS, depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+        ])
+    }
+}
+
+// Type-directed fuzzing of ABAC hierarchy/policy set/requests, where the
+// policies interact: a satisfied forbid policy must override any number of
+// satisfied permit policies.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    // Use fixed IDs so the generated policies can't collide
+    let policies: Vec<ast::StaticPolicy> = input
+        .policies
+        .into_iter()
+        .enumerate()
+        .map(|(i, policy)| {
+            ast::StaticPolicy::from(policy).new_id(ast::PolicyID::from_string(format!("policy{i}")))
+        })
+        .collect();
+    let mut policyset = ast::PolicySet::new();
+    for policy in policies.iter().cloned() {
+        policyset.add_static(policy).unwrap();
+    }
+    debug!("Schema: {}\n", input.schema.schemafile_string());
+    debug!("Policies: {policyset}\n");
+    debug!("Entities: {}\n", input.entities);
+
+    if let Ok(schema) = ValidatorSchema::try_from(input.schema.clone()) {
+        run_val_test(&def_impl, schema, &policyset, ValidationMode::Strict);
+    }
+
+    let authorizer = Authorizer::new();
+    for request in input.requests {
+        let request: ast::Request = request.into();
+        debug!("Request : {request}");
+        let (rust_res, total_dur) = time_function(|| {
+            run_auth_test(&def_impl, request.clone(), &policyset, &input.entities)
+        });
+        info!("{}{}", TOTAL_MSG, total_dur.as_nanos());
+
+        // additional invariant: the two engines agree on `rust_res`, so it's
+        // enough to check that forbid overrides permit there. A forbid policy
+        // is satisfied if authorizing it on its own makes it determining.
+        for policy in policies
+            .iter()
+            .filter(|p| p.effect() == ast::Effect::Forbid)
+        {
+            let mut alone = ast::PolicySet::new();
+            alone.add_static(policy.clone()).unwrap();
+            let res = authorizer.is_authorized(request.clone(), &alone, &input.entities);
+            if res.diagnostics.reason.contains(policy.id()) {
+                assert_eq!(
+                    rust_res.decision,
+                    Decision::Deny,
+                    "forbid policy {} is satisfied, but the policy set allows {request}",
+                    policy.id()
+                );
+                assert!(
+                    rust_res.diagnostics.reason.contains(policy.id()),
+                    "satisfied forbid policy {} isn't determining for {request}",
+                    policy.id()
+                );
+            }
+        }
+    }
+});
@@ -15,6 +15,7 @@
  */
 
 #![no_main]
+use cedar_drt::utils::entities_to_json;
 use cedar_drt::*;
 use cedar_drt_inner::*;
 use cedar_policy_core::ast;
@@ -24,6 +25,7 @@ use cedar_policy_generators::{
     abac::{ABACPolicy, ABACRequest},
     err::Error,
     hierarchy::HierarchyGenerator,
+    policy::GeneratedLinkedPolicy,
     schema::Schema,
     settings::ABACSettings,
 };
@@ -31,19 +33,24 @@ use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
 use log::{debug, info};
 use serde::Serialize;
 use std::convert::TryFrom;
+use std::path::Path;
 
 /// Input expected by this fuzz target:
-/// An ABAC hierarchy, policy, and 8 associated requests
+/// An ABAC hierarchy, policy, template with links, and 8 associated requests
 #[derive(Debug, Clone, Serialize)]
 pub struct FuzzTargetInput {
     /// generated schema
     #[serde(skip)]
     pub schema: Schema,
     /// generated entity slice
-    #[serde(skip)]
+    #[serde(serialize_with = "entities_to_json")]
     pub entities: Entities,
     /// generated policy
     pub policy: ABACPolicy,
+    /// generated template, with slots filled by the `links`
+    pub template: ABACPolicy,
+    /// generated links of the `template`. We generate up to 4 links.
+    pub links: Vec<GeneratedLinkedPolicy>,
     /// the requests to try for this hierarchy and policy. We try 8 requests per
     /// policy/hierarchy
     #[serde(skip)]
@@ -51,25 +58,31 @@ pub struct FuzzTargetInput {
 }
 
 /// settings for this fuzz target
-const SETTINGS: ABACSettings = ABACSettings {
-    match_types: true,
-    enable_extensions: true,
-    max_depth: 3,
-    max_width: 3,
-    enable_additional_attributes: false,
-    enable_like: true,
-    enable_action_groups_and_attrs: true,
-    enable_arbitrary_func_call: true,
-    enable_unknowns: false,
-    enable_action_in_constraints: true,
-    enable_unspecified_apply_spec: true,
-};
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_ownership_patterns(true)
+    .with_guarded_attr_chains(true)
+    .with_unguarded_attr_chains(true)
+    .build();
 
 impl<'a> Arbitrary<'a> for FuzzTargetInput {
     fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
         let schema = Schema::arbitrary(SETTINGS.clone(), u)?;
         let hierarchy = schema.arbitrary_hierarchy(u)?;
         let policy = schema.arbitrary_policy(&hierarchy, u)?;
+        // Use fixed IDs for the template and its links so they can't collide
+        // with each other or with the static policy
+        let template =
+            schema.arbitrary_template(ast::PolicyID::from_string("template"), &hierarchy, u)?;
+        let mut links = Vec::new();
+        u.arbitrary_loop(Some(1), Some(4), |u| {
+            links.push(schema.arbitrary_linked_policy(
+                ast::PolicyID::from_string(format!("template_l{}", links.len())),
+                &template,
+                &hierarchy,
+                u,
+            )?);
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
 
         let requests = [
             schema.arbitrary_request(&hierarchy, u)?,
@@ -87,6 +100,8 @@ impl<'a> Arbitrary<'a> for FuzzTargetInput {
             schema,
             entities,
             policy,
+            template,
+            links,
             requests,
         })
     }
@@ -96,6 +111,8 @@ impl<'a> Arbitrary<'a> for FuzzTargetInput {
             Schema::arbitrary_size_hint(depth),
             HierarchyGenerator::size_hint(depth),
             Schema::arbitrary_policy_size_hint(&SETTINGS, depth),
+            Schema::arbitrary_template_size_hint(&SETTINGS, depth),
+            Schema::arbitrary_linked_policy_size_hint(depth),
             Schema::arbitrary_request_size_hint(depth),
             Schema::arbitrary_request_size_hint(depth),
             Schema::arbitrary_request_size_hint(depth),
@@ -108,17 +125,52 @@ impl<'a> Arbitrary<'a> for FuzzTargetInput {
     }
 }
 
-// Type-directed fuzzing of ABAC hierarchy/policy/requests.
+impl FuzzTargetInput {
+    /// The policy set made of the static policy, the template, and its links
+    fn policy_set(&self) -> ast::PolicySet {
+        let mut policyset = ast::PolicySet::new();
+        let policy: ast::StaticPolicy = self.policy.clone().into();
+        policyset.add_static(policy).unwrap();
+        self.template.0.clone().add_to_policyset(&mut policyset);
+        for link in self.links.iter().cloned() {
+            link.add_to_policyset(&mut policyset);
+        }
+        policyset
+    }
+
+    /// Write this input to `dir` as files for the Cedar CLI (see
+    /// [`dump_cedar_files`])
+    pub fn dump_cedar_files(&self, dir: &Path) -> std::io::Result<()> {
+        dump_cedar_files(
+            dir,
+            &self.schema.clone().into(),
+            &self.policy_set(),
+            &self.entities,
+            self.requests.iter().cloned().map(Into::into),
+        )
+    }
+}
+
+// Type-directed fuzzing of ABAC hierarchy/policy/template/requests.
 fuzz_target!(|input: FuzzTargetInput| {
     initialize_log();
     let def_impl = LeanDefinitionalEngine::new();
-    let mut policyset = ast::PolicySet::new();
+    if let Some(dir) = std::env::var_os(CEDAR_FILES_DIR_ENV_VAR) {
+        input
+            .dump_cedar_files(Path::new(&dir))
+            .expect("failed to write Cedar files");
+    }
+    let policyset = input.policy_set();
     let policy: ast::StaticPolicy = input.policy.into();
-    policyset.add_static(policy.clone()).unwrap();
     debug!("Schema: {}\n", input.schema.schemafile_string());
     debug!("Policies: {policyset}\n");
     debug!("Entities: {}\n", input.entities);
 
+    if let Ok(schema) = ValidatorSchema::try_from(input.schema.clone()) {
+        check_generated_policies_validate(&schema, &policyset, ValidationMode::Strict);
+        run_val_test(&def_impl, schema, &policyset, ValidationMode::Strict);
+    }
+
     let requests = input
         .requests
         .into_iter()
@@ -146,9 +198,14 @@ fuzz_target!(|input: FuzzTargetInput| {
         );
     }
 
+    stream_input(&input.schema.clone().into(), &policyset, &input.entities)
+        .expect("failed to stream input");
+
     if let Ok(test_name) = std::env::var("DUMP_TEST_NAME") {
         // When the corpus is re-parsed, the policy will be given id "policy0".
         // Recreate the policy set and compute responses here to account for this.
+        // Links can't be written to the dumped policy file, so only the static
+        // policy is dumped.
         let mut policyset = ast::PolicySet::new();
         let policy = policy.new_id(ast::PolicyID::from_string("policy0"));
         policyset.add_static(policy).unwrap();
@@ -30,6 +30,7 @@ use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
 use log::{debug, info};
 use serde::Serialize;
 use std::convert::TryFrom;
+use std::time::Duration;
 
 /// Input expected by this fuzz target:
 /// An ABAC hierarchy, policy, and 8 associated requests
@@ -49,23 +50,19 @@ pub struct FuzzTargetInput {
     pub requests: [ABACRequest; 8],
 }
 
+/// how long the Lean authorizer may take on a request before we give up on
+/// comparing its response
+const LEAN_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// settings for this fuzz target
-const SETTINGS: ABACSettings = ABACSettings {
-    match_types: false,
-    enable_extensions: true,
-    max_depth: 3,
-    max_width: 7,
-    enable_additional_attributes: false,
-    enable_like: true,
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_match_types(false)
+    .with_max_width(7)
     // ABAC fuzzing restricts the use of action because it is used to generate
     // the corpus tests which will be run on Cedar and CedarCLI.
     // These packages only expose the restricted action behavior.
-    enable_action_groups_and_attrs: false,
-    enable_arbitrary_func_call: true,
-    enable_unknowns: false,
-    enable_action_in_constraints: true,
-    enable_unspecified_apply_spec: true,
-};
+    .with_action_groups_and_attrs(false)
+    .build();
 
 impl<'a> Arbitrary<'a> for FuzzTargetInput {
     fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
@@ -110,7 +107,7 @@ impl<'a> Arbitrary<'a> for FuzzTargetInput {
 // Simple fuzzing of ABAC hierarchy/policy/requests without respect to types.
 fuzz_target!(|input: FuzzTargetInput| {
     initialize_log();
-    let def_impl = LeanDefinitionalEngine::new();
+    let def_impl = LeanDefinitionalEngine::new_with_timeout(LEAN_TIMEOUT);
     if let Ok(entities) = Entities::try_from(input.hierarchy) {
         let mut policyset = ast::PolicySet::new();
         let policy: ast::StaticPolicy = input.policy.into();
@@ -123,12 +120,25 @@ fuzz_target!(|input: FuzzTargetInput| {
             .map(Into::into)
             .collect::<Vec<_>>();
 
-        for request in requests.iter().cloned() {
-            debug!("Request: {request}");
-            let (_, total_dur) =
-                time_function(|| run_auth_test(&def_impl, request, &policyset, &entities));
-            info!("{}{}", TOTAL_MSG, total_dur.as_nanos());
-        }
+        let abac_input = AbacInput {
+            entities: entities.clone(),
+            policy: policy.clone(),
+            requests: requests.clone(),
+        };
+        check_abac_minimized(&abac_input, |input| {
+            let mut policyset = ast::PolicySet::new();
+            policyset.add_static(input.policy.clone()).unwrap();
+            for request in input.requests.iter().cloned() {
+                debug!("Request: {request}");
+                let (_, total_dur) = time_function(|| {
+                    run_auth_test(&def_impl, request, &policyset, &input.entities)
+                });
+                info!("{}{}", TOTAL_MSG, total_dur.as_nanos());
+            }
+        });
+        stream_input(&input.schema.clone().into(), &policyset, &entities)
+            .expect("failed to stream input");
+
         if let Ok(test_name) = std::env::var("DUMP_TEST_NAME") {
             // When the corpus is re-parsed, the policy will be given id "policy0".
             // Recreate the policy set and compute responses here to account for this.
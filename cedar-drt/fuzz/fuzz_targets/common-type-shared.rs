@@ -0,0 +1,132 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::Entities;
+use cedar_policy_generators::{
+    abac::{ABACPolicy, ABACRequest},
+    err::Error,
+    hierarchy::HierarchyGenerator,
+    schema::Schema,
+    settings::ABACSettings,
+};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::{debug, info};
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Input expected by this fuzz target:
+/// An ABAC hierarchy for a schema in which one common type is used both as an
+/// entity attribute type and as a `context` attribute type, a policy touching
+/// both usages, an arbitrary policy, and 8 associated requests
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// generated schema
+    #[serde(skip)]
+    pub schema: Schema,
+    /// generated entity slice
+    #[serde(skip)]
+    pub entities: Entities,
+    /// policy comparing the two usages of the shared common type
+    pub shared_policy: ABACPolicy,
+    /// generated policy
+    pub policy: ABACPolicy,
+    /// the requests to try for this hierarchy and policy. We try 8 requests per
+    /// policy/hierarchy
+    #[serde(skip)]
+    pub requests: [ABACRequest; 8],
+}
+
+/// settings for this fuzz target
+const SETTINGS: ABACSettings = ABACSettings::builder().build();
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let (schema, usage) = Schema::arbitrary(SETTINGS.clone(), u)?.with_shared_common_type(u)?;
+        let hierarchy = schema.arbitrary_hierarchy(u)?;
+        let shared_policy = usage.policy(ast::PolicyID::from_string("shared"));
+        let policy = schema.arbitrary_policy(&hierarchy, u)?;
+
+        let requests = [
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+        ];
+        let entities = Entities::try_from(hierarchy).map_err(|_| Error::NotEnoughData)?;
+        Ok(Self {
+            schema,
+            entities,
+            shared_policy,
+            policy,
+            requests,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_size_hint(depth),
+            (1, None), // not sure how to hint for with_shared_common_type()
+            HierarchyGenerator::size_hint(depth),
+            Schema::arbitrary_policy_size_hint(&SETTINGS, depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+        ])
+    }
+}
+
+// Type-directed fuzzing of validation and authorization on schemas where one
+// common type is used in both entity-attribute and `context` position.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    let mut policyset = ast::PolicySet::new();
+    let shared_policy: ast::StaticPolicy = input.shared_policy.into();
+    policyset.add_static(shared_policy).unwrap();
+    let policy: ast::StaticPolicy = input.policy.into();
+    policyset
+        .add_static(policy.new_id(ast::PolicyID::from_string("policy0")))
+        .unwrap();
+    debug!("Schema: {}\n", input.schema.schemafile_string());
+    debug!("Policies: {policyset}\n");
+    debug!("Entities: {}\n", input.entities);
+
+    if let Ok(schema) = ValidatorSchema::try_from(input.schema) {
+        let (_, total_dur) =
+            time_function(|| run_val_test(&def_impl, schema, &policyset, ValidationMode::Strict));
+        info!("{}{}", TOTAL_MSG, total_dur.as_nanos());
+    }
+
+    for request in input.requests.into_iter().map(Into::into) {
+        debug!("Request : {request}");
+        let (_, total_dur) =
+            time_function(|| run_auth_test(&def_impl, request, &policyset, &input.entities));
+        info!("{}{}", TOTAL_MSG, total_dur.as_nanos());
+    }
+});
@@ -0,0 +1,100 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::Entities;
+use cedar_policy_core::extensions::Extensions;
+use cedar_policy_generators::request::{
+    arbitrary_boundary_number_context_json, BOUNDARY_NUMBER_JSON,
+};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::{debug, info};
+use serde::Serialize;
+use std::sync::OnceLock;
+
+/// Input expected by this fuzz target:
+/// The JSON for a context record of boundary numbers
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// generated context JSON
+    pub context_json: String,
+}
+
+/// Schema declaring every context attribute as a `Long`
+const SCHEMA: &str = r#"
+    entity E;
+    action a appliesTo { principal: E, resource: E, context: { x: Long, y: Long } };
+"#;
+
+/// Policies doing arithmetic and comparisons on the context attributes, which
+/// overflow near the bounds of `i64`
+const POLICIES: &str = r#"
+    permit(principal, action, resource) when { context.x + context.y > 0 };
+    permit(principal, action, resource) when { context.x - 1 < context.y };
+    forbid(principal, action, resource) when { -context.x == context.y * 2 };
+    permit(principal, action, resource) when { context.x == 9223372036854775807 };
+"#;
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let context_json = arbitrary_boundary_number_context_json(&["x".into(), "y".into()], u)?;
+        Ok(Self { context_json })
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        let choose = cedar_policy_generators::size_hint_utils::size_hint_for_choose(Some(
+            BOUNDARY_NUMBER_JSON.len(),
+        ));
+        arbitrary::size_hint::and(choose, choose)
+    }
+}
+
+fn schema() -> &'static cedar_policy::Schema {
+    static SCHEMA_CELL: OnceLock<cedar_policy::Schema> = OnceLock::new();
+    SCHEMA_CELL.get_or_init(|| {
+        cedar_policy::Schema::from_cedarschema_str(SCHEMA)
+            .expect("schema should parse")
+            .0
+    })
+}
+
+// Both parsers must read context numbers as `Long`s exactly when they are
+// integers that fit in an `i64`, and the authorizers must agree on the result.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    debug!("Context: {}", input.context_json);
+    let uid = |ty, eid| ast::EntityUID::with_eid_and_type(ty, eid).unwrap();
+    let action = uid("Action", "a");
+    if let Some(context) = check_context_json_numbers(schema(), &action, &input.context_json) {
+        let request = ast::Request::new(
+            (uid("E", "p"), None),
+            (action, None),
+            (uid("E", "r"), None),
+            context,
+            None::<&ast::RequestSchemaAllPass>,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let policies = cedar_policy_core::parser::parse_policyset(POLICIES).unwrap();
+        let (_, total_dur) =
+            time_function(|| run_auth_test(&def_impl, request, &policies, &Entities::new()));
+        info!("{}{}", TOTAL_MSG, total_dur.as_nanos());
+    }
+});
@@ -0,0 +1,65 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt_inner::schemas::check_namespace_parsing;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_generators::schema::arbitrary_deep_namespace;
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use serde::Serialize;
+
+/// maximum number of `::`-separated segments in a generated namespace
+const MAX_SEGMENTS: usize = 512;
+
+#[derive(Debug, Clone, Serialize)]
+struct Input {
+    pub namespace: ast::Name,
+}
+
+impl<'a> Arbitrary<'a> for Input {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            namespace: arbitrary_deep_namespace(MAX_SEGMENTS, u)?,
+        })
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (1, None)
+    }
+}
+
+// Generate a namespace with many segments, and check that the Cedar-syntax
+// and JSON schema parsers agree on a schema declared in it. Also check that a
+// policy referring to an entity in the namespace parses, and that its printed
+// form parses to the same policy.
+fuzz_target!(|i: Input| {
+    if let Err(msg) = check_namespace_parsing(&i.namespace.to_string()) {
+        panic!("{msg}");
+    }
+    let src = format!(
+        "permit(principal == {}::User::\"alice\", action, resource);",
+        i.namespace
+    );
+    if let Ok(policy) = cedar_policy_core::parser::parse_policy(None, &src) {
+        let reparsed = cedar_policy_core::parser::parse_policy(None, &policy.to_string())
+            .expect("Failed to parse printed policy");
+        assert_eq!(
+            policy.principal_constraint(),
+            reparsed.principal_constraint()
+        );
+    }
+});
@@ -0,0 +1,146 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::{Entities, EntityJsonParser, NoEntitiesSchema, TCComputation};
+use cedar_policy_core::extensions::Extensions;
+use cedar_policy_generators::{
+    abac::{ABACPolicy, ABACRequest},
+    err::Error,
+    hierarchy::{EntityUIDGenMode, HierarchyGenerator},
+    schema::Schema,
+    settings::ABACSettings,
+};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::{debug, info};
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Input expected by this fuzz target:
+/// An ABAC hierarchy, the same hierarchy as JSON with the attributes of each
+/// entity in an arbitrary order, a policy, and 8 associated requests
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// generated schema
+    #[serde(skip)]
+    pub schema: Schema,
+    /// generated entity slice
+    #[serde(skip)]
+    pub entities: Entities,
+    /// `entities` as JSON, with shuffled attributes
+    pub entities_json: String,
+    /// generated policy
+    pub policy: ABACPolicy,
+    /// the requests to try for this hierarchy and policy. We try 8 requests per
+    /// policy/hierarchy
+    #[serde(skip)]
+    pub requests: [ABACRequest; 8],
+}
+
+/// settings for this fuzz target
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_max_width(7)
+    .with_additional_attributes(true)
+    .build();
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let schema = Schema::arbitrary(SETTINGS.clone(), u)?;
+        let hierarchy = schema
+            .arbitrary_hierarchy_with_uid_mode(EntityUIDGenMode::default_hierarchical_mode(), u)?;
+        let policy = schema.arbitrary_policy(&hierarchy, u)?;
+
+        let requests = [
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+            schema.arbitrary_request(&hierarchy, u)?,
+        ];
+        let entities = Entities::try_from(hierarchy).map_err(|_| Error::NotEnoughData)?;
+        let entities_json = entities_json_with_shuffled_attrs(&entities, u)?;
+        Ok(Self {
+            schema,
+            entities,
+            entities_json,
+            policy,
+            requests,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_size_hint(depth),
+            HierarchyGenerator::size_hint(depth),
+            Schema::arbitrary_policy_size_hint(&SETTINGS, depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            (0, None), // not sure how to hint for the attribute shuffle
+        ])
+    }
+}
+
+// Check that the order of attributes in entity JSON doesn't matter: parsing
+// the shuffled JSON gives back the same entities, and authorization gives the
+// same responses against both.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    let mut policyset = ast::PolicySet::new();
+    let policy: ast::StaticPolicy = input.policy.into();
+    policyset.add_static(policy).unwrap();
+    debug!("Schema: {}\n", input.schema.schemafile_string());
+    debug!("Policies: {policyset}\n");
+    debug!("Entities: {}\n", input.entities);
+    debug!("Shuffled entities: {}\n", input.entities_json);
+
+    let reordered = EntityJsonParser::new(
+        None::<&NoEntitiesSchema>,
+        Extensions::all_available(),
+        TCComputation::AssumeAlreadyComputed,
+    )
+    .from_json_str(&input.entities_json)
+    .expect("failed to parse entities with shuffled attributes");
+    assert!(
+        entities_equivalent(&input.entities, &reordered),
+        "Mismatch between entities:\n{}\nand entities with shuffled attributes:\n{}",
+        input.entities,
+        reordered
+    );
+
+    for request in input.requests.into_iter().map(ast::Request::from) {
+        debug!("Request : {request}");
+        let (expected, total_dur) = time_function(|| {
+            run_auth_test(&def_impl, request.clone(), &policyset, &input.entities)
+        });
+        info!("{}{}", TOTAL_MSG, total_dur.as_nanos());
+        let actual = run_auth_test(&def_impl, request, &policyset, &reordered);
+        assert_eq!(expected.decision, actual.decision);
+        assert_eq!(expected.diagnostics.reason, actual.diagnostics.reason);
+    }
+});
@@ -0,0 +1,42 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+
+use cedar_drt::initialize_log;
+use cedar_drt_inner::{
+    check_for_internal_entities_errors, fuzz_target, time_function, RUST_ENTITIES_PARSE_MSG,
+    RUST_ENTITIES_PARSE_OK_MSG,
+};
+use cedar_policy_core::entities::{EntityJsonParser, NoEntitiesSchema, TCComputation};
+use cedar_policy_core::extensions::Extensions;
+use log::info;
+
+fuzz_target!(|input: String| {
+    initialize_log();
+    let parser = EntityJsonParser::new(
+        None::<&NoEntitiesSchema>,
+        Extensions::all_available(),
+        TCComputation::ComputeNow,
+    );
+    // Ensure the parser does not crash
+    let (res, dur) = time_function(|| parser.from_json_str(&input));
+    info!("{}{}", RUST_ENTITIES_PARSE_MSG, dur.as_nanos());
+    info!("{}{}", RUST_ENTITIES_PARSE_OK_MSG, res.is_ok());
+    if let Err(err) = res {
+        check_for_internal_entities_errors(&err);
+    }
+});
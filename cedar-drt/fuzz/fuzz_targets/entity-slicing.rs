@@ -0,0 +1,170 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::initialize_log;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::authorizer::{Authorizer, Decision, Response};
+use cedar_policy_core::entities::Entities;
+use cedar_policy_generators::{
+    abac::{ABACPolicy, ABACRequest},
+    hierarchy::{Hierarchy, HierarchyGenerator},
+    schema::Schema,
+    settings::ABACSettings,
+};
+use cedar_policy_validator::entity_manifest::compute_entity_manifest;
+use cedar_policy_validator::{ValidationMode, Validator, ValidatorSchema};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::debug;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+/// Input expected by this fuzz target:
+/// An ABAC hierarchy, schema, policy, and 8 associated requests, all of which
+/// pass request validation
+#[derive(Debug, Clone, Serialize)]
+struct FuzzTargetInput {
+    /// generated schema
+    #[serde(skip)]
+    pub schema: Schema,
+    /// generated hierarchy
+    #[serde(skip)]
+    pub hierarchy: Hierarchy,
+    /// the policy whose entity manifest we compute, if it validates
+    pub policy: ABACPolicy,
+    /// the requests to try, if the policy validates.
+    /// We try 8 requests per validated policy.
+    #[serde(skip)]
+    pub requests: [ABACRequest; 8],
+}
+
+/// settings for this fuzz target
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_max_depth(7)
+    .with_max_width(7)
+    .with_max_conditions(7)
+    .with_ownership_patterns(true)
+    .build();
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let schema: Schema = Schema::arbitrary(SETTINGS.clone(), u)?;
+        let hierarchy = schema.arbitrary_hierarchy(u)?;
+        let policy = schema.arbitrary_policy(&hierarchy, u)?;
+        let requests = [
+            schema.arbitrary_valid_request(&hierarchy, u)?,
+            schema.arbitrary_valid_request(&hierarchy, u)?,
+            schema.arbitrary_valid_request(&hierarchy, u)?,
+            schema.arbitrary_valid_request(&hierarchy, u)?,
+            schema.arbitrary_valid_request(&hierarchy, u)?,
+            schema.arbitrary_valid_request(&hierarchy, u)?,
+            schema.arbitrary_valid_request(&hierarchy, u)?,
+            schema.arbitrary_valid_request(&hierarchy, u)?,
+        ];
+        Ok(Self {
+            schema,
+            hierarchy,
+            policy,
+            requests,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_size_hint(depth),
+            HierarchyGenerator::size_hint(depth),
+            Schema::arbitrary_policy_size_hint(&SETTINGS, depth),
+            Schema::arbitrary_valid_request_size_hint(depth),
+            Schema::arbitrary_valid_request_size_hint(depth),
+            Schema::arbitrary_valid_request_size_hint(depth),
+            Schema::arbitrary_valid_request_size_hint(depth),
+            Schema::arbitrary_valid_request_size_hint(depth),
+            Schema::arbitrary_valid_request_size_hint(depth),
+            Schema::arbitrary_valid_request_size_hint(depth),
+            Schema::arbitrary_valid_request_size_hint(depth),
+        ])
+    }
+}
+
+/// The parts of a response that slicing must preserve
+fn decision_and_reason(res: &Response) -> (Decision, HashSet<ast::PolicyID>) {
+    (
+        res.decision,
+        res.diagnostics.reason.iter().cloned().collect(),
+    )
+}
+
+// The main fuzz target. Authorizing against only the entities in the entity
+// manifest of a validated policy must give the same decision and reason as
+// authorizing against the full store.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let schemafile_string = input.schema.schemafile_string();
+    let Ok(schema) = ValidatorSchema::try_from(input.schema) else {
+        return;
+    };
+    let Ok(entities) = Entities::try_from(input.hierarchy) else {
+        return;
+    };
+    let mut policyset = ast::PolicySet::new();
+    let policy: ast::StaticPolicy = input.policy.into();
+    policyset.add_static(policy).unwrap();
+    let validator = Validator::new(schema.clone());
+    if !validator
+        .validate(&policyset, ValidationMode::Strict)
+        .validation_passed()
+    {
+        return;
+    }
+    // the manifest doesn't support every policy that validates
+    let Ok(manifest) = compute_entity_manifest(&schema, &policyset) else {
+        return;
+    };
+    debug!("Policies: {policyset}");
+    debug!("Entities: {entities}");
+    let authorizer = Authorizer::new();
+    for r in input.requests.into_iter() {
+        let q = ast::Request::from(r);
+        debug!("Request: {q}");
+        let Ok(sliced) = manifest.slice_entities(&entities, &q) else {
+            continue;
+        };
+        // drop whole entities outside the slice, keeping the attributes of
+        // the ones inside
+        let in_slice: HashSet<_> = sliced.iter().map(|e| e.uid().clone()).collect();
+        let dropped: HashSet<_> = entities
+            .iter()
+            .map(|e| e.uid().clone())
+            .filter(|uid| !in_slice.contains(uid))
+            .collect();
+        debug!("Dropped {} entities outside the slice", dropped.len());
+        let restricted = drop_entities(&entities, &dropped);
+
+        let full = decision_and_reason(&authorizer.is_authorized(q.clone(), &policyset, &entities));
+        for (store, name) in [
+            (&restricted, "entities in the slice"),
+            (&sliced, "sliced entities"),
+        ] {
+            let res = decision_and_reason(&authorizer.is_authorized(q.clone(), &policyset, store));
+            assert_eq!(
+                full, res,
+                "authorizing with only the {name} changed the response\npolicies:\n{policyset}\nentities:\n{entities}\nsliced entities:\n{store}\nschema:\n{schemafile_string}\nrequest:\n{q}\n",
+            );
+        }
+    }
+});
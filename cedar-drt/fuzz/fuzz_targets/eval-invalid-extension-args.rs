@@ -0,0 +1,127 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::utils::expr_to_est;
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::{
+    ast::{self, Expr},
+    entities::Entities,
+};
+use cedar_policy_generators::abac::ABACRequest;
+use cedar_policy_generators::err::Error;
+use cedar_policy_generators::hierarchy::HierarchyGenerator;
+use cedar_policy_generators::schema::{
+    arbitrary_schematype_with_bounded_depth, Schema, SchemaStats,
+};
+use cedar_policy_generators::settings::ABACSettings;
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::debug;
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Input expected by this fuzz target:
+/// An ABAC hierarchy, an expression that may call extension constructors with
+/// invalid arguments, and an associated request
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// generated schema
+    #[serde(skip)]
+    pub schema: Schema,
+    /// generated entity slice
+    #[serde(skip)]
+    pub entities: Entities,
+    /// generated expression
+    #[serde(serialize_with = "expr_to_est")]
+    pub expression: Expr,
+    /// structural features of `expression`, logged with the input
+    pub features: ExprFeatures,
+    /// summary counts for `schema`, logged with the input
+    pub schema_stats: SchemaStats,
+    /// the request to evaluate the expression in
+    #[serde(skip)]
+    pub request: ABACRequest,
+}
+
+/// settings for this fuzz target
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_ipaddr(true)
+    .with_invalid_extension_args(true)
+    .build();
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let schema = Schema::arbitrary(SETTINGS.clone(), u)?;
+        let hierarchy = schema.arbitrary_hierarchy(u)?;
+        let toplevel_type = arbitrary_schematype_with_bounded_depth(
+            &SETTINGS,
+            schema.entity_types(),
+            SETTINGS.max_depth,
+            u,
+        )?;
+        let expr_gen = schema.exprgenerator(Some(&hierarchy));
+        let expression =
+            expr_gen.generate_expr_for_schematype(&toplevel_type, SETTINGS.max_depth, u)?;
+
+        let features = expr_features(&expression);
+        let schema_stats = schema.stats();
+
+        let request = schema.arbitrary_request(&hierarchy, u)?;
+        let all_entities = Entities::try_from(hierarchy).map_err(Error::EntitiesError)?;
+        let entities = drop_some_entities(all_entities, u)?;
+        Ok(Self {
+            schema,
+            entities,
+            expression,
+            features,
+            schema_stats,
+            request,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_size_hint(depth),
+            HierarchyGenerator::size_hint(depth),
+            Schema::arbitrary_policy_size_hint(&SETTINGS, depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+        ])
+    }
+}
+
+// Type-directed fuzzing of expression evaluation, targeting the errors from
+// extension constructors: both engines must produce the same kind of error.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    debug!("Schema: {}\n", input.schema.schemafile_string());
+    debug!("expr: {}\n", input.expression);
+    debug!("features: {:?}\n", input.features);
+    debug!("schema stats: {:?}\n", input.schema_stats);
+    debug!("Entities: {}\n", input.entities);
+    let request: ast::Request = input.request.into();
+    check_minimized(&input.expression, |expr| {
+        run_eval_test_with_error_categories(&def_impl, request.clone(), expr, &input.entities, true)
+    })
+});
@@ -18,11 +18,16 @@
 use cedar_drt::utils::expr_to_est;
 use cedar_drt::*;
 use cedar_drt_inner::*;
-use cedar_policy_core::{ast::Expr, entities::Entities};
+use cedar_policy_core::{
+    ast::{self, Expr},
+    entities::Entities,
+};
 use cedar_policy_generators::abac::ABACRequest;
 use cedar_policy_generators::err::Error;
 use cedar_policy_generators::hierarchy::HierarchyGenerator;
-use cedar_policy_generators::schema::{arbitrary_schematype_with_bounded_depth, Schema};
+use cedar_policy_generators::schema::{
+    arbitrary_schematype_with_bounded_depth, Schema, SchemaStats,
+};
 use cedar_policy_generators::settings::ABACSettings;
 use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
 use log::debug;
@@ -42,6 +47,10 @@ pub struct FuzzTargetInput {
     /// generated expression
     #[serde(serialize_with = "expr_to_est")]
     pub expression: Expr,
+    /// structural features of `expression`, logged with the input
+    pub features: ExprFeatures,
+    /// summary counts for `schema`, logged with the input
+    pub schema_stats: SchemaStats,
     /// the requests to try for this hierarchy and policy. We try 8 requests per
     /// policy/hierarchy
     #[serde(skip)]
@@ -49,19 +58,10 @@ pub struct FuzzTargetInput {
 }
 
 /// settings for this fuzz target
-const SETTINGS: ABACSettings = ABACSettings {
-    match_types: true,
-    enable_extensions: true,
-    max_depth: 3,
-    max_width: 3,
-    enable_additional_attributes: false,
-    enable_like: true,
-    enable_action_groups_and_attrs: true,
-    enable_arbitrary_func_call: true,
-    enable_unknowns: false,
-    enable_action_in_constraints: true,
-    enable_unspecified_apply_spec: true,
-};
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_ipaddr(true)
+    .with_record_set_nesting_weight(4)
+    .build();
 
 impl<'a> Arbitrary<'a> for FuzzTargetInput {
     fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
@@ -77,6 +77,9 @@ impl<'a> Arbitrary<'a> for FuzzTargetInput {
         let expression =
             expr_gen.generate_expr_for_schematype(&toplevel_type, SETTINGS.max_depth, u)?;
 
+        let features = expr_features(&expression);
+        let schema_stats = schema.stats();
+
         let request = schema.arbitrary_request(&hierarchy, u)?;
         let all_entities = Entities::try_from(hierarchy).map_err(Error::EntitiesError)?;
         let entities = drop_some_entities(all_entities, u)?;
@@ -84,6 +87,8 @@ impl<'a> Arbitrary<'a> for FuzzTargetInput {
             schema,
             entities,
             expression,
+            features,
+            schema_stats,
             request,
         })
     }
@@ -111,12 +116,17 @@ fuzz_target!(|input: FuzzTargetInput| {
     let def_impl = LeanDefinitionalEngine::new();
     debug!("Schema: {}\n", input.schema.schemafile_string());
     debug!("expr: {}\n", input.expression);
+    debug!("features: {:?}\n", input.features);
+    debug!("schema stats: {:?}\n", input.schema_stats);
     debug!("Entities: {}\n", input.entities);
-    run_eval_test(
-        &def_impl,
-        input.request.into(),
-        &input.expression,
-        &input.entities,
-        SETTINGS.enable_extensions,
-    )
+    let request: ast::Request = input.request.into();
+    check_minimized(&input.expression, |expr| {
+        run_eval_test(
+            &def_impl,
+            request.clone(),
+            expr,
+            &input.entities,
+            SETTINGS.enable_extensions,
+        )
+    })
 });
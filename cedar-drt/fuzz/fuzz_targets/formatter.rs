@@ -47,19 +47,14 @@ struct FuzzTargetInput {
 
 // settings for this fuzz target
 // copy-pasted from abac.rs
-const SETTINGS: ABACSettings = ABACSettings {
-    match_types: false,
-    enable_extensions: true,
-    max_depth: 7,
-    max_width: 7,
-    enable_additional_attributes: true,
-    enable_like: true,
-    enable_action_groups_and_attrs: true,
-    enable_arbitrary_func_call: false,
-    enable_unknowns: false,
-    enable_action_in_constraints: true,
-    enable_unspecified_apply_spec: true,
-};
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_match_types(false)
+    .with_max_depth(7)
+    .with_max_width(7)
+    .with_max_conditions(7)
+    .with_additional_attributes(true)
+    .with_arbitrary_func_call(false)
+    .build();
 
 impl<'a> Arbitrary<'a> for FuzzTargetInput {
     fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
@@ -0,0 +1,28 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt_inner::*;
+use cedar_policy_core::est;
+
+// JSON --> EST --> AST --> EST --> JSON --> EST --> AST
+// Any policy or template whose EST converts to an AST must roundtrip through
+// JSON without change.
+fuzz_target!(|est_json_str: String| {
+    if let Ok(est) = serde_json::from_str::<est::Policy>(&est_json_str) {
+        check_est_json_roundtrip(est);
+    }
+});
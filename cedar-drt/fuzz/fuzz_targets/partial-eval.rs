@@ -50,19 +50,7 @@ struct FuzzTargetInput {
 }
 
 /// settings for this fuzz target
-const SETTINGS: ABACSettings = ABACSettings {
-    match_types: true,
-    enable_extensions: true,
-    max_depth: 3,
-    max_width: 3,
-    enable_additional_attributes: false,
-    enable_like: true,
-    enable_action_groups_and_attrs: true,
-    enable_arbitrary_func_call: true,
-    enable_unknowns: true,
-    enable_action_in_constraints: true,
-    enable_unspecified_apply_spec: true,
-};
+const SETTINGS: ABACSettings = ABACSettings::builder().with_unknowns(true).build();
 
 impl<'a> Arbitrary<'a> for FuzzTargetInput {
     fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
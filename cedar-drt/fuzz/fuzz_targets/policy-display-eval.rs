@@ -0,0 +1,145 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast::{self, StaticPolicy};
+use cedar_policy_core::entities::Entities;
+use cedar_policy_core::evaluator::Evaluator;
+use cedar_policy_core::extensions::Extensions;
+use cedar_policy_core::parser::{self, parse_policy};
+use cedar_policy_generators::{
+    abac::{ABACPolicy, ABACRequest},
+    hierarchy::{Hierarchy, HierarchyGenerator},
+    schema::Schema,
+    settings::ABACSettings,
+};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::debug;
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Input expected by this fuzz target:
+/// An ABAC hierarchy, policy, and a request to evaluate its condition against
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// generated schema
+    #[serde(skip)]
+    pub schema: Schema,
+    /// generated hierarchy
+    #[serde(skip)]
+    pub hierarchy: Hierarchy,
+    /// generated policy
+    pub policy: ABACPolicy,
+    /// the request to evaluate the policy's condition against
+    #[serde(skip)]
+    pub request: ABACRequest,
+}
+
+/// settings for this fuzz target. Deep expressions exercise precedence, and
+/// extensions and `like` exercise the printing of negative numbers and
+/// escaped strings.
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_match_types(false)
+    .with_max_depth(7)
+    .with_max_width(7)
+    .with_max_conditions(7)
+    .with_arbitrary_func_call(false)
+    .build();
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let schema = Schema::arbitrary(SETTINGS.clone(), u)?;
+        let hierarchy = schema.arbitrary_hierarchy(u)?;
+        let policy = schema.arbitrary_policy(&hierarchy, u)?;
+        let request = schema.arbitrary_request(&hierarchy, u)?;
+        Ok(Self {
+            schema,
+            hierarchy,
+            policy,
+            request,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_size_hint(depth),
+            HierarchyGenerator::size_hint(depth),
+            Schema::arbitrary_policy_size_hint(&SETTINGS, depth),
+            Schema::arbitrary_request_size_hint(depth),
+        ])
+    }
+}
+
+// AST --> text --> CST --> AST
+fn reparse_display(p: &StaticPolicy) -> StaticPolicy {
+    parse_policy(None, &p.to_string()).unwrap_or_else(|err| {
+        panic!("Failed to parse pretty-printed policy: {err:?}\nPretty printed form:\n{p}")
+    })
+}
+
+// AST --> text --> CST --> EST --> AST
+fn reparse_est(p: &StaticPolicy) -> StaticPolicy {
+    let est = parser::parse_policy_or_template_to_est(&p.to_string()).unwrap_or_else(|err| {
+        panic!("Failed to parse pretty-printed policy to EST: {err:?}\nPretty printed form:\n{p}")
+    });
+    est.try_into_ast_policy_or_template(None)
+        .expect("failed to convert EST to AST")
+        .try_into()
+        .expect("failed to convert `Template` to `StaticPolicy`")
+}
+
+// Evaluate the condition of a policy directly, and after printing it and
+// parsing it back both to an AST and through the EST. The results must match,
+// or the printer changed the meaning of the policy. The original condition is
+// also checked against the Lean evaluator.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    if let Ok(entities) = Entities::try_from(input.hierarchy) {
+        let policy: StaticPolicy = input.policy.into();
+        let request: ast::Request = input.request.into();
+        debug!("Policy: {policy}");
+        debug!("Entities: {entities}");
+        debug!("Request: {request}");
+
+        let eval = Evaluator::new(request.clone(), &entities, Extensions::all_available());
+        let evaluate = |p: &StaticPolicy| {
+            eval.interpret(&p.condition(), &std::collections::HashMap::default())
+                .ok()
+        };
+        let expected = evaluate(&policy);
+        for (how, reparsed) in [
+            ("Display", reparse_display(&policy)),
+            ("EST", reparse_est(&policy)),
+        ] {
+            assert_eq!(
+                expected,
+                evaluate(&reparsed),
+                "Evaluation changed after a {how} round trip\nOriginal:\n{policy}\nReparsed:\n{reparsed}\nRequest: {request}\nEntities:\n{entities}"
+            );
+        }
+
+        run_eval_test(
+            &def_impl,
+            request,
+            &policy.condition(),
+            &entities,
+            SETTINGS.enable_extensions,
+        );
+    }
+});
@@ -0,0 +1,100 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+
+use cedar_drt::initialize_log;
+use cedar_drt_inner::{fuzz_target, policies_equivalent};
+use cedar_policy_core::ast::{StaticPolicy, Template};
+use cedar_policy_core::parser::parse_policyset;
+use cedar_policy_generators::{
+    abac::ABACPolicy,
+    hierarchy::{EntityUIDGenMode, HierarchyGenerator},
+    schema::Schema,
+    settings::ABACSettings,
+};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::debug;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Input expected by this fuzz target: a single policy, which may have
+/// annotations
+#[derive(Debug, Clone, Serialize)]
+struct FuzzTargetInput {
+    /// the generated policy
+    policy: ABACPolicy,
+}
+
+/// settings for this fuzz target. We don't need well-typed policies here, and
+/// deep expressions are what exercise `if-then-else` nesting and the
+/// precedence of `&&` and `||`.
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_match_types(false)
+    .with_max_depth(7)
+    .with_max_width(7)
+    .with_max_conditions(7)
+    .with_arbitrary_func_call(false)
+    .with_unicode_strings(true)
+    .build();
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let schema: Schema = Schema::arbitrary(SETTINGS.clone(), u)?;
+        let hierarchy = schema
+            .arbitrary_hierarchy_with_uid_mode(EntityUIDGenMode::default_hierarchical_mode(), u)?;
+        let policy = schema.arbitrary_policy(&hierarchy, u)?;
+        Ok(Self { policy })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_size_hint(depth),
+            HierarchyGenerator::size_hint(depth),
+            Schema::arbitrary_policy_size_hint(&SETTINGS, depth),
+        ])
+    }
+}
+
+// AST --> natural syntax --> policy set --> AST
+// Format the policy with `Display`, parse the text back as a policy set, and
+// check that the one policy in it is the original policy.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let p: StaticPolicy = input.policy.into();
+    let original: Arc<Template> = p.clone().into();
+    let text = p.to_string();
+    debug!("Running on policy: {text}");
+
+    let pset = parse_policyset(&text).unwrap_or_else(|err| {
+        panic!(
+            "Failed to parse pretty-printed policy set: {:?}\nPretty printed form:\n{text}",
+            miette::Report::new(err)
+        )
+    });
+    let reparsed: Vec<_> = pset.policies().collect();
+    assert_eq!(
+        reparsed.len(),
+        1,
+        "expected exactly one policy after reparsing:\n{text}"
+    );
+    let reparsed = reparsed[0].template();
+    assert!(
+        policies_equivalent(&original, reparsed),
+        "Policy changed after a natural-syntax round trip\nOriginal:\n{text}\nReparsed:\n{reparsed}\n{}",
+        similar_asserts::SimpleDiff::from_str(&text, &reparsed.to_string(), "original", "reparsed")
+    );
+});
@@ -0,0 +1,115 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::initialize_log;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::extensions::Extensions;
+use cedar_policy_generators::hierarchy::{
+    AttributesMode, EntityUIDGenMode, HierarchyGenerator, HierarchyGeneratorMode, NumEntities,
+};
+use cedar_policy_generators::policy::{GeneratedLinkedPolicy, ScopeWeights};
+use cedar_policy_generators::rbac::{RBACHierarchy, RBACPolicy};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::debug;
+use serde::Serialize;
+
+/// Input expected by this fuzz target:
+/// A policy set made up of static policies and templates with their links
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// the hierarchy the policies are generated for
+    #[serde(skip)]
+    pub hierarchy: RBACHierarchy,
+    /// static policies and templates. Policies `p0`, `p1`, ... in order.
+    pub policies: Vec<RBACPolicy>,
+    /// links of the templates in `policies`, each with a distinct ID
+    pub links: Vec<GeneratedLinkedPolicy>,
+}
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let hierarchy = RBACHierarchy(
+            HierarchyGenerator {
+                mode: HierarchyGeneratorMode::Arbitrary {
+                    attributes_mode: AttributesMode::NoAttributes,
+                    action_groups: false,
+                },
+                uid_gen_mode: EntityUIDGenMode::default(),
+                num_entities: NumEntities::RangePerEntityType(0..=4),
+                max_hierarchy_depth: None,
+                u,
+                extensions: Extensions::all_available(),
+            }
+            .generate()?,
+        );
+        // Policy IDs follow a fixed pattern, to avoid collisions
+        let mut policies: Vec<RBACPolicy> = vec![];
+        u.arbitrary_loop(Some(1), Some(8), |u| {
+            policies.push(RBACPolicy::arbitrary_for_hierarchy(
+                Some(ast::PolicyID::from_string(format!("p{}", policies.len()))),
+                &hierarchy,
+                true,
+                &ScopeWeights::DEFAULT,
+                u,
+            )?);
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        let templates: Vec<&RBACPolicy> = policies.iter().filter(|p| p.has_slots()).collect();
+        let mut links: Vec<GeneratedLinkedPolicy> = vec![];
+        if !templates.is_empty() {
+            u.arbitrary_loop(Some(0), Some(8), |u| {
+                let template = u.choose(&templates)?;
+                links.push(GeneratedLinkedPolicy::arbitrary(
+                    ast::PolicyID::from_string(format!("l{}", links.len())),
+                    template,
+                    &hierarchy,
+                    u,
+                )?);
+                Ok(std::ops::ControlFlow::Continue(()))
+            })?;
+        }
+        Ok(Self {
+            hierarchy,
+            policies,
+            links,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            HierarchyGenerator::size_hint(depth),
+            (0, None), // not sure how to hint for arbitrary_loop()
+            (0, None), // not sure how to hint for arbitrary_loop()
+        ])
+    }
+}
+
+// Check the policy-set introspection API of `cedar-policy` against the
+// structure of the generated policy set.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let mut policyset = ast::PolicySet::new();
+    for policy in input.policies {
+        policy.0.add_to_policyset(&mut policyset);
+    }
+    for link in input.links {
+        link.add_to_policyset(&mut policyset);
+    }
+    debug!("Policies: {policyset}");
+    check_policy_set_queries(&policyset);
+});
@@ -0,0 +1,96 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+
+use cedar_drt::initialize_log;
+use cedar_drt_inner::{check_public_json_roundtrip, fuzz_target};
+use cedar_policy_core::ast::{StaticPolicy, Template};
+use cedar_policy_generators::{
+    abac::{ABACPolicy, Type},
+    hierarchy::HierarchyGenerator,
+    policy::GeneratedPolicy,
+    schema::Schema,
+    settings::ABACSettings,
+};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::debug;
+use serde::Serialize;
+use std::sync::Arc;
+
+// A static policy and a template that may have slots
+#[derive(Debug, Clone, Serialize)]
+struct FuzzTargetInput {
+    // the generated static policy
+    policy: ABACPolicy,
+    // the generated template
+    template: GeneratedPolicy,
+}
+
+// settings for this fuzz target
+// copy-pasted from roundtrip.rs
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_match_types(false)
+    .with_max_depth(7)
+    .with_max_width(7)
+    .with_max_conditions(7)
+    .with_additional_attributes(true)
+    .with_unicode_strings(true)
+    .with_arbitrary_func_call(false)
+    .build();
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let schema: Schema = Schema::arbitrary(SETTINGS.clone(), u)?;
+        let hierarchy = schema.arbitrary_hierarchy(u)?;
+        let policy = schema.arbitrary_policy(&hierarchy, u)?;
+        let condition = schema
+            .exprgenerator(Some(&hierarchy))
+            .generate_expr_for_type(&Type::bool(), SETTINGS.max_depth, u)?;
+        let template = GeneratedPolicy::arbitrary_for_hierarchy(
+            None,
+            &hierarchy,
+            true,
+            &SETTINGS.scope_weights.unwrap_or_default(),
+            condition,
+            u,
+        )?;
+        Ok(Self { policy, template })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_size_hint(depth),
+            HierarchyGenerator::size_hint(depth),
+            Schema::arbitrary_policy_size_hint(&SETTINGS, depth),
+            GeneratedPolicy::arbitrary_for_hierarchy_size_hint(false, true, depth),
+        ])
+    }
+}
+
+// AST --> text --> public API --> JSON --> public API --> text --> AST
+// Unlike `roundtrip`, which goes through the internal EST directly, this goes
+// through `cedar_policy::{Policy, Template}::{to_json, from_json}`.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let policy: Arc<Template> = StaticPolicy::from(input.policy).into();
+    let template: Template = input.template.into();
+    debug!("Running on policy: {policy}");
+    debug!("Running on template: {template}");
+
+    check_public_json_roundtrip(&policy);
+    check_public_json_roundtrip(&template);
+});
@@ -0,0 +1,128 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::Entities;
+use cedar_policy_core::extensions::Extensions;
+use cedar_policy_generators::hierarchy::{
+    AttributesMode, EntityUIDGenMode, HierarchyGenerator, HierarchyGeneratorMode, NumEntities,
+};
+use cedar_policy_generators::policy::ScopeWeights;
+use cedar_policy_generators::rbac::{RBACHierarchy, RBACPolicy, RBACRequest};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::info;
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Input expected by this fuzz target:
+/// An RBAC hierarchy with action groups, policies whose action scopes are
+/// lists of action groups, and 8 associated requests for leaf actions
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// the hierarchy
+    #[serde(skip)]
+    pub hierarchy: RBACHierarchy,
+    /// the policies, each with an action scope `action in [...]` naming
+    /// action groups
+    pub policies: Vec<RBACPolicy>,
+    /// the requests to try, whose actions are members of action groups but
+    /// not groups themselves
+    #[serde(skip)]
+    pub requests: [RBACRequest; 8],
+}
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let hierarchy = RBACHierarchy(
+            HierarchyGenerator {
+                mode: HierarchyGeneratorMode::Arbitrary {
+                    attributes_mode: AttributesMode::NoAttributes,
+                    action_groups: true,
+                },
+                uid_gen_mode: EntityUIDGenMode::default(),
+                // more entities per type than the other RBAC targets, so
+                // that the action hierarchy is more than a level deep
+                num_entities: NumEntities::RangePerEntityType(2..=8),
+                max_hierarchy_depth: None,
+                u,
+                extensions: Extensions::all_available(),
+            }
+            .generate()?,
+        );
+        let mut policies: Vec<RBACPolicy> = vec![];
+        u.arbitrary_loop(Some(1), Some(4), |u| {
+            policies.push(RBACPolicy::arbitrary_action_group_list_for_hierarchy(
+                Some(ast::PolicyID::from_string(format!("p{}", policies.len()))),
+                &hierarchy,
+                &ScopeWeights::DEFAULT,
+                u,
+            )?);
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        let requests = [
+            RBACRequest::arbitrary_with_leaf_action_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_with_leaf_action_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_with_leaf_action_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_with_leaf_action_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_with_leaf_action_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_with_leaf_action_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_with_leaf_action_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_with_leaf_action_for_hierarchy(&hierarchy, u)?,
+        ];
+        Ok(Self {
+            hierarchy,
+            policies,
+            requests,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            HierarchyGenerator::size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            (0, None), // not sure how to hint for arbitrary_loop()
+        ])
+    }
+}
+
+// Fuzzing `action in [...]` scopes that are only satisfied through the action
+// group hierarchy, with pure-RBAC policies and requests.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    if let Ok(entities) = Entities::try_from(input.hierarchy) {
+        let mut policyset = ast::PolicySet::new();
+        for policy in input.policies {
+            policy.0.add_to_policyset(&mut policyset);
+        }
+        for request in input.requests {
+            let request = ast::Request::from(request);
+            let (_, dur) =
+                time_function(|| run_auth_test(&def_impl, request, &policyset, &entities));
+            info!("{}{}", TOTAL_MSG, dur.as_nanos());
+        }
+    }
+});
@@ -0,0 +1,153 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::Entities;
+use cedar_policy_core::extensions::Extensions;
+use cedar_policy_generators::hierarchy::{
+    AttributesMode, EntityUIDGenMode, HierarchyGenerator, HierarchyGeneratorMode, NumEntities,
+};
+use cedar_policy_generators::policy::{GeneratedLinkedPolicy, ScopeWeights};
+use cedar_policy_generators::rbac::{RBACHierarchy, RBACPolicy, RBACRequest};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::{debug, info};
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Input expected by this fuzz target:
+/// An RBAC hierarchy, a template linked several times, where slots are often
+/// filled with entities missing from the hierarchy, and 8 associated requests
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// the hierarchy
+    #[serde(skip)]
+    pub hierarchy: RBACHierarchy,
+    /// the template, whose principal constraint is `principal in ?principal`
+    pub template: RBACPolicy,
+    /// links of `template`, each with a distinct ID. Half of the slot values
+    /// are UIDs of entities that aren't in the `hierarchy`.
+    pub links: Vec<GeneratedLinkedPolicy>,
+    /// the requests to try for the links. Some of them have a missing entity
+    /// that a link refers to as their principal.
+    #[serde(skip)]
+    pub requests: [RBACRequest; 8],
+}
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let hierarchy = RBACHierarchy(
+            HierarchyGenerator {
+                mode: HierarchyGeneratorMode::Arbitrary {
+                    attributes_mode: AttributesMode::NoAttributes,
+                    action_groups: false,
+                },
+                uid_gen_mode: EntityUIDGenMode::default(),
+                num_entities: NumEntities::RangePerEntityType(0..=4),
+                max_hierarchy_depth: None,
+                u,
+                extensions: Extensions::all_available(),
+            }
+            .generate()?,
+        );
+        let template = RBACPolicy::arbitrary_principal_in_slot_for_hierarchy(
+            Some(ast::PolicyID::from_string("t")),
+            &hierarchy,
+            &ScopeWeights::DEFAULT,
+            u,
+        )?;
+        let mut links: Vec<GeneratedLinkedPolicy> = vec![];
+        u.arbitrary_loop(Some(1), Some(4), |u| {
+            let id = ast::PolicyID::from_string(format!("l{}", links.len()));
+            links.push(GeneratedLinkedPolicy::arbitrary_with_missing_entities(
+                id, &template, &hierarchy, u,
+            )?);
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        let mut requests = [
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+        ];
+        // make the principal of half of the requests one of the slot values,
+        // so that requests by missing entities match links to them
+        let slot_values: Vec<_> = links
+            .iter()
+            .flat_map(GeneratedLinkedPolicy::slot_values)
+            .cloned()
+            .collect();
+        for request in requests.iter_mut().skip(4) {
+            if !slot_values.is_empty() {
+                request.0.principal = u.choose(&slot_values)?.clone();
+            }
+        }
+        Ok(Self {
+            hierarchy,
+            template,
+            links,
+            requests,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            HierarchyGenerator::size_hint(depth),
+            RBACPolicy::arbitrary_principal_in_slot_size_hint(true, depth),
+            (0, None), // not sure how to hint for arbitrary_loop()
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+        ])
+    }
+}
+
+// Fuzzing an RBAC template whose links may refer to entities that don't
+// exist. Linking doesn't check for the entities, so both engines have to deal
+// with the dangling references when authorizing, and should agree.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    if let Ok(entities) = Entities::try_from(input.hierarchy) {
+        let mut policyset = ast::PolicySet::new();
+        input.template.0.add_to_policyset(&mut policyset);
+        for link in input.links {
+            let dangling = link
+                .slot_values()
+                .filter(|uid| !entities.iter().any(|e| e.uid() == *uid))
+                .count();
+            debug!("Link {} has {dangling} dangling slot value(s)", link.id);
+            link.add_to_policyset(&mut policyset);
+        }
+        for rbac_request in input.requests {
+            let request = ast::Request::from(rbac_request);
+            let (_, dur) =
+                time_function(|| run_auth_test(&def_impl, request, &policyset, &entities));
+            info!("{}{}", TOTAL_MSG, dur.as_nanos());
+        }
+    }
+});
@@ -0,0 +1,152 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::Entities;
+use cedar_policy_core::extensions::Extensions;
+use cedar_policy_generators::hierarchy::{
+    AttributesMode, EntityUIDGenMode, HierarchyGenerator, HierarchyGeneratorMode, NumEntities,
+};
+use cedar_policy_generators::policy::{GeneratedLinkedPolicy, ScopeWeights};
+use cedar_policy_generators::rbac::{RBACHierarchy, RBACPolicy, RBACRequest};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::info;
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Input expected by this fuzz target:
+/// An RBAC hierarchy, a template with the scope `principal in ?principal`
+/// linked several times, and 8 associated requests
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// the hierarchy
+    #[serde(skip)]
+    pub hierarchy: RBACHierarchy,
+    /// the template, whose principal constraint is `principal in ?principal`
+    pub template: RBACPolicy,
+    /// links of `template`, each with a distinct ID. Some links fill the
+    /// principal slot with an ancestor of the principal of one of the
+    /// `requests`, so that the request matches the link.
+    pub links: Vec<GeneratedLinkedPolicy>,
+    /// the requests to try for each link
+    #[serde(skip)]
+    pub requests: [RBACRequest; 8],
+}
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let hierarchy = RBACHierarchy(
+            HierarchyGenerator {
+                mode: HierarchyGeneratorMode::Arbitrary {
+                    attributes_mode: AttributesMode::NoAttributes,
+                    action_groups: false,
+                },
+                uid_gen_mode: EntityUIDGenMode::default(),
+                num_entities: NumEntities::RangePerEntityType(0..=4),
+                max_hierarchy_depth: None,
+                u,
+                extensions: Extensions::all_available(),
+            }
+            .generate()?,
+        );
+        let template = RBACPolicy::arbitrary_principal_in_slot_for_hierarchy(
+            Some(ast::PolicyID::from_string("t")),
+            &hierarchy,
+            &ScopeWeights::DEFAULT,
+            u,
+        )?;
+        let requests = [
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+        ];
+        let mut links: Vec<GeneratedLinkedPolicy> = vec![];
+        u.arbitrary_loop(Some(1), Some(4), |u| {
+            let id = ast::PolicyID::from_string(format!("l{}", links.len()));
+            let mut link = GeneratedLinkedPolicy::arbitrary(id, &template, &hierarchy, u)?;
+            if u.ratio(1, 2)? {
+                // fill the slot with the principal of one of the requests, or
+                // one of its ancestors, so that the request is in scope
+                let principal = &u.choose(&requests)?.principal;
+                let mut candidates = vec![principal.clone()];
+                if let Some(entity) = hierarchy.entity(principal) {
+                    candidates.extend(entity.ancestors().cloned());
+                }
+                link = link.with_principal(u.choose(&candidates)?.clone());
+            }
+            links.push(link);
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        Ok(Self {
+            hierarchy,
+            template,
+            links,
+            requests,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            HierarchyGenerator::size_hint(depth),
+            RBACPolicy::arbitrary_principal_in_slot_size_hint(true, depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            (0, None), // not sure how to hint for arbitrary_loop()
+        ])
+    }
+}
+
+// Fuzzing an RBAC template with a slotted membership scope. We authorize
+// against each link on its own, and then against all the links together.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    if let Ok(entities) = Entities::try_from(input.hierarchy) {
+        let requests: Vec<ast::Request> = input.requests.into_iter().map(Into::into).collect();
+        let mut all_links = ast::PolicySet::new();
+        input.template.0.clone().add_to_policyset(&mut all_links);
+        for link in input.links {
+            let mut single_link = ast::PolicySet::new();
+            input.template.0.clone().add_to_policyset(&mut single_link);
+            link.clone().add_to_policyset(&mut single_link);
+            link.add_to_policyset(&mut all_links);
+            for request in requests.iter().cloned() {
+                let (_, dur) =
+                    time_function(|| run_auth_test(&def_impl, request, &single_link, &entities));
+                info!("{}{}", TOTAL_MSG, dur.as_nanos());
+            }
+        }
+        for request in requests {
+            let (_, dur) =
+                time_function(|| run_auth_test(&def_impl, request, &all_links, &entities));
+            info!("{}{}", TOTAL_MSG, dur.as_nanos());
+        }
+    }
+});
@@ -0,0 +1,151 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::Entities;
+use cedar_policy_core::extensions::Extensions;
+use cedar_policy_generators::hierarchy::{
+    AttributesMode, EntityUIDGenMode, HierarchyGenerator, HierarchyGeneratorMode, NumEntities,
+};
+use cedar_policy_generators::policy::{GeneratedLinkedPolicy, ScopeWeights};
+use cedar_policy_generators::rbac::{RBACHierarchy, RBACPolicy, RBACRequest};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::info;
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Input expected by this fuzz target:
+/// An RBAC hierarchy, a single template linked several times, and 8
+/// associated requests
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// the hierarchy
+    #[serde(skip)]
+    pub hierarchy: RBACHierarchy,
+    /// the template, which always has at least one slot
+    pub template: RBACPolicy,
+    /// links of `template`, each with a distinct ID. Some links may reuse the
+    /// slot values of an earlier link, so that two links have overlapping
+    /// scopes.
+    pub links: Vec<GeneratedLinkedPolicy>,
+    /// the requests to try for each link
+    #[serde(skip)]
+    pub requests: [RBACRequest; 8],
+}
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let hierarchy = RBACHierarchy(
+            HierarchyGenerator {
+                mode: HierarchyGeneratorMode::Arbitrary {
+                    attributes_mode: AttributesMode::NoAttributes,
+                    action_groups: false,
+                },
+                uid_gen_mode: EntityUIDGenMode::default(),
+                num_entities: NumEntities::RangePerEntityType(0..=4),
+                max_hierarchy_depth: None,
+                u,
+                extensions: Extensions::all_available(),
+            }
+            .generate()?,
+        );
+        let template = RBACPolicy::arbitrary_for_hierarchy(
+            Some(ast::PolicyID::from_string("t")),
+            &hierarchy,
+            true,
+            &ScopeWeights::DEFAULT,
+            u,
+        )?;
+        if !template.has_slots() {
+            return Err(arbitrary::Error::IncorrectFormat);
+        }
+        let mut links: Vec<GeneratedLinkedPolicy> = vec![];
+        u.arbitrary_loop(Some(2), Some(4), |u| {
+            let id = ast::PolicyID::from_string(format!("l{}", links.len()));
+            let link = if !links.is_empty() && u.ratio(1, 3)? {
+                // same slot values as an earlier link
+                u.choose(&links)?.relinked(id)
+            } else {
+                GeneratedLinkedPolicy::arbitrary(id, &template, &hierarchy, u)?
+            };
+            links.push(link);
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        let requests = [
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+        ];
+        Ok(Self {
+            hierarchy,
+            template,
+            links,
+            requests,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            HierarchyGenerator::size_hint(depth),
+            RBACPolicy::arbitrary_size_hint(true, true, depth),
+            (0, None), // not sure how to hint for arbitrary_loop()
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+            RBACRequest::arbitrary_size_hint(depth),
+        ])
+    }
+}
+
+// Fuzzing one RBAC template linked several times. We authorize against each
+// link on its own, and then against all the links together.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    if let Ok(entities) = Entities::try_from(input.hierarchy) {
+        let requests: Vec<ast::Request> = input.requests.into_iter().map(Into::into).collect();
+        let mut all_links = ast::PolicySet::new();
+        input.template.0.clone().add_to_policyset(&mut all_links);
+        for link in input.links {
+            let mut single_link = ast::PolicySet::new();
+            input.template.0.clone().add_to_policyset(&mut single_link);
+            link.clone().add_to_policyset(&mut single_link);
+            link.add_to_policyset(&mut all_links);
+            for request in requests.iter().cloned() {
+                let (_, dur) =
+                    time_function(|| run_auth_test(&def_impl, request, &single_link, &entities));
+                info!("{}{}", TOTAL_MSG, dur.as_nanos());
+            }
+        }
+        for request in requests {
+            let (_, dur) =
+                time_function(|| run_auth_test(&def_impl, request, &all_links, &entities));
+            info!("{}{}", TOTAL_MSG, dur.as_nanos());
+        }
+    }
+});
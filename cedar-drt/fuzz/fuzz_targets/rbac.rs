@@ -15,6 +15,7 @@
  */
 
 #![no_main]
+use cedar_drt::utils::entities_to_json;
 use cedar_drt::*;
 use cedar_drt_inner::*;
 use cedar_policy_core::ast;
@@ -24,7 +25,7 @@ use cedar_policy_generators::err::Result;
 use cedar_policy_generators::hierarchy::{
     AttributesMode, EntityUIDGenMode, HierarchyGenerator, HierarchyGeneratorMode,
 };
-use cedar_policy_generators::policy::GeneratedLinkedPolicy;
+use cedar_policy_generators::policy::{GeneratedLinkedPolicy, ScopeWeights};
 use cedar_policy_generators::rbac::{RBACHierarchy, RBACPolicy, RBACRequest};
 use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
 use log::info;
@@ -35,8 +36,8 @@ use std::convert::TryFrom;
 /// An RBAC hierarchy, policy set, and 8 associated requests
 #[derive(Debug, Clone, Serialize)]
 pub struct FuzzTargetInput {
-    /// the hierarchy
-    #[serde(skip)]
+    /// the hierarchy, serialized as its entities
+    #[serde(serialize_with = "hierarchy_to_json")]
     pub hierarchy: RBACHierarchy,
     /// The policy set is made up of groups, each of which consists of either a
     /// single static policy or a template with one or more linked policies.
@@ -51,6 +52,18 @@ pub struct FuzzTargetInput {
     pub requests: [RBACRequest; 8],
 }
 
+/// Serialize `hierarchy` as the entities it converts to, or as an empty object
+/// if it doesn't convert
+fn hierarchy_to_json<S: serde::Serializer>(
+    hierarchy: &RBACHierarchy,
+    s: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match Entities::try_from(hierarchy.clone()) {
+        Ok(entities) => entities_to_json(&entities, s),
+        Err(_) => serde_json::json!({}).serialize(s),
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub enum PolicyGroup {
     StaticPolicy(RBACPolicy),
@@ -108,6 +121,7 @@ impl PolicyGroup {
             Some(ast::PolicyID::from_string(format!("p{}", pg_idx))),
             hierarchy,
             true,
+            &ScopeWeights::DEFAULT,
             u,
         )?;
         if policy.has_slots() {
@@ -135,11 +149,13 @@ impl<'a> Arbitrary<'a> for FuzzTargetInput {
             HierarchyGenerator {
                 mode: HierarchyGeneratorMode::Arbitrary {
                     attributes_mode: AttributesMode::NoAttributes,
+                    action_groups: true,
                 },
                 uid_gen_mode: EntityUIDGenMode::default(),
                 num_entities: cedar_policy_generators::hierarchy::NumEntities::RangePerEntityType(
                     0..=4,
                 ),
+                max_hierarchy_depth: None,
                 u,
                 extensions: Extensions::all_available(),
             }
@@ -148,15 +164,17 @@ impl<'a> Arbitrary<'a> for FuzzTargetInput {
         let policy_groups: Vec<PolicyGroup> = arbitrary_vec(u, Some(1), Some(2), |idx, u| {
             Ok(PolicyGroup::arbitrary_for_hierarchy(idx, &hierarchy, u)?)
         })?;
+        // half of the requests use actions that are members of action groups,
+        // so that `action in` constraints are exercised
         let requests = [
             RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
             RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
             RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
             RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
-            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
-            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
-            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
-            RBACRequest::arbitrary_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_with_member_action_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_with_member_action_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_with_member_action_for_hierarchy(&hierarchy, u)?,
+            RBACRequest::arbitrary_with_member_action_for_hierarchy(&hierarchy, u)?,
         ];
         Ok(Self {
             hierarchy,
@@ -186,14 +204,21 @@ impl<'a> Arbitrary<'a> for FuzzTargetInput {
 fuzz_target!(|input: FuzzTargetInput| {
     initialize_log();
     let def_impl = LeanDefinitionalEngine::new();
+    let mut features = RBACFeatures {
+        entities: input.hierarchy.num_entities(),
+        ..RBACFeatures::default()
+    };
     if let Ok(entities) = Entities::try_from(input.hierarchy) {
         let mut policyset = ast::PolicySet::new();
         for pg in input.policy_groups {
             match pg {
                 PolicyGroup::StaticPolicy(p) => {
+                    features.static_policies += 1;
                     p.0.add_to_policyset(&mut policyset);
                 }
                 PolicyGroup::TemplateWithLinks { template, links } => {
+                    features.templates += 1;
+                    features.links += links.len();
                     template.0.add_to_policyset(&mut policyset);
                     for link in links {
                         link.add_to_policyset(&mut policyset);
@@ -203,9 +228,15 @@ fuzz_target!(|input: FuzzTargetInput| {
         }
         for rbac_request in input.requests.into_iter() {
             let request = ast::Request::from(rbac_request);
-            let (_, dur) =
+            let (response, dur) =
                 time_function(|| run_auth_test(&def_impl, request, &policyset, &entities));
             info!("{}{}", TOTAL_MSG, dur.as_nanos());
+            features.record_decision(response.decision);
         }
+        info!(
+            "{}{}",
+            RBAC_FEATURES_MSG,
+            serde_json::to_string(&features).expect("features should serialize to JSON")
+        );
     }
 });
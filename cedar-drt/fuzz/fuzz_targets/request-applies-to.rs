@@ -0,0 +1,113 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::Entities;
+use cedar_policy_generators::{
+    abac::{ABACPolicy, ABACRequest},
+    err::Error,
+    hierarchy::HierarchyGenerator,
+    schema::{AppliesToMismatch, Schema},
+    settings::ABACSettings,
+};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::{debug, info};
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Input expected by this fuzz target:
+/// An ABAC hierarchy, policy, and a request whose principal or resource type
+/// isn't in the applies-to of its action
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// generated schema
+    #[serde(skip)]
+    pub schema: Schema,
+    /// generated entity slice
+    #[serde(skip)]
+    pub entities: Entities,
+    /// generated policy
+    pub policy: ABACPolicy,
+    /// which component of the request is outside the applies-to
+    pub mismatch: AppliesToMismatch,
+    /// the request to try
+    #[serde(skip)]
+    pub request: ABACRequest,
+}
+
+/// settings for this fuzz target
+const SETTINGS: ABACSettings = ABACSettings::builder().build();
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let schema = Schema::arbitrary(SETTINGS.clone(), u)?;
+        let hierarchy = schema.arbitrary_hierarchy(u)?;
+        let policy = schema.arbitrary_policy(&hierarchy, u)?;
+        let mismatch = if u.arbitrary()? {
+            AppliesToMismatch::Principal
+        } else {
+            AppliesToMismatch::Resource
+        };
+        let request = schema.arbitrary_applies_to_mismatched_request(&hierarchy, mismatch, u)?;
+        let entities = Entities::try_from(hierarchy).map_err(|_| Error::NotEnoughData)?;
+        Ok(Self {
+            schema,
+            entities,
+            policy,
+            mismatch,
+            request,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_size_hint(depth),
+            HierarchyGenerator::size_hint(depth),
+            Schema::arbitrary_policy_size_hint(&SETTINGS, depth),
+            <bool as Arbitrary>::size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+        ])
+    }
+}
+
+// Fuzzing of request validation for requests outside the applies-to of their
+// action. `cedar-policy` must reject the request with the error matching the
+// mismatched component, both validators must reject a policy scoped to
+// exactly the request, and both engines must agree on authorizing the
+// request when it isn't validated.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    if let Ok(schema) = ValidatorSchema::try_from(input.schema.clone()) {
+        debug!("Schema: {}\n", input.schema.schemafile_string());
+        debug!("Request: {}\n", input.request);
+        check_applies_to_mismatch(&def_impl, &schema, &input.request, input.mismatch);
+
+        let mut policyset = ast::PolicySet::new();
+        let policy: ast::StaticPolicy = input.policy.into();
+        policyset.add_static(policy).unwrap();
+        debug!("Policies: {policyset}\n");
+        debug!("Entities: {}\n", input.entities);
+
+        let (_, total_dur) = time_function(|| {
+            run_auth_test(&def_impl, input.request.into(), &policyset, &input.entities)
+        });
+        info!("{}{}", TOTAL_MSG, total_dur.as_nanos());
+    }
+});
@@ -0,0 +1,120 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::Entities;
+use cedar_policy_generators::{
+    abac::{ABACPolicy, ABACRequest},
+    err::Error,
+    hierarchy::HierarchyGenerator,
+    schema::{ContextViolation, Schema},
+    settings::ABACSettings,
+};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::{debug, info};
+use serde::Serialize;
+use std::convert::TryFrom;
+
+/// Input expected by this fuzz target:
+/// An ABAC hierarchy, policy, and a request whose context doesn't conform to
+/// the context type of its action
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// generated schema
+    #[serde(skip)]
+    pub schema: Schema,
+    /// generated entity slice
+    #[serde(skip)]
+    pub entities: Entities,
+    /// generated policy
+    pub policy: ABACPolicy,
+    /// how the context of the request violates the schema
+    pub violation: ContextViolation,
+    /// the request to try
+    #[serde(skip)]
+    pub request: ABACRequest,
+}
+
+/// settings for this fuzz target
+const SETTINGS: ABACSettings = ABACSettings::builder().build();
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let schema = Schema::arbitrary(SETTINGS.clone(), u)?;
+        let hierarchy = schema.arbitrary_hierarchy(u)?;
+        let policy = schema.arbitrary_policy(&hierarchy, u)?;
+        let violation = if u.arbitrary()? {
+            ContextViolation::MissingAttribute
+        } else {
+            ContextViolation::WrongType
+        };
+        let request = schema.arbitrary_invalid_context_request(&hierarchy, violation, u)?;
+        let entities = Entities::try_from(hierarchy).map_err(|_| Error::NotEnoughData)?;
+        Ok(Self {
+            schema,
+            entities,
+            policy,
+            violation,
+            request,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_size_hint(depth),
+            HierarchyGenerator::size_hint(depth),
+            Schema::arbitrary_policy_size_hint(&SETTINGS, depth),
+            <bool as Arbitrary>::size_hint(depth),
+            Schema::arbitrary_request_size_hint(depth),
+        ])
+    }
+}
+
+// Fuzzing of request validation for requests whose context violates the
+// schema. `cedar-policy` must reject the request as having an invalid context,
+// both validators must reject a policy comparing `context` to the request's
+// context, and both engines must agree on authorizing the request when it
+// isn't validated. The policy is checked only if it validates, so that it's one
+// that could be deployed against the schema.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+    if let Ok(schema) = ValidatorSchema::try_from(input.schema.clone()) {
+        debug!("Schema: {}\n", input.schema.schemafile_string());
+        debug!("Request: {}\n", input.request);
+        check_invalid_context(&def_impl, &schema, &input.request, input.violation);
+
+        let mut policyset = ast::PolicySet::new();
+        let policy: ast::StaticPolicy = input.policy.into();
+        policyset.add_static(policy).unwrap();
+        debug!("Policies: {policyset}\n");
+        if !Validator::new(schema)
+            .validate(&policyset, ValidationMode::Strict)
+            .validation_passed()
+        {
+            return;
+        }
+        debug!("Entities: {}\n", input.entities);
+
+        let (_, total_dur) = time_function(|| {
+            run_auth_test(&def_impl, input.request.into(), &policyset, &input.entities)
+        });
+        info!("{}{}", TOTAL_MSG, total_dur.as_nanos());
+    }
+});
@@ -22,7 +22,10 @@ use cedar_policy_core::ast::{self, StaticPolicy, Template};
 use cedar_policy_core::est;
 use cedar_policy_core::parser::{self, parse_policy};
 use cedar_policy_generators::{
-    abac::ABACPolicy, hierarchy::HierarchyGenerator, schema::Schema, settings::ABACSettings,
+    abac::ABACPolicy,
+    hierarchy::{EntityUIDGenMode, HierarchyGenerator},
+    schema::Schema,
+    settings::ABACSettings,
 };
 use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
 use log::debug;
@@ -38,24 +41,21 @@ struct FuzzTargetInput {
 
 // settings for this fuzz target
 // copy-pasted from abac.rs
-const SETTINGS: ABACSettings = ABACSettings {
-    match_types: false,
-    enable_extensions: true,
-    max_depth: 7,
-    max_width: 7,
-    enable_additional_attributes: true,
-    enable_like: true,
-    enable_action_groups_and_attrs: true,
-    enable_arbitrary_func_call: false,
-    enable_unknowns: false,
-    enable_action_in_constraints: true,
-    enable_unspecified_apply_spec: true,
-};
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_match_types(false)
+    .with_max_depth(7)
+    .with_max_width(7)
+    .with_max_conditions(7)
+    .with_additional_attributes(true)
+    .with_unicode_strings(true)
+    .with_arbitrary_func_call(false)
+    .build();
 
 impl<'a> Arbitrary<'a> for FuzzTargetInput {
     fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
         let schema: Schema = Schema::arbitrary(SETTINGS.clone(), u)?;
-        let hierarchy = schema.arbitrary_hierarchy(u)?;
+        let hierarchy = schema
+            .arbitrary_hierarchy_with_uid_mode(EntityUIDGenMode::default_hierarchical_mode(), u)?;
         let policy = schema.arbitrary_policy(&hierarchy, u)?;
         Ok(Self { policy })
     }
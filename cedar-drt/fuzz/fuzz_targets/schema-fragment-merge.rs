@@ -0,0 +1,164 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::initialize_log;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_generators::{
+    schema::{downgrade_frag_to_raw, Schema},
+    settings::ABACSettings,
+};
+use cedar_policy_validator::{json_schema, ValidatorSchema};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::debug;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Input expected by this fuzz target: two schema fragments, whose namespaces
+/// may overlap
+#[derive(Debug, Clone, Serialize)]
+struct Input {
+    /// the first fragment
+    pub first: json_schema::Fragment<ast::InternalName>,
+    /// the second fragment
+    pub second: json_schema::Fragment<ast::InternalName>,
+}
+
+/// settings for this fuzz target
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_match_types(false)
+    .with_max_namespaces(3)
+    .build();
+
+impl<'a> Arbitrary<'a> for Input {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let first = Schema::arbitrary_fragment(SETTINGS.clone(), u)?;
+        let second = Schema::arbitrary_fragment(SETTINGS.clone(), u)?;
+        let namespaces: Vec<_> = first.0.keys().cloned().collect();
+        let second = match u.int_in_range(0..=2u8)? {
+            // namespaces are disjoint, unless their names collide by chance
+            0 => second,
+            // a namespace of `first` again, so its declarations conflict
+            1 => {
+                let ns = u.choose(&namespaces)?;
+                json_schema::Fragment([(ns.clone(), first.0[ns].clone())].into_iter().collect())
+            }
+            // a namespace of `second`, declared in a namespace of `first`
+            _ => {
+                let ns = u.choose(&namespaces)?;
+                let nsdef = second
+                    .0
+                    .into_values()
+                    .next()
+                    .ok_or(arbitrary::Error::IncorrectFormat)?;
+                json_schema::Fragment([(ns.clone(), nsdef)].into_iter().collect())
+            }
+        };
+        Ok(Self { first, second })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_fragment_size_hint(depth),
+            Schema::arbitrary_fragment_size_hint(depth),
+            (1, Some(1)),
+        ])
+    }
+}
+
+/// Whether the two fragments both declare the same entity type, action, or
+/// common type in the same namespace
+fn declarations_conflict(
+    first: &json_schema::Fragment<ast::InternalName>,
+    second: &json_schema::Fragment<ast::InternalName>,
+) -> bool {
+    first.0.iter().any(|(ns, a)| {
+        second.0.get(ns).is_some_and(|b| {
+            a.entity_types
+                .keys()
+                .any(|k| b.entity_types.contains_key(k))
+                || a.actions.keys().any(|k| b.actions.contains_key(k))
+                || a.common_types
+                    .keys()
+                    .any(|k| b.common_types.contains_key(k))
+        })
+    })
+}
+
+/// Convert a fragment to the public `SchemaFragment` type
+fn to_public(
+    fragment: &json_schema::Fragment<ast::InternalName>,
+) -> Option<cedar_policy::SchemaFragment> {
+    let json = serde_json::to_value(fragment).expect("Failed to serialize schema to JSON");
+    cedar_policy::SchemaFragment::from_json_value(json).ok()
+}
+
+// Merge two schema fragments which are valid on their own. The merge must
+// fail if both declare the same entity type, action, or common type, and must
+// succeed if their namespaces are disjoint. A successful merge declares every
+// entity type of both fragments.
+fuzz_target!(|input: Input| {
+    initialize_log();
+    let valid = |fragment: &json_schema::Fragment<ast::InternalName>| {
+        ValidatorSchema::try_from(downgrade_frag_to_raw(fragment.clone())).is_ok()
+    };
+    if !valid(&input.first) || !valid(&input.second) {
+        return;
+    }
+    let (Some(first), Some(second)) = (to_public(&input.first), to_public(&input.second)) else {
+        return;
+    };
+    debug!("First fragment: {:?}", input.first);
+    debug!("Second fragment: {:?}", input.second);
+    let conflict = declarations_conflict(&input.first, &input.second);
+    let disjoint = input
+        .first
+        .0
+        .keys()
+        .all(|ns| !input.second.0.contains_key(ns));
+
+    match cedar_policy::Schema::from_schema_fragments([first, second]) {
+        Ok(merged) => {
+            assert!(
+                !conflict,
+                "Merged fragments with conflicting declarations\nFirst: {:?}\nSecond: {:?}",
+                input.first, input.second
+            );
+            let entity_types: HashSet<String> =
+                merged.entity_types().map(ToString::to_string).collect();
+            for (ns, nsdef) in input.first.0.iter().chain(&input.second.0) {
+                for ety in nsdef.entity_types.keys() {
+                    let name = match ns {
+                        Some(ns) => format!("{ns}::{ety}"),
+                        None => ety.to_string(),
+                    };
+                    assert!(
+                        entity_types.contains(&name),
+                        "Merged schema is missing entity type {name}"
+                    );
+                }
+            }
+        }
+        Err(err) => assert!(
+            conflict || !disjoint,
+            "Failed to merge fragments with disjoint namespaces: {:?}\nFirst: {:?}\nSecond: {:?}",
+            miette::Report::new(err),
+            input.first,
+            input.second
+        ),
+    }
+});
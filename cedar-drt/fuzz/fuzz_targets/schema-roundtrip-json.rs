@@ -0,0 +1,80 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt_inner::schemas::equivalence_check;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_generators::{
+    schema::{downgrade_frag_to_raw, Schema},
+    settings::ABACSettings,
+};
+use cedar_policy_validator::{json_schema, RawName};
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use serde::Serialize;
+use similar_asserts::SimpleDiff;
+
+#[derive(Debug, Clone, Serialize)]
+struct Input {
+    pub schema: json_schema::Fragment<ast::InternalName>,
+}
+
+/// settings for this fuzz target
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_match_types(false)
+    .with_max_width(7)
+    .with_max_namespaces(3)
+    // ABAC fuzzing restricts the use of action because it is used to generate
+    // the corpus tests which will be run on Cedar and CedarCLI.
+    // These packages only expose the restricted action behavior.
+    .with_action_groups_and_attrs(false)
+    .build();
+
+impl<'a> Arbitrary<'a> for Input {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let schema = Schema::arbitrary_fragment(SETTINGS.clone(), u)?;
+        Ok(Self { schema })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        Schema::arbitrary_fragment_size_hint(depth)
+    }
+}
+
+// Roundtrip schemas through their JSON representation. The human-readable
+// syntax is covered by `schema-roundtrip`; the two formats handle empty
+// namespaces and reserved `__cedar` names differently, so we test both.
+fuzz_target!(|i: Input| {
+    let json = serde_json::to_value(&i.schema).expect("Failed to serialize schema to JSON");
+    let parsed: json_schema::Fragment<RawName> =
+        json_schema::Fragment::from_json_value(json.clone())
+            .expect("Failed to parse serialized JSON schema");
+    let downgraded = downgrade_frag_to_raw(i.schema);
+    if let Err(msg) = equivalence_check(downgraded, parsed.clone()) {
+        let roundtripped =
+            serde_json::to_value(&parsed).expect("Failed to serialize parsed schema to JSON");
+        println!(
+            "{}",
+            SimpleDiff::from_str(
+                &serde_json::to_string_pretty(&json).unwrap(),
+                &serde_json::to_string_pretty(&roundtripped).unwrap(),
+                "Initial Schema",
+                "JSON Round tripped"
+            )
+        );
+        panic!("{msg}");
+    }
+});
@@ -15,7 +15,7 @@
  */
 
 #![no_main]
-use cedar_drt_inner::schemas::equivalence_check;
+use cedar_drt_inner::schemas::equivalence_check_normalized;
 use cedar_drt_inner::*;
 use cedar_policy_core::{ast, extensions::Extensions};
 use cedar_policy_generators::{
@@ -26,7 +26,6 @@ use cedar_policy_validator::json_schema;
 use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
 use serde::Serialize;
 use similar_asserts::SimpleDiff;
-use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize)]
 struct Input {
@@ -34,36 +33,24 @@ struct Input {
 }
 
 /// settings for this fuzz target
-const SETTINGS: ABACSettings = ABACSettings {
-    match_types: false,
-    enable_extensions: true,
-    max_depth: 3,
-    max_width: 7,
-    enable_additional_attributes: false,
-    enable_like: true,
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_match_types(false)
+    .with_max_width(7)
+    .with_max_namespaces(3)
     // ABAC fuzzing restricts the use of action because it is used to generate
     // the corpus tests which will be run on Cedar and CedarCLI.
     // These packages only expose the restricted action behavior.
-    enable_action_groups_and_attrs: false,
-    enable_arbitrary_func_call: true,
-    enable_unknowns: false,
-    enable_action_in_constraints: true,
-    enable_unspecified_apply_spec: true,
-};
+    .with_action_groups_and_attrs(false)
+    .build();
 
 impl<'a> Arbitrary<'a> for Input {
     fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
-        let arb_schema = Schema::arbitrary(SETTINGS.clone(), u)?;
-        let namespace = arb_schema.schema;
-        let name = arb_schema.namespace;
-
-        let schema = json_schema::Fragment(HashMap::from([(name, namespace)]));
-
+        let schema = Schema::arbitrary_fragment(SETTINGS.clone(), u)?;
         Ok(Self { schema })
     }
 
     fn size_hint(depth: usize) -> (usize, Option<usize>) {
-        Schema::arbitrary_size_hint(depth)
+        Schema::arbitrary_fragment_size_hint(depth)
     }
 }
 
@@ -76,7 +63,7 @@ fuzz_target!(|i: Input| {
         json_schema::Fragment::from_cedarschema_str(&src, Extensions::all_available())
             .expect("Failed to parse converted human readable schema");
     let downgraded = downgrade_frag_to_raw(i.schema.clone());
-    if let Err(msg) = equivalence_check(downgraded.clone(), parsed.clone()) {
+    if let Err(msg) = equivalence_check_normalized(downgraded.clone(), parsed.clone()) {
         println!("Schema: {src}");
         println!(
             "{}",
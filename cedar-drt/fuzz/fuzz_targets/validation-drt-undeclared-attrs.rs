@@ -0,0 +1,182 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![no_main]
+use cedar_drt::*;
+use cedar_drt_inner::*;
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::{Entities, TCComputation};
+use cedar_policy_core::extensions::Extensions;
+use cedar_policy_generators::{
+    abac::ABACPolicy, hierarchy::Hierarchy, schema::Schema, settings::ABACSettings,
+};
+use cedar_policy_validator::CoreSchema;
+use libfuzzer_sys::arbitrary::{self, Arbitrary, Unstructured};
+use log::{debug, info};
+use serde::Serialize;
+use smol_str::SmolStr;
+
+/// Input expected by this fuzz target
+#[derive(Debug, Clone, Serialize)]
+pub struct FuzzTargetInput {
+    /// generated schema
+    #[serde(skip)]
+    pub schema: Schema,
+    /// generated hierarchy, whose entities may have attributes that the
+    /// schema doesn't declare
+    #[serde(skip)]
+    pub hierarchy: Hierarchy,
+    /// the undeclared attributes in `hierarchy`
+    pub undeclared: Vec<(ast::EntityUID, SmolStr)>,
+    /// generated policy
+    pub policy: ABACPolicy,
+}
+
+/// settings for this fuzz target. `cedar-policy` doesn't support schemas with
+/// `additional_attributes`, so all record types are closed and every extra
+/// attribute on an entity is undeclared.
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_match_types(false)
+    .with_max_depth(7)
+    .with_max_width(7)
+    .with_max_conditions(7)
+    .with_undeclared_entity_attrs(true)
+    .build();
+
+impl<'a> Arbitrary<'a> for FuzzTargetInput {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let schema: Schema = Schema::arbitrary(SETTINGS.clone(), u)?;
+        let hierarchy = schema.arbitrary_hierarchy(u)?;
+        let undeclared = schema.undeclared_entity_attrs(&hierarchy);
+        let policy = schema.arbitrary_policy(&hierarchy, u)?;
+        Ok(Self {
+            schema,
+            hierarchy,
+            undeclared,
+            policy,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Schema::arbitrary_size_hint(depth),
+            Schema::arbitrary_policy_size_hint(&SETTINGS, depth),
+        ])
+    }
+}
+
+/// A policy that reads the undeclared attribute `attr` of `uid`
+fn undeclared_access_policy(id: &str, uid: &ast::EntityUID, attr: &SmolStr) -> ast::StaticPolicy {
+    let access = || ast::Expr::get_attr(ast::Expr::val(uid.clone()), attr.clone());
+    ast::StaticPolicy::new(
+        ast::PolicyID::from_string(id),
+        None,
+        ast::Annotations::new(),
+        ast::Effect::Permit,
+        ast::PrincipalConstraint::any(),
+        ast::ActionConstraint::any(),
+        ast::ResourceConstraint::any(),
+        ast::Expr::is_eq(access(), access()),
+    )
+    .expect("policy should not contain slots")
+}
+
+// Check how undeclared entity attributes are handled: they must be preserved
+// in the entity store but rejected by schema-based entity validation, both
+// validators must agree that policies reading them are invalid, and
+// `cedar-policy` must also reject those policies in permissive mode.
+fuzz_target!(|input: FuzzTargetInput| {
+    initialize_log();
+    let def_impl = LeanDefinitionalEngine::new();
+
+    let Ok(schema) = ValidatorSchema::try_from(input.schema) else {
+        return;
+    };
+    debug!("Schema: {:?}", schema);
+    debug!("Undeclared attributes: {:?}", input.undeclared);
+
+    let Ok(entities) = Entities::try_from(input.hierarchy.clone()) else {
+        return;
+    };
+    for (uid, attr) in &input.undeclared {
+        let preserved = entities
+            .iter()
+            .find(|e| e.uid() == uid)
+            .is_some_and(|e| e.get(attr).is_some());
+        assert!(
+            preserved,
+            "undeclared attribute {attr:?} of {uid} was dropped\nEntities:\n{}",
+            entities.to_json_value().unwrap()
+        );
+    }
+    if !input.undeclared.is_empty() {
+        let validated = Entities::from_entities(
+            input.hierarchy.entities().cloned(),
+            Some(&CoreSchema::new(&schema)),
+            TCComputation::ComputeNow,
+            Extensions::all_available(),
+        );
+        assert!(
+            validated.is_err(),
+            "entities with undeclared attributes {:?} should not conform to Schema:\n{:?}",
+            input.undeclared,
+            schema
+        );
+    }
+
+    let mut policyset = ast::PolicySet::new();
+    let policy: ast::StaticPolicy = input.policy.into();
+    policyset.add_static(policy).unwrap();
+    debug!("Policies: {policyset}");
+
+    let probes: Vec<ast::PolicySet> = input
+        .undeclared
+        .iter()
+        .enumerate()
+        .map(|(i, (uid, attr))| {
+            let mut probe = ast::PolicySet::new();
+            probe
+                .add_static(undeclared_access_policy(&format!("probe{i}"), uid, attr))
+                .unwrap();
+            probe
+        })
+        .collect();
+
+    let (_, total_dur) = time_function(|| {
+        // the definitional validator only supports strict mode
+        run_val_test(
+            &def_impl,
+            schema.clone(),
+            &policyset,
+            ValidationMode::Strict,
+        );
+        for probe in &probes {
+            run_val_rejection_test(&def_impl, schema.clone(), probe, ValidationMode::Strict);
+        }
+        // permissive mode doesn't relax attribute access on closed records,
+        // so `cedar-policy` must reject the probes there too
+        let validator = Validator::new(schema.clone());
+        for probe in &probes {
+            assert!(
+                !validator
+                    .validate(probe, ValidationMode::Permissive)
+                    .validation_passed(),
+                "cedar-policy should reject Policies in permissive mode:\n{probe}\nSchema:\n{schema:?}"
+            );
+        }
+    });
+    info!("{}{}", TOTAL_MSG, total_dur.as_nanos());
+});
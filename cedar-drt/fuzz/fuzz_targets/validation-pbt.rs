@@ -53,19 +53,13 @@ struct FuzzTargetInput {
 }
 
 /// settings for this fuzz target
-const SETTINGS: ABACSettings = ABACSettings {
-    match_types: false,
-    enable_extensions: true,
-    max_depth: 7,
-    max_width: 7,
-    enable_additional_attributes: true,
-    enable_like: true,
-    enable_action_groups_and_attrs: true,
-    enable_arbitrary_func_call: true,
-    enable_unknowns: false,
-    enable_action_in_constraints: true,
-    enable_unspecified_apply_spec: true,
-};
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_match_types(false)
+    .with_max_depth(7)
+    .with_max_width(7)
+    .with_max_conditions(7)
+    .with_additional_attributes(true)
+    .build();
 
 const LOG_FILENAME_GENERATION_START: &str = "./logs/01_generation_start.txt";
 const LOG_FILENAME_GENERATED_SCHEMA: &str = "./logs/02_generated_schema.txt";
@@ -79,6 +73,7 @@ const LOG_FILENAME_VALIDATION_PASS: &str = "./logs/08_validation_pass.txt";
 const LOG_FILENAME_ERR_NOT_ENOUGH_DATA: &str = "./logs/err_not_enough_data.txt";
 const LOG_FILENAME_ERR_EMPTY_CHOOSE: &str = "./logs/err_empty_choose.txt";
 const LOG_FILENAME_ERR_TOO_DEEP: &str = "./logs/err_too_deep.txt";
+const LOG_FILENAME_ERR_TOO_LARGE: &str = "./logs/err_too_large.txt";
 const LOG_FILENAME_ERR_NO_VALID_TYPES: &str = "./logs/err_no_valid_types.txt";
 const LOG_FILENAME_ERR_EXTENSIONS_DISABLED: &str = "./logs/err_extensions_disabled.txt";
 const LOG_FILENAME_ERR_LIKE_DISABLED: &str = "./logs/err_like_disabled.txt";
@@ -132,6 +127,9 @@ fn log_err<T>(res: Result<T>, doing_what: &str) -> Result<T> {
             Err(Error::TooDeep) => {
                 checkpoint(LOG_FILENAME_ERR_TOO_DEEP.to_string() + "_" + doing_what)
             }
+            Err(Error::TooLarge) => {
+                checkpoint(LOG_FILENAME_ERR_TOO_LARGE.to_string() + "_" + doing_what)
+            }
             Err(Error::NoValidPrincipalOrResourceTypes) => {
                 checkpoint(LOG_FILENAME_ERR_NO_VALID_TYPES.to_string() + "_" + doing_what)
             }
@@ -0,0 +1,202 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Replay the Cedar integration-test corpus through the differential engines.
+//! Unlike the integration tests in `cedar-drt/tests`, which check each engine
+//! against the expected results recorded in the corpus, this only checks that
+//! `cedar-policy` and the custom implementation agree with each other.
+
+use crate::{run_auth_test, run_val_test};
+use cedar_policy_validator::ValidationMode;
+use cedar_testing::cedar_test_impl::CedarTestImplementation;
+use cedar_testing::integration_testing::{
+    parse_entities_from_test, parse_policies_from_test, parse_request_from_test,
+    parse_schema_from_test, JsonTest,
+};
+use log::info;
+use std::path::{Path, PathBuf};
+
+/// Environment variable holding the path to the root of the integration-test
+/// corpus (e.g., a checkout of `cedar-integration-tests`)
+pub const INTEGRATION_CORPUS_ENV_VAR: &str = "CEDAR_INTEGRATION_CORPUS";
+
+/// Replay the integration-test corpus found at the path given by
+/// [`INTEGRATION_CORPUS_ENV_VAR`]. Returns the number of tests replayed, which
+/// is 0 if the variable isn't set or doesn't point to a directory.
+pub fn replay_integration_corpus_from_env(custom_impl: &impl CedarTestImplementation) -> usize {
+    match std::env::var_os(INTEGRATION_CORPUS_ENV_VAR) {
+        Some(root) if Path::new(&root).is_dir() => {
+            replay_integration_corpus(custom_impl, Path::new(&root))
+        }
+        _ => {
+            info!("{INTEGRATION_CORPUS_ENV_VAR} is not set to a directory; skipping corpus replay");
+            0
+        }
+    }
+}
+
+/// Replay every test in the integration-test corpus rooted at `root`. Relative
+/// paths to policies, schemas, and entities inside the tests are resolved
+/// against `root`. JSON files that aren't tests (e.g., entity files) are
+/// skipped. Returns the number of tests replayed.
+pub fn replay_integration_corpus(custom_impl: &impl CedarTestImplementation, root: &Path) -> usize {
+    let mut replayed = 0;
+    for path in json_files(root) {
+        let Ok(contents) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(test) = serde_json::from_str::<JsonTest>(&contents) else {
+            continue;
+        };
+        replay_integration_test(custom_impl, root, test, &path.display().to_string());
+        replayed += 1;
+    }
+    replayed
+}
+
+/// Replay a single integration test, asserting that `cedar-policy` and
+/// `custom_impl` agree on validation and on every request in the test.
+pub fn replay_integration_test(
+    custom_impl: &impl CedarTestImplementation,
+    root: &Path,
+    mut test: JsonTest,
+    test_name: &str,
+) {
+    let resolve = |file: &str| root.join(file).display().to_string();
+    test.policies = resolve(&test.policies);
+    test.schema = resolve(&test.schema);
+    test.entities = resolve(&test.entities);
+
+    let policies = parse_policies_from_test(&test);
+    let schema = parse_schema_from_test(&test);
+    let entities = parse_entities_from_test(&test, &schema);
+    run_val_test(
+        custom_impl,
+        schema.clone(),
+        &policies,
+        ValidationMode::Strict,
+    );
+    for json_request in &test.requests {
+        let request = parse_request_from_test(json_request, &schema, test_name);
+        run_auth_test(custom_impl, request, &policies, &entities);
+    }
+}
+
+/// All `*.json` files under `dir`, recursively, excluding `*.entities.json`
+/// files. Unreadable directories are skipped.
+fn json_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if path.is_dir() {
+            files.extend(json_files(&path));
+        } else if path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(".json") && !name.ends_with(".entities.json"))
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_replay_from_file() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let path = std::env::temp_dir().join(format!("drt-replay-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, &bytes).unwrap();
+        let expected: Vec<String> =
+            arbitrary::Arbitrary::arbitrary_take_rest(Unstructured::new(&bytes)).unwrap();
+        let replayed = cedar_drt::replay_from_file(&path, |input: Vec<String>| input).unwrap();
+        assert_eq!(replayed, expected);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_sample_corpus() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::authorizer::Decision;
+        use cedar_testing::integration_testing::{JsonRequest, JsonTest};
+
+        let root = std::env::temp_dir().join(format!("corpus-replay-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("tests")).unwrap();
+        std::fs::write(
+        root.join("tests/sample.cedarschema"),
+        "entity User; entity Photo; action view appliesTo { principal: User, resource: Photo };",
+    )
+    .unwrap();
+        std::fs::write(
+            root.join("tests/sample.cedar"),
+            r#"permit(principal == User::"alice", action, resource);"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("tests/sample.entities.json"),
+            r#"[{ "uid": { "type": "User", "id": "alice" }, "attrs": {}, "parents": [] }]"#,
+        )
+        .unwrap();
+        let request = |principal: &str, decision, reason: &[&str]| JsonRequest {
+            description: format!("{principal} views a photo"),
+            principal: serde_json::json!({ "type": "User", "id": principal }).into(),
+            action: serde_json::json!({ "type": "Action", "id": "view" }).into(),
+            resource: serde_json::json!({ "type": "Photo", "id": "vacation" }).into(),
+            context: serde_json::json!({}).into(),
+            validate_request: true,
+            decision,
+            reason: reason.iter().map(|id| PolicyId::new(*id)).collect(),
+            errors: vec![],
+        };
+        let test = JsonTest {
+            // relative paths are resolved against the corpus root
+            schema: "tests/sample.cedarschema".into(),
+            policies: "tests/sample.cedar".into(),
+            entities: "tests/sample.entities.json".into(),
+            should_validate: true,
+            requests: vec![
+                request("alice", Decision::Allow, &["policy0"]),
+                request("bob", Decision::Deny, &[]),
+            ],
+        };
+        std::fs::write(
+            root.join("tests/sample.json"),
+            serde_json::to_string(&test).unwrap(),
+        )
+        .unwrap();
+
+        let def_engine = LeanDefinitionalEngine::new();
+        // the entities file is skipped, and the test is replayed
+        assert_eq!(replay_integration_corpus(&def_engine, &root), 1);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_replay_integration_corpus_from_env() {
+        use cedar_drt::LeanDefinitionalEngine;
+
+        // skipped (replaying nothing) unless the corpus location is set
+        let def_engine = LeanDefinitionalEngine::new();
+        let replayed = replay_integration_corpus_from_env(&def_engine);
+        info!("replayed {replayed} integration tests");
+    }
+}
@@ -0,0 +1,207 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Structured reports of differential-testing failures. The DRT harnesses
+//! panic with a [`DiffReport`] on mismatch, and log it as JSON first, so that
+//! triage scripts can pick failures out of the logs and group them by
+//! [`DiffReport::signature`].
+
+use crate::FailureClass;
+use log::error;
+use serde::Serialize;
+
+/// Everything needed to triage a mismatch between `cedar-policy` and the
+/// custom implementation. Inputs that don't apply to a particular test (e.g.,
+/// the schema of an authorization test) are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DiffReport {
+    /// What kind of mismatch this is, if we could tell
+    pub class: Option<FailureClass>,
+    /// The schema, if any
+    pub schema: Option<String>,
+    /// The policies, if any
+    pub policies: Option<String>,
+    /// The expression under evaluation, if any
+    pub expr: Option<String>,
+    /// The entities, if any
+    pub entities: Option<String>,
+    /// The request, if any
+    pub request: Option<String>,
+    /// The result produced by `cedar-policy`
+    pub rust_result: String,
+    /// The result produced by the custom implementation
+    pub definitional_result: String,
+    /// Human-readable description of how the results differ, one difference
+    /// per line
+    pub diff: String,
+}
+
+impl DiffReport {
+    /// Create a report of a mismatch of the given class between the given
+    /// results. Use the `with_*` methods to add the inputs.
+    pub fn new(
+        class: Option<FailureClass>,
+        rust_result: impl std::fmt::Debug,
+        definitional_result: impl std::fmt::Debug,
+        diff: impl Into<String>,
+    ) -> Self {
+        Self {
+            class,
+            rust_result: format!("{rust_result:?}"),
+            definitional_result: format!("{definitional_result:?}"),
+            diff: diff.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Add the schema
+    pub fn with_schema(self, schema: impl std::fmt::Debug) -> Self {
+        Self {
+            schema: Some(format!("{schema:?}")),
+            ..self
+        }
+    }
+
+    /// Add the policies
+    pub fn with_policies(self, policies: impl std::fmt::Display) -> Self {
+        Self {
+            policies: Some(policies.to_string()),
+            ..self
+        }
+    }
+
+    /// Add the expression under evaluation
+    pub fn with_expr(self, expr: impl std::fmt::Display) -> Self {
+        Self {
+            expr: Some(expr.to_string()),
+            ..self
+        }
+    }
+
+    /// Add the entities
+    pub fn with_entities(self, entities: impl std::fmt::Display) -> Self {
+        Self {
+            entities: Some(entities.to_string()),
+            ..self
+        }
+    }
+
+    /// Add the request
+    pub fn with_request(self, request: impl std::fmt::Display) -> Self {
+        Self {
+            request: Some(request.to_string()),
+            ..self
+        }
+    }
+
+    /// The report as JSON
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("a `DiffReport` should serialize to JSON")
+    }
+
+    /// A signature for deduplicating failures: the class of the mismatch
+    /// and which parts of the results differ, without the specific policy
+    /// ids, values, or error messages involved. Failures with the same root
+    /// cause usually have the same signature.
+    pub fn signature(&self) -> String {
+        let mut parts: Vec<&str> = self
+            .diff
+            .lines()
+            .map(|line| line.split_once(':').map_or(line, |(what, _)| what).trim())
+            .filter(|what| !what.is_empty())
+            .collect();
+        parts.sort_unstable();
+        parts.dedup();
+        format!(
+            "{}[{}]",
+            self.class.map_or("unknown", |class| class.as_str()),
+            parts.join(";")
+        )
+    }
+
+    /// Log the report as JSON and panic with it
+    pub fn panic(self) -> ! {
+        error!("{}", self.to_json());
+        panic!("{self}")
+    }
+}
+
+impl std::fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Mismatch ({})",
+            self.class.map_or("unknown", |class| class.as_str())
+        )?;
+        if !self.diff.is_empty() {
+            writeln!(f, "{}", self.diff)?;
+        }
+        if let Some(request) = &self.request {
+            writeln!(f, "Request: {request}")?;
+        }
+        if let Some(expr) = &self.expr {
+            writeln!(f, "Expression: {expr}")?;
+        }
+        if let Some(policies) = &self.policies {
+            writeln!(f, "Policies:\n{policies}")?;
+        }
+        if let Some(entities) = &self.entities {
+            writeln!(f, "Entities:\n{entities}")?;
+        }
+        if let Some(schema) = &self.schema {
+            writeln!(f, "Schema:\n{schema}")?;
+        }
+        writeln!(f, "cedar-policy result: {}", self.rust_result)?;
+        write!(f, "Test engine result: {}", self.definitional_result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_and_json() {
+        let report = DiffReport::new(
+            Some(FailureClass::Decision),
+            "Allow",
+            "Deny",
+            "decision: expected Allow, actual Deny",
+        )
+        .with_request("request with principal User::\"alice\"")
+        .with_policies("permit(principal, action, resource);");
+        let shown = report.to_string();
+        assert!(shown.starts_with("Mismatch (decision)\n"));
+        assert!(shown.contains("Policies:\npermit(principal, action, resource);"));
+        assert!(!shown.contains("Schema:"));
+        let json = report.to_json();
+        assert_eq!(json["class"], "decision");
+        assert_eq!(json["rust_result"], "\"Allow\"");
+        assert_eq!(json["schema"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn signature_ignores_specifics() {
+        let report = |diff: &str| DiffReport::new(Some(FailureClass::Reason), (), (), diff);
+        let a = report("determining policies only in actual: \"p0\"");
+        let b = report("determining policies only in actual: \"p3\", \"p4\"");
+        let c = report("determining policies only in expected: \"p0\"");
+        assert_eq!(a.signature(), b.signature());
+        assert_ne!(a.signature(), c.signature());
+        assert_eq!(a.signature(), "reason[determining policies only in actual]");
+        assert_eq!(DiffReport::default().signature(), "unknown[]");
+    }
+}
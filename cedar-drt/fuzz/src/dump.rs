@@ -154,6 +154,101 @@ pub fn dump(
     Ok(())
 }
 
+/// Environment variable holding the directory to write Cedar CLI files to (see
+/// [`dump_cedar_files`]). Targets that support it write the files for every
+/// input they run, so running a target on a single crashing input with this
+/// set gives a reproducer for it.
+pub const CEDAR_FILES_DIR_ENV_VAR: &str = "DRT_CEDAR_FILES_DIR";
+
+/// Write an input to `dir` as files that can be passed directly to the Cedar
+/// CLI: `schema.cedarschema`, `policy.cedar`, which holds the static policies
+/// and the templates, `links.json`, which holds the template links in the
+/// format of the CLI's `--template-linked` option, `entities.json`, and
+/// `requests.json`, which holds a list of requests in the CLI's request
+/// format. Unlike [`dump`], this needs no responses and doesn't check
+/// anything, so it also works for inputs on which the engines disagree.
+pub fn dump_cedar_files(
+    dir: impl AsRef<Path>,
+    schema: &json_schema::Fragment<RawName>,
+    policies: &PolicySet,
+    entities: &Entities,
+    requests: impl IntoIterator<Item = Request>,
+) -> std::io::Result<()> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+
+    let schema_text = schema
+        .to_cedarschema()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+    std::fs::write(dir.join("schema.cedarschema"), format!("{schema_text}\n"))?;
+
+    // The CLI names the policies and templates of `policy.cedar` by their
+    // position in the file, unless they have an `@id` annotation, so the links
+    // refer to the templates by those names.
+    let mut policy_texts: Vec<_> = policies
+        .static_policies()
+        .map(ToString::to_string)
+        .collect();
+    let mut template_ids = HashMap::new();
+    for template in policies.templates() {
+        let cli_id = template
+            .annotations()
+            .find(|(key, _)| key.as_ref() == "id")
+            .map_or_else(
+                || format!("policy{}", policy_texts.len()),
+                |(_, annotation)| annotation.val.to_string(),
+            );
+        template_ids.insert(template.id().clone(), cli_id);
+        policy_texts.push(template.to_string());
+    }
+    std::fs::write(
+        dir.join("policy.cedar"),
+        format!("{}\n", policy_texts.join("\n")),
+    )?;
+
+    let links: Vec<_> = policies
+        .policies()
+        .filter(|policy| !policy.is_static())
+        .map(|link| {
+            serde_json::json!({
+                "template_id": template_ids[link.template().id()],
+                "link_id": link.id().to_string(),
+                "args": link
+                    .env()
+                    .iter()
+                    .map(|(slot, euid)| (slot.to_string(), euid.to_string()))
+                    .collect::<HashMap<_, _>>(),
+            })
+        })
+        .collect();
+    let links_file = std::fs::File::create(dir.join("links.json"))?;
+    serde_json::to_writer_pretty(links_file, &links)?;
+
+    let entities_file = std::fs::File::create(dir.join("entities.json"))?;
+    entities
+        .write_to_json(entities_file)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+
+    let requests: Vec<_> = requests
+        .into_iter()
+        .map(|q| {
+            serde_json::json!({
+                "principal": dump_request_var(q.principal()),
+                "action": dump_request_var(q.action()),
+                "resource": dump_request_var(q.resource()),
+                "context": dump_context(
+                    q.context()
+                        .expect("`dump_cedar_files` does not support requests missing context")
+                        .clone(),
+                ),
+            })
+        })
+        .collect();
+    let requests_file = std::fs::File::create(dir.join("requests.json"))?;
+    serde_json::to_writer_pretty(requests_file, &requests)?;
+    Ok(())
+}
+
 // Check that the generated test passes the `perform_integration_test` function
 fn check_test(
     formatted_policies: String,
@@ -195,7 +290,7 @@ fn check_test(
 }
 
 /// Check whether a policy set can be successfully parsed
-fn well_formed(policies: &PolicySet) -> bool {
+pub(crate) fn well_formed(policies: &PolicySet) -> bool {
     policies
         .static_policies()
         .map(ToString::to_string)
@@ -246,3 +341,83 @@ fn dump_context(context: Context) -> JsonValueWithNoDuplicateKeys {
         .expect("failed to serialize context")
         .into()
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{request, uid};
+    use crate::*;
+
+    #[test]
+    fn test_dump_cedar_files() {
+        use cedar_policy_validator::json_schema;
+
+        let (fragment, _) = json_schema::Fragment::from_cedarschema_str(
+            r#"
+        entity User;
+        entity Photo;
+        action view appliesTo { principal: User, resource: Photo, context: { n: Long } };
+        "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let mut policies = cedar_policy_core::parser::parse_policyset(
+            r#"
+        permit(principal, action, resource) when { context.n > 0 };
+        permit(principal == ?principal, action, resource);
+        "#,
+        )
+        .unwrap();
+        policies
+            .link(
+                ast::PolicyID::from_string("policy1"),
+                ast::PolicyID::from_string("link"),
+                [(ast::SlotId::principal(), uid("User", "alice"))].into(),
+            )
+            .unwrap();
+        let request = request(
+            uid("User", "alice"),
+            uid("Action", "view"),
+            uid("Photo", "vacation"),
+            ast::Context::from_pairs(
+                [("n".into(), ast::RestrictedExpr::val(1))],
+                Extensions::none(),
+            )
+            .unwrap(),
+        );
+
+        let dir = std::env::temp_dir().join(format!("drt-cedar-files-{}", uuid::Uuid::new_v4()));
+        dump_cedar_files(&dir, &fragment, &policies, &Entities::new(), [request]).unwrap();
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        ValidatorSchema::from_cedarschema_str(
+            &read("schema.cedarschema"),
+            Extensions::all_available(),
+        )
+        .expect("dumped schema should parse");
+        let dumped_policies = cedar_policy_core::parser::parse_policyset(&read("policy.cedar"))
+            .expect("dumped policies should parse");
+        assert_eq!(dumped_policies.policies().count(), 1);
+        assert_eq!(dumped_policies.templates().count(), 1);
+        let links: serde_json::Value = serde_json::from_str(&read("links.json")).unwrap();
+        assert_eq!(
+            links,
+            serde_json::json!([{
+                "template_id": "policy1",
+                "link_id": "link",
+                "args": { "?principal": r#"User::"alice""# },
+            }])
+        );
+        let entities: serde_json::Value = serde_json::from_str(&read("entities.json")).unwrap();
+        assert_eq!(entities, serde_json::json!([]));
+        let requests: serde_json::Value = serde_json::from_str(&read("requests.json")).unwrap();
+        assert_eq!(
+            requests,
+            serde_json::json!([{
+                "principal": { "type": "User", "id": "alice" },
+                "action": { "type": "Action", "id": "view" },
+                "resource": { "type": "Photo", "id": "vacation" },
+                "context": { "n": 1 },
+            }])
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
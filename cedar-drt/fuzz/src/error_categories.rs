@@ -0,0 +1,141 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Categories of evaluation errors, normalized across `cedar-policy` and the
+//! Lean spec, so that we can check both engines error for the same reason and
+//! not just that both error.
+
+use cedar_drt::LeanDefinitionalEngine;
+use cedar_policy_core::ast;
+use cedar_policy_core::entities::Entities;
+use cedar_policy_core::evaluator::EvaluationError;
+use cedar_testing::cedar_test_impl::TestResult;
+use log::info;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of `cedar-policy` evaluation errors seen so far by this process
+/// whose category couldn't be compared, because they have no [`ErrorCategory`]
+static UNCATEGORIZED_ERRORS: AtomicUsize = AtomicUsize::new(0);
+
+/// Category of an evaluation error, corresponding to the error kinds of the
+/// Lean spec
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// An entity does not exist in the entity store
+    EntityDoesNotExist,
+    /// An entity or record does not have the attribute
+    AttrDoesNotExist,
+    /// An operand has the wrong type, or a function the wrong number of
+    /// arguments
+    TypeError,
+    /// Integer overflow
+    ArithBoundsError,
+    /// An extension function failed, e.g., on an invalid literal
+    ExtensionError,
+}
+
+impl ErrorCategory {
+    /// The category of a `cedar-policy` evaluation error, or `None` if it has
+    /// no counterpart in the Lean spec
+    pub fn from_rust(err: &EvaluationError) -> Option<Self> {
+        match err {
+            EvaluationError::EntityDoesNotExist(_) => Some(Self::EntityDoesNotExist),
+            EvaluationError::EntityAttrDoesNotExist(_)
+            | EvaluationError::RecordAttrDoesNotExist(_) => Some(Self::AttrDoesNotExist),
+            EvaluationError::TypeError(_) | EvaluationError::WrongNumArguments(_) => {
+                Some(Self::TypeError)
+            }
+            EvaluationError::IntegerOverflow(_) => Some(Self::ArithBoundsError),
+            EvaluationError::FailedExtensionFunctionExecution(_) => Some(Self::ExtensionError),
+            _ => None,
+        }
+    }
+
+    /// The category of an error kind reported by the Lean spec, or `None` if
+    /// the kind is unknown
+    pub fn from_lean(kind: &str) -> Option<Self> {
+        match kind {
+            "entityDoesNotExist" => Some(Self::EntityDoesNotExist),
+            "attrDoesNotExist" => Some(Self::AttrDoesNotExist),
+            "typeError" => Some(Self::TypeError),
+            "arithBoundsError" => Some(Self::ArithBoundsError),
+            "extensionError" => Some(Self::ExtensionError),
+            _ => None,
+        }
+    }
+
+    /// The name of the category, as reported by the Lean spec
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::EntityDoesNotExist => "entityDoesNotExist",
+            Self::AttrDoesNotExist => "attrDoesNotExist",
+            Self::TypeError => "typeError",
+            Self::ArithBoundsError => "arithBoundsError",
+            Self::ExtensionError => "extensionError",
+        }
+    }
+}
+
+/// Pairs of `cedar-policy` and Lean error categories that are known to
+/// disagree on the same input without either being a bug, e.g., because the
+/// two engines check error conditions in a different order. Such mismatches
+/// are ignored by [`error_categories_agree`].
+pub const KNOWN_ERROR_CATEGORY_DIVERGENCES: &[(ErrorCategory, ErrorCategory)] = &[];
+
+/// Whether the category of the `cedar-policy` error `rust` agrees with the
+/// category of the Lean error `lean`, i.e., they are equal, or the mismatch
+/// is listed in [`KNOWN_ERROR_CATEGORY_DIVERGENCES`]
+pub fn error_categories_agree(rust: ErrorCategory, lean: ErrorCategory) -> bool {
+    rust == lean || KNOWN_ERROR_CATEGORY_DIVERGENCES.contains(&(rust, lean))
+}
+
+/// Record that `err` has no [`ErrorCategory`], so the error category check
+/// was skipped for it. Each one is logged along with the number seen so far,
+/// so that categories missing from [`ErrorCategory::from_rust`] stand out.
+pub fn record_uncategorized_error(err: &EvaluationError) {
+    let count = UNCATEGORIZED_ERRORS.fetch_add(1, Ordering::Relaxed) + 1;
+    info!("uncategorized evaluation errors seen: {count}, latest: {err:?}");
+}
+
+/// The number of errors recorded so far by this process with
+/// [`record_uncategorized_error`]
+pub fn uncategorized_error_count() -> usize {
+    UNCATEGORIZED_ERRORS.load(Ordering::Relaxed)
+}
+
+/// Implementations that can report which kind of error evaluating an
+/// expression produces
+pub trait EvaluationErrorKind {
+    /// The kind of error evaluating `expr` produces, as named by the Lean
+    /// spec, or `None` if evaluation succeeds
+    fn evaluation_error_kind(
+        &self,
+        request: &ast::Request,
+        entities: &Entities,
+        expr: &ast::Expr,
+    ) -> TestResult<Option<String>>;
+}
+
+impl EvaluationErrorKind for LeanDefinitionalEngine {
+    fn evaluation_error_kind(
+        &self,
+        request: &ast::Request,
+        entities: &Entities,
+        expr: &ast::Expr,
+    ) -> TestResult<Option<String>> {
+        LeanDefinitionalEngine::evaluation_error_kind(self, request, entities, expr)
+    }
+}
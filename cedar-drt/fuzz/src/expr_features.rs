@@ -0,0 +1,135 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Structural features of generated expressions. These are logged along with
+//! the inputs of a campaign, so we can see whether the fuzzer actually reaches
+//! deep or call-heavy expressions.
+
+use crate::ast_node_count;
+use cedar_policy_core::ast::{Expr, ExprKind};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Structural features of an expression
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ExprFeatures {
+    /// Total number of AST nodes
+    pub node_count: usize,
+    /// Length of the longest path from the root to a leaf, counted in nodes
+    pub max_depth: usize,
+    /// Number of AST nodes of each kind, e.g., `and`, `has`, or `call`
+    pub kind_counts: BTreeMap<&'static str, usize>,
+    /// Number of distinct extension functions called
+    pub distinct_ext_calls: usize,
+}
+
+/// Compute the structural features of `expr`
+pub fn expr_features(expr: &Expr) -> ExprFeatures {
+    let mut kind_counts = BTreeMap::new();
+    let mut ext_calls = BTreeSet::new();
+    for e in expr.subexpressions() {
+        *kind_counts
+            .entry(expr_kind_name(e.expr_kind()))
+            .or_insert(0) += 1;
+        if let ExprKind::ExtensionFunctionApp { fn_name, .. } = e.expr_kind() {
+            ext_calls.insert(fn_name.to_string());
+        }
+    }
+    ExprFeatures {
+        node_count: ast_node_count(expr),
+        max_depth: expr_depth(expr),
+        kind_counts,
+        distinct_ext_calls: ext_calls.len(),
+    }
+}
+
+/// Name of the kind of an AST node, as used in [`ExprFeatures::kind_counts`]
+fn expr_kind_name(kind: &ExprKind) -> &'static str {
+    match kind {
+        ExprKind::Lit(_) => "lit",
+        ExprKind::Var(_) => "var",
+        ExprKind::Slot(_) => "slot",
+        ExprKind::Unknown(_) => "unknown",
+        ExprKind::If { .. } => "if",
+        ExprKind::And { .. } => "and",
+        ExprKind::Or { .. } => "or",
+        ExprKind::UnaryApp { .. } => "unaryApp",
+        ExprKind::BinaryApp { .. } => "binaryApp",
+        ExprKind::ExtensionFunctionApp { .. } => "call",
+        ExprKind::GetAttr { .. } => "getAttr",
+        ExprKind::HasAttr { .. } => "has",
+        ExprKind::Like { .. } => "like",
+        ExprKind::Is { .. } => "is",
+        ExprKind::Set(_) => "set",
+        ExprKind::Record(_) => "record",
+    }
+}
+
+/// The immediate subexpressions of `expr`
+fn children(expr: &Expr) -> Vec<&Expr> {
+    match expr.expr_kind() {
+        ExprKind::Lit(_) | ExprKind::Var(_) | ExprKind::Slot(_) | ExprKind::Unknown(_) => vec![],
+        ExprKind::If {
+            test_expr,
+            then_expr,
+            else_expr,
+        } => vec![test_expr.as_ref(), then_expr.as_ref(), else_expr.as_ref()],
+        ExprKind::And { left, right } | ExprKind::Or { left, right } => {
+            vec![left.as_ref(), right.as_ref()]
+        }
+        ExprKind::UnaryApp { arg, .. } => vec![arg.as_ref()],
+        ExprKind::BinaryApp { arg1, arg2, .. } => vec![arg1.as_ref(), arg2.as_ref()],
+        ExprKind::ExtensionFunctionApp { args, .. } => args.iter().collect(),
+        ExprKind::GetAttr { expr, .. }
+        | ExprKind::HasAttr { expr, .. }
+        | ExprKind::Like { expr, .. }
+        | ExprKind::Is { expr, .. } => vec![expr.as_ref()],
+        ExprKind::Set(elems) => elems.iter().collect(),
+        ExprKind::Record(attrs) => attrs.values().collect(),
+    }
+}
+
+/// Length of the longest path from `expr` to a leaf, counted in nodes
+fn expr_depth(expr: &Expr) -> usize {
+    1 + children(expr)
+        .into_iter()
+        .map(expr_depth)
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_expr_features() {
+        let expr = cedar_policy_core::parser::parse_expr(
+        r#"principal has name && (decimal("1.0").lessThan(decimal("2.0")) || ip("::1").isLoopback())"#,
+    )
+    .expect("Failed to parse");
+        let features = expr_features(&expr);
+        assert_eq!(features.node_count, ast_node_count(&expr));
+        // `&&` -> `||` -> `lessThan` -> `decimal` -> `"1.0"`
+        assert_eq!(features.max_depth, 5);
+        assert_eq!(features.kind_counts.get("and"), Some(&1));
+        assert_eq!(features.kind_counts.get("or"), Some(&1));
+        assert_eq!(features.kind_counts.get("has"), Some(&1));
+        assert_eq!(features.kind_counts.get("call"), Some(&5));
+        assert_eq!(features.kind_counts.get("getAttr"), None);
+        assert_eq!(features.distinct_ext_calls, 4);
+    }
+}
@@ -0,0 +1,238 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use cedar_policy::ffi;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Coarse root-cause bucket for a differential failure, used to pre-sort
+/// failures during triage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailureClass {
+    /// The two engines reached different authorization decisions
+    Decision,
+    /// The decisions agree, but the determining policies differ
+    Reason,
+    /// Decisions and determining policies agree, but the errors differ
+    Errors,
+    /// The two evaluators produced different values for an expression
+    Evaluation,
+    /// The two validators disagree on whether the policies are valid
+    Validation,
+    /// The test engine failed to produce a response at all
+    Panic,
+}
+
+impl FailureClass {
+    /// Short name for this class, suitable for use as a log or feature tag
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Decision => "decision",
+            Self::Reason => "reason",
+            Self::Errors => "errors",
+            Self::Evaluation => "evaluation",
+            Self::Validation => "validation",
+            Self::Panic => "panic",
+        }
+    }
+}
+
+impl std::fmt::Display for FailureClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Classify a mismatch between two authorization responses. Returns `None`
+/// if the responses agree.
+///
+/// When several components differ, the most significant one is reported:
+/// a decision mismatch takes priority over a reason mismatch, which takes
+/// priority over an error mismatch.
+pub fn classify_auth_mismatch(
+    expected: &ffi::Response,
+    actual: &ffi::Response,
+) -> Option<FailureClass> {
+    if expected.decision() != actual.decision() {
+        return Some(FailureClass::Decision);
+    }
+    let expected_reason: HashSet<_> = expected.diagnostics().reason().collect();
+    let actual_reason: HashSet<_> = actual.diagnostics().reason().collect();
+    if expected_reason != actual_reason {
+        return Some(FailureClass::Reason);
+    }
+    let expected_errors: HashSet<_> = expected.diagnostics().errors().collect();
+    let actual_errors: HashSet<_> = actual.diagnostics().errors().collect();
+    if expected_errors != actual_errors {
+        return Some(FailureClass::Errors);
+    }
+    None
+}
+
+/// Describe how two authorization responses differ: the decisions if they
+/// differ, and the determining policies and errors that appear in only one of
+/// the responses. Returns an empty string if the responses agree.
+pub fn auth_mismatch_diff(expected: &ffi::Response, actual: &ffi::Response) -> String {
+    fn only_in<T: std::fmt::Debug + Eq + std::hash::Hash>(
+        this: &HashSet<T>,
+        other: &HashSet<T>,
+    ) -> Vec<String> {
+        let mut items: Vec<_> = this.difference(other).map(|x| format!("{x:?}")).collect();
+        items.sort();
+        items
+    }
+    let mut lines = Vec::new();
+    if expected.decision() != actual.decision() {
+        lines.push(format!(
+            "decision: expected {:?}, actual {:?}",
+            expected.decision(),
+            actual.decision()
+        ));
+    }
+    let expected_reason: HashSet<_> = expected.diagnostics().reason().collect();
+    let actual_reason: HashSet<_> = actual.diagnostics().reason().collect();
+    let expected_errors: HashSet<_> = expected.diagnostics().errors().collect();
+    let actual_errors: HashSet<_> = actual.diagnostics().errors().collect();
+    for (what, items) in [
+        (
+            "determining policies only in expected",
+            only_in(&expected_reason, &actual_reason),
+        ),
+        (
+            "determining policies only in actual",
+            only_in(&actual_reason, &expected_reason),
+        ),
+        (
+            "errors only in expected",
+            only_in(&expected_errors, &actual_errors),
+        ),
+        (
+            "errors only in actual",
+            only_in(&actual_errors, &expected_errors),
+        ),
+    ] {
+        if !items.is_empty() {
+            lines.push(format!("{what}: {}", items.join(", ")));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Classify a mismatch between two validation results, given whether each
+/// validator accepted the policies. Returns `None` if the results agree.
+pub fn classify_val_mismatch(expected_passed: bool, actual_passed: bool) -> Option<FailureClass> {
+    (expected_passed != actual_passed).then_some(FailureClass::Validation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cedar_policy::{Decision, PolicyId};
+    use miette::miette;
+
+    fn response(decision: Decision, reason: &[&str], errors: &[&str]) -> ffi::Response {
+        ffi::Response::new(
+            decision,
+            reason.iter().map(|id| PolicyId::new(*id)).collect(),
+            errors
+                .iter()
+                .map(|id| {
+                    ffi::AuthorizationError::new_from_report(PolicyId::new(*id), miette!("{id}"))
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn agreement() {
+        let r = response(Decision::Allow, &["p0"], &["p1"]);
+        assert_eq!(classify_auth_mismatch(&r, &r.clone()), None);
+        assert_eq!(classify_val_mismatch(true, true), None);
+        assert_eq!(classify_val_mismatch(false, false), None);
+    }
+
+    #[test]
+    fn decision() {
+        let expected = response(Decision::Allow, &["p0"], &[]);
+        let actual = response(Decision::Deny, &[], &["p1"]);
+        assert_eq!(
+            classify_auth_mismatch(&expected, &actual),
+            Some(FailureClass::Decision)
+        );
+    }
+
+    #[test]
+    fn reason() {
+        let expected = response(Decision::Deny, &["p0"], &[]);
+        let actual = response(Decision::Deny, &["p1"], &["p2"]);
+        assert_eq!(
+            classify_auth_mismatch(&expected, &actual),
+            Some(FailureClass::Reason)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let expected = response(Decision::Deny, &[], &["p0"]);
+        let actual = response(Decision::Deny, &[], &[]);
+        assert_eq!(
+            classify_auth_mismatch(&expected, &actual),
+            Some(FailureClass::Errors)
+        );
+    }
+
+    #[test]
+    fn diff() {
+        let r = response(Decision::Allow, &["p0"], &["p1"]);
+        assert_eq!(auth_mismatch_diff(&r, &r.clone()), "");
+        let expected = response(Decision::Allow, &["p0", "p1"], &["p2"]);
+        let actual = response(Decision::Allow, &["p0", "p3"], &["p2"]);
+        let diff = auth_mismatch_diff(&expected, &actual);
+        assert!(!diff.contains("decision"));
+        assert!(!diff.contains("errors"));
+        let lines: Vec<_> = diff.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(
+            lines[0].starts_with("determining policies only in expected")
+                && lines[0].contains("p1")
+        );
+        assert!(
+            lines[1].starts_with("determining policies only in actual") && lines[1].contains("p3")
+        );
+        let actual = response(Decision::Deny, &[], &[]);
+        let diff = auth_mismatch_diff(&expected, &actual);
+        assert!(diff.contains("decision: expected Allow, actual Deny"));
+        assert!(diff.contains("errors only in expected"));
+    }
+
+    #[test]
+    fn validation() {
+        assert_eq!(
+            classify_val_mismatch(true, false),
+            Some(FailureClass::Validation)
+        );
+        assert_eq!(
+            classify_val_mismatch(false, true),
+            Some(FailureClass::Validation)
+        );
+    }
+
+    #[test]
+    fn panic_tag() {
+        assert_eq!(FailureClass::Panic.to_string(), "panic");
+    }
+}
@@ -14,13 +14,35 @@
  * limitations under the License.
  */
 
+mod corpus_replay;
+mod diff_report;
 mod dump;
+mod error_categories;
+mod expr_features;
+mod failure_class;
+mod minimize;
 mod parsing_utils;
+mod policy_set_queries;
 mod prt;
+mod rbac_features;
+mod seed_corpus;
+mod stream;
+mod validation_kinds;
 
+pub use corpus_replay::*;
+pub use diff_report::*;
 pub use dump::*;
+pub use error_categories::*;
+pub use expr_features::*;
+pub use failure_class::*;
+pub use minimize::*;
 pub use parsing_utils::*;
+pub use policy_set_queries::*;
 pub use prt::*;
+pub use rbac_features::*;
+pub use seed_corpus::*;
+pub use stream::*;
+pub use validation_kinds::*;
 pub mod schemas;
 
 use cedar_policy::ffi;
@@ -28,21 +50,72 @@ use cedar_policy::PolicyId;
 use cedar_policy_core::ast;
 use cedar_policy_core::authorizer::{AuthorizationError, Authorizer, Response};
 use cedar_policy_core::entities::{Entities, NoEntitiesSchema, TCComputation};
-use cedar_policy_core::evaluator::Evaluator;
+use cedar_policy_core::evaluator::{EvaluationError, Evaluator};
 use cedar_policy_core::extensions::Extensions;
+use cedar_policy_generators::{
+    hierarchy::Hierarchy,
+    request::Request,
+    schema::{AppliesToMismatch, ContextViolation, Schema},
+};
+use cedar_policy_validator::RequestValidationError;
 pub use cedar_policy_validator::{ValidationMode, Validator, ValidatorSchema};
+use cedar_testing::cedar_test_impl::partial::FlatPartialResponse;
 pub use cedar_testing::cedar_test_impl::{
-    time_function, CedarTestImplementation, ErrorComparisonMode, TestResult,
+    time_function, CedarTestImplementation, ErrorComparisonMode, RustEngine, TestResult,
     ValidationComparisonMode,
 };
 use libfuzzer_sys::arbitrary::{self, Unstructured};
 use log::info;
 use miette::miette;
 use std::collections::HashSet;
+use std::time::Duration;
 
 /// Times for cedar-policy authorization and validation.
 pub const RUST_AUTH_MSG: &str = "rust_auth (ns) : ";
 pub const RUST_VALIDATION_MSG: &str = "rust_validation (ns) : ";
+/// Throughput of the `cedar-policy` validator on a wide policy set, validated
+/// in a single call.
+pub const WIDE_VALIDATION_MSG: &str = "wide_validation (policies/s) : ";
+/// Times for evaluation and authorization in the custom implementation, e.g.,
+/// the Lean engine (including the FFI call), and for evaluation in
+/// cedar-policy.
+pub const DEFINITIONAL_AUTH_MSG: &str = "definitional_auth (ns) : ";
+pub const DEFINITIONAL_EVAL_MSG: &str = "definitional_eval (ns) : ";
+pub const RUST_EVAL_MSG: &str = "rust_eval (ns) : ";
+/// Time for parsing entities JSON in cedar-policy, and whether it parsed.
+pub const RUST_ENTITIES_PARSE_MSG: &str = "rust_entities_parse (ns) : ";
+pub const RUST_ENTITIES_PARSE_OK_MSG: &str = "rust_entities_parse_ok : ";
+/// Summary counts for an RBAC input, as JSON (see [`RBACFeatures`])
+pub const RBAC_FEATURES_MSG: &str = "rbac_features : ";
+
+/// Time spent on each side of a differential test
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EngineTimes {
+    /// Time spent in cedar-policy
+    pub rust: Duration,
+    /// Time spent in the custom implementation
+    pub definitional: Duration,
+}
+
+/// Like `time_function`, but runs and times the cedar-policy side and the
+/// custom-implementation side of a differential test separately. The
+/// custom-implementation side gets the result of the cedar-policy side, e.g.,
+/// as the expected value.
+pub fn time_function_labeled<R, D>(
+    rust: impl FnOnce() -> R,
+    definitional: impl FnOnce(&R) -> D,
+) -> (R, D, EngineTimes) {
+    let (rust_res, rust_dur) = time_function(rust);
+    let (definitional_res, definitional_dur) = time_function(|| definitional(&rust_res));
+    (
+        rust_res,
+        definitional_res,
+        EngineTimes {
+            rust: rust_dur,
+            definitional: definitional_dur,
+        },
+    )
+}
 
 /// Compare the behavior of the partial evaluator in `cedar-policy` against a custom Cedar
 /// implementation. Panics if the two do not agree. `expr` is the expression to
@@ -102,6 +175,77 @@ pub fn run_pe_test(
     }
 }
 
+/// Compare the behavior of the partial authorizer in `cedar-policy` against a
+/// custom Cedar implementation. Panics if the two do not agree. `policies` may
+/// contain `unknown()` nodes. Residual policies are compared as sets of policy
+/// IDs, not as expressions: the custom implementation's partial response only
+/// reports policy IDs. Residual expressions are compared by [`run_pe_test`]
+/// instead. If neither `request` nor `policies` contain unknowns, this also
+/// checks that the partial response is the concrete response that
+/// [`run_auth_test`] agrees on. The principal, action, and resource of
+/// `request` may be unknown. Its context may be unknown too, but the custom
+/// implementation can't represent an unknown context, so then we only check
+/// that `cedar-policy` produces a response.
+/// Returns the partial response that the two agree on.
+pub fn run_partial_auth_test(
+    custom_impl: &impl CedarTestImplementation,
+    request: ast::Request,
+    policies: &ast::PolicySet,
+    entities: &Entities,
+) -> FlatPartialResponse {
+    let (rust_res, rust_auth_dur) =
+        time_function(|| RustEngine::new().partial_is_authorized(&request, entities, policies));
+    info!("{}{}", RUST_AUTH_MSG, rust_auth_dur.as_nanos());
+    let rust_res = match rust_res {
+        TestResult::Success(res) => res,
+        TestResult::Failure(err) => panic!(
+            "Rust partial authorizer failed for {request}\nPolicies:\n{policies}\nError: {err}"
+        ),
+    };
+    if request.context().is_none() {
+        return rust_res;
+    }
+
+    match custom_impl.partial_is_authorized(&request, entities, policies) {
+        TestResult::Failure(err) => {
+            // TODO(#175): Ignore cases where the definitional code returned an error due to
+            // an unknown extension function.
+            if !err.contains("jsonToExtFun: unknown extension function") {
+                panic!(
+                    "Unexpected error ({}) for {request}\nPolicies:\n{policies}\nEntities:\n{entities}\nError: {err}",
+                    FailureClass::Panic
+                );
+            }
+        }
+        TestResult::Success(definitional_res) => {
+            assert_eq!(
+                rust_res, definitional_res,
+                "Mismatch for {request}\nPolicies:\n{policies}\nEntities:\n{entities}"
+            );
+        }
+    }
+
+    let has_unknowns = [request.principal(), request.action(), request.resource()]
+        .into_iter()
+        .any(|entry| matches!(entry, ast::EntityUIDEntry::Unknown { .. }))
+        || policies.policies().any(|p| {
+            p.condition()
+                .subexpressions()
+                .any(|e| matches!(e.expr_kind(), ast::ExprKind::Unknown(_)))
+        });
+    if !has_unknowns {
+        let partial = Authorizer::new().is_authorized_core(request.clone(), policies, entities);
+        let concrete = run_auth_test(custom_impl, request, policies, entities);
+        assert_eq!(
+            partial.decision(),
+            Some(concrete.decision),
+            "Partial and concrete decisions differ ({}) for concrete policies\nPolicies:\n{policies}",
+            FailureClass::Decision
+        );
+    }
+    rust_res
+}
+
 /// Compare the behavior of the evaluator in `cedar-policy` against a custom Cedar
 /// implementation. Panics if the two do not agree. `expr` is the expression to
 /// evaluate and `request` and `entities` are used to populate the evaluator.
@@ -112,44 +256,150 @@ pub fn run_eval_test(
     entities: &Entities,
     enable_extensions: bool,
 ) {
+    compare_eval(custom_impl, &request, expr, entities, enable_extensions);
+}
+
+/// Like [`run_eval_test`], but if evaluation errors, also check that both
+/// engines produce the same [`ErrorCategory`] of error, not just that both
+/// error, up to [`KNOWN_ERROR_CATEGORY_DIVERGENCES`].
+pub fn run_eval_test_with_error_categories(
+    custom_impl: &(impl CedarTestImplementation + EvaluationErrorKind),
+    request: ast::Request,
+    expr: &ast::Expr,
+    entities: &Entities,
+    enable_extensions: bool,
+) {
+    if let Some(err) = compare_eval(custom_impl, &request, expr, entities, enable_extensions) {
+        check_error_category(custom_impl, &request, expr, entities, &err);
+    }
+}
+
+/// Shared implementation of [`run_eval_test`] and
+/// [`run_eval_test_with_error_categories`]. Returns the error `cedar-policy`
+/// produced, if any, once the custom implementation has been checked against
+/// it.
+fn compare_eval(
+    custom_impl: &impl CedarTestImplementation,
+    request: &ast::Request,
+    expr: &ast::Expr,
+    entities: &Entities,
+    enable_extensions: bool,
+) -> Option<EvaluationError> {
     let exts = if enable_extensions {
         Extensions::all_available()
     } else {
         Extensions::none()
     };
     let eval = Evaluator::new(request.clone(), entities, exts);
-    let expected = match eval.interpret(expr, &std::collections::HashMap::default()) {
-        Ok(v) => Some(v),
-        Err(_) => None,
-    };
-
     // `custom_impl.interpret()` returns true when the result of evaluating `expr`
     // matches `expected`
-    let definitional_res = custom_impl.interpret(
-        &request,
-        entities,
-        expr,
-        enable_extensions,
-        expected.clone(),
+    let (rust_res, definitional_res, times) = time_function_labeled(
+        || eval.interpret(expr, &std::collections::HashMap::default()),
+        |rust_res| {
+            custom_impl.interpret(
+                request,
+                entities,
+                expr,
+                enable_extensions,
+                rust_res.as_ref().ok().cloned(),
+            )
+        },
     );
+    info!("{}{}", RUST_EVAL_MSG, times.rust.as_nanos());
+    info!("{}{}", DEFINITIONAL_EVAL_MSG, times.definitional.as_nanos());
+    let expected = rust_res.as_ref().ok();
 
     match definitional_res {
         TestResult::Failure(err) => {
             // TODO(#175): Ignore cases where the definitional code returned an error due to
             // an unknown extension function.
             if err.contains("jsonToExtFun: unknown extension function") {
-                return;
+                return None;
             }
             // No other errors are expected
-            panic!("Unexpected error for {request}\nExpression: {expr}\nError: {err}");
+            DiffReport::new(
+                Some(FailureClass::Panic),
+                expected,
+                err,
+                "the test engine failed to evaluate the expression",
+            )
+            .with_request(request)
+            .with_expr(expr)
+            .with_entities(entities)
+            .panic();
         }
         TestResult::Success(response) => {
             // The definitional interpreter response should be `true`
-            assert!(
-                response,
-                "Incorrect evaluation result for {request}\nExpression: {expr}\nEntities:\n{entities}\nExpected value:\n{:?}\n",
-                expected
-            )
+            if !response {
+                DiffReport::new(
+                    Some(FailureClass::Evaluation),
+                    expected,
+                    response,
+                    "the test engine's result doesn't match cedar-policy's",
+                )
+                .with_request(request)
+                .with_expr(expr)
+                .with_entities(entities)
+                .panic();
+            }
+        }
+    }
+
+    rust_res.err()
+}
+
+/// Check that the custom implementation produces the same [`ErrorCategory`] of
+/// error as `cedar-policy`, which produced `err`. Panics if the two do not
+/// agree. Errors without a category are recorded with
+/// [`record_uncategorized_error`] instead.
+fn check_error_category(
+    custom_impl: &impl EvaluationErrorKind,
+    request: &ast::Request,
+    expr: &ast::Expr,
+    entities: &Entities,
+    err: &EvaluationError,
+) {
+    let Some(expected) = ErrorCategory::from_rust(err) else {
+        record_uncategorized_error(err);
+        return;
+    };
+    match custom_impl.evaluation_error_kind(request, entities, expr) {
+        TestResult::Failure(def_err) => {
+            // TODO(#175): Ignore cases where the definitional code returned an error due to
+            // an unknown extension function.
+            if !def_err.contains("jsonToExtFun: unknown extension function") {
+                DiffReport::new(
+                    Some(FailureClass::Panic),
+                    err,
+                    def_err,
+                    "the test engine failed to evaluate the expression",
+                )
+                .with_request(request)
+                .with_expr(expr)
+                .with_entities(entities)
+                .panic();
+            }
+        }
+        TestResult::Success(actual) => {
+            let agree = actual
+                .as_deref()
+                .and_then(ErrorCategory::from_lean)
+                .is_some_and(|actual| error_categories_agree(expected, actual));
+            if !agree {
+                DiffReport::new(
+                    Some(FailureClass::Evaluation),
+                    err,
+                    &actual,
+                    format!(
+                        "error category: expected {}, actual {actual:?}",
+                        expected.as_str()
+                    ),
+                )
+                .with_request(request)
+                .with_expr(expr)
+                .with_entities(entities)
+                .panic();
+            }
         }
     }
 }
@@ -164,11 +414,12 @@ pub fn run_auth_test(
     entities: &Entities,
 ) -> Response {
     let authorizer = Authorizer::new();
-    let (rust_res, rust_auth_dur) =
-        time_function(|| authorizer.is_authorized(request.clone(), policies, entities));
-    info!("{}{}", RUST_AUTH_MSG, rust_auth_dur.as_nanos());
-
-    let definitional_res = custom_impl.is_authorized(&request, policies, entities);
+    let (rust_res, definitional_res, times) = time_function_labeled(
+        || authorizer.is_authorized(request.clone(), policies, entities),
+        |_| custom_impl.is_authorized(&request, policies, entities),
+    );
+    info!("{}{}", RUST_AUTH_MSG, times.rust.as_nanos());
+    info!("{}{}", DEFINITIONAL_AUTH_MSG, times.definitional.as_nanos());
 
     match definitional_res {
         TestResult::Failure(err) => {
@@ -176,11 +427,22 @@ pub fn run_auth_test(
             // an unknown extension function.
             if err.contains("jsonToExtFun: unknown extension function") {
                 rust_res
+            } else if err.starts_with(cedar_drt::LEAN_TIMEOUT_MSG) {
+                // the engine gave up on a slow input; there's nothing to
+                // compare, but record that it happened
+                info!("{err}");
+                rust_res
             } else {
-                panic!(
-                    "Unexpected error for {request}\nPolicies:\n{}\nEntities:\n{}\nError: {err}",
-                    &policies, &entities
-                );
+                DiffReport::new(
+                    Some(FailureClass::Panic),
+                    rust_res,
+                    err,
+                    "the test engine failed to authorize the request",
+                )
+                .with_request(&request)
+                .with_policies(policies)
+                .with_entities(entities)
+                .panic();
             }
         }
         TestResult::Success(definitional_res) => {
@@ -220,16 +482,36 @@ pub fn run_auth_test(
                     errors,
                 )
             };
-            assert_eq!(
-                rust_res_for_comparison, definitional_res.response,
-                "Mismatch for {request}\nPolicies:\n{}\nEntities:\n{}",
-                &policies, &entities
-            );
+            if rust_res_for_comparison != definitional_res.response {
+                DiffReport::new(
+                    classify_auth_mismatch(&rust_res_for_comparison, &definitional_res.response),
+                    &rust_res_for_comparison,
+                    &definitional_res.response,
+                    auth_mismatch_diff(&rust_res_for_comparison, &definitional_res.response),
+                )
+                .with_request(&request)
+                .with_policies(policies)
+                .with_entities(entities)
+                .panic();
+            }
             rust_res
         }
     }
 }
 
+/// Render the errors and warnings in a `ValidationResult`, for comparing
+/// two results byte-for-byte
+fn validation_result_summary(
+    res: &cedar_policy_validator::ValidationResult,
+) -> (Vec<String>, Vec<String>) {
+    (
+        res.validation_errors().map(|e| format!("{e:?}")).collect(),
+        res.validation_warnings()
+            .map(|w| format!("{w:?}"))
+            .collect(),
+    )
+}
+
 /// Compare the behavior of the validator in `cedar-policy` against a custom Cedar
 /// implementation. Panics if the two do not agree.
 pub fn run_val_test(
@@ -241,6 +523,19 @@ pub fn run_val_test(
     let validator = Validator::new(schema.clone());
     let (rust_res, rust_validation_dur) = time_function(|| validator.validate(policies, mode));
     info!("{}{}", RUST_VALIDATION_MSG, rust_validation_dur.as_nanos());
+    record_validation_error_kinds(&rust_res);
+
+    // Validation should be idempotent and side-effect-free: validating the
+    // same policies again must produce exactly the same errors and warnings.
+    let rust_res_again = validator.validate(policies, mode);
+    assert_eq!(
+        validation_result_summary(&rust_res),
+        validation_result_summary(&rust_res_again),
+        "Mismatch ({}) between repeated validations for Policies:\n{}\nSchema:\n{:?}",
+        FailureClass::Validation,
+        &policies,
+        schema
+    );
 
     let definitional_res = custom_impl.validate(&schema, policies, mode);
 
@@ -249,13 +544,35 @@ pub fn run_val_test(
             // TODO(#175): For now, ignore cases where the Lean code returned an error due to
             // an unknown extension function.
             if !err.contains("jsonToExtFun: unknown extension function") {
-                panic!(
-                    "Unexpected error\nPolicies:\n{}\nSchema:\n{:?}\nError: {err}",
-                    &policies, schema
-                );
+                DiffReport::new(
+                    Some(FailureClass::Panic),
+                    rust_res,
+                    err,
+                    "the test engine failed to validate the policies",
+                )
+                .with_policies(policies)
+                .with_schema(schema)
+                .panic();
             }
         }
         TestResult::Success(definitional_res) => {
+            let report = |diff: String| {
+                DiffReport::new(
+                    Some(FailureClass::Validation),
+                    &rust_res,
+                    &definitional_res,
+                    diff,
+                )
+                .with_policies(policies)
+                .with_schema(&schema)
+            };
+            let passed_diff = || {
+                format!(
+                    "validation passed: expected {}, actual {}",
+                    rust_res.validation_passed(),
+                    definitional_res.validation_passed()
+                )
+            };
             if rust_res.validation_passed() {
                 // If `cedar-policy` does not return an error, then the spec should not return an error.
                 // This implies type soundness of the `cedar-policy` validator since type soundness of the
@@ -265,27 +582,24 @@ pub fn run_val_test(
                 // then there are no authorization-time errors modulo some restrictions (C). So (B) ==> (C).
                 // DRT checks that if the `cedar-policy` validator does not return an error (A), then neither
                 // does the spec validator (B). So (A) ==> (B). By transitivity then, (A) ==> (C).
-                assert!(
-                    definitional_res.validation_passed(),
-                    "Mismatch for Policies:\n{}\nSchema:\n{:?}\ncedar-policy response: {:?}\nTest engine response: {:?}\n",
-                    &policies,
-                    schema,
-                    rust_res,
-                    definitional_res,
-                );
+                if !definitional_res.validation_passed() {
+                    report(passed_diff()).panic();
+                }
             } else {
+                // If both validators return an error, then they should agree
+                // on what kind of error it is.
+                if !definitional_res.validation_passed() {
+                    if let Some(diff) = validation_kinds_diff(&rust_res, &definitional_res.errors) {
+                        report(diff.to_string()).panic();
+                    }
+                }
                 // If `cedar-policy` returns an error, then only check the spec response
                 // if the validation comparison mode is `AgreeOnAll`.
                 match custom_impl.validation_comparison_mode() {
                     ValidationComparisonMode::AgreeOnAll => {
-                        assert!(
-                            !definitional_res.validation_passed(),
-                            "Mismatch for Policies:\n{}\nSchema:\n{:?}\ncedar-policy response: {:?}\nTest engine response: {:?}\n",
-                            &policies,
-                            schema,
-                            rust_res,
-                            definitional_res,
-                        );
+                        if definitional_res.validation_passed() {
+                            report(passed_diff()).panic();
+                        }
                     }
                     ValidationComparisonMode::AgreeOnValid => {} // ignore
                 };
@@ -294,88 +608,262 @@ pub fn run_val_test(
     }
 }
 
-#[test]
-fn test_run_auth_test() {
-    use cedar_drt::LeanDefinitionalEngine;
-    use cedar_policy_core::ast::{
-        Entity, EntityUID, PolicyID, RequestSchemaAllPass, RestrictedExpr,
-    };
-    use cedar_policy_core::entities::{NoEntitiesSchema, TCComputation};
-    use smol_str::SmolStr;
+/// Environment variable that makes type-directed targets check that the
+/// policies they generate validate against the generated schema (see
+/// [`check_generated_policies_validate`]). Off by default, so that known gaps
+/// in type-directed generation don't stop a campaign.
+pub const STRICT_VALIDATION_ENV_VAR: &str = "DRT_STRICT_VALIDATION";
 
-    let def_engine = LeanDefinitionalEngine::new();
-    let principal = ast::EntityUIDEntry::Known {
-        euid: std::sync::Arc::new(EntityUID::with_eid_and_type("User", "alice").unwrap()),
-        loc: None,
-    };
-    let action = ast::EntityUIDEntry::Known {
-        euid: std::sync::Arc::new(EntityUID::with_eid_and_type("Action", "view").unwrap()),
-        loc: None,
-    };
-    let resource = ast::EntityUIDEntry::Known {
-        euid: std::sync::Arc::new(EntityUID::with_eid_and_type("Photo", "vacation").unwrap()),
-        loc: None,
-    };
-    let query = ast::Request::new_with_unknowns(
-        principal,
-        action,
-        resource,
-        Some(cedar_policy_core::ast::Context::empty()),
-        None::<&RequestSchemaAllPass>,
+/// If [`STRICT_VALIDATION_ENV_VAR`] is set, check that `cedar-policy` accepts
+/// `policies` when validating against `schema` in `mode`. Policies generated
+/// with `match_types` set should always validate against their own schema, so
+/// a failure here points at a bug in the generator rather than in Cedar.
+/// Panics, listing the validation errors, otherwise.
+pub fn check_generated_policies_validate(
+    schema: &ValidatorSchema,
+    policies: &ast::PolicySet,
+    mode: ValidationMode,
+) {
+    if std::env::var_os(STRICT_VALIDATION_ENV_VAR).is_none() {
+        return;
+    }
+    let res = Validator::new(schema.clone()).validate(policies, mode);
+    assert!(
+        res.validation_passed(),
+        "Type-directed policies failed validation against their own schema\nPolicies:\n{policies}\nSchema:\n{schema:?}\nErrors:\n{}",
+        res.validation_errors()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+}
+
+/// Compare the validators as in [`run_val_test`], and also check that both
+/// validators reject `policies`. Panics otherwise.
+pub fn run_val_rejection_test(
+    custom_impl: &impl CedarTestImplementation,
+    schema: ValidatorSchema,
+    policies: &ast::PolicySet,
+    mode: ValidationMode,
+) {
+    run_val_test(custom_impl, schema.clone(), policies, mode);
+    let rust_res = Validator::new(schema.clone()).validate(policies, mode);
+    assert!(
+        !rust_res.validation_passed(),
+        "cedar-policy should reject Policies:\n{}\nSchema:\n{:?}",
+        &policies,
+        schema
+    );
+    // `run_val_test` has already reported any failure of the custom
+    // implementation that matters
+    if let TestResult::Success(definitional_res) = custom_impl.validate(&schema, policies, mode) {
+        assert!(
+            !definitional_res.validation_passed(),
+            "Mismatch ({}) for Policies:\n{}\nSchema:\n{:?}\ncedar-policy response: {:?}\nTest engine response: {:?}\n",
+            FailureClass::Validation,
+            &policies,
+            schema,
+            rust_res,
+            definitional_res,
+        );
+    }
+}
+
+/// Check that validation in `cedar-policy` is monotonic in the validation
+/// mode: policies that are valid in `Strict` mode must also be valid in
+/// `Permissive` mode. Panics otherwise, reporting the mode that rejected the
+/// policies and its errors.
+pub fn check_validation_mode_monotonic(schema: &ValidatorSchema, policies: &ast::PolicySet) {
+    let validator = Validator::new(schema.clone());
+    let strict_res = validator.validate(policies, ValidationMode::Strict);
+    if !strict_res.validation_passed() {
+        return;
+    }
+    let permissive_res = validator.validate(policies, ValidationMode::Permissive);
+    assert!(
+        permissive_res.validation_passed(),
+        "Mismatch ({}) between validation modes: valid in Strict mode but not in Permissive mode\nPermissive errors: {:?}\nPolicies:\n{}\nSchema:\n{:?}",
+        FailureClass::Validation,
+        permissive_res.validation_errors().collect::<Vec<_>>(),
+        policies,
+        schema
+    );
+}
+
+/// Check that request validation in `cedar-policy` rejects `request` because
+/// the type of its principal or resource (as chosen by `mismatch`) isn't in the
+/// applies-to of its action. Panics otherwise.
+///
+/// The definitional engine doesn't expose request validation on its own, so
+/// the expected error category comes from how `request` was generated, and
+/// `custom_impl` is compared through policy validation instead: as in
+/// [`run_val_rejection_test`], both validators must reject the policy whose
+/// scope is exactly `request`, since the scope matches no action application.
+pub fn check_applies_to_mismatch(
+    custom_impl: &impl CedarTestImplementation,
+    schema: &ValidatorSchema,
+    request: &Request,
+    mismatch: AppliesToMismatch,
+) {
+    let res = ast::Request::new(
+        (request.principal.clone(), None),
+        (request.action.clone(), None),
+        (request.resource.clone(), None),
+        request.context.clone(),
+        Some(schema),
         Extensions::all_available(),
-    )
-    .unwrap();
-    let mut policies = ast::PolicySet::new();
+    );
+    let category = match &res {
+        Err(RequestValidationError::InvalidPrincipalType(_)) => Some(AppliesToMismatch::Principal),
+        Err(RequestValidationError::InvalidResourceType(_)) => Some(AppliesToMismatch::Resource),
+        _ => None,
+    };
+    assert_eq!(
+        category,
+        Some(mismatch),
+        "Unexpected request validation result for request with a {mismatch:?} outside the applies-to\nRequest: {:?}\nSchema:\n{:?}\nResult: {:?}",
+        request,
+        schema,
+        res,
+    );
 
-    let policy_string = r#"
-    permit(principal,action,resource) when
-    {
-        if principal has foo then
-            principal.foo
-        else
-            false
-    };"#;
+    let policies = request_scoped_policy(request, ast::Expr::val(true));
+    run_val_rejection_test(
+        custom_impl,
+        schema.clone(),
+        &policies,
+        ValidationMode::Strict,
+    );
+}
 
-    let static_policy = cedar_policy_core::parser::parse_policy(
-        Some(PolicyID::from_string("policy0")),
-        policy_string,
+/// A policy set of the single policy whose scope is exactly `request`, with
+/// the given `condition`
+fn request_scoped_policy(request: &Request, condition: ast::Expr) -> ast::PolicySet {
+    let policy = ast::StaticPolicy::new(
+        ast::PolicyID::from_string("request_scope"),
+        None,
+        ast::Annotations::new(),
+        ast::Effect::Permit,
+        ast::PrincipalConstraint::is_eq(std::sync::Arc::new(request.principal.clone())),
+        ast::ActionConstraint::is_eq(request.action.clone()),
+        ast::ResourceConstraint::is_eq(std::sync::Arc::new(request.resource.clone())),
+        condition,
     )
-    .expect("Failed to parse");
-    let static_policy: cedar_policy_core::ast::Policy = static_policy.into();
+    .expect("policy should not contain slots");
+    let mut policies = ast::PolicySet::new();
+    policies.add_static(policy).unwrap();
     policies
-        .add(static_policy)
-        .expect("Adding static policy in Policy form should succeed");
-
-    let alice_attributes: std::collections::HashMap<SmolStr, RestrictedExpr> =
-        std::collections::HashMap::from_iter([(
-            "foo".into(),
-            RestrictedExpr::val(cedar_policy_core::ast::Literal::Bool(true)),
-        )]);
-    let entity_alice = Entity::new(
-        EntityUID::with_eid_and_type("User", "alice").unwrap(),
-        alice_attributes,
-        std::collections::HashSet::new(),
-        &Extensions::all_available(),
-    )
-    .unwrap();
-    let entity_view = Entity::new_with_attr_partial_value(
-        EntityUID::with_eid_and_type("Action", "view").unwrap(),
-        std::collections::HashMap::new(),
-        std::collections::HashSet::new(),
+}
+
+/// Check that request validation in `cedar-policy` rejects `request` because
+/// its context doesn't conform to the context type of its action, in the way
+/// described by `violation`. Panics otherwise.
+///
+/// As for [`check_applies_to_mismatch()`], the expected error comes from how
+/// `request` was generated, and `custom_impl` is compared through policy
+/// validation: both validators must reject the policy scoped to exactly
+/// `request` whose condition compares `context` to the request's context,
+/// since in strict mode the two record types have no least upper bound.
+pub fn check_invalid_context(
+    custom_impl: &impl CedarTestImplementation,
+    schema: &ValidatorSchema,
+    request: &Request,
+    violation: ContextViolation,
+) {
+    let res = ast::Request::new(
+        (request.principal.clone(), None),
+        (request.action.clone(), None),
+        (request.resource.clone(), None),
+        request.context.clone(),
+        Some(schema),
+        Extensions::all_available(),
     );
-    let entity_vacation = Entity::new_with_attr_partial_value(
-        EntityUID::with_eid_and_type("Photo", "vacation").unwrap(),
-        std::collections::HashMap::new(),
-        std::collections::HashSet::new(),
+    assert!(
+        matches!(res, Err(RequestValidationError::InvalidContext(_))),
+        "Unexpected request validation result for request with a {violation:?} context\nRequest: {:?}\nSchema:\n{:?}\nResult: {:?}",
+        request,
+        schema,
+        res,
     );
-    let entities = Entities::from_entities(
-        vec![entity_alice, entity_view, entity_vacation],
-        None::<&NoEntitiesSchema>,
-        TCComputation::AssumeAlreadyComputed,
-        Extensions::all_available(),
+
+    let context = ast::Expr::record(
+        request
+            .context
+            .clone()
+            .into_iter()
+            .map(|(attr, val)| (attr, ast::Expr::from(val))),
     )
-    .unwrap();
-    run_auth_test(&def_engine, query, &policies, &entities);
+    .expect("context attributes should be distinct");
+    let policies = request_scoped_policy(
+        request,
+        ast::Expr::is_eq(ast::Expr::var(ast::Var::Context), context),
+    );
+    run_val_rejection_test(
+        custom_impl,
+        schema.clone(),
+        &policies,
+        ValidationMode::Strict,
+    );
+}
+
+/// Check that forbid overrides permit in `response`, the response to a request
+/// against a permit policy and the forbid policy `forbid_id` with the same
+/// scope (see `Schema::arbitrary_overlapping_policies()`). If the condition of
+/// the forbid policy is `true`, it is satisfied whenever the permit policy is,
+/// so the request must be denied. Otherwise, the request may only be allowed
+/// if the forbid policy is not satisfied, e.g., because its condition errors.
+/// Panics otherwise.
+pub fn check_forbid_overrides_permit(
+    response: &Response,
+    forbid_id: &ast::PolicyID,
+    forbid_condition: &ast::Expr,
+) {
+    let forbid_satisfied = response.diagnostics.reason.contains(forbid_id);
+    if forbid_satisfied || forbid_condition == &ast::Expr::val(true) {
+        assert_eq!(
+            response.decision,
+            cedar_policy_core::authorizer::Decision::Deny,
+            "Forbid policy {forbid_id} didn't override permit ({})\nForbid condition: {forbid_condition}\nResponse: {response:?}",
+            FailureClass::Decision
+        );
+    }
+}
+
+/// Parse `context_json`, a JSON record of numbers, as the context of `action`
+/// twice: with `cedar-policy` against `schema`, which declares every
+/// attribute of the context as a `Long`, and with `cedar-policy-core` without
+/// a schema. Check that both accept it exactly when every number is an
+/// integer that fits in an `i64`. Panics otherwise. Returns the parsed
+/// context if it was accepted.
+pub fn check_context_json_numbers(
+    schema: &cedar_policy::Schema,
+    action: &ast::EntityUID,
+    context_json: &str,
+) -> Option<ast::Context> {
+    use cedar_policy_core::entities::{ContextJsonParser, NullContextSchema};
+
+    let expected_ok =
+        serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(context_json)
+            .is_ok_and(|attrs| attrs.values().all(|v| v.as_i64().is_some()));
+    let action: cedar_policy::EntityUid = action
+        .to_string()
+        .parse()
+        .expect("action should be a valid entity UID");
+    let with_schema = cedar_policy::Context::from_json_str(context_json, Some((schema, &action)));
+    assert_eq!(
+        with_schema.is_ok(),
+        expected_ok,
+        "Unexpected result parsing context against schema\nContext: {context_json}\nResult: {with_schema:?}"
+    );
+    let without_schema =
+        ContextJsonParser::new(None::<&NullContextSchema>, Extensions::all_available())
+            .from_json_str(context_json);
+    assert_eq!(
+        without_schema.is_ok(),
+        expected_ok,
+        "Unexpected result parsing context without a schema\nContext: {context_json}\nResult: {without_schema:?}"
+    );
+    without_schema.ok()
 }
 
 /// Randomly drop some of the entities from the list so the generator can produce
@@ -383,26 +871,1716 @@ fn test_run_auth_test() {
 pub fn drop_some_entities(
     entities: Entities,
     u: &mut Unstructured<'_>,
+) -> arbitrary::Result<Entities> {
+    drop_some_entities_bounded(entities, 0, usize::MAX, u)
+}
+
+/// Like `drop_some_entities`, but keeps at least `min_keep` entities (or all of
+/// them, if there are fewer) and drops at most `max_drop`.
+pub fn drop_some_entities_bounded(
+    entities: Entities,
+    min_keep: usize,
+    max_drop: usize,
+    u: &mut Unstructured<'_>,
 ) -> arbitrary::Result<Entities> {
     let should_drop: bool = u.arbitrary()?;
     if should_drop {
-        let mut set: Vec<_> = vec![];
+        let max_drop = max_drop.min(entities.iter().count().saturating_sub(min_keep));
+        let mut dropped = HashSet::new();
         for entity in entities.iter() {
-            match u.int_in_range(0..=9)? {
-                0 => (),
-                _ => {
-                    set.push(entity.clone());
-                }
+            // always consume a choice per entity, so that the bounds don't
+            // change how the rest of the input is interpreted
+            if u.int_in_range(0..=9)? == 0 && dropped.len() < max_drop {
+                dropped.insert(entity.uid().clone());
+            }
+        }
+        Ok(drop_entities(&entities, &dropped))
+    } else {
+        Ok(entities)
+    }
+}
+
+/// Like `drop_some_entities`, but only drops leaf entities, i.e., entities that
+/// are not an ancestor of any other entity. Remaining entities never have a
+/// dropped entity as an ancestor, so the hierarchy stays internally consistent,
+/// while requests and policies can still reference missing entities.
+pub fn drop_some_leaf_entities(
+    entities: Entities,
+    u: &mut Unstructured<'_>,
+) -> arbitrary::Result<Entities> {
+    let should_drop: bool = u.arbitrary()?;
+    if should_drop {
+        let ancestors: HashSet<&ast::EntityUID> = entities
+            .iter()
+            .flat_map(|entity| entity.ancestors())
+            .collect();
+        let mut dropped = HashSet::new();
+        for entity in entities.iter() {
+            if !ancestors.contains(entity.uid()) && u.int_in_range(0..=9)? == 0 {
+                dropped.insert(entity.uid().clone());
             }
         }
-        Ok(Entities::from_entities(
-            set,
+        Ok(drop_entities(&entities, &dropped))
+    } else {
+        Ok(entities)
+    }
+}
+
+/// Remove the entities with UIDs in `dropped` from `entities`. Ancestors are
+/// not recomputed, so remaining entities may still have dropped entities as
+/// ancestors, just like in a store that is missing some entities.
+pub fn drop_entities(entities: &Entities, dropped: &HashSet<ast::EntityUID>) -> Entities {
+    Entities::from_entities(
+        entities
+            .iter()
+            .filter(|entity| !dropped.contains(entity.uid()))
+            .cloned(),
+        None::<&NoEntitiesSchema>,
+        TCComputation::AssumeAlreadyComputed,
+        Extensions::all_available(),
+    )
+    .expect("Should be valid")
+}
+
+/// Serialize `entities` to JSON text, writing the attributes of each entity in
+/// an arbitrary order. The order of keys in a JSON object is not significant,
+/// so parsing the result should give back the same entities.
+pub fn entities_json_with_shuffled_attrs(
+    entities: &Entities,
+    u: &mut Unstructured<'_>,
+) -> arbitrary::Result<String> {
+    let serde_json::Value::Array(values) = entities
+        .to_json_value()
+        .expect("entities should serialize to JSON")
+    else {
+        panic!("expected entities to serialize to a JSON array");
+    };
+    let mut json_entities = Vec::with_capacity(values.len());
+    for value in values {
+        let serde_json::Value::Object(fields) = value else {
+            panic!("expected each entity to serialize to a JSON object");
+        };
+        let mut json_fields = Vec::new();
+        for (key, field) in fields {
+            let field = match (key.as_str(), field) {
+                ("attrs", serde_json::Value::Object(attrs)) => {
+                    let mut attrs: Vec<_> = attrs.into_iter().collect();
+                    // Fisher-Yates shuffle
+                    for i in (1..attrs.len()).rev() {
+                        attrs.swap(i, u.int_in_range(0..=i)?);
+                    }
+                    let attrs: Vec<_> = attrs
+                        .into_iter()
+                        .map(|(k, v)| format!("{}:{v}", serde_json::Value::from(k)))
+                        .collect();
+                    format!("{{{}}}", attrs.join(","))
+                }
+                (_, field) => field.to_string(),
+            };
+            json_fields.push(format!("{}:{field}", serde_json::Value::from(key)));
+        }
+        json_entities.push(format!("{{{}}}", json_fields.join(",")));
+    }
+    Ok(format!("[{}]", json_entities.join(",")))
+}
+
+/// Check whether two entity stores contain the same entities, with the same
+/// attributes and parents, independent of iteration order
+pub fn entities_equivalent(lhs: &Entities, rhs: &Entities) -> bool {
+    fn by_uid(entities: &Entities) -> std::collections::HashMap<String, serde_json::Value> {
+        let serde_json::Value::Array(values) = entities
+            .to_json_value()
+            .expect("entities should serialize to JSON")
+        else {
+            panic!("expected entities to serialize to a JSON array");
+        };
+        values
+            .into_iter()
+            .map(|value| (value["uid"].to_string(), value))
+            .collect()
+    }
+    by_uid(lhs) == by_uid(rhs)
+}
+
+/// Environment variable holding the number of policies that the
+/// `validation-drt-wide` target generates for each schema
+pub const WIDE_POLICY_COUNT_ENV_VAR: &str = "WIDE_POLICY_COUNT";
+
+/// Number of policies that the `validation-drt-wide` target generates for each
+/// schema if [`WIDE_POLICY_COUNT_ENV_VAR`] isn't set
+pub const DEFAULT_WIDE_POLICY_COUNT: usize = 200;
+
+/// The number of policies to generate for each schema in the
+/// `validation-drt-wide` target
+pub fn wide_policy_count() -> usize {
+    std::env::var(WIDE_POLICY_COUNT_ENV_VAR)
+        .ok()
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(DEFAULT_WIDE_POLICY_COUNT)
+}
+
+/// Generate a policy set of up to `count` policies conforming to `schema`, with
+/// IDs `policy0`, `policy1`, and so on. Stops early if the generator fails,
+/// e.g., because `u` ran out of data.
+pub fn arbitrary_wide_policy_set(
+    schema: &Schema,
+    hierarchy: &Hierarchy,
+    count: usize,
+    u: &mut Unstructured<'_>,
+) -> ast::PolicySet {
+    let mut policies = ast::PolicySet::new();
+    for i in 0..count {
+        let Ok(policy) = schema.arbitrary_policy(hierarchy, u) else {
+            break;
+        };
+        let policy: ast::StaticPolicy = policy.into();
+        policies
+            .add_static(policy.new_id(ast::PolicyID::from_string(format!("policy{i}"))))
+            .unwrap();
+    }
+    policies
+}
+
+/// Validate all of `policies` in a single call to each validator, checking
+/// that the validators agree on the aggregate result (see [`run_val_test`]),
+/// and log the throughput of the `cedar-policy` validator on its own
+pub fn run_wide_val_test(
+    custom_impl: &impl CedarTestImplementation,
+    schema: ValidatorSchema,
+    policies: &ast::PolicySet,
+    mode: ValidationMode,
+) {
+    let validator = Validator::new(schema.clone());
+    let (_, dur) = time_function(|| validator.validate(policies, mode));
+    let count = policies.policies().count();
+    info!(
+        "{}{}",
+        WIDE_VALIDATION_MSG,
+        count as f64 / dur.as_secs_f64()
+    );
+    run_val_test(custom_impl, schema, policies, mode);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The entity uid `ty::"eid"`
+    pub(crate) fn uid(ty: &str, eid: &str) -> ast::EntityUID {
+        ast::EntityUID::with_eid_and_type(ty, eid).unwrap()
+    }
+
+    /// A request for `principal` to perform `action` on `resource` with
+    /// `context`, which isn't validated against any schema
+    pub(crate) fn request(
+        principal: ast::EntityUID,
+        action: ast::EntityUID,
+        resource: ast::EntityUID,
+        context: ast::Context,
+    ) -> ast::Request {
+        let entry = |euid| ast::EntityUIDEntry::Known {
+            euid: std::sync::Arc::new(euid),
+            loc: None,
+        };
+        ast::Request::new_with_unknowns(
+            entry(principal),
+            entry(action),
+            entry(resource),
+            Some(context),
+            None::<&ast::RequestSchemaAllPass>,
+            Extensions::none(),
+        )
+        .unwrap()
+    }
+
+    /// The [`request()`] most tests use, for `User::"alice"` to perform
+    /// `Action::"view"` on `Photo::"vacation"` with an empty context
+    pub(crate) fn alice_views_vacation() -> ast::Request {
+        request(
+            uid("User", "alice"),
+            uid("Action", "view"),
+            uid("Photo", "vacation"),
+            ast::Context::empty(),
+        )
+    }
+
+    /// The entity store of `entities`, with the transitive closure of their
+    /// parents computed
+    pub(crate) fn entities(entities: impl IntoIterator<Item = ast::Entity>) -> Entities {
+        Entities::from_entities(
+            entities,
             None::<&NoEntitiesSchema>,
-            TCComputation::AssumeAlreadyComputed,
+            TCComputation::ComputeNow,
             Extensions::all_available(),
         )
-        .expect("Should be valid"))
-    } else {
-        Ok(entities)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_run_auth_test() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::{Entity, PolicyID, RestrictedExpr};
+        use smol_str::SmolStr;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let query = alice_views_vacation();
+        let mut policies = ast::PolicySet::new();
+
+        let policy_string = r#"
+    permit(principal,action,resource) when
+    {
+        if principal has foo then
+            principal.foo
+        else
+            false
+    };"#;
+
+        let static_policy = cedar_policy_core::parser::parse_policy(
+            Some(PolicyID::from_string("policy0")),
+            policy_string,
+        )
+        .expect("Failed to parse");
+        let static_policy: cedar_policy_core::ast::Policy = static_policy.into();
+        policies
+            .add(static_policy)
+            .expect("Adding static policy in Policy form should succeed");
+
+        let alice_attributes: std::collections::HashMap<SmolStr, RestrictedExpr> =
+            std::collections::HashMap::from_iter([(
+                "foo".into(),
+                RestrictedExpr::val(cedar_policy_core::ast::Literal::Bool(true)),
+            )]);
+        let entity_alice = Entity::new(
+            uid("User", "alice"),
+            alice_attributes,
+            std::collections::HashSet::new(),
+            &Extensions::all_available(),
+        )
+        .unwrap();
+        let entity_view = Entity::new_with_attr_partial_value(
+            uid("Action", "view"),
+            std::collections::HashMap::new(),
+            std::collections::HashSet::new(),
+        );
+        let entity_vacation = Entity::new_with_attr_partial_value(
+            uid("Photo", "vacation"),
+            std::collections::HashMap::new(),
+            std::collections::HashSet::new(),
+        );
+        let entities = entities(vec![entity_alice, entity_view, entity_vacation]);
+        run_auth_test(&def_engine, query, &policies, &entities);
+    }
+
+    #[test]
+    fn test_nested_bool_short_circuit() {
+        use cedar_drt::LeanDefinitionalEngine;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let query = alice_views_vacation();
+
+        // nest an erroring subexpression 32 deep in `&&`/`||` operators which
+        // short-circuit it, and then once more in an operator which doesn't
+        let mut short_circuited = r#"(1 + "a") == 2"#.to_string();
+        for i in 0..32 {
+            short_circuited = if i % 2 == 0 {
+                format!("(false && {short_circuited})")
+            } else {
+                format!("(true || {short_circuited})")
+            };
+        }
+        let not_short_circuited = format!("(true && (!{short_circuited} || (1 + \"a\") == 2))");
+        for src in [&short_circuited, &not_short_circuited] {
+            let expr = cedar_policy_core::parser::parse_expr(src).expect("Failed to parse");
+            run_eval_test(&def_engine, query.clone(), &expr, &Entities::new(), true);
+        }
+
+        // the left operand of `||` is false, so both engines evaluate the erroring
+        // right operand
+        let expr =
+            cedar_policy_core::parser::parse_expr(&not_short_circuited).expect("Failed to parse");
+        assert!(
+            Evaluator::new(query.clone(), &Entities::new(), Extensions::all_available())
+                .interpret(&expr, &std::collections::HashMap::default())
+                .is_err()
+        );
+        assert!(matches!(
+            def_engine.interpret(&query, &Entities::new(), &expr, true, None),
+            TestResult::Success(true)
+        ));
+    }
+
+    #[test]
+    fn test_empty_set_vs_absent_attr() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::{Entity, RestrictedExpr};
+        use smol_str::SmolStr;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        // `alice` has `tags`, but it's empty; `bob` doesn't have `tags` at all
+        let alice = uid("User", "alice");
+        let bob = uid("User", "bob");
+        let entities = entities([
+            Entity::new(
+                alice.clone(),
+                std::collections::HashMap::from_iter([(
+                    SmolStr::from("tags"),
+                    RestrictedExpr::set([]),
+                )]),
+                std::collections::HashSet::new(),
+                Extensions::none(),
+            )
+            .unwrap(),
+            Entity::new_with_attr_partial_value(
+                bob.clone(),
+                std::collections::HashMap::new(),
+                std::collections::HashSet::new(),
+            ),
+        ]);
+
+        let exprs = [
+            "principal has tags && principal.tags == []",
+            "!(principal has tags)",
+            "principal.tags == []",
+        ]
+        .map(|src| cedar_policy_core::parser::parse_expr(src).expect("Failed to parse"));
+        for principal in [alice, bob] {
+            let query = request(
+                principal,
+                uid("Action", "view"),
+                uid("Photo", "vacation"),
+                ast::Context::empty(),
+            );
+            for expr in &exprs {
+                run_eval_test(&def_engine, query.clone(), expr, &entities, true);
+            }
+        }
+    }
+
+    #[test]
+    fn test_duplicate_set_elements() {
+        use cedar_drt::LeanDefinitionalEngine;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let query = alice_views_vacation();
+
+        // sets with duplicate elements are the same as sets without them, in
+        // both `cedar-policy` and the test engine
+        let eval = Evaluator::new(query.clone(), &Entities::new(), Extensions::none());
+        for src in [
+            "[1, 1] == [1]",
+            "[1, 2, 1] == [2, 1]",
+            "[1, 1].containsAll([1]) && [1].containsAll([1, 1])",
+            "[1, 1].containsAny([1])",
+            "[1, 1].contains(1)",
+            "[] == [] && {} == {}",
+            "![].contains(1) && [1].containsAll([]) && ![].containsAny([])",
+        ] {
+            let expr = cedar_policy_core::parser::parse_expr(src).expect("Failed to parse");
+            assert_eq!(
+                eval.interpret(&expr, &std::collections::HashMap::default())
+                    .ok(),
+                Some(ast::Value::from(true)),
+                "{src} should be true"
+            );
+            run_eval_test(&def_engine, query.clone(), &expr, &Entities::new(), true);
+        }
+    }
+
+    #[test]
+    fn test_ownership_pattern() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::{Entity, PolicyID, RestrictedExpr};
+        use cedar_policy_core::authorizer::Decision;
+        use smol_str::SmolStr;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let alice = uid("User", "alice");
+        let bob = uid("User", "bob");
+        let doc = uid("Document", "doc");
+        let entities = entities([Entity::new(
+            doc.clone(),
+            std::collections::HashMap::from_iter([(
+                SmolStr::from("owner"),
+                RestrictedExpr::val(alice.clone()),
+            )]),
+            std::collections::HashSet::new(),
+            Extensions::none(),
+        )
+        .unwrap()]);
+        let mut policies = ast::PolicySet::new();
+        let policy = cedar_policy_core::parser::parse_policy(
+            Some(PolicyID::from_string("policy0")),
+            "permit(principal, action, resource) when { resource.owner == principal };",
+        )
+        .expect("Failed to parse");
+        policies.add_static(policy).unwrap();
+
+        for (principal, expected) in [(alice, Decision::Allow), (bob, Decision::Deny)] {
+            let query = request(
+                principal,
+                uid("Action", "view"),
+                doc.clone(),
+                ast::Context::empty(),
+            );
+            let response = run_auth_test(&def_engine, query, &policies, &entities);
+            assert_eq!(response.decision, expected);
+        }
+    }
+
+    #[test]
+    fn test_template_relinks() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::{PolicyID, SlotId};
+        use cedar_policy_core::authorizer::Decision;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let alice = uid("User", "alice");
+        let bob = uid("User", "bob");
+        let template = cedar_policy_core::parser::parse_policy_or_template(
+            Some(PolicyID::from_string("t")),
+            "permit(principal == ?principal, action, resource);",
+        )
+        .expect("Failed to parse");
+        let mut policies = ast::PolicySet::new();
+        policies.add_template(template).unwrap();
+        // `l0` and `l2` have the same slot value, so their scopes overlap
+        for (id, principal) in [("l0", &alice), ("l1", &bob), ("l2", &alice)] {
+            policies
+                .link(
+                    PolicyID::from_string("t"),
+                    PolicyID::from_string(id),
+                    std::collections::HashMap::from_iter([(
+                        SlotId::principal(),
+                        principal.clone(),
+                    )]),
+                )
+                .unwrap();
+        }
+
+        for (principal, expected_reason) in [(alice, vec!["l0", "l2"]), (bob, vec!["l1"])] {
+            let query = request(
+                principal,
+                uid("Action", "view"),
+                uid("Photo", "vacation"),
+                ast::Context::empty(),
+            );
+            let response = run_auth_test(&def_engine, query, &policies, &Entities::new());
+            assert_eq!(response.decision, Decision::Allow);
+            assert_eq!(
+                response.diagnostics.reason,
+                expected_reason
+                    .into_iter()
+                    .map(PolicyID::from_string)
+                    .collect::<HashSet<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_principal_in_slot_template() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::{Entity, PolicyID, SlotId};
+        use cedar_policy_core::authorizer::Decision;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let template = cedar_policy_core::parser::parse_policy_or_template(
+            Some(PolicyID::from_string("t")),
+            "permit(principal in ?principal, action, resource);",
+        )
+        .expect("Failed to parse");
+        let mut policies = ast::PolicySet::new();
+        policies.add_template(template).unwrap();
+        policies
+            .link(
+                PolicyID::from_string("t"),
+                PolicyID::from_string("l0"),
+                std::collections::HashMap::from_iter([(
+                    SlotId::principal(),
+                    uid("Group", "admins"),
+                )]),
+            )
+            .unwrap();
+        // alice is a member of the group, bob isn't
+        let alice = Entity::new(
+            uid("User", "alice"),
+            std::collections::HashMap::new(),
+            std::collections::HashSet::from_iter([uid("Group", "admins")]),
+            Extensions::none(),
+        )
+        .unwrap();
+        let entities = entities([alice, Entity::with_uid(uid("User", "bob"))]);
+
+        for (principal, expected) in [
+            (uid("User", "alice"), Decision::Allow),
+            (uid("User", "bob"), Decision::Deny),
+            // the slot value itself is in scope
+            (uid("Group", "admins"), Decision::Allow),
+        ] {
+            let query = request(
+                principal,
+                uid("Action", "view"),
+                uid("Photo", "vacation"),
+                ast::Context::empty(),
+            );
+            let response = run_auth_test(&def_engine, query, &policies, &entities);
+            assert_eq!(response.decision, expected);
+        }
+    }
+
+    #[test]
+    fn test_time_function_labeled() {
+        let (rust, definitional, times) = time_function_labeled(
+            || 2,
+            |r| {
+                std::thread::sleep(Duration::from_millis(1));
+                r + 1
+            },
+        );
+        assert_eq!((rust, definitional), (2, 3));
+        assert!(times.definitional >= Duration::from_millis(1));
+    }
+
+    #[test]
+    fn test_validation_is_idempotent() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::PolicyID;
+        use cedar_policy_validator::json_schema;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let (fragment, _) = json_schema::Fragment::from_cedarschema_str(
+            r#"
+        entity User { age: Long };
+        entity Photo;
+        action view appliesTo { principal: User, resource: Photo };
+        "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let schema = ValidatorSchema::try_from(fragment).unwrap();
+        // each of these policies has at least one validation error
+        let mut policies = ast::PolicySet::new();
+        for (id, src) in [
+            (
+                "p0",
+                r#"permit(principal, action, resource) when { principal.age == "old" };"#,
+            ),
+            (
+                "p1",
+                "permit(principal, action, resource) when { principal.name == resource };",
+            ),
+            (
+                "p2",
+                r#"forbid(principal == Usr::"alice", action, resource) when { 1 + true };"#,
+            ),
+        ] {
+            let policy =
+                cedar_policy_core::parser::parse_policy(Some(PolicyID::from_string(id)), src)
+                    .expect("Failed to parse");
+            policies.add_static(policy).unwrap();
+        }
+        let validator = Validator::new(schema.clone());
+        let first = validator.validate(&policies, ValidationMode::Strict);
+        assert!(first.validation_errors().count() >= 3);
+        let second = validator.validate(&policies, ValidationMode::Strict);
+        assert_eq!(
+            validation_result_summary(&first),
+            validation_result_summary(&second)
+        );
+        run_val_test(&def_engine, schema, &policies, ValidationMode::Strict);
+    }
+
+    #[test]
+    fn test_union_principal_attr_access() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::{Entity, PolicyID, RestrictedExpr};
+        use cedar_policy_core::authorizer::Decision;
+        use cedar_policy_validator::json_schema;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let (fragment, _) = json_schema::Fragment::from_cedarschema_str(
+            r#"
+        entity User { name: String };
+        entity Robot;
+        entity Photo;
+        action view appliesTo { principal: [User, Robot], resource: Photo };
+        "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let schema = ValidatorSchema::try_from(fragment).unwrap();
+        // `name` is only declared on `User`, but the scope also allows `Robot`
+        let mut policies = ast::PolicySet::new();
+        let policy = cedar_policy_core::parser::parse_policy(
+        Some(PolicyID::from_string("policy0")),
+        r#"permit(principal, action == Action::"view", resource) when { principal.name == "alice" };"#,
+    )
+    .expect("Failed to parse");
+        policies.add_static(policy).unwrap();
+        run_val_rejection_test(&def_engine, schema, &policies, ValidationMode::Strict);
+
+        let entities = entities([
+            Entity::new(
+                uid("User", "alice"),
+                std::collections::HashMap::from_iter([(
+                    "name".into(),
+                    RestrictedExpr::val("alice"),
+                )]),
+                std::collections::HashSet::new(),
+                Extensions::none(),
+            )
+            .unwrap(),
+            Entity::with_uid(uid("Robot", "r2d2")),
+        ]);
+        // both requests conform to the schema; the `Robot` one errors at runtime
+        for (principal, expected) in [
+            (uid("User", "alice"), Decision::Allow),
+            (uid("Robot", "r2d2"), Decision::Deny),
+        ] {
+            let query = request(
+                principal,
+                uid("Action", "view"),
+                uid("Photo", "vacation"),
+                ast::Context::empty(),
+            );
+            let response = run_auth_test(&def_engine, query, &policies, &entities);
+            assert_eq!(response.decision, expected);
+        }
+    }
+
+    #[test]
+    fn test_incompatible_ite_branches() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::PolicyID;
+        use cedar_policy_validator::json_schema;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let (fragment, _) = json_schema::Fragment::from_cedarschema_str(
+            r#"
+        entity User;
+        action view appliesTo { principal: User, resource: User };
+        "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let schema = ValidatorSchema::try_from(fragment).unwrap();
+        let query = request(
+            uid("User", "alice"),
+            uid("Action", "view"),
+            uid("User", "bob"),
+            ast::Context::empty(),
+        );
+        let entities = Entities::new();
+        let conditions = [
+            r#"(if 1 < 2 then ip("1.2.3.4") else 3) == ip("1.2.3.4")"#,
+            r#"(if 2 < 1 then ip("1.2.3.4") else 3) == ip("1.2.3.4")"#,
+            r#"(if 1 < 2 then "a" else decimal("1.5")) == decimal("1.5")"#,
+            r#"(if 1 < 2 then true else ip("::1")) == ip("::1")"#,
+        ];
+        for condition in conditions {
+            let mut policies = ast::PolicySet::new();
+            let policy = cedar_policy_core::parser::parse_policy(
+                Some(PolicyID::from_string("policy0")),
+                &format!("permit(principal, action, resource) when {{ {condition} }};"),
+            )
+            .expect("Failed to parse");
+            policies.add_static(policy).unwrap();
+            run_val_rejection_test(
+                &def_engine,
+                schema.clone(),
+                &policies,
+                ValidationMode::Strict,
+            );
+            run_auth_test(&def_engine, query.clone(), &policies, &entities);
+        }
+    }
+
+    #[test]
+    fn test_wide_policy_set_validation() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_generators::settings::ABACSettings;
+        use cedar_policy_validator::json_schema;
+        use rand::RngCore;
+
+        const SETTINGS: ABACSettings = ABACSettings::builder().build();
+        const COUNT: usize = 50;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let (fragment, _) = json_schema::Fragment::from_cedarschema_str(
+            r#"
+        entity Group;
+        entity User in [Group] { age: Long, name: String, manager?: User };
+        entity Photo { owner: User, tags: Set<String> };
+        action view, edit appliesTo { principal: User, resource: Photo, context: { ip: ipaddr } };
+        "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let mut bytes = vec![0; 1 << 20];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let mut u = Unstructured::new(&bytes);
+        let schema = Schema::from_raw_schemafrag(fragment, SETTINGS, &mut u).unwrap();
+        let hierarchy = schema.arbitrary_hierarchy(&mut u).unwrap();
+        let policies = arbitrary_wide_policy_set(&schema, &hierarchy, COUNT, &mut u);
+        assert_eq!(policies.policies().count(), COUNT);
+        let vschema = ValidatorSchema::try_from(schema).unwrap();
+        run_wide_val_test(&def_engine, vschema, &policies, ValidationMode::Strict);
+    }
+
+    #[test]
+    fn test_check_generated_policies_validate() {
+        use cedar_policy_core::ast::PolicyID;
+        use cedar_policy_validator::json_schema;
+
+        let (fragment, _) = json_schema::Fragment::from_cedarschema_str(
+            r#"
+        entity User;
+        action view appliesTo { principal: User, resource: User };
+        "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let schema = ValidatorSchema::try_from(fragment).unwrap();
+        let policy_set = |src| {
+            let mut policies = ast::PolicySet::new();
+            let policy = cedar_policy_core::parser::parse_policy(
+                Some(PolicyID::from_string("policy0")),
+                src,
+            )
+            .expect("Failed to parse");
+            policies.add_static(policy).unwrap();
+            policies
+        };
+        let valid = policy_set(r#"permit(principal == User::"alice", action, resource);"#);
+        let invalid = policy_set(r#"permit(principal == Usr::"alice", action, resource);"#);
+
+        // without the environment variable, nothing is checked
+        if std::env::var_os(STRICT_VALIDATION_ENV_VAR).is_none() {
+            check_generated_policies_validate(&schema, &invalid, ValidationMode::Strict);
+        }
+        std::env::set_var(STRICT_VALIDATION_ENV_VAR, "1");
+        check_generated_policies_validate(&schema, &valid, ValidationMode::Strict);
+        assert!(
+            panics(|| check_generated_policies_validate(&schema, &invalid, ValidationMode::Strict)),
+            "invalid policy should fail the strict check"
+        );
+    }
+
+    #[test]
+    fn test_context_arith_cmp() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::RestrictedExpr;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let exprs = [
+            "context.count + 1 > context.limit",
+            "context.count - context.limit <= context.limit",
+            "context.count * 2 == context.limit",
+        ]
+        .map(|src| cedar_policy_core::parser::parse_expr(src).expect("Failed to parse"));
+        // the last two contexts make the arithmetic overflow
+        for (count, limit) in [(1, 10), (i64::MAX, 0), (i64::MIN, 1)] {
+            let context = ast::Context::from_pairs(
+                [
+                    ("count".into(), RestrictedExpr::val(count)),
+                    ("limit".into(), RestrictedExpr::val(limit)),
+                ],
+                Extensions::none(),
+            )
+            .unwrap();
+            let query = request(
+                uid("User", "alice"),
+                uid("Action", "view"),
+                uid("Photo", "vacation"),
+                context,
+            );
+            for expr in &exprs {
+                run_eval_test(&def_engine, query.clone(), expr, &Entities::new(), true);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lean_timeout() {
+        use cedar_drt::{LeanDefinitionalEngine, LEAN_TIMEOUT_MSG};
+        use cedar_policy_core::authorizer::Decision;
+
+        let policies =
+            cedar_policy_core::parser::parse_policyset("permit(principal, action, resource);")
+                .unwrap();
+        let query = alice_views_vacation();
+        // engines with a timeout share one worker thread, which keeps answering
+        // calls from engine after engine
+        for _ in 0..3 {
+            let engine = LeanDefinitionalEngine::new_with_timeout(Duration::from_secs(60));
+            let response = run_auth_test(&engine, query.clone(), &policies, &Entities::new());
+            assert_eq!(response.decision, Decision::Allow);
+        }
+        // with no time at all, the call gives up instead of waiting for Lean
+        let engine = LeanDefinitionalEngine::new_with_timeout(Duration::ZERO);
+        match engine.is_authorized(&query, &policies, &Entities::new()) {
+            TestResult::Failure(err) => assert!(err.starts_with(LEAN_TIMEOUT_MSG), "{err}"),
+            TestResult::Success(_) => panic!("a call with a zero timeout should time out"),
+        }
+        // the timed-out call still occupies the worker, which is shared by the
+        // whole process. Wait until a call goes through again, so that the
+        // worker is idle once this test is done.
+        let engine = LeanDefinitionalEngine::new_with_timeout(Duration::from_secs(60));
+        while let TestResult::Failure(err) =
+            engine.is_authorized(&query, &policies, &Entities::new())
+        {
+            assert!(err.starts_with(LEAN_TIMEOUT_MSG), "{err}");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_overflow_boundary_arith() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_generators::abac::OVERFLOW_BOUNDARY_INTS;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let query = alice_views_vacation();
+        let mut overflows = 0;
+        for &a in OVERFLOW_BOUNDARY_INTS {
+            for &b in OVERFLOW_BOUNDARY_INTS {
+                for (expr, result) in [
+                    (
+                        ast::Expr::add(ast::Expr::val(a), ast::Expr::val(b)),
+                        a.checked_add(b),
+                    ),
+                    (
+                        ast::Expr::sub(ast::Expr::val(a), ast::Expr::val(b)),
+                        a.checked_sub(b),
+                    ),
+                    (
+                        ast::Expr::mul(ast::Expr::val(a), ast::Expr::val(b)),
+                        a.checked_mul(b),
+                    ),
+                ] {
+                    if result.is_none() {
+                        overflows += 1;
+                    }
+                    // the engines must agree on which of these overflow, and on
+                    // the kind of error
+                    run_eval_test_with_error_categories(
+                        &def_engine,
+                        query.clone(),
+                        &expr,
+                        &Entities::new(),
+                        true,
+                    );
+                }
+            }
+        }
+        assert!(overflows > 0, "no pair of boundary ints overflows");
+    }
+
+    #[test]
+    fn test_set_ops_on_empty_and_disjoint_sets() {
+        use cedar_drt::LeanDefinitionalEngine;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let query = alice_views_vacation();
+        let set = |elements: &[i64]| ast::Expr::set(elements.iter().map(|i| ast::Expr::val(*i)));
+        for (expr, expected) in [
+            (ast::Expr::contains_all(set(&[]), set(&[])), true),
+            (ast::Expr::contains_any(set(&[]), set(&[])), false),
+            (ast::Expr::contains_all(set(&[1]), set(&[])), true),
+            (ast::Expr::contains_any(set(&[1]), set(&[])), false),
+            (ast::Expr::contains_all(set(&[]), set(&[1])), false),
+            (ast::Expr::contains_any(set(&[]), set(&[1])), false),
+            // overlapping
+            (ast::Expr::contains_all(set(&[1, 2]), set(&[2, 3])), false),
+            (ast::Expr::contains_any(set(&[1, 2]), set(&[2, 3])), true),
+            (ast::Expr::contains_all(set(&[1, 2]), set(&[2, 2])), true),
+            // disjoint
+            (ast::Expr::contains_all(set(&[1]), set(&[2])), false),
+            (ast::Expr::contains_any(set(&[1]), set(&[2])), false),
+        ] {
+            let entities = Entities::new();
+            let eval = Evaluator::new(query.clone(), &entities, Extensions::none());
+            assert_eq!(
+                eval.interpret(&expr, &std::collections::HashMap::default())
+                    .ok(),
+                Some(ast::Value::from(expected)),
+                "unexpected result for {expr}"
+            );
+            run_eval_test_with_error_categories(&def_engine, query.clone(), &expr, &entities, true);
+        }
+    }
+
+    #[test]
+    fn test_nested_ext_literals() {
+        use cedar_drt::LeanDefinitionalEngine;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let query = alice_views_vacation();
+        let exprs = [
+        r#"[{addr: ip("1.2.3.4")}] == [{addr: ip("1.2.3.4")}]"#,
+        r#"[{addr: ip("1.2.3.4")}] == [{addr: ip("1.2.3.4/32")}]"#,
+        r#"[{addr: ip("::1"), limit: decimal("1.5")}, {limit: decimal("-0.25")}] == [{limit: decimal("-0.25")}, {limit: decimal("1.5"), addr: ip("::1")}]"#,
+        r#"[{addr: ip("10.0.0.1"), limit: decimal("1.23")}].contains({limit: decimal("1.23"), addr: ip("10.0.0.1")})"#,
+        r#"[{limit: decimal("1.0")}].contains({limit: decimal("1.00")})"#,
+        r#"[{addr: ip("192.168.0.1")}].contains({addr: ip("192.168.0.1/24")})"#,
+        // invalid literals error out inside the composite
+        r#"[{addr: ip("1.2.3")}].contains({addr: ip("1.2.3.4")})"#,
+        r#"[{limit: decimal("1.23456")}] == [{limit: decimal("1.23456")}]"#,
+    ]
+    .map(|src| cedar_policy_core::parser::parse_expr(src).expect("Failed to parse"));
+        for expr in &exprs {
+            run_eval_test(&def_engine, query.clone(), expr, &Entities::new(), true);
+        }
+    }
+
+    #[test]
+    fn test_error_categories() {
+        use cedar_drt::LeanDefinitionalEngine;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let query = alice_views_vacation();
+        let exprs = [
+            (r#"1 + "one""#, ErrorCategory::TypeError),
+            (r#"{a: 1}.b"#, ErrorCategory::AttrDoesNotExist),
+            (r#"principal.name"#, ErrorCategory::EntityDoesNotExist),
+            (
+                r#"9223372036854775807 + 1"#,
+                ErrorCategory::ArithBoundsError,
+            ),
+            (r#"decimal("1.23456")"#, ErrorCategory::ExtensionError),
+        ];
+        for (src, category) in exprs {
+            let expr = cedar_policy_core::parser::parse_expr(src).expect("Failed to parse");
+            let err = Evaluator::new(query.clone(), &Entities::new(), Extensions::all_available())
+                .interpret(&expr, &std::collections::HashMap::default())
+                .expect_err("expression should error");
+            assert_eq!(ErrorCategory::from_rust(&err), Some(category), "{src}");
+            assert_eq!(ErrorCategory::from_lean(category.as_str()), Some(category));
+            run_eval_test_with_error_categories(
+                &def_engine,
+                query.clone(),
+                &expr,
+                &Entities::new(),
+                true,
+            );
+        }
+    }
+
+    #[test]
+    fn test_context_entity_eq() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::{PolicyID, RestrictedExpr};
+        use cedar_policy_core::authorizer::Decision;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let mut policies = ast::PolicySet::new();
+        let policy = cedar_policy_core::parser::parse_policy(
+            Some(PolicyID::from_string("policy0")),
+            "permit(principal, action, resource) when { context.owner == context.viewer };",
+        )
+        .expect("Failed to parse");
+        policies.add_static(policy).unwrap();
+        let expr =
+            cedar_policy_core::parser::parse_expr("context.owner == context.viewer").unwrap();
+        for (viewer, expected) in [
+            // both attributes reference the same entity
+            (uid("User", "alice"), Decision::Allow),
+            (uid("User", "bob"), Decision::Deny),
+            // same eid, different entity type
+            (uid("Admin", "alice"), Decision::Deny),
+        ] {
+            let context = ast::Context::from_pairs(
+                [
+                    ("owner".into(), RestrictedExpr::val(uid("User", "alice"))),
+                    ("viewer".into(), RestrictedExpr::val(viewer)),
+                ],
+                Extensions::none(),
+            )
+            .unwrap();
+            let query = request(
+                uid("User", "alice"),
+                uid("Action", "view"),
+                uid("Photo", "vacation"),
+                context,
+            );
+            run_eval_test(&def_engine, query.clone(), &expr, &Entities::new(), true);
+            let response = run_auth_test(&def_engine, query, &policies, &Entities::new());
+            assert_eq!(response.decision, expected);
+        }
+    }
+
+    #[test]
+    fn test_is_operator() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::Entity;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let alice = Entity::new(
+            uid("User", "alice"),
+            std::collections::HashMap::new(),
+            std::collections::HashSet::from_iter([uid("Group", "admins")]),
+            Extensions::none(),
+        )
+        .unwrap();
+        let entities = entities([alice]);
+        let query = alice_views_vacation();
+        let exprs = [
+            "principal is User",
+            "principal is Group",
+            "resource is Photo",
+            r#"principal is User in Group::"admins""#,
+            r#"principal is User in Group::"guests""#,
+            r#"principal is Group in Group::"admins""#,
+            r#"principal is User in [Group::"guests", Group::"admins"]"#,
+            // operands that aren't entities are type errors
+            "1 is User",
+            r#"principal is User in "admins""#,
+        ]
+        .map(|src| cedar_policy_core::parser::parse_expr(src).expect("Failed to parse"));
+        for expr in &exprs {
+            run_eval_test(&def_engine, query.clone(), expr, &entities, true);
+        }
+    }
+
+    #[test]
+    fn test_action_attrs() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::{Entity, PolicyID, RestrictedExpr};
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let entities = entities([
+            Entity::new(
+                uid("Action", "view"),
+                std::collections::HashMap::from_iter([(
+                    "riskLevel".into(),
+                    RestrictedExpr::val(5),
+                )]),
+                std::collections::HashSet::from_iter([uid("Action", "read")]),
+                Extensions::none(),
+            )
+            .unwrap(),
+            Entity::with_uid(uid("Action", "read")),
+            // `edit` has no attributes, and `delete` isn't in the store at all
+            Entity::with_uid(uid("Action", "edit")),
+        ]);
+        let mut policies = ast::PolicySet::new();
+        for (i, src) in [
+        r#"permit(principal, action, resource) when { action.riskLevel > 3 };"#,
+        r#"permit(principal, action in Action::"read", resource) when { action has riskLevel && action.riskLevel <= 3 };"#,
+        r#"forbid(principal, action, resource) when { action has sensitivity };"#,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        policies
+            .add_static(
+                cedar_policy_core::parser::parse_policy(
+                    Some(PolicyID::from_string(format!("policy{i}"))),
+                    src,
+                )
+                .expect("Failed to parse"),
+            )
+            .unwrap();
+    }
+        for action in ["view", "edit", "delete"] {
+            let query = request(
+                uid("User", "alice"),
+                uid("Action", action),
+                uid("Photo", "vacation"),
+                ast::Context::empty(),
+            );
+            run_auth_test(&def_engine, query, &policies, &entities);
+        }
+    }
+
+    #[test]
+    fn test_context_json_numbers() {
+        use cedar_drt::LeanDefinitionalEngine;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let (schema, _) = cedar_policy::Schema::from_cedarschema_str(
+            "entity E; action a appliesTo { principal: E, resource: E, context: { n: Long } };",
+        )
+        .unwrap();
+        let policies = cedar_policy_core::parser::parse_policyset(
+            r#"permit(principal, action, resource) when { context.n + 1 > context.n };
+        permit(principal, action, resource) when { context.n - 1 < context.n };"#,
+        )
+        .unwrap();
+        for (n, accepted) in [
+            ("0", true),
+            ("-0", false),
+            ("9223372036854775807", true),
+            ("-9223372036854775808", true),
+            ("9223372036854775808", false),
+            ("-9223372036854775809", false),
+            ("1.5", false),
+            ("3.0", false),
+            ("1e3", false),
+        ] {
+            let context_json = format!(r#"{{"n": {n}}}"#);
+            let context = check_context_json_numbers(&schema, &uid("Action", "a"), &context_json);
+            assert_eq!(context.is_some(), accepted, "{context_json}");
+            if let Some(context) = context {
+                let query = request(uid("E", "p"), uid("Action", "a"), uid("E", "r"), context);
+                run_auth_test(&def_engine, query, &policies, &Entities::new());
+            }
+        }
+    }
+
+    #[test]
+    fn test_partial_auth() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::{Effect, Expr, Policy, PolicyID, Unknown};
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let query = alice_views_vacation();
+        let entities = Entities::new();
+        let policy_set = |conditions: Vec<(Effect, Expr)>| {
+            let mut policies = ast::PolicySet::new();
+            for (i, (effect, when)) in conditions.into_iter().enumerate() {
+                policies
+                    .add(Policy::from_when_clause(
+                        effect,
+                        when,
+                        PolicyID::from_string(format!("policy{i}")),
+                        None,
+                    ))
+                    .unwrap();
+            }
+            policies
+        };
+        let unknown = |name: &str| Expr::unknown(Unknown::new_untyped(name));
+
+        // residuals
+        let residual = policy_set(vec![
+            (Effect::Permit, Expr::is_eq(unknown("a"), Expr::val(1))),
+            (
+                Effect::Forbid,
+                Expr::and(Expr::val(true), Expr::greater(unknown("b"), Expr::val(2))),
+            ),
+            (Effect::Permit, Expr::val(false)),
+        ]);
+        run_partial_auth_test(&def_engine, query.clone(), &residual, &entities);
+
+        // fully concrete policies, including one that errors
+        let concrete = policy_set(vec![
+            (Effect::Permit, Expr::val(true)),
+            (Effect::Forbid, Expr::add(Expr::val(1), Expr::val(false))),
+            (Effect::Forbid, Expr::val(false)),
+        ]);
+        run_partial_auth_test(&def_engine, query, &concrete, &entities);
+    }
+
+    #[test]
+    fn test_multi_type_membership() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::{Entity, EntityUID, PolicyID};
+        use cedar_policy_core::authorizer::Decision;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let entity = |uid, parents: &[EntityUID]| {
+            Entity::new(
+                uid,
+                std::collections::HashMap::new(),
+                parents.iter().cloned().collect(),
+                Extensions::none(),
+            )
+            .unwrap()
+        };
+        // alice is in parents of two different types; `GroupB::"y"` is in turn in
+        // `GroupC::"z"`
+        let entities = entities([
+            entity(
+                uid("User", "alice"),
+                &[uid("GroupA", "x"), uid("GroupB", "y")],
+            ),
+            entity(uid("GroupB", "y"), &[uid("GroupC", "z")]),
+        ]);
+        let query = request(
+            uid("User", "alice"),
+            uid("Action", "view"),
+            uid("GroupA", "x"),
+            ast::Context::empty(),
+        );
+        for (parent, expected) in [
+            (r#"GroupA::"x""#, Decision::Allow),
+            (r#"GroupB::"y""#, Decision::Allow),
+            (r#"GroupC::"z""#, Decision::Allow),
+            (r#"[GroupA::"x", GroupB::"y"]"#, Decision::Allow),
+            // same eids, other types
+            (r#"GroupA::"y""#, Decision::Deny),
+            (r#"GroupB::"x""#, Decision::Deny),
+        ] {
+            let expr = cedar_policy_core::parser::parse_expr(&format!("principal in {parent}"))
+                .expect("Failed to parse");
+            run_eval_test(&def_engine, query.clone(), &expr, &entities, true);
+            let mut policies = ast::PolicySet::new();
+            let policy = cedar_policy_core::parser::parse_policy(
+                Some(PolicyID::from_string("policy0")),
+                &format!("permit(principal, action, resource) when {{ principal in {parent} }};"),
+            )
+            .expect("Failed to parse");
+            policies.add_static(policy).unwrap();
+            let response = run_auth_test(&def_engine, query.clone(), &policies, &entities);
+            assert_eq!(response.decision, expected);
+        }
+    }
+
+    #[test]
+    fn test_bool_operator_matrix() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::{Entity, RestrictedExpr};
+        use cedar_policy_generators::expr::{
+            bool_operator_combinations, BOOL_OPERATOR_COMBINATIONS,
+        };
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let operand = |src| cedar_policy_core::parser::parse_expr(src).expect("Failed to parse");
+        let combinations = bool_operator_combinations([
+            operand("context.trusted"),
+            operand("principal.admin"),
+            operand("resource.public"),
+        ]);
+        assert_eq!(combinations.len(), BOOL_OPERATOR_COMBINATIONS);
+        // `resource.draft` is absent, so reading it errors unless short-circuited
+        let erroring_combinations = bool_operator_combinations([
+            operand("context.trusted"),
+            operand("principal.admin"),
+            operand("resource.draft"),
+        ]);
+        let mut policies = ast::PolicySet::new();
+        for (i, condition) in combinations.iter().enumerate() {
+            let policy = cedar_policy_core::parser::parse_policy(
+                Some(ast::PolicyID::from_string(format!("combination{i}"))),
+                &format!("permit(principal, action, resource) when {{ {condition} }};"),
+            )
+            .expect("Failed to parse");
+            policies.add_static(policy).unwrap();
+        }
+        for assignment in 0..8 {
+            let [trusted, admin, public] = [0, 1, 2].map(|i| assignment & (1 << i) != 0);
+            let entity = |uid, attr: &str, value| {
+                Entity::new(
+                    uid,
+                    std::collections::HashMap::from_iter([(
+                        attr.into(),
+                        RestrictedExpr::val(value),
+                    )]),
+                    std::collections::HashSet::new(),
+                    Extensions::none(),
+                )
+                .unwrap()
+            };
+            let entities = entities([
+                entity(uid("User", "alice"), "admin", admin),
+                entity(uid("Doc", "report"), "public", public),
+            ]);
+            let context = ast::Context::from_pairs(
+                [("trusted".into(), RestrictedExpr::val(trusted))],
+                Extensions::none(),
+            )
+            .unwrap();
+            let query = request(
+                uid("User", "alice"),
+                uid("Action", "view"),
+                uid("Doc", "report"),
+                context,
+            );
+            for expr in combinations.iter().chain(&erroring_combinations) {
+                run_eval_test(&def_engine, query.clone(), expr, &entities, true);
+            }
+            run_auth_test(&def_engine, query, &policies, &entities);
+        }
+    }
+
+    #[test]
+    fn test_entity_typed_context_attr() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::{Entity, PolicyID, RestrictedExpr};
+        use cedar_policy_core::authorizer::Decision;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let mut policies = ast::PolicySet::new();
+        let policy = cedar_policy_core::parser::parse_policy(
+            Some(PolicyID::from_string("policy0")),
+            "permit(principal, action, resource) when { context.device.trusted };",
+        )
+        .expect("Failed to parse");
+        policies.add_static(policy).unwrap();
+        let laptop = Entity::new(
+            uid("Device", "laptop"),
+            std::collections::HashMap::from_iter([("trusted".into(), RestrictedExpr::val(true))]),
+            std::collections::HashSet::new(),
+            Extensions::none(),
+        )
+        .unwrap();
+        let context = ast::Context::from_pairs(
+            [(
+                "device".into(),
+                RestrictedExpr::val(uid("Device", "laptop")),
+            )],
+            Extensions::none(),
+        )
+        .unwrap();
+        let query = request(
+            uid("User", "alice"),
+            uid("Action", "view"),
+            uid("Photo", "vacation"),
+            context,
+        );
+        // with the referenced entity present, and with it dropped from the store
+        for (store, expected) in [(vec![laptop], Decision::Allow), (vec![], Decision::Deny)] {
+            let entities = entities(store);
+            let response = run_auth_test(&def_engine, query.clone(), &policies, &entities);
+            assert_eq!(response.decision, expected);
+        }
+    }
+
+    #[test]
+    fn test_undeclared_action_in_action_list() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::PolicyID;
+        use cedar_policy_validator::{json_schema, ValidationError};
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let (fragment, _) = json_schema::Fragment::from_cedarschema_str(
+            r#"
+        entity User;
+        entity Photo;
+        action view, edit appliesTo { principal: User, resource: Photo };
+        "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let schema = ValidatorSchema::try_from(fragment).unwrap();
+        let mut policies = ast::PolicySet::new();
+        let policy = cedar_policy_core::parser::parse_policy(
+        Some(PolicyID::from_string("policy0")),
+        r#"permit(principal, action in [Action::"view", Action::"delete", Action::"edit"], resource);"#,
+    )
+    .expect("Failed to parse");
+        policies.add_static(policy).unwrap();
+
+        let rust_res = Validator::new(schema.clone()).validate(&policies, ValidationMode::Strict);
+        assert!(!rust_res.validation_passed());
+        assert!(rust_res
+            .validation_errors()
+            .all(|e| matches!(e, ValidationError::UnrecognizedActionId(_))));
+        match def_engine.validate(&schema, &policies, ValidationMode::Strict) {
+            TestResult::Success(def_res) => assert!(!def_res.validation_passed()),
+            TestResult::Failure(err) => panic!("Unexpected error: {err}"),
+        }
+        run_val_test(&def_engine, schema, &policies, ValidationMode::Strict);
+    }
+
+    #[test]
+    fn test_entity_attr_order_independence() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::PolicyID;
+        use cedar_policy_core::entities::EntityJsonParser;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let parse = |json: &str| {
+            EntityJsonParser::new(
+                None::<&NoEntitiesSchema>,
+                Extensions::all_available(),
+                TCComputation::AssumeAlreadyComputed,
+            )
+            .from_json_str(json)
+            .unwrap()
+        };
+        let entities = parse(
+            r#"[{ "uid": { "type": "User", "id": "alice" },
+              "attrs": { "a": 1, "b": "two", "c": [true], "d": { "x": 1, "y": 2 } },
+              "parents": [] }]"#,
+        );
+        let reordered = parse(
+            r#"[{ "parents": [],
+              "attrs": { "d": { "y": 2, "x": 1 }, "c": [true], "a": 1, "b": "two" },
+              "uid": { "id": "alice", "type": "User" } }]"#,
+        );
+        assert!(entities_equivalent(&entities, &reordered));
+        // shuffling with any bytes gives back an equivalent store
+        for seed in 0..16u8 {
+            let bytes: Vec<u8> = (0..64)
+                .map(|i| seed.wrapping_mul(31).wrapping_add(i))
+                .collect();
+            let json = entities_json_with_shuffled_attrs(&entities, &mut Unstructured::new(&bytes))
+                .unwrap();
+            assert!(entities_equivalent(&entities, &parse(&json)));
+        }
+
+        let mut policies = ast::PolicySet::new();
+        let policy = cedar_policy_core::parser::parse_policy(
+        Some(PolicyID::from_string("policy0")),
+        r#"permit(principal, action, resource) when { principal.a == 1 && principal.d.y == 2 };"#,
+    )
+    .expect("Failed to parse");
+        policies.add_static(policy).unwrap();
+        let query = alice_views_vacation();
+        let expected = run_auth_test(&def_engine, query.clone(), &policies, &entities);
+        let actual = run_auth_test(&def_engine, query, &policies, &reordered);
+        assert_eq!(expected.decision, actual.decision);
+        assert_eq!(expected.diagnostics.reason, actual.diagnostics.reason);
+    }
+
+    #[test]
+    fn test_common_type_shared_by_entity_and_context() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::{Entity, EntityUID, PolicyID, RestrictedExpr};
+        use cedar_policy_core::authorizer::Decision;
+        use cedar_policy_validator::json_schema;
+        use smol_str::SmolStr;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let (fragment, _) = json_schema::Fragment::from_cedarschema_str(
+            r#"
+        type Address = { street: String, zip: Long };
+        entity User { home: Address };
+        entity Photo;
+        action view appliesTo { principal: User, resource: Photo, context: { location: Address } };
+        "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let schema = ValidatorSchema::try_from(fragment).unwrap();
+        let mut policies = ast::PolicySet::new();
+        let policy = cedar_policy_core::parser::parse_policy(
+        Some(PolicyID::from_string("policy0")),
+        r#"permit(principal is User, action == Action::"view", resource) when { principal.home == context.location && context.location.zip > 0 };"#,
+    )
+    .expect("Failed to parse");
+        policies.add_static(policy).unwrap();
+        run_val_test(
+            &def_engine,
+            schema.clone(),
+            &policies,
+            ValidationMode::Strict,
+        );
+        assert!(Validator::new(schema.clone())
+            .validate(&policies, ValidationMode::Strict)
+            .validation_passed());
+
+        let address = |street: &str, zip: i64| {
+            RestrictedExpr::record([
+                (SmolStr::from("street"), RestrictedExpr::val(street)),
+                (SmolStr::from("zip"), RestrictedExpr::val(zip)),
+            ])
+            .unwrap()
+        };
+        let alice = uid("User", "alice");
+        let entities = entities([Entity::new(
+            alice.clone(),
+            std::collections::HashMap::from_iter([(
+                SmolStr::from("home"),
+                address("Main St", 12345),
+            )]),
+            std::collections::HashSet::new(),
+            Extensions::none(),
+        )
+        .unwrap()]);
+        for (location, expected) in [
+            (address("Main St", 12345), Decision::Allow),
+            (address("Elm St", 12345), Decision::Deny),
+        ] {
+            let euid = |euid: EntityUID| ast::EntityUIDEntry::Known {
+                euid: std::sync::Arc::new(euid),
+                loc: None,
+            };
+            let context = ast::Context::from_pairs(
+                [(SmolStr::from("location"), location)],
+                Extensions::none(),
+            )
+            .unwrap();
+            let query = ast::Request::new_with_unknowns(
+                euid(alice.clone()),
+                euid(uid("Action", "view")),
+                euid(uid("Photo", "vacation")),
+                Some(context),
+                Some(&schema),
+                Extensions::none(),
+            )
+            .unwrap();
+            let response = run_auth_test(&def_engine, query, &policies, &entities);
+            assert_eq!(response.decision, expected);
+        }
+    }
+
+    fn applies_to_mismatch_fixture() -> (ValidatorSchema, ast::PolicySet, Entities) {
+        use cedar_policy_validator::json_schema;
+
+        let (fragment, _) = json_schema::Fragment::from_cedarschema_str(
+            r#"
+        entity User;
+        entity Photo;
+        action view appliesTo { principal: User, resource: Photo };
+        "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let schema = ValidatorSchema::try_from(fragment).unwrap();
+        let mut policies = ast::PolicySet::new();
+        let policy = cedar_policy_core::parser::parse_policy(
+            Some(ast::PolicyID::from_string("policy0")),
+            r#"permit(principal, action == Action::"view", resource);"#,
+        )
+        .expect("Failed to parse");
+        policies.add_static(policy).unwrap();
+        (schema, policies, Entities::new())
+    }
+
+    #[test]
+    fn test_principal_type_not_in_applies_to() {
+        use cedar_drt::LeanDefinitionalEngine;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let (schema, policies, entities) = applies_to_mismatch_fixture();
+        // `Photo` is a declared entity type, but not a principal type for `view`
+        let request = Request {
+            principal: uid("Photo", "vacation"),
+            action: uid("Action", "view"),
+            resource: uid("Photo", "vacation"),
+            context: ast::Context::empty(),
+        };
+        check_applies_to_mismatch(&def_engine, &schema, &request, AppliesToMismatch::Principal);
+        run_auth_test(
+            &def_engine,
+            cedar_policy_generators::abac::ABACRequest(request).into(),
+            &policies,
+            &entities,
+        );
+    }
+
+    #[test]
+    fn test_resource_type_not_in_applies_to() {
+        use cedar_drt::LeanDefinitionalEngine;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let (schema, policies, entities) = applies_to_mismatch_fixture();
+        // `User` is a declared entity type, but not a resource type for `view`
+        let request = Request {
+            principal: uid("User", "alice"),
+            action: uid("Action", "view"),
+            resource: uid("User", "bob"),
+            context: ast::Context::empty(),
+        };
+        check_applies_to_mismatch(&def_engine, &schema, &request, AppliesToMismatch::Resource);
+        run_auth_test(
+            &def_engine,
+            cedar_policy_generators::abac::ABACRequest(request).into(),
+            &policies,
+            &entities,
+        );
+    }
+
+    #[test]
+    fn test_invalid_context() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_validator::json_schema;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let (fragment, _) = json_schema::Fragment::from_cedarschema_str(
+            r#"
+        entity User;
+        entity Photo;
+        action view appliesTo { principal: User, resource: Photo, context: { n: Long } };
+        "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let schema = ValidatorSchema::try_from(fragment).unwrap();
+        for (context, violation) in [
+            (ast::Context::empty(), ContextViolation::MissingAttribute),
+            (
+                ast::Context::from_pairs(
+                    [("n".into(), ast::RestrictedExpr::val("one"))],
+                    Extensions::none(),
+                )
+                .unwrap(),
+                ContextViolation::WrongType,
+            ),
+        ] {
+            let request = Request {
+                principal: uid("User", "alice"),
+                action: uid("Action", "view"),
+                resource: uid("Photo", "vacation"),
+                context,
+            };
+            check_invalid_context(&def_engine, &schema, &request, violation);
+        }
+    }
+
+    #[test]
+    fn test_drop_some_entities_bounded() {
+        use cedar_policy_core::ast::Entity;
+
+        let entities = entities((0..10).map(|i| {
+            Entity::new(
+                uid("User", &i.to_string()),
+                std::collections::HashMap::new(),
+                std::collections::HashSet::new(),
+                Extensions::none(),
+            )
+            .unwrap()
+        }));
+        // `1` decides to drop, and the exhausted input then picks `0` for every
+        // entity, i.e., drops everything the bounds allow
+        let count = |min_keep, max_drop| {
+            let mut u = Unstructured::new(&[1]);
+            drop_some_entities_bounded(entities.clone(), min_keep, max_drop, &mut u)
+                .unwrap()
+                .iter()
+                .count()
+        };
+        assert_eq!(count(0, usize::MAX), 0);
+        assert_eq!(count(3, usize::MAX), 3);
+        assert_eq!(count(0, 2), 8);
+        assert_eq!(count(5, 2), 8);
+        assert_eq!(count(20, 2), 10);
+    }
+
+    #[test]
+    fn test_drop_some_leaf_entities() {
+        use cedar_policy_core::ast::{Entity, EntityUID};
+
+        let entity = |uid: EntityUID, parents: &[EntityUID]| {
+            Entity::new(
+                uid,
+                std::collections::HashMap::new(),
+                parents.iter().cloned().collect(),
+                Extensions::none(),
+            )
+            .unwrap()
+        };
+        let entities = entities([
+            entity(uid("Org", "root"), &[]),
+            entity(uid("Group", "a"), &[uid("Org", "root")]),
+            entity(uid("Group", "b"), &[uid("Org", "root")]),
+            entity(uid("User", "alice"), &[uid("Group", "a")]),
+            entity(uid("User", "bob"), &[uid("Group", "a"), uid("Group", "b")]),
+            entity(uid("User", "carol"), &[]),
+        ]);
+        // `1` decides to drop, and the exhausted input then picks `0` for every
+        // leaf, i.e., drops all of them
+        let mut u = Unstructured::new(&[1]);
+        let remaining = drop_some_leaf_entities(entities.clone(), &mut u).unwrap();
+        let remaining_uids: HashSet<&EntityUID> = remaining.iter().map(|e| e.uid()).collect();
+        assert_eq!(
+            remaining_uids,
+            HashSet::from([&uid("Org", "root"), &uid("Group", "a"), &uid("Group", "b")])
+        );
+        for entity in remaining.iter() {
+            for ancestor in entity.ancestors() {
+                assert!(
+                    remaining_uids.contains(ancestor),
+                    "{} references the dropped entity {ancestor}",
+                    entity.uid()
+                );
+            }
+        }
+        // `0` decides not to drop anything
+        let mut u = Unstructured::new(&[0]);
+        let remaining = drop_some_leaf_entities(entities.clone(), &mut u).unwrap();
+        assert_eq!(remaining.iter().count(), 6);
     }
 }
@@ -0,0 +1,285 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Minimize failing inputs at the level of the AST. libFuzzer shrinks the raw
+//! bytes of an input, which doesn't necessarily shrink the expression, policy,
+//! or entities decoded from them, so a reproducer can still contain large
+//! irrelevant parts.
+
+use crate::drop_entities;
+use cedar_policy_core::ast::{self, Expr, ExprKind, Literal};
+use cedar_policy_core::entities::Entities;
+use std::collections::HashSet;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Environment variable that enables minimizing failing expressions before
+/// reporting them. If it isn't set, failures are reported as is.
+pub const MINIMIZE_ENV_VAR: &str = "DRT_MINIMIZE";
+
+/// Number of AST nodes in `expr`
+pub fn ast_node_count(expr: &Expr) -> usize {
+    expr.subexpressions().count()
+}
+
+/// Repeatedly replace `expr` by its smallest subexpression on which `fails`
+/// still holds, until no smaller subexpression fails. Assumes `fails(expr)`.
+pub fn minimize_expr(expr: &Expr, mut fails: impl FnMut(&Expr) -> bool) -> Expr {
+    let mut current = expr.clone();
+    loop {
+        let size = ast_node_count(&current);
+        let mut candidates: Vec<Expr> = current
+            .subexpressions()
+            .filter(|e| ast_node_count(e) < size)
+            .cloned()
+            .collect();
+        candidates.sort_by_key(ast_node_count);
+        match candidates.into_iter().find(|e| fails(e)) {
+            Some(smaller) => current = smaller,
+            None => return current,
+        }
+    }
+}
+
+/// Whether `f` panics. The panic message isn't printed.
+pub fn panics(f: impl FnOnce()) -> bool {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let res = panic::catch_unwind(AssertUnwindSafe(f));
+    panic::set_hook(hook);
+    res.is_err()
+}
+
+/// Run `check` on `expr`. If [`MINIMIZE_ENV_VAR`] is set and `check` panics,
+/// first print the smallest subexpression of `expr` on which `check` still
+/// panics (see [`minimize_expr`]), then panic as `check(expr)` does.
+pub fn check_minimized(expr: &Expr, check: impl Fn(&Expr)) {
+    if std::env::var_os(MINIMIZE_ENV_VAR).is_some() && panics(|| check(expr)) {
+        let minimized = minimize_expr(expr, |e| panics(|| check(e)));
+        eprintln!(
+            "Minimized failing expression ({} of {} AST nodes): {minimized}",
+            ast_node_count(&minimized),
+            ast_node_count(expr)
+        );
+    }
+    check(expr)
+}
+
+/// An ABAC test case in terms of the ASTs the harnesses run, i.e., after the
+/// generated hierarchy, policy, and requests of a `FuzzTargetInput` have been
+/// converted. This is what [`shrink_abac_input`] shrinks.
+#[derive(Debug, Clone)]
+pub struct AbacInput {
+    /// Entities
+    pub entities: Entities,
+    /// Policy
+    pub policy: ast::StaticPolicy,
+    /// Requests to authorize against the policy and entities
+    pub requests: Vec<ast::Request>,
+}
+
+impl AbacInput {
+    /// Total size of the input: the number of requests, entities, and AST
+    /// nodes in the policy condition. Every reduction tried by
+    /// [`shrink_abac_input`] decreases it.
+    pub fn size(&self) -> usize {
+        self.requests.len()
+            + self.entities.iter().count()
+            + ast_node_count(self.policy.non_scope_constraints())
+    }
+
+    /// This input with the policy condition replaced by `condition`
+    fn with_condition(&self, condition: Expr) -> Self {
+        let policy = &self.policy;
+        let policy = ast::StaticPolicy::new(
+            policy.id().clone(),
+            None,
+            policy
+                .annotations()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            policy.effect(),
+            policy.principal_constraint().clone(),
+            policy.action_constraint().clone(),
+            policy.resource_constraint().clone(),
+            condition,
+        )
+        .expect("replacing the condition of a static policy shouldn't add slots");
+        Self {
+            policy,
+            ..self.clone()
+        }
+    }
+
+    /// Inputs that are one reduction away from this one, roughly from the
+    /// largest reduction to the smallest
+    fn reductions(&self) -> Vec<Self> {
+        let mut candidates = Vec::new();
+        // keep a single request
+        if self.requests.len() > 1 {
+            candidates.extend(self.requests.iter().map(|request| Self {
+                requests: vec![request.clone()],
+                ..self.clone()
+            }));
+        }
+        // remove all the entities, or one of them
+        let uids: Vec<_> = self.entities.iter().map(|e| e.uid().clone()).collect();
+        if uids.len() > 1 {
+            candidates.push(Self {
+                entities: drop_entities(&self.entities, &uids.iter().cloned().collect()),
+                ..self.clone()
+            });
+        }
+        candidates.extend(uids.into_iter().map(|uid| Self {
+            entities: drop_entities(&self.entities, &HashSet::from([uid])),
+            ..self.clone()
+        }));
+        // drop the condition, or replace it by one of its subexpressions
+        let condition = self.policy.non_scope_constraints();
+        if !matches!(condition.expr_kind(), ExprKind::Lit(Literal::Bool(true))) {
+            candidates.push(self.with_condition(Expr::val(true)));
+        }
+        let size = ast_node_count(condition);
+        let mut subexprs: Vec<&Expr> = condition
+            .subexpressions()
+            .filter(|e| ast_node_count(e) < size)
+            .collect();
+        subexprs.sort_by_key(|e| ast_node_count(e));
+        candidates.extend(subexprs.into_iter().map(|e| self.with_condition(e.clone())));
+        candidates
+    }
+}
+
+/// Shrink `input`, on which `harness` panics, by repeatedly trying to keep
+/// only one request, remove entities, drop the policy condition, or replace
+/// the condition by one of its subexpressions, and keeping the first
+/// reduction on which `harness` still panics. Stops when no reduction panics.
+pub fn shrink_abac_input(input: &AbacInput, harness: impl Fn(&AbacInput)) -> AbacInput {
+    let mut current = input.clone();
+    loop {
+        match current
+            .reductions()
+            .into_iter()
+            .find(|candidate| panics(|| harness(candidate)))
+        {
+            Some(smaller) => current = smaller,
+            None => return current,
+        }
+    }
+}
+
+/// Like [`check_minimized`], but for ABAC inputs: if [`MINIMIZE_ENV_VAR`] is
+/// set and `check` panics, first print the input as shrunk by
+/// [`shrink_abac_input`], then panic as `check(input)` does.
+pub fn check_abac_minimized(input: &AbacInput, check: impl Fn(&AbacInput)) {
+    if std::env::var_os(MINIMIZE_ENV_VAR).is_some() && panics(|| check(input)) {
+        let shrunk = shrink_abac_input(input, &check);
+        eprintln!(
+            "Shrunk failing input (size {} of {}):\nPolicy: {}\nEntities: {}\nRequests:",
+            shrunk.size(),
+            input.size(),
+            shrunk.policy,
+            shrunk.entities
+        );
+        for request in &shrunk.requests {
+            eprintln!("  {request}");
+        }
+    }
+    check(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{entities, uid};
+    use crate::*;
+
+    #[test]
+    fn test_minimize_expr() {
+        let expr = cedar_policy_core::parser::parse_expr(
+        r#"(1 + 2 == 3) && (principal.name like "a*" || !(context.n > 4)) && [1, 2].contains(2)"#,
+    )
+    .expect("Failed to parse");
+        // a check that fails on any expression using `like`
+        let check = |e: &ast::Expr| assert!(!e.to_string().contains("like"), "found `like` in {e}");
+        assert!(panics(|| check(&expr)));
+        let minimized = minimize_expr(&expr, |e| panics(|| check(e)));
+        assert!(panics(|| check(&minimized)));
+        assert!(ast_node_count(&minimized) < ast_node_count(&expr));
+        assert!(matches!(minimized.expr_kind(), ast::ExprKind::Like { .. }));
+        // an expression with no smaller failing subexpression is already minimal
+        assert_eq!(
+            minimize_expr(&minimized, |e| panics(|| check(e))),
+            minimized
+        );
+    }
+
+    #[test]
+    fn test_shrink_abac_input() {
+        use cedar_policy_core::ast::Entity;
+
+        let entities = entities(["alice", "bob", "carol"].into_iter().map(|eid| {
+            Entity::new(
+                uid("User", eid),
+                std::collections::HashMap::new(),
+                std::collections::HashSet::new(),
+                Extensions::none(),
+            )
+            .unwrap()
+        }));
+        let policy = cedar_policy_core::parser::parse_policy(
+        None,
+        r#"permit(principal, action, resource) when { principal.age > 3 && context has x && resource like "a*" };"#,
+    )
+    .expect("Failed to parse");
+        let request = |principal: &str| {
+            ast::Request::new(
+                (uid("User", principal), None),
+                (uid("Action", "view"), None),
+                (uid("User", "bob"), None),
+                ast::Context::empty(),
+                None::<&ast::RequestSchemaAllPass>,
+                Extensions::none(),
+            )
+            .unwrap()
+        };
+        let input = AbacInput {
+            entities,
+            policy,
+            requests: vec![request("alice"), request("carol")],
+        };
+        // a harness that fails whenever the policy uses `like` and there is a
+        // request for `carol`
+        let harness = |input: &AbacInput| {
+            assert!(
+                !(input.policy.to_string().contains("like")
+                    && input
+                        .requests
+                        .iter()
+                        .any(|r| r.to_string().contains("carol"))),
+                "found `like` and `carol`"
+            );
+        };
+        assert!(panics(|| harness(&input)));
+        let shrunk = shrink_abac_input(&input, harness);
+        assert!(panics(|| harness(&shrunk)));
+        assert!(shrunk.size() < input.size());
+        assert_eq!(shrunk.requests.len(), 1);
+        assert_eq!(shrunk.entities.iter().count(), 0);
+        assert!(matches!(
+            shrunk.policy.non_scope_constraints().expr_kind(),
+            ast::ExprKind::Like { .. }
+        ));
+    }
+}
@@ -14,8 +14,11 @@
  * limitations under the License.
  */
 
-use cedar_policy_core::ast::{AnyId, Template};
+use cedar_policy_core::ast::{AnyId, PolicyID, Template};
+use cedar_policy_core::entities::err::EntitiesError;
+use cedar_policy_core::est;
 use cedar_policy_core::parser::err::{ParseError, ParseErrors, ToASTErrorKind};
+use cedar_policy_core::parser::parse_policy_or_template;
 use smol_str::SmolStr;
 use std::collections::HashMap;
 
@@ -47,6 +50,24 @@ pub fn check_policy_equivalence(p_old: &Template, p_new: &Template) {
     );
 }
 
+// Check whether two policies are equivalent, ignoring policy ids and source
+// locations. Like `check_policy_equivalence`, but returns `false` instead of
+// panicking, so callers can report the mismatch in their own terms.
+pub fn policies_equivalent(p_old: &Template, p_new: &Template) -> bool {
+    let new_anno: HashMap<&AnyId, &SmolStr> =
+        p_new.annotations().map(|(k, v)| (k, &v.val)).collect();
+    let old_anno: HashMap<&AnyId, &SmolStr> =
+        p_old.annotations().map(|(k, v)| (k, &v.val)).collect();
+    new_anno == old_anno
+        && p_new.effect() == p_old.effect()
+        && p_new.principal_constraint() == p_old.principal_constraint()
+        && p_new.action_constraint() == p_old.action_constraint()
+        && p_new.resource_constraint() == p_old.resource_constraint()
+        && p_new
+            .non_scope_constraints()
+            .eq_shape(p_old.non_scope_constraints())
+}
+
 // Check that we don't see a few specific errors, which correspond to violations
 // of internal invariants.
 pub fn check_for_internal_errors(errs: ParseErrors) {
@@ -61,3 +82,144 @@ pub fn check_for_internal_errors(errs: ParseErrors) {
         miette::Report::new(errs).wrap_err("unexpected internal error")
     )
 }
+
+// Check that an error from parsing entities JSON isn't one of the errors that
+// correspond to violations of internal invariants of the policy parser, which
+// parses the entity types and extension function names embedded in the JSON.
+// The entities parser doesn't expose those errors structurally, so we look for
+// them in the error's debug representation.
+pub fn check_for_internal_entities_errors(err: &EntitiesError) {
+    let debug = format!("{err:?}");
+    assert!(
+        ![
+            "MembershipInvariantViolation",
+            "EmptyNodeInvariantViolation"
+        ]
+        .iter()
+        .any(|kind| debug.contains(kind)),
+        "Entities parse errors included unexpected internal errors: {debug}"
+    )
+}
+
+// Roundtrip a policy or template through the JSON API of `cedar-policy`
+// (`to_json()` then `from_json()` on `cedar_policy::Policy`, or on
+// `cedar_policy::Template` if it has slots), rather than through the internal
+// EST. Panic if any step fails, if the JSON changes, or if the result isn't
+// equivalent to the original.
+pub fn check_public_json_roundtrip(template: &Template) {
+    let src = template.to_string();
+    let (json, roundtripped_json, roundtripped_src) = if template.slots().next().is_none() {
+        let policy = cedar_policy::Policy::parse(None, &src)
+            .unwrap_or_else(|err| panic!("failed to parse policy: {err:?}\n{src}"));
+        let json = policy.to_json().expect("failed to convert policy to JSON");
+        let roundtripped = cedar_policy::Policy::from_json(None, json.clone())
+            .unwrap_or_else(|err| panic!("failed to parse policy from JSON: {err:?}\n{json}"));
+        let roundtripped_json = roundtripped
+            .to_json()
+            .expect("failed to convert roundtripped policy to JSON");
+        (json, roundtripped_json, roundtripped.to_string())
+    } else {
+        let template = cedar_policy::Template::parse(None, &src)
+            .unwrap_or_else(|err| panic!("failed to parse template: {err:?}\n{src}"));
+        let json = template
+            .to_json()
+            .expect("failed to convert template to JSON");
+        let roundtripped = cedar_policy::Template::from_json(None, json.clone())
+            .unwrap_or_else(|err| panic!("failed to parse template from JSON: {err:?}\n{json}"));
+        let roundtripped_json = roundtripped
+            .to_json()
+            .expect("failed to convert roundtripped template to JSON");
+        (json, roundtripped_json, roundtripped.to_string())
+    };
+    similar_asserts::assert_eq!(json, roundtripped_json);
+    let roundtripped = parse_policy_or_template(None, &roundtripped_src).unwrap_or_else(|err| {
+        panic!("failed to parse roundtripped policy: {err:?}\n{roundtripped_src}")
+    });
+    check_policy_equivalence(template, &roundtripped);
+}
+
+// Convert an EST to an AST, back to an EST, to JSON, and then back to an AST
+// again. ESTs that don't convert to an AST are skipped; any other policy or
+// template should survive the roundtrip unchanged, including annotations and
+// slots. Panic if any step fails, if the JSON changes, or if the result isn't
+// equivalent to the first AST.
+pub fn check_est_json_roundtrip(est: est::Policy) {
+    let id = PolicyID::from_string("policy0");
+    let Ok(ast) = est.try_into_ast_policy_or_template(Some(id.clone())) else {
+        return;
+    };
+    let json = serde_json::to_value(est::Policy::from(ast.clone()))
+        .expect("failed to convert EST to JSON");
+    let roundtripped = serde_json::from_value::<est::Policy>(json.clone())
+        .unwrap_or_else(|err| panic!("failed to parse EST from JSON: {err:?}\n{json}"))
+        .try_into_ast_policy_or_template(Some(id))
+        .unwrap_or_else(|err| panic!("failed to convert EST to AST: {err:?}\n{json}"));
+    let roundtripped_json = serde_json::to_value(est::Policy::from(roundtripped.clone()))
+        .expect("failed to convert roundtripped EST to JSON");
+    if json != roundtripped_json {
+        println!("Policy: {ast}");
+        println!(
+            "{}",
+            similar_asserts::SimpleDiff::from_str(
+                &format!("{json:#}"),
+                &format!("{roundtripped_json:#}"),
+                "Initial JSON",
+                "JSON Round tripped"
+            )
+        );
+        panic!("EST changed after roundtripping through JSON");
+    }
+    check_policy_equivalence(&ast, &roundtripped);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_public_json_roundtrip() {
+        for src in [
+            // a template with both slots and annotations
+            r#"@id("admins") @advice("only for admins")
+        permit(principal in ?principal, action == Action::"view", resource == ?resource)
+        when { resource.public || principal.admin }
+        unless { context.blocked };"#,
+            // a static policy with an empty annotation and extension values
+            r#"@advice("")
+        forbid(principal is User, action in [Action::"edit", Action::"delete"], resource)
+        when { context.src.isInRange(ip("10.0.0.0/8")) && [decimal("1.5")].contains(context.limit) };"#,
+        ] {
+            let template = cedar_policy_core::parser::parse_policy_or_template(None, src)
+                .expect("Failed to parse");
+            check_public_json_roundtrip(&template);
+        }
+    }
+
+    #[test]
+    fn test_est_json_roundtrip() {
+        for src in [
+            // a template with both slots and annotations
+            r#"@id("admins") @advice("only for admins")
+        permit(principal in ?principal, action == Action::"view", resource == ?resource)
+        when { resource.public || principal.admin }
+        unless { context.blocked };"#,
+            // a static policy with `is` scopes and extension values
+            r#"forbid(principal is User in Group::"a", action, resource is Photo)
+        when { context.src.isInRange(ip("10.0.0.0/8")) && { limit: decimal("1.5") } has limit };"#,
+        ] {
+            let est = cedar_policy_core::parser::parse_policy_or_template_to_est(src)
+                .expect("Failed to parse");
+            check_est_json_roundtrip(est);
+        }
+        // an EST that doesn't convert to an AST (the action isn't an `Action`) is skipped
+        let est = serde_json::from_value(serde_json::json!({
+            "effect": "permit",
+            "principal": { "op": "All" },
+            "action": { "op": "==", "entity": { "type": "User", "id": "alice" } },
+            "resource": { "op": "All" },
+            "conditions": []
+        }))
+        .expect("Failed to parse EST");
+        check_est_json_roundtrip(est);
+    }
+}
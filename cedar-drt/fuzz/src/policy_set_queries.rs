@@ -0,0 +1,365 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Check the policy-set introspection API of `cedar-policy` (looking up
+//! policies and templates by ID, and listing the links of a template) against
+//! the structure of a generated policy set, and its `PolicySet::link()` API
+//! against the generator's own instantiation of templates.
+
+use crate::FailureClass;
+use cedar_policy::{EntityUid, PolicyId, SlotId};
+use cedar_policy_core::ast;
+use cedar_policy_core::authorizer::Authorizer;
+use cedar_policy_core::entities::Entities;
+use cedar_policy_core::est;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+/// Convert a core `PolicyID` into the `cedar-policy` equivalent
+fn api_policy_id(id: &ast::PolicyID) -> PolicyId {
+    PolicyId::new(id.clone())
+}
+
+/// Convert the slot values of a linked policy into the form returned by
+/// `cedar_policy::Policy::template_links()`
+fn api_slot_env(policy: &ast::Policy) -> HashMap<SlotId, EntityUid> {
+    policy
+        .env()
+        .iter()
+        .map(|(slot, euid)| {
+            let slot = if slot.is_principal() {
+                SlotId::principal()
+            } else {
+                SlotId::resource()
+            };
+            let euid = EntityUid::from_str(&euid.to_string())
+                .expect("entity UID should roundtrip through its string form");
+            (slot, euid)
+        })
+        .collect()
+}
+
+/// Build a `cedar_policy::PolicySet` with the same static policies, templates,
+/// and links as `policies`
+pub fn to_api_policy_set(policies: &ast::PolicySet) -> cedar_policy::PolicySet {
+    let mut api = cedar_policy::PolicySet::new();
+    for template in policies.templates() {
+        let template =
+            cedar_policy::Template::parse(Some(api_policy_id(template.id())), template.to_string())
+                .expect("template should roundtrip through its string form");
+        api.add_template(template).unwrap();
+    }
+    for policy in policies.policies() {
+        if policy.is_static() {
+            let policy =
+                cedar_policy::Policy::parse(Some(api_policy_id(policy.id())), policy.to_string())
+                    .expect("policy should roundtrip through its string form");
+            api.add(policy).unwrap();
+        } else {
+            api.link(
+                api_policy_id(policy.template().id()),
+                api_policy_id(policy.id()),
+                api_slot_env(policy),
+            )
+            .unwrap();
+        }
+    }
+    api
+}
+
+/// Check that the query methods of the `cedar_policy::PolicySet` built from
+/// `policies` agree with the structure of `policies`: every policy and
+/// template can be looked up by its ID, links report the template and slot
+/// values they were created with, and each template lists exactly its links.
+/// Panics otherwise.
+pub fn check_policy_set_queries(policies: &ast::PolicySet) {
+    let api = to_api_policy_set(policies);
+    assert_eq!(
+        api.policies().count(),
+        policies.policies().count(),
+        "Wrong number of policies\nPolicies:\n{policies}"
+    );
+    assert_eq!(
+        api.templates().count(),
+        policies.templates().count(),
+        "Wrong number of templates\nPolicies:\n{policies}"
+    );
+    for policy in policies.policies() {
+        let id = api_policy_id(policy.id());
+        let found = api
+            .policy(&id)
+            .unwrap_or_else(|| panic!("policy {id} not found\nPolicies:\n{policies}"));
+        assert_eq!(found.id(), &id);
+        assert_eq!(found.is_static(), policy.is_static(), "policy {id}");
+        if policy.is_static() {
+            assert_eq!(found.template_id(), None, "static policy {id}");
+            assert!(api.template(&id).is_none(), "static policy {id}");
+        } else {
+            assert_eq!(
+                found.template_id(),
+                Some(&api_policy_id(policy.template().id())),
+                "linked policy {id}"
+            );
+            assert_eq!(
+                found.template_links(),
+                Some(api_slot_env(policy)),
+                "linked policy {id}"
+            );
+        }
+    }
+    for template in policies.templates() {
+        let id = api_policy_id(template.id());
+        let found = api
+            .template(&id)
+            .unwrap_or_else(|| panic!("template {id} not found\nPolicies:\n{policies}"));
+        assert_eq!(found.id(), &id);
+        assert!(api.policy(&id).is_none(), "template {id}");
+        let expected_links: HashSet<PolicyId> = policies
+            .policies()
+            .filter(|policy| !policy.is_static() && policy.template().id() == template.id())
+            .map(|policy| api_policy_id(policy.id()))
+            .collect();
+        let links: HashSet<PolicyId> = api
+            .get_linked_policies(id.clone())
+            .unwrap_or_else(|e| panic!("template {id}: {e}"))
+            .cloned()
+            .collect();
+        assert_eq!(links, expected_links, "links of template {id}");
+    }
+    // no policy set generated by the fuzzer uses this ID
+    let unknown = PolicyId::new("not a generated policy id");
+    assert!(api.policy(&unknown).is_none());
+    assert!(api.template(&unknown).is_none());
+}
+
+/// Get the linked policy `id` of `api` as an `ast::Policy`. This goes through
+/// the JSON form of the policy, in which the slots are filled in with the
+/// linked values, so the result is a static policy.
+fn api_linked_policy(api: &cedar_policy::PolicySet, id: &ast::PolicyID) -> ast::Policy {
+    let json = api
+        .policy(&api_policy_id(id))
+        .unwrap_or_else(|| panic!("linked policy {id} not found"))
+        .to_json()
+        .unwrap_or_else(|err| panic!("failed to convert linked policy {id} to JSON: {err:?}"));
+    serde_json::from_value::<est::Policy>(json.clone())
+        .unwrap_or_else(|err| panic!("failed to parse EST from JSON: {err:?}\n{json}"))
+        .try_into_ast_policy(Some(id.clone()))
+        .unwrap_or_else(|err| panic!("failed to convert EST to AST: {err:?}\n{json}"))
+}
+
+/// Link the policy `link_id` of `policies` again through the public
+/// `cedar_policy::PolicySet::link()` API, and check that the resulting policy
+/// authorizes each of `requests` the same way as `expected`, the generator's
+/// own instantiation of the link. Returns a policy set containing just the
+/// publicly linked policy. Panics otherwise.
+pub fn check_public_link(
+    policies: &ast::PolicySet,
+    link_id: &ast::PolicyID,
+    expected: ast::StaticPolicy,
+    requests: &[ast::Request],
+    entities: &Entities,
+) -> ast::PolicySet {
+    let api = to_api_policy_set(policies);
+    let linked = api_linked_policy(&api, link_id);
+    assert!(
+        linked.is_static(),
+        "linked policy {link_id} still has slots"
+    );
+    let mut linked_set = ast::PolicySet::new();
+    linked_set.add(linked).unwrap();
+    let mut expected_set = ast::PolicySet::new();
+    expected_set.add_static(expected).unwrap();
+    let authorizer = Authorizer::new();
+    for request in requests {
+        let actual = authorizer.is_authorized(request.clone(), &linked_set, entities);
+        let expected = authorizer.is_authorized(request.clone(), &expected_set, entities);
+        assert_eq!(
+            actual.decision,
+            expected.decision,
+            "Mismatch ({}) for {request}\nLinked:\n{linked_set}\nInstantiated:\n{expected_set}",
+            FailureClass::Decision
+        );
+        assert_eq!(
+            actual.diagnostics.reason,
+            expected.diagnostics.reason,
+            "Mismatch ({}) for {request}\nLinked:\n{linked_set}\nInstantiated:\n{expected_set}",
+            FailureClass::Reason
+        );
+        assert_eq!(
+            actual.diagnostics.errors.len(),
+            expected.diagnostics.errors.len(),
+            "Mismatch ({}) for {request}\nLinked:\n{linked_set}\nInstantiated:\n{expected_set}",
+            FailureClass::Errors
+        );
+    }
+    linked_set
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{entities, request, uid};
+    use crate::*;
+
+    #[test]
+    fn test_policy_set_queries() {
+        use cedar_policy_core::ast::{PolicyID, SlotId};
+
+        let mut policies = ast::PolicySet::new();
+        let static_policy = cedar_policy_core::parser::parse_policy(
+            Some(PolicyID::from_string("p0")),
+            r#"permit(principal, action == Action::"view", resource);"#,
+        )
+        .expect("Failed to parse");
+        policies.add_static(static_policy).unwrap();
+        for (id, src) in [
+            ("t0", "permit(principal == ?principal, action, resource);"),
+            (
+                "t1",
+                "forbid(principal in ?principal, action, resource == ?resource);",
+            ),
+            // a template without any links
+            ("t2", "permit(principal, action, resource in ?resource);"),
+        ] {
+            let template = cedar_policy_core::parser::parse_policy_or_template(
+                Some(PolicyID::from_string(id)),
+                src,
+            )
+            .expect("Failed to parse");
+            policies.add_template(template).unwrap();
+        }
+        let alice = uid("User", "alice");
+        let photo = uid("Photo", "vacation");
+        for (template, id, vals) in [
+            ("t0", "t0_l0", vec![(SlotId::principal(), alice.clone())]),
+            ("t0", "t0_l1", vec![(SlotId::principal(), photo.clone())]),
+            (
+                "t1",
+                "t1_l0",
+                vec![(SlotId::principal(), alice), (SlotId::resource(), photo)],
+            ),
+        ] {
+            policies
+                .link(
+                    PolicyID::from_string(template),
+                    PolicyID::from_string(id),
+                    vals.into_iter().collect(),
+                )
+                .unwrap();
+        }
+        check_policy_set_queries(&policies);
+
+        let api = to_api_policy_set(&policies);
+        assert_eq!(api.policies().count(), 4);
+        assert_eq!(api.templates().count(), 3);
+        assert_eq!(
+            api.get_linked_policies(PolicyId::new("t0"))
+                .unwrap()
+                .cloned()
+                .collect::<HashSet<_>>(),
+            HashSet::from([PolicyId::new("t0_l0"), PolicyId::new("t0_l1")])
+        );
+        assert_eq!(
+            api.get_linked_policies(PolicyId::new("t2"))
+                .unwrap()
+                .count(),
+            0
+        );
+        assert_eq!(
+            api.policy(&PolicyId::new("t1_l0")).unwrap().template_id(),
+            Some(&PolicyId::new("t1"))
+        );
+    }
+
+    #[test]
+    fn test_public_link() {
+        use cedar_policy_core::ast::{Effect, Entity, PolicyID};
+        use cedar_policy_core::authorizer::Decision;
+        use cedar_policy_generators::policy::{
+            ActionConstraint, GeneratedLinkedPolicy, GeneratedPolicy, PrincipalOrResourceConstraint,
+        };
+
+        let photo_type = uid("Photo", "vacation").entity_type().clone();
+        let template = GeneratedPolicy::new(
+            PolicyID::from_string("t"),
+            [],
+            Effect::Permit,
+            PrincipalOrResourceConstraint::InSlot,
+            ActionConstraint::NoConstraint,
+            PrincipalOrResourceConstraint::IsTypeInSlot(photo_type.clone()),
+            ast::Expr::val(true),
+        );
+        let link = GeneratedLinkedPolicy::new(
+            PolicyID::from_string("l0"),
+            &template,
+            uid("Group", "admins"),
+            uid("Album", "trip"),
+        );
+        let expected = link.instantiate(&template);
+        assert_eq!(
+            expected.principal_constraint(),
+            &PrincipalOrResourceConstraint::In(uid("Group", "admins"))
+        );
+        assert_eq!(
+            expected.resource_constraint(),
+            &PrincipalOrResourceConstraint::IsTypeIn(photo_type, uid("Album", "trip"))
+        );
+        let mut policies = ast::PolicySet::new();
+        template.add_to_policyset(&mut policies);
+        link.add_to_policyset(&mut policies);
+
+        // alice is a member of the group, bob isn't
+        let alice = Entity::new(
+            uid("User", "alice"),
+            std::collections::HashMap::new(),
+            std::collections::HashSet::from_iter([uid("Group", "admins")]),
+            Extensions::none(),
+        )
+        .unwrap();
+        let photo = Entity::new(
+            uid("Photo", "vacation"),
+            std::collections::HashMap::new(),
+            std::collections::HashSet::from_iter([uid("Album", "trip")]),
+            Extensions::none(),
+        )
+        .unwrap();
+        let entities = entities([alice, photo, Entity::with_uid(uid("User", "bob"))]);
+        let requests: Vec<ast::Request> = [uid("User", "alice"), uid("User", "bob")]
+            .into_iter()
+            .map(|principal| {
+                request(
+                    principal,
+                    uid("Action", "view"),
+                    uid("Photo", "vacation"),
+                    ast::Context::empty(),
+                )
+            })
+            .collect();
+
+        let linked = check_public_link(
+            &policies,
+            &PolicyID::from_string("l0"),
+            expected.into(),
+            &requests,
+            &entities,
+        );
+        assert_eq!(linked.policies().count(), 1);
+        let authorizer = Authorizer::new();
+        for (request, expected) in requests.into_iter().zip([Decision::Allow, Decision::Deny]) {
+            let response = authorizer.is_authorized(request, &linked, &entities);
+            assert_eq!(response.decision, expected);
+        }
+    }
+}
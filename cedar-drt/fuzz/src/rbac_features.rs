@@ -0,0 +1,74 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Summary counts for RBAC inputs. These are logged along with each input of
+//! the RBAC campaign, so we can see how often the generated policies actually
+//! allow a request.
+
+use cedar_policy_core::authorizer::Decision;
+use serde::Serialize;
+
+/// Summary counts for an RBAC input and the decisions on its requests
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RBACFeatures {
+    /// Number of policy groups consisting of a single static policy
+    pub static_policies: usize,
+    /// Number of policy groups consisting of a template and its links
+    pub templates: usize,
+    /// Total number of links, over all templates
+    pub links: usize,
+    /// Number of entities in the hierarchy
+    pub entities: usize,
+    /// Number of requests that were allowed
+    pub allowed: usize,
+    /// Number of requests that were denied
+    pub denied: usize,
+}
+
+impl RBACFeatures {
+    /// Count the decision on one of the requests
+    pub fn record_decision(&mut self, decision: Decision) {
+        match decision {
+            Decision::Allow => self.allowed += 1,
+            Decision::Deny => self.denied += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_rbac_features() {
+        use cedar_policy_core::authorizer::Decision;
+
+        let mut features = RBACFeatures {
+            static_policies: 1,
+            templates: 1,
+            links: 3,
+            entities: 5,
+            ..RBACFeatures::default()
+        };
+        for decision in [Decision::Allow, Decision::Deny, Decision::Deny] {
+            features.record_decision(decision);
+        }
+        assert_eq!((features.allowed, features.denied), (1, 2));
+        let json = serde_json::to_value(&features).unwrap();
+        assert_eq!(json["links"], 3);
+        assert_eq!(json["denied"], 2);
+    }
+}
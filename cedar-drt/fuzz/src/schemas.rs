@@ -70,6 +70,69 @@ pub fn equivalence_check<N: Clone + PartialEq + Debug + Display + TypeName + Ord
     }
 }
 
+/// Like [`equivalence_check`], but also considers the fragments equivalent if
+/// the groups an action is a member of (its `memberOf` list) only differ in
+/// order. `equivalence_check` already compares the types an entity type can
+/// be a member of, and the principal and resource types of an action, as
+/// sets, so action groups are the only declarations whose order it notices.
+pub fn equivalence_check_normalized<N: Clone + PartialEq + Debug + Display + TypeName + Ord>(
+    lhs: json_schema::Fragment<N>,
+    rhs: json_schema::Fragment<N>,
+) -> Result<(), String> {
+    equivalence_check(normalize(lhs), normalize(rhs))
+}
+
+/// Sort the `memberOf` list of every action in `schema`
+fn normalize<N: Display>(mut schema: json_schema::Fragment<N>) -> json_schema::Fragment<N> {
+    for namespace in schema.0.values_mut() {
+        for action in namespace.actions.values_mut() {
+            if let Some(member_of) = action.member_of.as_mut() {
+                member_of
+                    .sort_by_key(|uid| (uid.id.clone(), uid.ty.as_ref().map(ToString::to_string)));
+            }
+        }
+    }
+    schema
+}
+
+/// Check that the Cedar-syntax and JSON schema parsers agree on a small
+/// schema declared in `namespace`: either both reject it, or both accept it
+/// and produce equivalent fragments. This is mostly interesting for namespaces
+/// with very many `::`-separated segments, which may hit parser limits.
+pub fn check_namespace_parsing(namespace: &str) -> Result<(), String> {
+    let cedar_src = format!(
+        "namespace {namespace} {{ entity User; action view appliesTo {{ principal: User, resource: User }}; }}"
+    );
+    let json_src = serde_json::json!({
+        namespace: {
+            "entityTypes": { "User": {} },
+            "actions": {
+                "view": {
+                    "appliesTo": {
+                        "principalTypes": ["User"],
+                        "resourceTypes": ["User"],
+                    }
+                }
+            }
+        }
+    });
+    let cedar_parsed = json_schema::Fragment::<RawName>::from_cedarschema_str(
+        &cedar_src,
+        cedar_policy_core::extensions::Extensions::all_available(),
+    );
+    let json_parsed = json_schema::Fragment::<RawName>::from_json_value(json_src);
+    match (cedar_parsed, json_parsed) {
+        (Ok((cedar_frag, _)), Ok(json_frag)) => equivalence_check(cedar_frag, json_frag),
+        (Err(_), Err(_)) => Ok(()),
+        (Ok(_), Err(err)) => Err(format!(
+            "Cedar schema parser accepted namespace `{namespace}`, but JSON schema parser rejected it: {err}"
+        )),
+        (Err(err), Ok(_)) => Err(format!(
+            "JSON schema parser accepted namespace `{namespace}`, but Cedar schema parser rejected it: {err}"
+        )),
+    }
+}
+
 fn remove_trivial_empty_namespace<N>(schema: &mut json_schema::Fragment<N>) {
     match schema.0.get(&None) {
         Some(def)
@@ -141,7 +204,7 @@ fn entity_type_equivalence<N: Clone + PartialEq + Debug + Display + TypeName + O
         .get(&name)
         .ok_or_else(|| format!("Type `{name}` was missing from right-hand-side"))?;
 
-    if vector_equiv(&lhs_type.member_of_types, &rhs_type.member_of_types) {
+    if !vector_equiv(&lhs_type.member_of_types, &rhs_type.member_of_types) {
         Err(format!(
             "For `{name}`: lhs and rhs membership are not equal. LHS: [{}], RHS: [{}].",
             lhs_type
@@ -381,3 +444,122 @@ pub fn validator_schema_attr_types_equivalent(
     );
     entity_attr_tys1 == entity_attr_tys2 && context_ty1 == context_ty2
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_deep_namespace_parsing() {
+        for segments in [1, 32, 512, 4096] {
+            let namespace = (0..segments).map(|i| format!("N{i}")).collect::<Vec<_>>();
+            schemas::check_namespace_parsing(&namespace.join("::")).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_schema_equivalence_normalized() {
+        use cedar_policy_validator::{json_schema, RawName};
+
+        let schema = |groups: serde_json::Value, principals: serde_json::Value| {
+            json_schema::Fragment::<RawName>::from_json_value(serde_json::json!({
+                "": {
+                    "entityTypes": {
+                        "User": { "memberOfTypes": ["Group", "Team"] },
+                        "Group": {},
+                        "Team": {}
+                    },
+                    "actions": {
+                        "read": {},
+                        "write": {},
+                        "view": {
+                            "memberOf": groups,
+                            "appliesTo": { "principalTypes": principals, "resourceTypes": ["User"] }
+                        }
+                    }
+                }
+            }))
+            .unwrap()
+        };
+        let lhs = schema(
+            serde_json::json!([{ "id": "read" }, { "id": "write" }]),
+            serde_json::json!(["User", "Group"]),
+        );
+        let rhs = schema(
+            serde_json::json!([{ "id": "write" }, { "id": "read" }]),
+            serde_json::json!(["Group", "User"]),
+        );
+        schemas::equivalence_check_normalized(lhs.clone(), rhs.clone()).unwrap();
+        // the strict check notices that the action groups were reordered
+        assert!(schemas::equivalence_check(lhs.clone(), rhs).is_err());
+        // normalizing doesn't make different schemas equivalent
+        let other = schema(
+            serde_json::json!([{ "id": "read" }]),
+            serde_json::json!(["User", "Group"]),
+        );
+        assert!(schemas::equivalence_check_normalized(lhs, other).is_err());
+    }
+
+    #[test]
+    fn test_schema_equivalence_entity_membership() {
+        use cedar_policy_validator::{json_schema, RawName};
+
+        let schema = |member_of_types: serde_json::Value| {
+            json_schema::Fragment::<RawName>::from_json_value(serde_json::json!({
+                "": {
+                    "entityTypes": {
+                        "User": { "memberOfTypes": member_of_types },
+                        "Group": {},
+                        "Team": {}
+                    },
+                    "actions": {}
+                }
+            }))
+            .unwrap()
+        };
+        let groups_and_teams = schema(serde_json::json!(["Group", "Team"]));
+        // the same memberships, in any order, are equivalent
+        schemas::equivalence_check(groups_and_teams.clone(), groups_and_teams.clone()).unwrap();
+        schemas::equivalence_check(
+            groups_and_teams.clone(),
+            schema(serde_json::json!(["Team", "Group"])),
+        )
+        .unwrap();
+        schemas::equivalence_check(schema(serde_json::json!([])), schema(serde_json::json!([])))
+            .unwrap();
+        // different memberships aren't
+        assert!(
+            schemas::equivalence_check(groups_and_teams, schema(serde_json::json!(["Group"])))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_schema_json_roundtrip() {
+        use cedar_policy_validator::{json_schema, RawName};
+
+        // an empty namespace next to a named one, with types referring to the
+        // reserved `__cedar` namespace
+        let json = serde_json::json!({
+            "": {
+                "entityTypes": {
+                    "User": { "shape": { "type": "Record", "attributes": {
+                        "name": { "type": "__cedar::String" }
+                    } } }
+                },
+                "actions": {}
+            },
+            "NS": {
+                "entityTypes": { "Photo": {} },
+                "actions": { "view": {} }
+            }
+        });
+        let fragment: json_schema::Fragment<RawName> =
+            json_schema::Fragment::from_json_value(json).unwrap();
+        let roundtripped = json_schema::Fragment::from_json_value(
+            serde_json::to_value(&fragment).expect("Failed to serialize schema to JSON"),
+        )
+        .expect("Failed to parse serialized JSON schema");
+        schemas::equivalence_check(fragment, roundtripped).unwrap();
+    }
+}
@@ -0,0 +1,79 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Seed corpora for new fuzzing campaigns. Rather than starting from noise,
+//! libFuzzer or AFL can start from byte strings that already decode to
+//! structured inputs of the target.
+
+use libfuzzer_sys::arbitrary::{Arbitrary, Unstructured};
+use rand::{thread_rng, Rng, RngCore};
+use std::path::Path;
+
+/// Range of the lengths of the random byte strings, so that the corpus has
+/// both small and large inputs
+const SEED_LENGTHS: std::ops::RangeInclusive<usize> = 64..=8192;
+
+/// Number of random byte strings to try for each input before giving up
+const MAX_ATTEMPTS_PER_SEED: usize = 100;
+
+/// Generate `n` seeds for a fuzz target whose input type is `T`, e.g.,
+/// `generate_seed_corpus::<FuzzTargetInput>(1000, "corpus/abac-type-directed")`,
+/// and write each one to its own file in `out_dir`, which is created if it
+/// doesn't exist. A seed is a random byte string from which
+/// `T::arbitrary_take_rest()` succeeds, as it is called by `fuzz_target!`.
+/// Byte strings from which it fails are discarded. Returns the number of seeds
+/// written, which is less than `n` if too many byte strings fail.
+pub fn generate_seed_corpus<T: for<'a> Arbitrary<'a>>(
+    n: usize,
+    out_dir: impl AsRef<Path>,
+) -> std::io::Result<usize> {
+    let out_dir = out_dir.as_ref();
+    std::fs::create_dir_all(out_dir)?;
+    let mut rng = thread_rng();
+    let mut written = 0;
+    let mut attempts = 0;
+    while written < n && attempts < n.saturating_mul(MAX_ATTEMPTS_PER_SEED) {
+        attempts += 1;
+        let mut bytes = vec![0; rng.gen_range(SEED_LENGTHS)];
+        rng.fill_bytes(&mut bytes);
+        if T::arbitrary_take_rest(Unstructured::new(&bytes)).is_ok() {
+            std::fs::write(out_dir.join(format!("seed-{written:06}")), &bytes)?;
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_generate_seed_corpus() {
+        use libfuzzer_sys::arbitrary::Arbitrary;
+
+        type Input = (ast::Name, ast::Eid);
+        let dir = std::env::temp_dir().join(format!("drt-seed-corpus-{}", uuid::Uuid::new_v4()));
+        assert_eq!(generate_seed_corpus::<Input>(20, &dir).unwrap(), 20);
+        let seeds: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(seeds.len(), 20);
+        for seed in seeds {
+            let bytes = std::fs::read(seed.unwrap().path()).unwrap();
+            assert!(Input::arbitrary_take_rest(Unstructured::new(&bytes)).is_ok());
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
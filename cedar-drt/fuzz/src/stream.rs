@@ -0,0 +1,170 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Stream generated inputs to a directory as the standard Cedar file trio
+//! (`.cedarschema`, `.cedar`, `.entities.json`), so that an external process
+//! can consume them and differentially test other implementations, decoupled
+//! from the checks done by the fuzz targets themselves.
+
+use crate::dump::well_formed;
+use cedar_policy_core::ast::PolicySet;
+use cedar_policy_core::entities::Entities;
+use cedar_policy_validator::{json_schema, RawName};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Environment variable holding the directory to stream generated inputs to.
+/// If it isn't set, nothing is streamed.
+pub const STREAM_DIR_ENV_VAR: &str = "DRT_STREAM_DIR";
+
+/// Index of the next input streamed by this process
+static NEXT_INDEX: AtomicUsize = AtomicUsize::new(0);
+
+/// If [`STREAM_DIR_ENV_VAR`] is set, write the input to that directory (see
+/// [`stream_input_to`]) under the next sequential index for this process.
+/// Returns the common path of the written files, without extension, or
+/// `None` if streaming is disabled or the policies can't be re-parsed.
+pub fn stream_input(
+    schema: &json_schema::Fragment<RawName>,
+    policies: &PolicySet,
+    entities: &Entities,
+) -> std::io::Result<Option<PathBuf>> {
+    let Some(dir) = std::env::var_os(STREAM_DIR_ENV_VAR) else {
+        return Ok(None);
+    };
+    let index = NEXT_INDEX.fetch_add(1, Ordering::Relaxed);
+    // several fuzzing jobs may share the directory, so include the process ID
+    let name = format!("{}-{index:08}", std::process::id());
+    stream_input_to(dir, &name, schema, policies, entities)
+}
+
+/// Write the input to `dir` as `<name>.cedarschema`, `<name>.entities.json`,
+/// and `<name>.cedar`, in that order. Each file is written to a temporary
+/// file first and then renamed, so a consumer that waits for `<name>.cedar`
+/// never sees a partial trio. Static policies only, as in [`crate::dump`].
+/// Returns `dir/<name>`, or `None` if the policies can't be re-parsed.
+pub fn stream_input_to(
+    dir: impl AsRef<Path>,
+    name: &str,
+    schema: &json_schema::Fragment<RawName>,
+    policies: &PolicySet,
+    entities: &Entities,
+) -> std::io::Result<Option<PathBuf>> {
+    if !well_formed(policies) {
+        return Ok(None);
+    }
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+
+    let schema_text = schema
+        .to_cedarschema()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+    write_atomically(&dir.join(format!("{name}.cedarschema")), |file| {
+        writeln!(file, "{schema_text}")
+    })?;
+    write_atomically(&dir.join(format!("{name}.entities.json")), |file| {
+        entities
+            .write_to_json(file)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))
+    })?;
+    write_atomically(&dir.join(format!("{name}.cedar")), |file| {
+        for policy in policies.static_policies() {
+            writeln!(file, "{policy}")?;
+        }
+        Ok(())
+    })?;
+    Ok(Some(dir.join(name)))
+}
+
+/// Write `path` by writing a temporary file next to it and renaming it
+fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut std::fs::File) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+    let mut file = std::fs::File::create(&tmp_path)?;
+    write(&mut file)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_stream_input() {
+        use cedar_policy_core::entities::EntityJsonParser;
+        use cedar_policy_generators::settings::ABACSettings;
+        use cedar_policy_validator::{json_schema, CoreSchema};
+        use rand::RngCore;
+
+        const SETTINGS: ABACSettings = ABACSettings::builder().build();
+
+        let (fragment, _) = json_schema::Fragment::from_cedarschema_str(
+            r#"
+        entity Group;
+        entity User in [Group] { age: Long, name: String };
+        entity Photo { owner: User, tags: Set<String> };
+        action view appliesTo { principal: User, resource: Photo, context: { src: ipaddr } };
+        "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let mut bytes = vec![0; 1 << 16];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let mut u = Unstructured::new(&bytes);
+        let schema = Schema::from_raw_schemafrag(fragment, SETTINGS, &mut u).unwrap();
+        let hierarchy = schema.arbitrary_hierarchy(&mut u).unwrap();
+        let mut policies = ast::PolicySet::new();
+        policies
+            .add_static(schema.arbitrary_policy(&hierarchy, &mut u).unwrap().into())
+            .unwrap();
+        let entities = Entities::try_from(hierarchy).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("drt-stream-{}", uuid::Uuid::new_v4()));
+        let Some(stem) = stream_input_to(&dir, "0", &schema.into(), &policies, &entities).unwrap()
+        else {
+            // the generated policy can't be re-parsed, so nothing was written
+            std::fs::remove_dir_all(&dir).ok();
+            return;
+        };
+        let read = |ext: &str| std::fs::read_to_string(stem.with_extension(ext)).unwrap();
+
+        let (vschema, _) = ValidatorSchema::from_cedarschema_str(
+            &read("cedarschema"),
+            Extensions::all_available(),
+        )
+        .expect("streamed schema should parse");
+        let streamed_policies = cedar_policy_core::parser::parse_policyset(&read("cedar"))
+            .expect("streamed policies should parse");
+        assert_eq!(streamed_policies.policies().count(), 1);
+        let core_schema = CoreSchema::new(&vschema);
+        EntityJsonParser::new(
+            Some(&core_schema),
+            Extensions::all_available(),
+            TCComputation::ComputeNow,
+        )
+        .from_json_str(&read("entities.json"))
+        .expect("streamed entities should parse");
+        // no temporary files are left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,242 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Track which kinds of validation errors a fuzzing campaign has exercised.
+//! Kinds that never show up point at validator error paths the generators
+//! don't reach. Also compare the kinds of errors reported by `cedar-policy`
+//! and the Lean spec when both reject the same policies.
+
+use cedar_policy_validator::ValidationResult;
+use log::info;
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::sync::Mutex;
+
+/// Distinct validation error kinds seen so far by this process
+static VALIDATION_ERROR_KINDS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// The kind of a validation error: the name of its `ValidationError` variant,
+/// taken from its `Debug` representation
+fn validation_error_kind(err: &impl std::fmt::Debug) -> String {
+    format!("{err:?}")
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_')
+        .collect()
+}
+
+/// Record the kinds of all errors in `res`. Whenever a new kind shows up, the
+/// full set seen so far is logged, so the last such log line summarizes the
+/// campaign.
+pub fn record_validation_error_kinds(res: &ValidationResult) {
+    let mut kinds = VALIDATION_ERROR_KINDS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut new_kind = false;
+    for err in res.validation_errors() {
+        new_kind |= kinds.insert(validation_error_kind(err));
+    }
+    if new_kind {
+        info!("validation error kinds seen: {:?}", kinds);
+    }
+}
+
+/// The distinct validation error kinds recorded so far by this process
+pub fn validation_error_kinds() -> BTreeSet<String> {
+    VALIDATION_ERROR_KINDS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+
+/// Lean validation error kinds for which `cedar-policy` is known to report
+/// unrelated error kinds. Mismatches involving these kinds are ignored by
+/// [`validation_kinds_diff`].
+pub const KNOWN_VALIDATION_KIND_DIVERGENCES: &[&str] = &[];
+
+/// The `cedar-policy` validation error kinds that correspond to a validation
+/// error kind reported by the Lean spec, or `None` if `lean_kind` is not a
+/// kind the Lean spec reports
+pub fn rust_kinds_for_lean_kind(lean_kind: &str) -> Option<&'static [&'static str]> {
+    match lean_kind {
+        "lubErr" => Some(&["IncompatibleTypes", "UnexpectedType"]),
+        "unexpectedType" => Some(&["UnexpectedType", "IncompatibleTypes"]),
+        "attrNotFound" => Some(&["UnsafeAttributeAccess", "UnsafeOptionalAttributeAccess"]),
+        "unknownEntity" => Some(&["UnrecognizedEntityType", "UnrecognizedActionId"]),
+        "extensionErr" => Some(&[
+            "FunctionArgumentValidation",
+            "NonLitExtConstructor",
+            "UndefinedFunction",
+            "WrongNumberArguments",
+        ]),
+        "emptySetErr" => Some(&["EmptySetForbidden"]),
+        "incompatibleSetTypes" => Some(&["IncompatibleTypes"]),
+        "impossiblePolicy" => Some(&["InvalidActionApplication"]),
+        _ => None,
+    }
+}
+
+/// Disagreement on why some policies are invalid, when both validators
+/// reject them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationKindsDiff {
+    /// Error kind reported by the Lean spec
+    pub lean_kind: String,
+    /// `cedar-policy` error kinds that would have agreed with `lean_kind`
+    pub expected_rust_kinds: BTreeSet<String>,
+    /// Error kinds actually reported by `cedar-policy`
+    pub rust_kinds: BTreeSet<String>,
+}
+
+impl Display for ValidationKindsDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Lean error kind: {}", self.lean_kind)?;
+        writeln!(
+            f,
+            "expected one of the cedar-policy error kinds: {:?}",
+            self.expected_rust_kinds
+        )?;
+        write!(f, "cedar-policy error kinds: {:?}", self.rust_kinds)
+    }
+}
+
+/// Compare the error kinds in `rust_res` against the errors reported by the
+/// Lean spec, assuming both rejected the policies. The Lean spec stops at the
+/// first error, so this only checks that each Lean error corresponds to at
+/// least one of the (possibly many) errors reported by `cedar-policy`.
+/// Returns `None` if they agree, or if the Lean error kind is listed in
+/// [`KNOWN_VALIDATION_KIND_DIVERGENCES`].
+pub fn validation_kinds_diff(
+    rust_res: &ValidationResult,
+    lean_errors: &[String],
+) -> Option<ValidationKindsDiff> {
+    let rust_kinds: BTreeSet<String> = rust_res
+        .validation_errors()
+        .map(validation_error_kind)
+        .collect();
+    lean_errors
+        .iter()
+        .filter(|kind| !KNOWN_VALIDATION_KIND_DIVERGENCES.contains(&kind.as_str()))
+        .find_map(|lean_kind| {
+            let expected_rust_kinds: BTreeSet<String> = rust_kinds_for_lean_kind(lean_kind)
+                .unwrap_or_default()
+                .iter()
+                .map(|kind| kind.to_string())
+                .collect();
+            if expected_rust_kinds.is_disjoint(&rust_kinds) {
+                Some(ValidationKindsDiff {
+                    lean_kind: lean_kind.clone(),
+                    expected_rust_kinds,
+                    rust_kinds: rust_kinds.clone(),
+                })
+            } else {
+                None
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_validation_error_kinds_accumulate() {
+        use cedar_drt::LeanDefinitionalEngine;
+        use cedar_policy_core::ast::PolicyID;
+        use cedar_policy_validator::json_schema;
+
+        let def_engine = LeanDefinitionalEngine::new();
+        let (fragment, _) = json_schema::Fragment::from_cedarschema_str(
+            r#"
+        entity User { age: Long };
+        entity Photo;
+        action view appliesTo { principal: User, resource: Photo };
+        "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let schema = ValidatorSchema::try_from(fragment).unwrap();
+        // each policy is validated on its own, so the kinds accumulate across
+        // several calls to `run_val_test`
+        for (src, kind) in [
+            (
+                r#"permit(principal == Usr::"alice", action, resource);"#,
+                "UnrecognizedEntityType",
+            ),
+            (
+                "permit(principal, action, resource) when { principal.name == resource };",
+                "UnsafeAttributeAccess",
+            ),
+            (
+                "permit(principal, action, resource) when { 1 + true };",
+                "UnexpectedType",
+            ),
+        ] {
+            let mut policies = ast::PolicySet::new();
+            let policy = cedar_policy_core::parser::parse_policy(
+                Some(PolicyID::from_string("policy0")),
+                src,
+            )
+            .expect("Failed to parse");
+            policies.add_static(policy).unwrap();
+            run_val_test(
+                &def_engine,
+                schema.clone(),
+                &policies,
+                ValidationMode::Strict,
+            );
+            assert!(
+                validation_error_kinds().contains(kind),
+                "expected {kind} in {:?}",
+                validation_error_kinds()
+            );
+        }
+    }
+
+    #[test]
+    fn test_validation_kinds_diff() {
+        use cedar_policy_core::ast::PolicyID;
+        use cedar_policy_validator::json_schema;
+
+        let (fragment, _) = json_schema::Fragment::from_cedarschema_str(
+            r#"
+        entity User;
+        action view appliesTo { principal: User, resource: User };
+        "#,
+            Extensions::all_available(),
+        )
+        .unwrap();
+        let schema = ValidatorSchema::try_from(fragment).unwrap();
+        let mut policies = ast::PolicySet::new();
+        let policy = cedar_policy_core::parser::parse_policy(
+            Some(PolicyID::from_string("policy0")),
+            r#"permit(principal == Usr::"alice", action, resource);"#,
+        )
+        .expect("Failed to parse");
+        policies.add_static(policy).unwrap();
+        let rust_res = Validator::new(schema).validate(&policies, ValidationMode::Strict);
+
+        assert_eq!(
+            validation_kinds_diff(&rust_res, &["unknownEntity".to_string()]),
+            None
+        );
+        let diff = validation_kinds_diff(&rust_res, &["emptySetErr".to_string()])
+            .expect("kinds should differ");
+        assert_eq!(diff.lean_kind, "emptySetErr");
+        assert!(diff.expected_rust_kinds.contains("EmptySetForbidden"));
+        assert!(diff.rust_kinds.contains("UnrecognizedEntityType"));
+        assert!(validation_kinds_diff(&rust_res, &["notAKind".to_string()]).is_some());
+    }
+}
@@ -23,7 +23,9 @@
 use core::panic;
 use std::collections::HashMap;
 use std::ffi::CString;
-use std::sync::Once;
+use std::sync::mpsc;
+use std::sync::{Once, OnceLock};
+use std::time::Duration;
 
 use crate::definitional_request_types::*;
 use cedar_policy::ffi;
@@ -52,6 +54,7 @@ extern "C" {
     fn isAuthorizedDRT(req: *mut lean_object) -> *mut lean_object;
     fn validateDRT(req: *mut lean_object) -> *mut lean_object;
     fn evaluateDRT(req: *mut lean_object) -> *mut lean_object;
+    fn evaluateErrorDRT(req: *mut lean_object) -> *mut lean_object;
     fn partialEvaluateDRT(req: *mut lean_object) -> *mut lean_object;
     fn partialAuthorizeDRT(req: *mut lean_object) -> *mut lean_object;
     fn initialize_DiffTest_Main(builtin: u8, ob: *mut lean_object) -> *mut lean_object;
@@ -62,8 +65,27 @@ pub const LEAN_EVAL_MSG: &str = "Lean evaluation time (ns) : ";
 pub const LEAN_VAL_MSG: &str = "Lean validation time (ns) : ";
 pub const LEAN_PE_MSG: &str = "Lean partial evaluation time (ns) : ";
 pub const LEAN_PA_MSG: &str = "Lean partial authorization time (ns) : ";
+/// Prefix of the `TestResult::Failure` message for a Lean call that timed out
+pub const LEAN_TIMEOUT_MSG: &str = "Lean timeout";
 static START: Once = Once::new();
 
+/// One of the `*DRT` functions exported by the Lean code
+type LeanFn = unsafe extern "C" fn(*mut lean_object) -> *mut lean_object;
+
+/// A call for the Lean worker thread to make: the function, its JSON request,
+/// and where to send the JSON response
+type LeanJob = (LeanFn, String, mpsc::Sender<String>);
+
+/// How many calls may wait for the Lean worker thread while it's busy. Calls
+/// beyond this fail right away, so that a call stuck on a pathological input
+/// doesn't build up a backlog.
+const LEAN_WORKER_QUEUE_LEN: usize = 1;
+
+/// Queue of the Lean worker thread, which makes the calls of every engine
+/// with a timeout. It is started by the first such engine and lives for the
+/// rest of the process.
+static LEAN_WORKER: OnceLock<mpsc::SyncSender<LeanJob>> = OnceLock::new();
+
 #[derive(Debug, Deserialize)]
 struct ListDef<T> {
     l: Vec<T>,
@@ -111,12 +133,43 @@ enum ValidationResponseInner {
 
 type AuthorizationResponse = ResultDef<TimedDef<AuthorizationResponseInner>>;
 type EvaluationResponse = ResultDef<TimedDef<bool>>;
+type EvaluationErrorResponse = ResultDef<TimedDef<Option<String>>>;
 type PartialEvaluationResponse = ResultDef<TimedDef<bool>>;
 type ValidationResponse = ResultDef<TimedDef<ValidationResponseInner>>;
 type PartialAuthorizationResponse = ResultDef<TimedDef<FlatPartialResponse>>;
 
 #[derive(Default)]
-pub struct LeanDefinitionalEngine {}
+pub struct LeanDefinitionalEngine {
+    /// If set, authorization calls into Lean give up after this long
+    timeout: Option<Duration>,
+}
+
+/// Call `lean_fn` on `request` on the current thread, which must be a Lean
+/// thread
+fn call_lean(lean_fn: LeanFn, request: String) -> String {
+    let cstring = CString::new(request).expect("`CString::new` failed");
+    // Lean will decrement the reference count when we pass this object: https://github.com/leanprover/lean4/blob/master/src/include/lean/lean.h
+    let req = unsafe { lean_mk_string(cstring.as_ptr() as *const u8) };
+    let response = unsafe { lean_fn(req) };
+    // req can no longer be assumed to exist
+    lean_obj_p_to_rust_string(response)
+}
+
+/// Get the queue of the Lean worker thread, starting the thread if needed
+fn lean_worker() -> &'static mpsc::SyncSender<LeanJob> {
+    LEAN_WORKER.get_or_init(|| {
+        let (jobs, queue) = mpsc::sync_channel::<LeanJob>(LEAN_WORKER_QUEUE_LEN);
+        std::thread::spawn(move || {
+            unsafe { lean_initialize_thread() };
+            for (lean_fn, request, response) in queue {
+                // the receiver is gone if the call timed out
+                let _ = response.send(call_lean(lean_fn, request));
+            }
+            unsafe { lean_finalize_thread() };
+        });
+        jobs
+    })
+}
 
 fn lean_obj_p_to_rust_string(lean_str_obj: *mut lean_object) -> String {
     let lean_obj_p = unsafe { lean_string_cstr(lean_str_obj) };
@@ -132,8 +185,8 @@ fn lean_obj_p_to_rust_string(lean_str_obj: *mut lean_object) -> String {
 }
 
 impl LeanDefinitionalEngine {
-    /// WARNING: we can only have one Lean thread
-    pub fn new() -> Self {
+    /// Initialize the Lean runtime, once per process
+    fn initialize_runtime() {
         START.call_once(|| {
             unsafe {
                 // following: https://lean-lang.org/lean4/doc/dev/ffi.html
@@ -150,8 +203,43 @@ impl LeanDefinitionalEngine {
                 lean_io_mark_end_initialization();
             };
         });
+    }
+
+    /// WARNING: we can only have one Lean thread
+    pub fn new() -> Self {
+        Self::initialize_runtime();
         unsafe { lean_initialize_thread() };
-        Self {}
+        Self { timeout: None }
+    }
+
+    /// Like `new()`, but calls into Lean give up after `timeout` and return a
+    /// `TestResult::Failure` starting with [`LEAN_TIMEOUT_MSG`], so a
+    /// pathological input can't stall the fuzzer. The calls run on a single
+    /// Lean worker thread shared by all such engines, rather than on the
+    /// calling thread. The Lean computation can't be interrupted, so a
+    /// timed-out call keeps the worker busy until it finishes, and calls made
+    /// in the meantime wait in a short queue or fail right away.
+    pub fn new_with_timeout(timeout: Duration) -> Self {
+        Self::initialize_runtime();
+        Self {
+            timeout: Some(timeout),
+        }
+    }
+
+    /// Call `lean_fn` on `request`: on the calling thread, or with a timeout,
+    /// on the Lean worker thread. Returns the response, or the failure message
+    /// if the call timed out.
+    fn call(&self, lean_fn: LeanFn, request: String) -> std::result::Result<String, String> {
+        let Some(timeout) = self.timeout else {
+            return Ok(call_lean(lean_fn, request));
+        };
+        let (response, receiver) = mpsc::channel();
+        lean_worker()
+            .try_send((lean_fn, request, response))
+            .map_err(|_| format!("{LEAN_TIMEOUT_MSG}: worker is busy with an earlier call"))?;
+        receiver
+            .recv_timeout(timeout)
+            .map_err(|_| format!("{LEAN_TIMEOUT_MSG}: no response after {timeout:?}"))
     }
 
     fn deserialize_authorization_response(response_string: String) -> TestResult<TestResponse> {
@@ -212,13 +300,10 @@ impl LeanDefinitionalEngine {
             entities,
         })
         .expect("failed to serialize request, policies, or entities");
-        let cstring = CString::new(request).expect("`CString::new` failed");
-        // Lean will decrement the reference count when we pass this object: https://github.com/leanprover/lean4/blob/master/src/include/lean/lean.h
-        let req = unsafe { lean_mk_string(cstring.as_ptr() as *const u8) };
-        let response = unsafe { isAuthorizedDRT(req) };
-        // req can no longer be assumed to exist
-        let response_string = lean_obj_p_to_rust_string(response);
-        Self::deserialize_authorization_response(response_string)
+        match self.call(isAuthorizedDRT, request) {
+            Ok(response_string) => Self::deserialize_authorization_response(response_string),
+            Err(err) => TestResult::Failure(err),
+        }
     }
 
     fn deserialize_evaluation_response(response_string: String) -> TestResult<bool> {
@@ -233,6 +318,20 @@ impl LeanDefinitionalEngine {
         }
     }
 
+    fn deserialize_evaluation_error_response(
+        response_string: String,
+    ) -> TestResult<Option<String>> {
+        let resp: EvaluationErrorResponse =
+            serde_json::from_str(&response_string).expect("could not deserialize json");
+        match resp {
+            EvaluationErrorResponse::Ok(resp) => {
+                info!("{}{}", LEAN_EVAL_MSG, resp.duration);
+                TestResult::Success(resp.data)
+            }
+            EvaluationErrorResponse::Error(err) => TestResult::Failure(err),
+        }
+    }
+
     fn deserialize_partial_evaluation_response(response_string: String) -> TestResult<bool> {
         use log::debug;
         debug!("Response: `{response_string}`");
@@ -274,11 +373,12 @@ impl LeanDefinitionalEngine {
             policies,
         })
         .expect("Failed to serialize request");
-        let cstring = CString::new(request).expect("CString::new failed");
-        let req = unsafe { lean_mk_string(cstring.as_ptr() as *const u8) };
-        let response = unsafe { partialAuthorizeDRT(req) };
-        let response_string = lean_obj_p_to_rust_string(response);
-        Self::deserialize_partial_authorization_response(response_string)
+        match self.call(partialAuthorizeDRT, request) {
+            Ok(response_string) => {
+                Self::deserialize_partial_authorization_response(response_string)
+            }
+            Err(err) => TestResult::Failure(err),
+        }
     }
 
     pub fn partial_evaluate(
@@ -297,12 +397,13 @@ impl LeanDefinitionalEngine {
         .expect("Failed to serialize request");
         use log::debug;
         debug!("Request JSON: `{}`", request);
-        let cstring = CString::new(request).expect("CString::new failed");
-        let req = unsafe { lean_mk_string(cstring.as_ptr() as *const u8) };
-        let response = unsafe { partialEvaluateDRT(req) };
-        let response_string = lean_obj_p_to_rust_string(response);
-        debug!("response string: {response_string}");
-        Self::deserialize_partial_evaluation_response(response_string)
+        match self.call(partialEvaluateDRT, request) {
+            Ok(response_string) => {
+                debug!("response string: {response_string}");
+                Self::deserialize_partial_evaluation_response(response_string)
+            }
+            Err(err) => TestResult::Failure(err),
+        }
     }
 
     /// Ask the definitional engine whether the input expression evaluates to the
@@ -322,13 +423,32 @@ impl LeanDefinitionalEngine {
             expected: expected_as_expr.as_ref(),
         })
         .expect("failed to serialize request, expression, or entities");
-        let cstring = CString::new(request).expect("`CString::new` failed");
-        // Lean will decrement the reference count when we pass this object: https://github.com/leanprover/lean4/blob/master/src/include/lean/lean.h
-        let req = unsafe { lean_mk_string(cstring.as_ptr() as *const u8) };
-        let response = unsafe { evaluateDRT(req) };
-        // req can no longer be assumed to exist
-        let response_string = lean_obj_p_to_rust_string(response);
-        Self::deserialize_evaluation_response(response_string)
+        match self.call(evaluateDRT, request) {
+            Ok(response_string) => Self::deserialize_evaluation_response(response_string),
+            Err(err) => TestResult::Failure(err),
+        }
+    }
+
+    /// Ask the definitional engine which kind of error evaluating the input
+    /// expression produces, e.g., `"extensionError"`, or `None` if evaluation
+    /// succeeds
+    pub fn evaluation_error_kind(
+        &self,
+        request: &ast::Request,
+        entities: &Entities,
+        expr: &Expr,
+    ) -> TestResult<Option<String>> {
+        let request: String = serde_json::to_string(&EvaluationRequest {
+            request,
+            entities,
+            expr,
+            expected: None,
+        })
+        .expect("failed to serialize request, expression, or entities");
+        match self.call(evaluateErrorDRT, request) {
+            Ok(response_string) => Self::deserialize_evaluation_error_response(response_string),
+            Err(err) => TestResult::Failure(err),
+        }
     }
 
     fn deserialize_validation_response(
@@ -365,19 +485,19 @@ impl LeanDefinitionalEngine {
             mode: cedar_policy_validator::ValidationMode::default(), // == Strict
         })
         .expect("failed to serialize schema or policies");
-        let cstring = CString::new(request).expect("`CString::new` failed");
-        // Lean will decrement the reference count when we pass this object: https://github.com/leanprover/lean4/blob/master/src/include/lean/lean.h
-        let req = unsafe { lean_mk_string(cstring.as_ptr() as *const u8) };
-        let response = unsafe { validateDRT(req) };
-        // req can no longer be assumed to exist
-        let response_string = lean_obj_p_to_rust_string(response);
-        Self::deserialize_validation_response(response_string)
+        match self.call(validateDRT, request) {
+            Ok(response_string) => Self::deserialize_validation_response(response_string),
+            Err(err) => TestResult::Failure(err),
+        }
     }
 }
 
 impl Drop for LeanDefinitionalEngine {
     fn drop(&mut self) {
-        unsafe { lean_finalize_thread() }
+        // with a timeout, the calling thread was never a Lean thread
+        if self.timeout.is_none() {
+            unsafe { lean_finalize_thread() }
+        }
     }
 }
 
@@ -17,8 +17,10 @@
 mod definitional_request_types;
 mod lean_impl;
 mod logger;
+mod replay;
 pub mod utils;
 
 pub use definitional_request_types::*;
 pub use lean_impl::*;
 pub use logger::*;
+pub use replay::*;
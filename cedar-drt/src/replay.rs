@@ -0,0 +1,46 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Deterministic replay of a saved fuzzer input, independent of the fuzzer
+//! binary.
+
+use libfuzzer_sys::arbitrary::{Arbitrary, Unstructured};
+use std::fmt::Debug;
+use std::path::Path;
+
+/// Read the raw input saved at `path` (e.g., a libFuzzer crash file), rebuild
+/// the fuzz target's input from it the same way libFuzzer does, and run
+/// `harness` on it once. Debug logging is enabled unless `RUST_LOG` says
+/// otherwise, and the rebuilt input (for the ABAC targets, its schema,
+/// policies, entities, and requests) is printed before `harness` runs.
+pub fn replay_from_file<T, R>(
+    path: impl AsRef<Path>,
+    harness: impl FnOnce(T) -> R,
+) -> std::io::Result<R>
+where
+    T: for<'a> Arbitrary<'a> + Debug,
+{
+    let bytes = std::fs::read(path)?;
+    let input = T::arbitrary_take_rest(Unstructured::new(&bytes))
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    // ignore the error if a logger is already set
+    let _ = env_logger::Builder::new()
+        .filter_level(log::LevelFilter::Debug)
+        .parse_default_env()
+        .try_init();
+    println!("Input:\n{input:#?}");
+    Ok(harness(input))
+}
@@ -15,6 +15,7 @@
  */
 
 use cedar_policy_core::ast::Expr;
+use cedar_policy_core::entities::Entities;
 use cedar_policy_core::est;
 use serde::Serialize;
 use serde::Serializer;
@@ -23,3 +24,13 @@ pub fn expr_to_est<S: Serializer>(e: &Expr, s: S) -> Result<S::Ok, S::Error> {
     let est: est::Expr = e.clone().into();
     est.serialize(s)
 }
+
+/// Serialize `entities` in the JSON entity format, so that a logged input can
+/// be replayed with its entity store. If the entities fail to convert to JSON,
+/// serialize an empty object instead of losing the rest of the input.
+pub fn entities_to_json<S: Serializer>(entities: &Entities, s: S) -> Result<S::Ok, S::Error> {
+    entities
+        .to_json_value()
+        .unwrap_or_else(|_| serde_json::json!({}))
+        .serialize(s)
+}
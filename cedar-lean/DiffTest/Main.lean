@@ -85,6 +85,31 @@ def runAndTime (f : Unit -> α) : BaseIO (Timed α) := do
       .ok { data, duration }
   toString (Lean.toJson result)
 
+def errorName : Error → String
+  | .entityDoesNotExist => "entityDoesNotExist"
+  | .attrDoesNotExist => "attrDoesNotExist"
+  | .typeError => "typeError"
+  | .arithBoundsError => "arithBoundsError"
+  | .extensionError => "extensionError"
+
+-- variant of `evaluateDRT` that returns the kind of error evaluation produced,
+-- or `none` if evaluation succeeded
+@[export evaluateErrorDRT] unsafe def evaluateErrorDRT (req : String) : String :=
+  let result : ParseResult (Timed (Option String)) :=
+    match Lean.Json.parse req with
+    | .error e => .error s!"evaluateErrorDRT: failed to parse input: {e}"
+    | .ok json => do
+      let expr ← getJsonField json "expr" >>= jsonToExpr
+      let request ← getJsonField json "request" >>= jsonToRequest
+      let entities ← getJsonField json "entities" >>= jsonToEntities
+      let result := runAndTime (λ () => evaluate expr request entities)
+      let { data, duration } := unsafeBaseIO result
+      let data := match data with
+        | .error e => some (errorName e)
+        | .ok _ => none
+      .ok { data, duration }
+  toString (Lean.toJson result)
+
 @[export partialAuthorizeDRT] unsafe def partialAuthorizeDRT (req : String) : String :=
   let result : ParseResult (Timed Cedar.Partial.Response) :=
     match Lean.Json.parse req with
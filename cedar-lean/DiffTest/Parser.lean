@@ -341,15 +341,28 @@ def jsonToContext (json : Lean.Json) : ParseResult (Map Attr Value) := do
   | _ => .error ("jsonToContext: context must be a record\n" ++ toString (repr value))
 
 
+/-
+An unknown principal, action, or resource is named after the variable, which
+matches the name used by the Rust partial evaluator.
+-/
+def jsonToUidOrUnknown (var : String) (json : Lean.Json) : ParseResult Cedar.Partial.UidOrUnknown :=
+  match json.getObjVal? "Known" with
+  | .ok known => do
+    let uid ← getJsonField known "euid" >>= jsonToEuid
+    .ok (.known uid)
+  | .error _ => do
+    let _ ← getJsonField json "Unknown"
+    .ok (.unknown var)
+
 def jsonToPartialRequest (json : Lean.Json) : ParseResult Cedar.Partial.Request := do
-  let principal ← getJsonField json "principal" >>= (getJsonField · "Known") >>= (getJsonField · "euid") >>= jsonToEuid
-  let action ← getJsonField json "action" >>= (getJsonField · "Known") >>= (getJsonField · "euid") >>= jsonToEuid
-  let resource ← getJsonField json "resource" >>= (getJsonField · "Known") >>= (getJsonField · "euid") >>= jsonToEuid
+  let principal ← getJsonField json "principal" >>= jsonToUidOrUnknown "principal"
+  let action ← getJsonField json "action" >>= jsonToUidOrUnknown "action"
+  let resource ← getJsonField json "resource" >>= jsonToUidOrUnknown "resource"
   let context ← getJsonField json "context" >>= jsonToPartialContext
   .ok {
-    principal := .known principal,
-    action := .known action,
-    resource := .known resource,
+    principal := principal,
+    action := action,
+    resource := resource,
     context := context
   }
 
@@ -0,0 +1,76 @@
+/*
+ * Copyright Cedar Contributors
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Time converting generated hierarchies of a fixed total size to `Entities`.
+//! Run with `cargo bench --bench hierarchy_size`.
+
+use arbitrary::Unstructured;
+use cedar_policy_core::entities::Entities;
+use cedar_policy_core::extensions::Extensions;
+use cedar_policy_generators::hierarchy::{
+    EntityUIDGenMode, HierarchyGenerator, HierarchyGeneratorMode, NumEntities,
+};
+use cedar_policy_generators::schema::Schema;
+use cedar_policy_generators::settings::ABACSettings;
+use cedar_policy_validator::json_schema;
+use rand::RngCore;
+use std::time::Instant;
+
+const SETTINGS: ABACSettings = ABACSettings::builder()
+    .with_arbitrary_func_call(false)
+    .build();
+
+const SCHEMA: &str = r#"
+    entity Org;
+    entity Group in [Org];
+    entity User in [Group, Org] { age: Long, name: String };
+    entity Document in [Org] { owner: User };
+    action view appliesTo { principal: User, resource: Document };
+"#;
+
+/// Bytes of randomness to provide per entity
+const BYTES_PER_ENTITY: usize = 256;
+
+fn main() {
+    let (fragment, _) =
+        json_schema::Fragment::from_cedarschema_str(SCHEMA, Extensions::all_available())
+            .expect("schema should parse");
+    for total in [1_000, 10_000, 100_000] {
+        let mut bytes = vec![0; total * BYTES_PER_ENTITY];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let mut u = Unstructured::new(&bytes);
+        let schema = Schema::from_raw_schemafrag(fragment.clone(), SETTINGS, &mut u)
+            .expect("failed to generate schema");
+        let start = Instant::now();
+        let hierarchy = HierarchyGenerator {
+            mode: HierarchyGeneratorMode::SchemaBased { schema: &schema },
+            uid_gen_mode: EntityUIDGenMode::default_nanoid_mode(),
+            num_entities: NumEntities::ExactTotal(total),
+            max_hierarchy_depth: None,
+            u: &mut u,
+            extensions: Extensions::all_available(),
+        }
+        .generate()
+        .expect("failed to generate hierarchy");
+        let generate_dur = start.elapsed();
+        assert_eq!(hierarchy.num_entities(), total);
+
+        let start = Instant::now();
+        Entities::try_from(hierarchy).expect("failed to convert hierarchy");
+        let convert_dur = start.elapsed();
+        println!("{total} entities: generate {generate_dur:?}, Entities::try_from {convert_dur:?}");
+    }
+}
@@ -108,6 +108,86 @@ impl UnknownPool {
     }
 }
 
+/// Characters that matter for `like` escaping: the wildcard, the escape
+/// character, a plain ASCII character, and some non-ASCII characters
+/// (including one that looks like `*`)
+const LIKE_ESCAPING_CHARS: &[char] = &['*', '\\', 'a', 'é', '∗'];
+
+/// Integers at or near the boundaries of 64-bit overflow. Adding,
+/// subtracting, or multiplying two of these often overflows, but not always:
+/// e.g., `i64::MAX + -1` and `3037000499 * 3037000499` don't, while
+/// `i64::MIN - 1` and `3037000500 * 3037000500` do.
+pub const OVERFLOW_BOUNDARY_INTS: &[i64] = &[
+    i64::MAX,
+    i64::MIN,
+    0,
+    -1,
+    1,
+    2,
+    -2,
+    i64::MAX - 1,
+    i64::MIN + 1,
+    i64::MAX / 2,
+    i64::MAX / 2 + 1,
+    i64::MIN / 2,
+    i64::MIN / 2 - 1,
+    // `floor(sqrt(i64::MAX))` and one more, which squares to just past it
+    3_037_000_499,
+    3_037_000_500,
+    -3_037_000_499,
+    -3_037_000_500,
+    1 << 32,
+    -(1 << 32),
+];
+
+/// Characters for Unicode-heavy strings: ASCII, multi-byte characters
+/// (including ones outside the BMP, which are surrogate pairs in UTF-16),
+/// combining characters, and the codepoints on either side of the surrogate
+/// range. `é` can come out precomposed (NFC) or as `e` followed by a combining
+/// acute accent (NFD). Cedar doesn't normalize strings, so the two forms are
+/// different strings and must each survive printing and parsing unchanged.
+const UNICODE_STRING_CHARS: &[char] = &[
+    'a',
+    'Z',
+    '_',
+    ' ',
+    'e',
+    'é',
+    '\u{0301}',
+    '\u{0308}',
+    'ß',
+    'Ω',
+    '日',
+    '本',
+    '\u{200D}',
+    '\u{D7FF}',
+    '\u{E000}',
+    '\u{FFFD}',
+    '\u{FFFF}',
+    '\u{10000}',
+    '🦀',
+    '\u{10FFFF}',
+];
+
+/// Maximum length, in characters, of strings from `arbitrary_unicode_string()`
+pub(crate) const MAX_UNICODE_STRING_LEN: usize = 8;
+
+/// Generate an arbitrary nonempty string of up to `max_len` characters drawn
+/// from `UNICODE_STRING_CHARS`
+pub(crate) fn arbitrary_unicode_string(
+    u: &mut Unstructured<'_>,
+    max_len: usize,
+) -> Result<SmolStr> {
+    let len = u.int_in_range(1..=max_len.max(1))?;
+    (0..len)
+        .map(|_| {
+            u.choose(UNICODE_STRING_CHARS)
+                .copied()
+                .map_err(|e| while_doing("choosing a unicode string character".into(), e))
+        })
+        .collect()
+}
+
 /// Pool of integer and string constants
 #[derive(Debug, Clone)]
 pub struct ConstantPool {
@@ -178,6 +258,15 @@ impl ConstantPool {
         })
     }
 
+    /// Get an arbitrary int at or near the boundaries of 64-bit overflow (see
+    /// `OVERFLOW_BOUNDARY_INTS`), so that arithmetic on it is likely to land on
+    /// either side of an overflow error
+    pub fn arbitrary_boundary_int(&self, u: &mut Unstructured<'_>) -> Result<i64> {
+        u.choose(OVERFLOW_BOUNDARY_INTS)
+            .copied()
+            .map_err(|e| while_doing("choosing an overflow boundary int".into(), e))
+    }
+
     /// size hint for arbitrary_int_constant()
     pub fn arbitrary_int_constant_size_hint(_depth: usize) -> (usize, Option<usize>) {
         size_hint_for_choose(None)
@@ -212,13 +301,33 @@ impl ConstantPool {
         })
     }
 
+    /// Get an arbitrary string of up to `MAX_PATTERN_LEN` characters drawn
+    /// from `*`, `\`, and a few ASCII and non-ASCII characters. These exercise
+    /// the escaping in `like`, both as the matched string and as the basis
+    /// of a pattern, where a `*` may become a literal `\*` or a wildcard.
+    pub fn arbitrary_like_escaping_string(&self, u: &mut Unstructured<'_>) -> Result<SmolStr> {
+        let len = u.int_in_range(0..=MAX_PATTERN_LEN)?;
+        (0..len)
+            .map(|_| {
+                u.choose(LIKE_ESCAPING_CHARS)
+                    .copied()
+                    .map_err(|e| while_doing("choosing a like escaping character".into(), e))
+            })
+            .collect()
+    }
+
     /// Produce a RHS of a like operation
-    /// It's derived from a random string constant in the pool: We perform transformations over it such as adding a char, deleting a char and adding a wildcard star.
+    /// It's derived from a random string constant in the pool, or sometimes
+    /// from `arbitrary_like_escaping_string()`: We perform transformations over it such as adding a char, deleting a char and adding a wildcard star.
     pub fn arbitrary_pattern_literal(
         &self,
         u: &mut Unstructured<'_>,
     ) -> Result<Vec<ast::PatternElem>> {
-        let matched_string = self.arbitrary_string_constant_bounded(u, MAX_PATTERN_LEN)?;
+        let matched_string = if u.ratio(1, 4)? {
+            self.arbitrary_like_escaping_string(u)?
+        } else {
+            self.arbitrary_string_constant_bounded(u, MAX_PATTERN_LEN)?
+        };
 
         let mut pattern = Vec::new();
         for c in matched_string.chars() {
@@ -275,16 +384,42 @@ impl ConstantPool {
         })
     }
 
-    /// Generate a valid IP net representation and mutate it
-    pub fn arbitrary_ip_str(&self, u: &mut Unstructured<'_>) -> Result<SmolStr> {
-        let valid_str = if u.ratio(1, 2)? {
+    /// Generate a valid IP net representation
+    pub fn arbitrary_valid_ip_str(&self, u: &mut Unstructured<'_>) -> Result<SmolStr> {
+        Ok(if u.ratio(1, 2)? {
             self.arbitrary_ipv4_str(u)?
         } else {
             self.arbitrary_ipv6_str(u)?
-        };
+        }
+        .into())
+    }
+
+    /// Generate a valid IP net representation and mutate it
+    pub fn arbitrary_ip_str(&self, u: &mut Unstructured<'_>) -> Result<SmolStr> {
+        let valid_str = self.arbitrary_valid_ip_str(u)?;
         mutate_str(u, &valid_str).map(SmolStr::new)
     }
 
+    /// Generate an IP net representation at a boundary: a `/0`, `/32`, or
+    /// `/128` prefix, or an IPv4 address embedded in IPv6. Cedar rejects the
+    /// embedded forms, so these exercise the `ip()` error path.
+    pub fn arbitrary_boundary_ip_str(&self, u: &mut Unstructured<'_>) -> Result<SmolStr> {
+        let ipv4: Ipv4Addr = u.arbitrary()?;
+        let ipv6: Ipv6Addr = u.arbitrary()?;
+        let s = uniform!(
+            u,
+            format!("{ipv4}/0"),
+            format!("{ipv4}/32"),
+            format!("{ipv6}/0"),
+            format!("{ipv6}/128"),
+            // IPv4-mapped
+            format!("::ffff:{ipv4}"),
+            // IPv4-compatible, with a prefix covering the IPv4 part
+            format!("::{ipv4}/{}", u.int_in_range(96..=128)?)
+        );
+        Ok(s.into())
+    }
+
     /// Generate a valid decimal number representation and mutate it
     pub fn arbitrary_decimal_str(&self, u: &mut Unstructured<'_>) -> Result<SmolStr> {
         let i = self.arbitrary_int_constant(u)?;
@@ -296,6 +431,48 @@ impl ConstantPool {
         .map(SmolStr::new)
     }
 
+    /// Generate an IP net representation that `ip()` rejects: an octet or
+    /// prefix out of range, too few octets, a leading zero, an IPv4 address
+    /// embedded in IPv6, or not an address at all
+    pub fn arbitrary_invalid_ip_str(&self, u: &mut Unstructured<'_>) -> Result<SmolStr> {
+        let ipv4: Ipv4Addr = u.arbitrary()?;
+        let ipv6: Ipv6Addr = u.arbitrary()?;
+        let [a, b, c, _] = ipv4.octets();
+        let s = uniform!(
+            u,
+            format!("{a}.{b}.{c}.{}", u.int_in_range(256..=999)?),
+            format!("{ipv4}/{}", u.int_in_range(33..=255)?),
+            format!("{ipv6}/{}", u.int_in_range(129..=255)?),
+            format!("{a}.{b}.{c}"),
+            format!("0{a}.{b}.{c}.1"),
+            format!("::ffff:{ipv4}"),
+            "999.999.999.999".to_string(),
+            "".to_string(),
+            "not an ip".to_string()
+        );
+        Ok(s.into())
+    }
+
+    /// Generate a decimal number representation that `decimal()` rejects:
+    /// more than four fractional digits, a missing integer or fractional part,
+    /// out of range, or not a number at all
+    pub fn arbitrary_invalid_decimal_str(&self, u: &mut Unstructured<'_>) -> Result<SmolStr> {
+        let i = self.arbitrary_int_constant(u)?;
+        let s = uniform!(
+            u,
+            format!("{i}.{}", u.int_in_range(10000..=99999999)?),
+            format!("{i}"),
+            format!("{i}."),
+            format!(".{}", i.unsigned_abs()),
+            "922337203685477.5808".to_string(),
+            "-922337203685477.5809".to_string(),
+            "1e3".to_string(),
+            "".to_string(),
+            "abc".to_string()
+        );
+        Ok(s.into())
+    }
+
     /// size hint for arbitrary_string_constant()
     pub fn arbitrary_string_constant_size_hint(_depth: usize) -> (usize, Option<usize>) {
         size_hint_for_choose(None)
@@ -32,6 +32,12 @@ pub enum Error {
     /// Tried to generate an expression deeper than allowed, and couldn't
     /// recover by putting some depth-0 expression in the leaf
     TooDeep,
+    /// Generated more AST nodes than allowed by
+    /// [`crate::settings::max_ast_nodes`]. Like `NotEnoughData`, this aborts
+    /// generation of the current input, and it converts to
+    /// `arbitrary::Error::NotEnoughData`, so callers that only see the
+    /// converted error can't tell the two apart
+    TooLarge,
     /// Generated schema ended up with no valid principal types, no valid
     /// resource types, or both
     NoValidPrincipalOrResourceTypes,
@@ -41,6 +47,9 @@ pub enum Error {
     /// Tried to generate something using the `like` operator, but the `like`
     /// operator was disabled in settings
     LikeDisabled,
+    /// Tried to generate action attributes or accesses to them, but action
+    /// groups and attributes were disabled in settings
+    ActionAttrsDisabled,
     /// `IncorrectFormat` error that was generated by the `arbitrary` crate directly.
     /// We try to maintain the invariant that we don't generate these ourselves,
     /// preferring the more specific errors above
@@ -54,6 +63,10 @@ pub enum Error {
     /// An error that occurs building an `ast::Context`, possibly due to errors
     /// in extension constructors.
     ContextError(ast::ContextCreationError),
+    /// A schema given to us as input couldn't be parsed, or uses features we
+    /// can't generate policies, entities, or requests for (e.g., multiple
+    /// namespaces or unrecognized extension types)
+    UnsupportedSchema(String),
     /// Error generated by the `arbitrary` crate, other than the three in
     /// `arbitrary::Error` as of this writing. This is necessary because
     /// `arbitrary::Error` is marked non-exhaustive. We don't generate these
@@ -70,12 +83,15 @@ impl From<Error> for arbitrary::Error {
             Error::NotEnoughData => arbitrary::Error::NotEnoughData,
             Error::EmptyChoose { .. } => arbitrary::Error::EmptyChoose,
             Error::TooDeep => arbitrary::Error::IncorrectFormat,
+            Error::TooLarge => arbitrary::Error::NotEnoughData,
             Error::NoValidPrincipalOrResourceTypes => arbitrary::Error::IncorrectFormat,
             Error::ExtensionsDisabled => arbitrary::Error::IncorrectFormat,
             Error::LikeDisabled => arbitrary::Error::IncorrectFormat,
+            Error::ActionAttrsDisabled => arbitrary::Error::IncorrectFormat,
             Error::EntitiesError(_) => arbitrary::Error::IncorrectFormat,
             Error::IncorrectFormat { .. } => arbitrary::Error::IncorrectFormat,
             Error::ContextError(_) => arbitrary::Error::IncorrectFormat,
+            Error::UnsupportedSchema(_) => arbitrary::Error::IncorrectFormat,
             Error::OtherArbitrary(e) => e,
         }
     }
@@ -14,7 +14,10 @@
  * limitations under the License.
  */
 
-use crate::abac::{AttrValue, AvailableExtensionFunctions, ConstantPool, Type, UnknownPool};
+use crate::abac::{
+    arbitrary_unicode_string, AttrValue, AvailableExtensionFunctions, ConstantPool, Type,
+    UnknownPool, MAX_UNICODE_STRING_LEN,
+};
 use crate::collections::HashMap;
 use crate::err::{while_doing, Error, Result};
 use crate::hierarchy::{
@@ -52,9 +55,51 @@ pub struct ExprGenerator<'a> {
     /// For any entity UIDs that are generated as part of the expression.
     /// As of this writing, this is only used when `hierarchy` is `None`.
     pub uid_gen_mode: EntityUIDGenMode,
+    /// If this is present, the action that the policy being generated applies
+    /// to. Expressions over `context` attributes then only use attributes
+    /// this action declares; otherwise, only attributes every action declares.
+    pub action: Option<&'a ast::EntityUID>,
 }
 
 impl<'a> ExprGenerator<'a> {
+    /// Weight of `if-then-else` among the alternatives of a `gen!`, where
+    /// `default` is its weight unless `conditional_weight` is set
+    fn ite_weight(&self, default: u8) -> u8 {
+        match self.settings.conditional_weight {
+            0 => default,
+            weight => weight,
+        }
+    }
+
+    /// Get an int literal: usually from the constant pool, but sometimes at or
+    /// near the boundaries of 64-bit overflow, so that arithmetic on it
+    /// exercises overflow errors
+    fn arbitrary_int_literal(&self, u: &mut Unstructured<'_>) -> Result<i64> {
+        gen!(u,
+        3 => self.constant_pool.arbitrary_int_constant(u),
+        1 => self.constant_pool.arbitrary_boundary_int(u))
+    }
+
+    /// Get a string literal: usually from the constant pool, but a quarter of
+    /// the time a Unicode-heavy string if `enable_unicode_strings` is set
+    fn arbitrary_string_literal(&self, u: &mut Unstructured<'_>) -> Result<SmolStr> {
+        if self.settings.enable_unicode_strings && u.ratio(1, 4)? {
+            arbitrary_unicode_string(u, MAX_UNICODE_STRING_LEN)
+        } else {
+            self.constant_pool.arbitrary_string_constant(u)
+        }
+    }
+
+    /// Weight of the operator `op` among the alternatives of a `gen!`, where
+    /// `default` is its weight unless `operator_weights` sets it
+    fn op_weight(&self, op: &str, default: u8) -> u8 {
+        self.settings
+            .operator_weights
+            .iter()
+            .find(|(name, _)| *name == op)
+            .map_or(default, |(_, weight)| *weight)
+    }
+
     /// get a (fully general) arbitrary expression conforming to the schema, but
     /// no attempt to match types.
     ///
@@ -75,97 +120,128 @@ impl<'a> ExprGenerator<'a> {
                 // a literal or variable
                 self.generate_literal_or_var(u)
             },
-            1 => {
+            self.op_weight("==", 1) => {
                 // == expression
                 Ok(ast::Expr::is_eq(
                     self.generate_expr(max_depth - 1, u)?,
                     self.generate_expr(max_depth - 1, u)?,
                 ))
             },
-            1 => {
+            self.op_weight("!", 1) => {
                 // not expression
                 Ok(ast::Expr::not(self.generate_expr(max_depth - 1, u)?))
             },
             1 => {
                 // any other expression
+                let is_weight: u8 = if self.settings.enable_is_operator {
+                    self.op_weight("is", 1)
+                } else {
+                    0
+                };
                 gen!(u,
-                    2 => Ok(ast::Expr::ite(
+                    self.ite_weight(2) => Ok(ast::Expr::ite(
                         self.generate_expr(max_depth - 1, u)?,
                         self.generate_expr(max_depth - 1, u)?,
                         self.generate_expr(max_depth - 1, u)?,
                     )),
-                    2 => Ok(ast::Expr::and(
+                    self.op_weight("&&", 2) => Ok(ast::Expr::and(
                         self.generate_expr(max_depth - 1, u)?,
                         self.generate_expr(max_depth - 1, u)?,
                     )),
-                    2 => Ok(ast::Expr::or(
+                    self.op_weight("||", 2) => Ok(ast::Expr::or(
                         self.generate_expr(max_depth - 1, u)?,
                         self.generate_expr(max_depth - 1, u)?,
                     )),
-                    1 => Ok(ast::Expr::less(
+                    self.op_weight("<", 1) => Ok(ast::Expr::less(
                         self.generate_expr(max_depth - 1, u)?,
                         self.generate_expr(max_depth - 1, u)?,
                     )),
-                    1 => Ok(ast::Expr::lesseq(
+                    self.op_weight("<=", 1) => Ok(ast::Expr::lesseq(
                         self.generate_expr(max_depth - 1, u)?,
                         self.generate_expr(max_depth - 1, u)?,
                     )),
-                    1 => Ok(ast::Expr::greater(
+                    self.op_weight(">", 1) => Ok(ast::Expr::greater(
                         self.generate_expr(max_depth - 1, u)?,
                         self.generate_expr(max_depth - 1, u)?,
                     )),
-                    1 => Ok(ast::Expr::greatereq(
+                    self.op_weight(">=", 1) => Ok(ast::Expr::greatereq(
                         self.generate_expr(max_depth - 1, u)?,
                         self.generate_expr(max_depth - 1, u)?,
                     )),
-                    1 => Ok(ast::Expr::add(
+                    self.op_weight("+", 1) => Ok(ast::Expr::add(
                         self.generate_expr(max_depth - 1, u)?,
                         self.generate_expr(max_depth - 1, u)?,
                     )),
-                    1 => Ok(ast::Expr::sub(
+                    self.op_weight("-", 1) => Ok(ast::Expr::sub(
                         self.generate_expr(max_depth - 1, u)?,
                         self.generate_expr(max_depth - 1, u)?,
                     )),
-                    1 => Ok(ast::Expr::mul(
+                    self.op_weight("*", 1) => Ok(ast::Expr::mul(
                         self.generate_expr(max_depth - 1, u)?,
                         self.generate_expr(max_depth - 1, u)?,
                     )),
-                    1 => {
+                    self.op_weight("neg", 1) => {
                         // negation expression
                         Ok(ast::Expr::neg(self.generate_expr(max_depth - 1, u)?))
                     },
-                    6 => Ok(ast::Expr::is_in(
+                    self.op_weight("in", 6) => Ok(ast::Expr::is_in(
                         self.generate_expr(max_depth - 1, u)?,
                         self.generate_expr(max_depth - 1, u)?,
                     )),
-                    1 => Ok(ast::Expr::contains(
+                    self.op_weight("contains", 1) => Ok(ast::Expr::contains(
                         self.generate_expr(max_depth - 1, u)?,
                         self.generate_expr(max_depth - 1, u)?,
                     )),
-                    1 => Ok(ast::Expr::contains_all(
+                    self.op_weight("containsAll", 1) => Ok(ast::Expr::contains_all(
                         self.generate_expr(max_depth - 1, u)?,
                         self.generate_expr(max_depth - 1, u)?,
                     )),
-                    1 => Ok(ast::Expr::contains_any(
+                    self.op_weight("containsAny", 1) => Ok(ast::Expr::contains_any(
                         self.generate_expr(max_depth - 1, u)?,
                         self.generate_expr(max_depth - 1, u)?,
                     )),
-                    2 => {
+                    // `containsAll` and `containsAny` on related set literals
+                    self.op_weight("containsAll", 1) => {
+                        let (receiver, arg) = self.generate_set_op_operands(max_depth - 1, u)?;
+                        Ok(ast::Expr::contains_all(receiver, arg))
+                    },
+                    self.op_weight("containsAny", 1) => {
+                        let (receiver, arg) = self.generate_set_op_operands(max_depth - 1, u)?;
+                        Ok(ast::Expr::contains_any(receiver, arg))
+                    },
+                    self.op_weight("like", 2) => {
                         if self.settings.enable_like {
                             Ok(ast::Expr::like(
-                                self.generate_expr(max_depth - 1, u)?,
+                                if u.ratio(1, 4)? {
+                                    ast::Expr::val(
+                                        self.constant_pool.arbitrary_like_escaping_string(u)?,
+                                    )
+                                } else {
+                                    self.generate_expr(max_depth - 1, u)?
+                                },
                                 self.constant_pool.arbitrary_pattern_literal(u)?,
                             ))
                         } else {
                             Err(Error::LikeDisabled)
                         }
                     },
-                    1 => {
+                    is_weight => {
                             Ok(ast::Expr::is_entity_type(
                                 self.generate_expr(max_depth - 1, u)?,
                                 u.choose(&self.schema.entity_types)?.clone(),
                             ))
                     },
+                    // `e is T in e2`, which is sugar for `e is T && e in e2`
+                    is_weight => {
+                            let e = self.generate_expr(max_depth - 1, u)?;
+                            Ok(ast::Expr::and(
+                                ast::Expr::is_entity_type(
+                                    e.clone(),
+                                    u.choose(&self.schema.entity_types)?.clone(),
+                                ),
+                                ast::Expr::is_in(e, self.generate_expr(max_depth - 1, u)?),
+                            ))
+                    },
                     1 => {
                         let mut l = Vec::new();
                         u.arbitrary_loop(Some(0), Some(self.settings.max_width as u32), |u| {
@@ -174,6 +250,7 @@ impl<'a> ExprGenerator<'a> {
                         })?;
                         Ok(ast::Expr::set(l))
                     },
+                    1 => self.generate_degenerate_set(u, |gen, u| gen.generate_expr(max_depth - 1, u)),
                     1 => {
                         let mut r = HashMap::new();
                         u.arbitrary_loop(Some(0), Some(self.settings.max_width as u32), |u| {
@@ -214,7 +291,7 @@ impl<'a> ExprGenerator<'a> {
                                 .collect::<Result<_>>()?,
                         ))
                     },
-                    7 => {
+                    self.op_weight(".", 7) => {
                         let attr_name = gen!(u,
                             1 => {
                                 let s: String = u.arbitrary()?;
@@ -240,7 +317,7 @@ impl<'a> ExprGenerator<'a> {
                         };
                         Ok(ast::Expr::get_attr(e, attr_name))
                     },
-                    4 => {
+                    self.op_weight("has", 4) => {
                         let attr_name = uniform!(u,
                            self.schema.arbitrary_attr(u)?.0.clone(),
                             {
@@ -256,6 +333,484 @@ impl<'a> ExprGenerator<'a> {
         }
     }
 
+    /// get a degenerate set literal, to exercise the edge cases of `contains`,
+    /// `containsAll`, `containsAny`, and set equality: `[]`, a single element
+    /// `[e]`, or a duplicate element `[e, e]` or `[e, e2, e]`, which should
+    /// behave the same as `[e]` or `[e, e2]`.
+    ///
+    /// `element`: generates each element
+    fn generate_degenerate_set(
+        &mut self,
+        u: &mut Unstructured<'_>,
+        mut element: impl FnMut(&mut Self, &mut Unstructured<'_>) -> Result<ast::Expr>,
+    ) -> Result<ast::Expr> {
+        uniform!(
+            u,
+            Ok(ast::Expr::set([])),
+            Ok(ast::Expr::set([element(self, u)?])),
+            {
+                let e = element(self, u)?;
+                Ok(ast::Expr::set([e.clone(), e]))
+            },
+            {
+                let e = element(self, u)?;
+                let other = element(self, u)?;
+                Ok(ast::Expr::set([e.clone(), other, e]))
+            }
+        )
+    }
+
+    /// get the receiver and argument of a `containsAll()` or `containsAny()`
+    /// as set literals drawn from one pool of elements, so that they are both
+    /// empty, exactly one empty, overlapping, or disjoint. Independently
+    /// generated sets rarely share elements, so otherwise cases like
+    /// `[].containsAll([])` (true) and `[1].containsAny([])` (false) are only
+    /// reached by chance.
+    ///
+    /// The pooled elements are syntactically distinct, so the disjoint sets
+    /// are disjoint unless two distinct elements evaluate to the same value.
+    ///
+    /// `max_depth`: maximum size (i.e., depth) of each element.
+    pub fn generate_set_op_operands(
+        &self,
+        max_depth: usize,
+        u: &mut Unstructured<'_>,
+    ) -> Result<(ast::Expr, ast::Expr)> {
+        let element_ty: Type = u.arbitrary()?;
+        let mut elements: Vec<ast::Expr> = Vec::new();
+        u.arbitrary_loop(Some(1), Some(self.settings.max_width as u32), |u| {
+            let e = self.generate_expr_for_type(&element_ty, max_depth, u)?;
+            if !elements.contains(&e) {
+                elements.push(e);
+            }
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        let (left, right) = elements.split_at(u.int_in_range(0..=elements.len())?);
+        let (receiver, arg): (Vec<ast::Expr>, Vec<ast::Expr>) = uniform!(
+            u,
+            // both empty
+            (vec![], vec![]),
+            // only the argument empty
+            (elements.clone(), vec![]),
+            // only the receiver empty
+            (vec![], elements.clone()),
+            // overlapping in (at least) one shared element
+            {
+                let shared = u.choose(&elements)?;
+                (
+                    left.iter().chain([shared]).cloned().collect(),
+                    right.iter().chain([shared]).cloned().collect(),
+                )
+            },
+            // disjoint
+            (left.to_vec(), right.to_vec())
+        );
+        Ok((ast::Expr::set(receiver), ast::Expr::set(arg)))
+    }
+
+    /// get a tree of `&&` and `||` operators nested exactly `nesting` deep,
+    /// e.g., `(((a && b) || c) && d) || e`.
+    ///
+    /// The leaves are mostly boolean-typed expressions, but are occasionally
+    /// untyped so that some subexpressions error. Depending on the
+    /// surrounding operands, those errors may or may not be short-circuited.
+    ///
+    /// `max_depth`: maximum size (i.e., depth) of each leaf expression.
+    pub fn generate_nested_bool_expr(
+        &mut self,
+        nesting: usize,
+        max_depth: usize,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ast::Expr> {
+        if nesting == 0 {
+            return self.generate_nested_bool_leaf(max_depth, u);
+        }
+        // one operand always recurses, so that the tree reaches the full
+        // `nesting` depth
+        let nested = self.generate_nested_bool_expr(nesting - 1, max_depth, u)?;
+        let other = self.generate_nested_bool_leaf(max_depth, u)?;
+        let (left, right) = if u.arbitrary()? {
+            (nested, other)
+        } else {
+            (other, nested)
+        };
+        if u.arbitrary()? {
+            Ok(ast::Expr::and(left, right))
+        } else {
+            Ok(ast::Expr::or(left, right))
+        }
+    }
+
+    /// internal helper function: get a leaf for `generate_nested_bool_expr()`
+    fn generate_nested_bool_leaf(
+        &mut self,
+        max_depth: usize,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ast::Expr> {
+        gen!(u,
+        4 => self.generate_expr_for_type(&Type::bool(), max_depth, u),
+        1 => self.generate_expr(max_depth, u))
+    }
+
+    /// get an expression following the ownership pattern
+    /// `resource is Document && resource.owner == principal`, where `owner`
+    /// is an attribute of the resource type `Document` whose type is a
+    /// principal type. The `is` test guards the access, so the expression
+    /// type-checks whatever the type of `resource`.
+    pub fn generate_ownership_expr(&self, u: &mut Unstructured<'_>) -> Result<ast::Expr> {
+        let attrs = self.schema.ownership_attributes();
+        let (resource_type, attr_name) = u
+            .choose(&attrs)
+            .map_err(|e| while_doing("choosing an ownership attribute".into(), e))?;
+        let resource = ast::Expr::var(ast::Var::Resource);
+        let owner = ast::Expr::get_attr(resource.clone(), attr_name.clone());
+        let principal = ast::Expr::var(ast::Var::Principal);
+        let ownership = uniform!(
+            u,
+            Ok(ast::Expr::is_eq(owner, principal)),
+            Ok(ast::Expr::is_eq(principal, owner)),
+            Ok(ast::Expr::and(
+                ast::Expr::has_attr(resource.clone(), attr_name.clone()),
+                ast::Expr::is_eq(owner, principal),
+            ))
+        )?;
+        Ok(ast::Expr::and(
+            ast::Expr::is_entity_type(resource, resource_type.clone()),
+            ownership,
+        ))
+    }
+
+    /// get an expression combining arithmetic and comparison on `Long`-typed
+    /// `context` attributes, e.g., `context.count + 1 > context.limit`.
+    /// The attributes are required ones that `self.action` declares (see
+    /// [`Schema::context_long_attributes`]). The arithmetic may overflow.
+    pub fn generate_context_arith_cmp_expr(&self, u: &mut Unstructured<'_>) -> Result<ast::Expr> {
+        let attrs = self.schema.context_long_attributes(self.action);
+        let context_attr = |u: &mut Unstructured<'_>| -> Result<ast::Expr> {
+            let attr_name = u
+                .choose(&attrs)
+                .map_err(|e| while_doing("choosing a Long context attribute".into(), e))?;
+            Ok(ast::Expr::get_attr(
+                ast::Expr::var(ast::Var::Context),
+                attr_name.clone(),
+            ))
+        };
+        let lhs = context_attr(u)?;
+        let operand = if u.ratio(1, 2)? {
+            context_attr(u)?
+        } else {
+            ast::Expr::val(self.arbitrary_int_literal(u)?)
+        };
+        let lhs = uniform!(
+            u,
+            ast::Expr::add(lhs, operand),
+            ast::Expr::sub(lhs, operand),
+            ast::Expr::mul(lhs, operand)
+        );
+        let rhs = context_attr(u)?;
+        uniform!(
+            u,
+            Ok(ast::Expr::less(lhs, rhs)),
+            Ok(ast::Expr::lesseq(lhs, rhs)),
+            Ok(ast::Expr::greater(lhs, rhs)),
+            Ok(ast::Expr::greatereq(lhs, rhs)),
+            Ok(ast::Expr::is_eq(lhs, rhs))
+        )
+    }
+
+    /// get an equality between two entity-typed `context` attributes with the
+    /// same entity type, e.g., `context.owner == context.viewer`. Both sides
+    /// may be the same attribute. The attributes are required ones that
+    /// `self.action` declares (see [`Schema::context_entity_attributes`]).
+    pub fn generate_context_entity_eq_expr(&self, u: &mut Unstructured<'_>) -> Result<ast::Expr> {
+        let attrs = self.schema.context_entity_attributes(self.action);
+        let (etype, lhs) = u
+            .choose(&attrs)
+            .map_err(|e| while_doing("choosing an entity-typed context attribute".into(), e))?;
+        let same_type: Vec<&SmolStr> = attrs
+            .iter()
+            .filter(|(ty, _)| ty == etype)
+            .map(|(_, attr_name)| attr_name)
+            .collect();
+        let rhs = u.choose(&same_type).map_err(|e| {
+            while_doing(
+                "choosing a context attribute of the same entity type".into(),
+                e,
+            )
+        })?;
+        Ok(ast::Expr::is_eq(
+            ast::Expr::get_attr(ast::Expr::var(ast::Var::Context), lhs.clone()),
+            ast::Expr::get_attr(ast::Expr::var(ast::Var::Context), (*rhs).clone()),
+        ))
+    }
+
+    /// get a call to the constructor for the extension type `target_type`,
+    /// applied to a literal that the constructor rejects, e.g.,
+    /// `decimal("1.23456")`. Evaluating the call always errors.
+    pub fn generate_invalid_ext_constructor_call(
+        &self,
+        target_type: &Type,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ast::Expr> {
+        if !self.settings.enable_extensions {
+            return Err(Error::ExtensionsDisabled);
+        }
+        let constructor = self
+            .ext_funcs
+            .arbitrary_constructor_for_type(target_type, u)?;
+        let arg = match target_type {
+            Type::IPAddr => self.constant_pool.arbitrary_invalid_ip_str(u)?,
+            Type::Decimal => self.constant_pool.arbitrary_invalid_decimal_str(u)?,
+            _ => {
+                return Err(Error::EmptyChoose {
+                    doing_what: format!(
+                        "getting an invalid extension literal for type {target_type:?}"
+                    ),
+                })
+            }
+        };
+        Ok(ast::Expr::call_extension_fn(
+            constructor.name.clone(),
+            vec![ast::Expr::val(arg)],
+        ))
+    }
+
+    /// get an equality or membership test over sets of records whose fields
+    /// are `ipaddr` or `decimal` literals, e.g.,
+    /// `[{addr: ip("1.2.3.4")}].contains({addr: ip("1.2.3.4")})`
+    pub fn generate_nested_ext_literal_expr(&self, u: &mut Unstructured<'_>) -> Result<ast::Expr> {
+        if !self.settings.enable_extensions {
+            return Err(Error::ExtensionsDisabled);
+        }
+        let ext_literal = |u: &mut Unstructured<'_>| -> Result<ast::Expr> {
+            let ty = if u.ratio(1, 2)? {
+                Type::ipaddr()
+            } else {
+                Type::decimal()
+            };
+            let constructor = self.ext_funcs.arbitrary_constructor_for_type(&ty, u)?;
+            let arg = match ty {
+                Type::IPAddr => self.constant_pool.arbitrary_ip_str(u)?,
+                _ => self.constant_pool.arbitrary_decimal_str(u)?,
+            };
+            Ok(ast::Expr::call_extension_fn(
+                constructor.name.clone(),
+                vec![ast::Expr::val(arg)],
+            ))
+        };
+        let record = |u: &mut Unstructured<'_>| -> Result<ast::Expr> {
+            let mut r = HashMap::new();
+            u.arbitrary_loop(Some(1), Some(self.settings.max_width as u32), |u| {
+                r.insert(
+                    self.constant_pool.arbitrary_string_constant(u)?,
+                    ext_literal(u)?,
+                );
+                Ok(std::ops::ControlFlow::Continue(()))
+            })?;
+            Ok(ast::Expr::record(r)
+                .expect("can't have duplicate keys because `r` was already a HashMap"))
+        };
+        let mut elements = Vec::new();
+        u.arbitrary_loop(Some(1), Some(self.settings.max_width as u32), |u| {
+            elements.push(record(u)?);
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        let set = ast::Expr::set(elements.clone());
+        uniform!(
+            u,
+            // membership of one of the elements
+            Ok(ast::Expr::contains(set, u.choose(&elements)?.clone())),
+            // membership of a fresh record
+            Ok(ast::Expr::contains(set, record(u)?)),
+            // equality with the same elements in reverse order
+            Ok(ast::Expr::is_eq(
+                set,
+                ast::Expr::set(elements.into_iter().rev())
+            )),
+            // equality with a fresh set
+            Ok(ast::Expr::is_eq(set, ast::Expr::set([record(u)?])))
+        )
+    }
+
+    /// get a chain of `ipaddr` predicates on `ip(..)` literals joined by `&&`
+    /// and `||`, e.g.,
+    /// `ip("10.0.0.1").isIpv4() && ip("10.0.0.1").isInRange(ip("0.0.0.0/0"))`.
+    /// Half of the literals are boundary cases (see
+    /// [`ConstantPool::arbitrary_boundary_ip_str`]).
+    pub fn generate_ipaddr_expr(&self, u: &mut Unstructured<'_>) -> Result<ast::Expr> {
+        if !self.settings.enable_extensions || !self.settings.enable_ipaddr {
+            return Err(Error::ExtensionsDisabled);
+        }
+        let ext_fn = |name: &str| {
+            ast::Name::parse_unqualified_name(name).expect("should be a valid identifier")
+        };
+        let ip = |u: &mut Unstructured<'_>| -> Result<ast::Expr> {
+            let s = if u.ratio(1, 2)? {
+                self.constant_pool.arbitrary_boundary_ip_str(u)?
+            } else {
+                self.constant_pool.arbitrary_valid_ip_str(u)?
+            };
+            Ok(ast::Expr::call_extension_fn(
+                ext_fn("ip"),
+                vec![ast::Expr::val(s)],
+            ))
+        };
+        let predicate = |u: &mut Unstructured<'_>| -> Result<ast::Expr> {
+            let addr = ip(u)?;
+            let name =
+                *u.choose(&["isIpv4", "isIpv6", "isLoopback", "isMulticast", "isInRange"])?;
+            let args = if name == "isInRange" {
+                vec![addr, ip(u)?]
+            } else {
+                vec![addr]
+            };
+            Ok(ast::Expr::call_extension_fn(ext_fn(name), args))
+        };
+        let mut expr = predicate(u)?;
+        u.arbitrary_loop(None, Some(self.settings.max_width as u32), |u| {
+            let next = predicate(u)?;
+            expr = if u.ratio(1, 2)? {
+                ast::Expr::and(expr.clone(), next)
+            } else {
+                ast::Expr::or(expr.clone(), next)
+            };
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        Ok(expr)
+    }
+
+    /// get an access to a (possibly nested) attribute of an entity, compared
+    /// with an expression of the attribute's type, e.g.,
+    /// `principal has a && principal.a has b && principal.a.b == 3`.
+    /// The path descends through record-typed attributes. If `guarded`, every
+    /// optional attribute on the path is guarded by `has`, so the access can't
+    /// error because an attribute is missing. Otherwise the guards are omitted,
+    /// and the access errors whenever an optional attribute is absent.
+    pub fn generate_attr_chain_expr(
+        &self,
+        guarded: bool,
+        max_depth: usize,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ast::Expr> {
+        let entity_types: Vec<_> = self
+            .schema
+            .schema
+            .entity_types
+            .iter()
+            .map(|(name, et)| {
+                (
+                    name,
+                    attrs_from_attrs_or_context(&self.schema.schema, &et.shape).attrs,
+                )
+            })
+            .filter(|(_, attrs)| !attrs.is_empty())
+            .collect();
+        let (entity_name, mut attrs) = *u
+            .choose(&entity_types)
+            .map_err(|e| while_doing("choosing an entity type with attributes".into(), e))?;
+        // The base is a UID literal or variable rather than an arbitrary
+        // expression, so that the guards cover every attribute access on the
+        // path. `principal` and `resource` may have another type, in which
+        // case the guards are `false`.
+        let mut access = uniform!(
+            u,
+            {
+                let entity_type = ast::EntityType::from(ast::Name::from(entity_name.clone()))
+                    .qualify_with(self.schema.namespace());
+                ast::Expr::val(self.arbitrary_uid_with_type(&entity_type, u)?)
+            },
+            ast::Expr::var(ast::Var::Principal),
+            ast::Expr::var(ast::Var::Resource)
+        );
+        let mut guards = Vec::new();
+        let attr_ty = loop {
+            let candidates: Vec<_> = attrs.iter().collect();
+            let (attr_name, attr_ty) = *u.choose(&candidates)?;
+            if guarded && !attr_ty.required {
+                guards.push(ast::Expr::has_attr(access.clone(), attr_name.clone()));
+            }
+            access = ast::Expr::get_attr(access, attr_name.clone());
+            match &attr_ty.ty {
+                json_schema::Type::Type(json_schema::TypeVariant::Record(
+                    json_schema::RecordType { attributes, .. },
+                )) if !attributes.is_empty() && u.ratio(2, 3)? => attrs = attributes,
+                _ => break &attr_ty.ty,
+            }
+        };
+        let cmp = ast::Expr::is_eq(
+            access,
+            self.generate_expr_for_schematype(attr_ty, max_depth, u)?,
+        );
+        // `g1 && (g2 && (... && cmp))`, so each guard short-circuits the
+        // accesses after it
+        Ok(guards
+            .into_iter()
+            .rev()
+            .fold(cmp, |acc, guard| ast::Expr::and(guard, acc)))
+    }
+
+    /// get a conditional whose branches are an extension-typed and a
+    /// primitive-typed expression, compared with its extension-typed branch,
+    /// e.g., `(if 1 < 2 then ip("1.2.3.4") else 3) == ip("1.2.3.4")`.
+    /// The branches have no least upper bound, so strict validation should
+    /// reject it. The guard is a comparison rather than a literal because
+    /// validators only typecheck the taken branch when the guard is `true` or
+    /// `false`.
+    pub fn generate_incompatible_ite_expr(
+        &self,
+        max_depth: usize,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ast::Expr> {
+        if !self.settings.enable_extensions {
+            return Err(Error::ExtensionsDisabled);
+        }
+        let depth = max_depth.saturating_sub(1);
+        let guard = ast::Expr::less(
+            self.generate_expr_for_type(&Type::long(), depth, u)?,
+            self.generate_expr_for_type(&Type::long(), depth, u)?,
+        );
+        let ext_type = if u.ratio(1, 2)? {
+            Type::ipaddr()
+        } else {
+            Type::decimal()
+        };
+        let ext_branch = self.generate_expr_for_type(&ext_type, depth, u)?;
+        let prim_types = [Type::bool(), Type::long(), Type::string()];
+        let prim_branch = self.generate_expr_for_type(u.choose(&prim_types)?, depth, u)?;
+        let ite = if u.ratio(1, 2)? {
+            ast::Expr::ite(guard, ext_branch.clone(), prim_branch)
+        } else {
+            ast::Expr::ite(guard, prim_branch, ext_branch.clone())
+        };
+        Ok(ast::Expr::is_eq(ite, ext_branch))
+    }
+
+    /// get a comparison of one of the [`ACTION_ATTR_NAMES`] of `action` with a
+    /// `Long` literal, sometimes guarded by `has`, e.g.,
+    /// `action has riskLevel && action.riskLevel > 3`
+    pub fn generate_action_attr_expr(&self, u: &mut Unstructured<'_>) -> Result<ast::Expr> {
+        if !self.settings.enable_action_groups_and_attrs {
+            return Err(Error::ActionAttrsDisabled);
+        }
+        let attr = SmolStr::from(*u.choose(&ACTION_ATTR_NAMES)?);
+        let read = ast::Expr::get_attr(ast::Expr::var(ast::Var::Action), attr.clone());
+        let n = ast::Expr::val(self.constant_pool.arbitrary_int_constant(u)?);
+        let cmp = uniform!(
+            u,
+            Ok(ast::Expr::greater(read, n)),
+            Ok(ast::Expr::lesseq(read, n)),
+            Ok(ast::Expr::is_eq(read, n))
+        )?;
+        if u.ratio(1, 2)? {
+            Ok(ast::Expr::and(
+                ast::Expr::has_attr(ast::Expr::var(ast::Var::Action), attr),
+                cmp,
+            ))
+        } else {
+            Ok(cmp)
+        }
+    }
+
     /// get an arbitrary expression of a given type conforming to the schema
     ///
     /// `max_depth`: maximum size (i.e., depth) of the expression.
@@ -282,18 +837,47 @@ impl<'a> ExprGenerator<'a> {
                         // no recursion allowed, so, just do a literal
                         Ok(ast::Expr::val(u.arbitrary::<bool>()?))
                     } else {
+                        let context_arith_weight: u8 =
+                            if self.schema.context_long_attributes(self.action).is_empty() {
+                                0
+                            } else {
+                                2
+                            };
+                        let context_entity_eq_weight: u8 = if self
+                            .schema
+                            .context_entity_attributes(self.action)
+                            .is_empty()
+                        {
+                            0
+                        } else {
+                            2
+                        };
+                        let is_weight: u8 = if self.settings.enable_is_operator {
+                            self.op_weight("is", 2)
+                        } else {
+                            0
+                        };
+                        let ownership_weight: u8 = if self.settings.enable_ownership_patterns
+                            && !self.schema.ownership_attributes().is_empty()
+                        {
+                            6
+                        } else {
+                            0
+                        };
                         gen!(u,
                         // bool literal
                         2 => Ok(ast::Expr::val(u.arbitrary::<bool>()?)),
                         // == expression, where types on both sides match
-                        5 => {
+                        self.op_weight("==", 5) => {
                             let ty: Type = u.arbitrary()?;
                             Ok(ast::Expr::is_eq(
                                 self.generate_expr_for_type(&ty, max_depth - 1, u)?,
                                 self.generate_expr_for_type(&ty, max_depth - 1, u)?,
                             ))
                         },
-                        // == expression, where types do not match
+                        // == expression, where types do not match. Its weight
+                        // is fixed, so that `operator_weights` for `==` only
+                        // changes the well-typed comparisons above.
                         2 => {
                             let ty1: Type = u.arbitrary()?;
                             let ty2: Type = u.arbitrary()?;
@@ -311,13 +895,13 @@ impl<'a> ExprGenerator<'a> {
                             ))
                         },
                         // not expression
-                        5 => Ok(ast::Expr::not(self.generate_expr_for_type(
+                        self.op_weight("!", 5) => Ok(ast::Expr::not(self.generate_expr_for_type(
                             &Type::bool(),
                             max_depth - 1,
                             u,
                         )?)),
                         // if-then-else expression, where both arms are bools
-                        5 => Ok(ast::Expr::ite(
+                        self.ite_weight(5) => Ok(ast::Expr::ite(
                             self.generate_expr_for_type(
                                 &Type::bool(),
                                 max_depth - 1,
@@ -335,7 +919,7 @@ impl<'a> ExprGenerator<'a> {
                             )?,
                         )),
                         // && expression
-                        5 => Ok(ast::Expr::and(
+                        self.op_weight("&&", 5) => Ok(ast::Expr::and(
                             self.generate_expr_for_type(
                                 &Type::bool(),
                                 max_depth - 1,
@@ -348,7 +932,7 @@ impl<'a> ExprGenerator<'a> {
                             )?,
                         )),
                         // || expression
-                        5 => Ok(ast::Expr::or(
+                        self.op_weight("||", 5) => Ok(ast::Expr::or(
                             self.generate_expr_for_type(
                                 &Type::bool(),
                                 max_depth - 1,
@@ -361,7 +945,7 @@ impl<'a> ExprGenerator<'a> {
                             )?,
                         )),
                         // < expression
-                        1 => Ok(ast::Expr::less(
+                        self.op_weight("<", 1) => Ok(ast::Expr::less(
                             self.generate_expr_for_type(
                                 &Type::long(),
                                 max_depth - 1,
@@ -374,7 +958,7 @@ impl<'a> ExprGenerator<'a> {
                             )?,
                         )),
                         // <= expression
-                        1 => Ok(ast::Expr::lesseq(
+                        self.op_weight("<=", 1) => Ok(ast::Expr::lesseq(
                             self.generate_expr_for_type(
                                 &Type::long(),
                                 max_depth - 1,
@@ -387,7 +971,7 @@ impl<'a> ExprGenerator<'a> {
                             )?,
                         )),
                         // > expression
-                        1 => Ok(ast::Expr::greater(
+                        self.op_weight(">", 1) => Ok(ast::Expr::greater(
                             self.generate_expr_for_type(
                                 &Type::long(),
                                 max_depth - 1,
@@ -400,7 +984,7 @@ impl<'a> ExprGenerator<'a> {
                             )?,
                         )),
                         // >= expression
-                        1 => Ok(ast::Expr::greatereq(
+                        self.op_weight(">=", 1) => Ok(ast::Expr::greatereq(
                             self.generate_expr_for_type(
                                 &Type::long(),
                                 max_depth - 1,
@@ -413,7 +997,7 @@ impl<'a> ExprGenerator<'a> {
                             )?,
                         )),
                         // in expression, non-set form
-                        11 => Ok(ast::Expr::is_in(
+                        self.op_weight("in", 11) => Ok(ast::Expr::is_in(
                             self.generate_expr_for_type(
                                 &Type::entity(),
                                 max_depth - 1,
@@ -426,7 +1010,7 @@ impl<'a> ExprGenerator<'a> {
                             )?,
                         )),
                         // in expression, set form
-                        2 => Ok(ast::Expr::is_in(
+                        self.op_weight("in", 2) => Ok(ast::Expr::is_in(
                             self.generate_expr_for_type(
                                 &Type::entity(),
                                 max_depth - 1,
@@ -439,7 +1023,7 @@ impl<'a> ExprGenerator<'a> {
                             )?,
                         )),
                         // contains() on a set
-                        2 => {
+                        self.op_weight("contains", 2) => {
                             let element_ty = u.arbitrary()?;
                             let element = self.generate_expr_for_type(
                                 &element_ty,
@@ -454,7 +1038,7 @@ impl<'a> ExprGenerator<'a> {
                             Ok(ast::Expr::contains(set, element))
                         },
                         // containsAll()
-                        1 => Ok(ast::Expr::contains_all(
+                        self.op_weight("containsAll", 1) => Ok(ast::Expr::contains_all(
                             // doesn't require the input sets to have the same element type
                             self.generate_expr_for_type(
                                 &Type::set_of(u.arbitrary()?),
@@ -468,7 +1052,7 @@ impl<'a> ExprGenerator<'a> {
                             )?,
                         )),
                         // containsAny()
-                        1 => Ok(ast::Expr::contains_any(
+                        self.op_weight("containsAny", 1) => Ok(ast::Expr::contains_any(
                             // doesn't require the input sets to have the same element type
                             self.generate_expr_for_type(
                                 &Type::set_of(u.arbitrary()?),
@@ -481,15 +1065,34 @@ impl<'a> ExprGenerator<'a> {
                                 u,
                             )?,
                         )),
+                        // containsAll() and containsAny() on set literals which
+                        // are empty, overlapping, or disjoint
+                        self.op_weight("containsAll", 1) => {
+                            let (receiver, arg) =
+                                self.generate_set_op_operands(max_depth - 1, u)?;
+                            Ok(ast::Expr::contains_all(receiver, arg))
+                        },
+                        self.op_weight("containsAny", 1) => {
+                            let (receiver, arg) =
+                                self.generate_set_op_operands(max_depth - 1, u)?;
+                            Ok(ast::Expr::contains_any(receiver, arg))
+                        },
                         // like
-                        2 => {
+                        self.op_weight("like", 2) => {
                             if self.settings.enable_like {
                                 Ok(ast::Expr::like(
-                                    self.generate_expr_for_type(
-                                        &Type::string(),
-                                        max_depth - 1,
-                                        u,
-                                    )?,
+                                    if u.ratio(1, 4)? {
+                                        ast::Expr::val(
+                                            self.constant_pool
+                                                .arbitrary_like_escaping_string(u)?,
+                                        )
+                                    } else {
+                                        self.generate_expr_for_type(
+                                            &Type::string(),
+                                            max_depth - 1,
+                                            u,
+                                        )?
+                                    },
                                     self.constant_pool.arbitrary_pattern_literal(u)?,
                                 ))
                             } else {
@@ -497,7 +1100,7 @@ impl<'a> ExprGenerator<'a> {
                             }
                         },
                         // is
-                        2 => {
+                        is_weight => {
                                 Ok(ast::Expr::is_entity_type(
                                     self.generate_expr_for_type(
                                         &Type::entity(),
@@ -507,6 +1110,29 @@ impl<'a> ExprGenerator<'a> {
                                     u.choose(&self.schema.entity_types)?.clone(),
                                 ))
                         },
+                        // is ... in, e.g., `principal is User in Group::"admins"`,
+                        // which is sugar for `principal is User && principal in Group::"admins"`
+                        is_weight => {
+                                let entity = self.generate_expr_for_type(
+                                    &Type::entity(),
+                                    max_depth - 1,
+                                    u,
+                                )?;
+                                Ok(ast::Expr::and(
+                                    ast::Expr::is_entity_type(
+                                        entity.clone(),
+                                        u.choose(&self.schema.entity_types)?.clone(),
+                                    ),
+                                    ast::Expr::is_in(
+                                        entity,
+                                        self.generate_expr_for_type(
+                                            &Type::entity(),
+                                            max_depth - 1,
+                                            u,
+                                        )?,
+                                    ),
+                                ))
+                        },
                         // extension function that returns bool
                         2 => self.generate_ext_func_call_for_type(
                             &Type::bool(),
@@ -514,7 +1140,7 @@ impl<'a> ExprGenerator<'a> {
                             u,
                         ),
                         // getting an attr (on an entity) with type bool
-                        1 => {
+                        self.op_weight(".", 1) => {
                             let (entity_type, attr_name) = self.schema.arbitrary_attr_for_schematype(
                                 json_schema::TypeVariant::Boolean,
                                 u,
@@ -529,7 +1155,7 @@ impl<'a> ExprGenerator<'a> {
                             ))
                         },
                         // getting an attr (on a record) with type bool
-                        1 => {
+                        self.op_weight(".", 1) => {
                             let attr_name = self.constant_pool.arbitrary_string_constant(u)?;
                             Ok(ast::Expr::get_attr(
                                 self.generate_expr_for_schematype(
@@ -544,7 +1170,7 @@ impl<'a> ExprGenerator<'a> {
                             ))
                         },
                         // has expression on an entity, for a (possibly optional) attribute the entity does have in the schema
-                        2 => {
+                        self.op_weight("has", 2) => {
                             let (entity_name, entity_type) = self
                                 .schema
                                 .schema
@@ -574,8 +1200,55 @@ impl<'a> ExprGenerator<'a> {
                                 attr_name,
                             ))
                         },
+                        // distinguish a set-typed attribute that is present but
+                        // empty from one that is absent
+                        2 => {
+                            let (entity_type, attr_name) = self.schema.arbitrary_set_attr(u)?;
+                            let entity = self.generate_expr_for_schematype(
+                                &entity_type_name_to_schema_type(&entity_type),
+                                max_depth - 1,
+                                u,
+                            )?;
+                            let has = ast::Expr::has_attr(entity.clone(), attr_name.clone());
+                            let is_empty = ast::Expr::is_eq(
+                                ast::Expr::get_attr(entity, attr_name),
+                                ast::Expr::set([]),
+                            );
+                            uniform!(u,
+                                // present, but empty
+                                Ok(ast::Expr::and(has.clone(), is_empty.clone())),
+                                // absent
+                                Ok(ast::Expr::not(has)),
+                                // errors if absent
+                                Ok(is_empty))
+                        },
+                        // ownership pattern, e.g.,
+                        // `resource is Document && resource.owner == principal`
+                        ownership_weight =>
+                            self.generate_ownership_expr(u),
+                        // access to a (possibly nested) attribute, with the
+                        // optional attributes on the path guarded by `has`
+                        (if self.settings.enable_guarded_attr_chains { 3 } else { 0 }) =>
+                            self.generate_attr_chain_expr(true, max_depth - 1, u),
+                        // the same, but unguarded, so it may error
+                        (if self.settings.enable_unguarded_attr_chains { 2 } else { 0 }) =>
+                            self.generate_attr_chain_expr(false, max_depth - 1, u),
+                        // arithmetic and comparison on `Long` context attributes,
+                        // e.g., `context.count + 1 > context.limit`
+                        context_arith_weight => self.generate_context_arith_cmp_expr(u),
+                        // equality of entity-typed `context` attributes,
+                        // e.g., `context.owner == context.viewer`
+                        context_entity_eq_weight => self.generate_context_entity_eq_expr(u),
+                        // equality and membership on sets of records holding
+                        // extension values, e.g., `[{addr: ip("1.2.3.4")}] == ...`
+                        (if self.settings.enable_extensions { 2 } else { 0 }) =>
+                            self.generate_nested_ext_literal_expr(u),
+                        // chains of `ipaddr` predicates on `ip(..)` literals,
+                        // e.g., `ip("::ffff:1.2.3.4").isIpv4() || ...`
+                        (if self.settings.enable_extensions && self.settings.enable_ipaddr { 3 } else { 0 }) =>
+                            self.generate_ipaddr_expr(u),
                         // has expression on an entity, for an arbitrary attribute name
-                        1 => Ok(ast::Expr::has_attr(
+                        self.op_weight("has", 1) => Ok(ast::Expr::has_attr(
                             self.generate_expr_for_type(
                                 &Type::entity(),
                                 max_depth - 1,
@@ -584,7 +1257,7 @@ impl<'a> ExprGenerator<'a> {
                             self.constant_pool.arbitrary_string_constant(u)?,
                         )),
                         // has expression on a record
-                        2 => Ok(ast::Expr::has_attr(
+                        self.op_weight("has", 2) => Ok(ast::Expr::has_attr(
                             self.generate_expr_for_type(
                                 &Type::record(),
                                 max_depth - 1,
@@ -597,19 +1270,17 @@ impl<'a> ExprGenerator<'a> {
                 Type::Long => {
                     if max_depth == 0 || u.len() < 10 {
                         // no recursion allowed, so, just do a literal
-                        Ok(ast::Expr::val(
-                            self.constant_pool.arbitrary_int_constant(u)?,
-                        ))
+                        Ok(ast::Expr::val(self.arbitrary_int_literal(u)?))
                     } else {
                         gen!(u,
                         // int literal. weighted highly because all the other choices
                         // are recursive, and we don't want a scenario where we have,
                         // say, a 90% chance to recurse every time
                         16 => Ok(ast::Expr::val(
-                            self.constant_pool.arbitrary_int_constant(u)?,
+                            self.arbitrary_int_literal(u)?,
                         )),
                         // if-then-else expression, where both arms are longs
-                        5 => Ok(ast::Expr::ite(
+                        self.ite_weight(5) => Ok(ast::Expr::ite(
                             self.generate_expr_for_type(
                                 &Type::bool(),
                                 max_depth - 1,
@@ -712,19 +1383,17 @@ impl<'a> ExprGenerator<'a> {
                 Type::String => {
                     if max_depth == 0 || u.len() < 10 {
                         // no recursion allowed, so, just do a literal
-                        Ok(ast::Expr::val(
-                            self.constant_pool.arbitrary_string_constant(u)?,
-                        ))
+                        Ok(ast::Expr::val(self.arbitrary_string_literal(u)?))
                     } else {
                         gen!(u,
                         // string literal. weighted highly because all the other choices
                         // are recursive, and we don't want a scenario where we have, say,
                         // a 90% chance to recurse every time
                         16 => Ok(ast::Expr::val(
-                            self.constant_pool.arbitrary_string_constant(u)?,
+                            self.arbitrary_string_literal(u)?,
                         )),
                         // if-then-else expression, where both arms are strings
-                        5 => Ok(ast::Expr::ite(
+                        self.ite_weight(5) => Ok(ast::Expr::ite(
                             self.generate_expr_for_type(
                                 &Type::bool(),
                                 max_depth - 1,
@@ -805,8 +1474,17 @@ impl<'a> ExprGenerator<'a> {
                             )?;
                             Ok(ast::Expr::set(l))
                         },
+                        // empty, single-element, or duplicate-element set literal
+                        2 => {
+                            let target_element_ty = target_element_ty
+                                .as_ref()
+                                .map_or_else(|| u.arbitrary(), |ty| Ok((*ty).clone()))?;
+                            self.generate_degenerate_set(u, |gen, u| {
+                                gen.generate_expr_for_type(&target_element_ty, max_depth - 1, u)
+                            })
+                        },
                         // if-then-else expression, where both arms are (appropriate) sets
-                        2 => Ok(ast::Expr::ite(
+                        self.ite_weight(2) => Ok(ast::Expr::ite(
                             self.generate_expr_for_type(
                                 &Type::bool(),
                                 max_depth - 1,
@@ -891,8 +1569,25 @@ impl<'a> ExprGenerator<'a> {
                             )?;
                             Ok(ast::Expr::record(r).expect("can't have duplicate keys because `r` was already a HashMap"))
                         },
+                        // empty or single-attribute record literal
+                        1 => {
+                            if u.ratio(1, 2)? {
+                                Ok(ast::Expr::record([]).expect("can't have duplicate keys because there are no keys"))
+                            } else {
+                                let attr_val = self.generate_expr_for_type(
+                                    &u.arbitrary()?,
+                                    max_depth - 1,
+                                    u,
+                                )?;
+                                Ok(ast::Expr::record([(
+                                    self.constant_pool.arbitrary_string_constant(u)?,
+                                    attr_val,
+                                )])
+                                .expect("can't have duplicate keys because there is only one key"))
+                            }
+                        },
                         // if-then-else expression, where both arms are records
-                        2 => Ok(ast::Expr::ite(
+                        self.ite_weight(2) => Ok(ast::Expr::ite(
                             self.generate_expr_for_type(
                                 &Type::bool(),
                                 max_depth - 1,
@@ -977,7 +1672,7 @@ impl<'a> ExprGenerator<'a> {
                         // `resource`
                         6 => Ok(ast::Expr::var(ast::Var::Resource)),
                         // if-then-else expression, where both arms are entities
-                        2 => Ok(ast::Expr::ite(
+                        self.ite_weight(2) => Ok(ast::Expr::ite(
                             self.generate_expr_for_type(
                                 &Type::bool(),
                                 max_depth - 1,
@@ -1058,7 +1753,7 @@ impl<'a> ExprGenerator<'a> {
                         };
                         gen!(u,
                         // if-then-else expression, where both arms are extension types
-                        2 => Ok(ast::Expr::ite(
+                        self.ite_weight(2) => Ok(ast::Expr::ite(
                             self.generate_expr_for_type(
                                 &Type::bool(),
                                 max_depth - 1,
@@ -1081,6 +1776,9 @@ impl<'a> ExprGenerator<'a> {
                             max_depth - 1,
                             u,
                         ),
+                        // the constructor, applied to a literal it rejects
+                        (if self.settings.enable_invalid_extension_args { 2 } else { 0 }) =>
+                            self.generate_invalid_ext_constructor_call(target_type, u),
                         // getting an attr (on an entity) with extension type
                         2 => {
                             let (entity_type, attr_name) = self.schema.arbitrary_attr_for_schematype(
@@ -1188,8 +1886,12 @@ impl<'a> ExprGenerator<'a> {
                         })?;
                         Ok(ast::Expr::set(l))
                     },
+                    // empty, single-element, or duplicate-element set literal
+                    2 => self.generate_degenerate_set(u, |gen, u| {
+                        gen.generate_expr_for_schematype(element_ty, max_depth - 1, u)
+                    }),
                     // if-then-else expression, where both arms are (appropriate) sets
-                    2 => Ok(ast::Expr::ite(
+                    self.ite_weight(2) => Ok(ast::Expr::ite(
                         self.generate_expr_for_type(
                             &Type::bool(),
                             max_depth - 1,
@@ -1304,7 +2006,7 @@ impl<'a> ExprGenerator<'a> {
                     // return it if it is.
                     14 => Err(Error::TooDeep),
                     // if-then-else expression, where both arms are (appropriate) records
-                    2 => Ok(ast::Expr::ite(
+                    self.ite_weight(2) => Ok(ast::Expr::ite(
                         self.generate_expr_for_type(
                             &Type::bool(),
                             max_depth - 1,
@@ -1381,7 +2083,7 @@ impl<'a> ExprGenerator<'a> {
                     // `resource`
                     6 => Ok(ast::Expr::var(ast::Var::Resource)),
                     // if-then-else expression, where both arms are entities with the appropriate type
-                    2 => Ok(ast::Expr::ite(
+                    self.ite_weight(2) => Ok(ast::Expr::ite(
                         self.generate_expr_for_type(
                             &Type::bool(),
                             max_depth - 1,
@@ -1456,12 +2158,8 @@ impl<'a> ExprGenerator<'a> {
     ) -> Result<ast::Expr> {
         match target_type {
             Type::Bool => Ok(ast::Expr::val(u.arbitrary::<bool>()?)),
-            Type::Long => Ok(ast::Expr::val(
-                self.constant_pool.arbitrary_int_constant(u)?,
-            )),
-            Type::String => Ok(ast::Expr::val(
-                self.constant_pool.arbitrary_string_constant(u)?,
-            )),
+            Type::Long => Ok(ast::Expr::val(self.arbitrary_int_literal(u)?)),
+            Type::String => Ok(ast::Expr::val(self.arbitrary_string_literal(u)?)),
             Type::Set(el_ty) => {
                 let mut l = Vec::new();
                 u.arbitrary_loop(Some(0), Some(self.settings.max_width as u32), |u| {
@@ -1611,15 +2309,11 @@ impl<'a> ExprGenerator<'a> {
             }
             Type::Long => {
                 // the only valid long-typed attribute value is an int literal
-                Ok(AttrValue::IntLit(
-                    self.constant_pool.arbitrary_int_constant(u)?,
-                ))
+                Ok(AttrValue::IntLit(self.arbitrary_int_literal(u)?))
             }
             Type::String => {
                 // the only valid string-typed attribute value is a string literal
-                Ok(AttrValue::StringLit(
-                    self.constant_pool.arbitrary_string_constant(u)?,
-                ))
+                Ok(AttrValue::StringLit(self.arbitrary_string_literal(u)?))
             }
             Type::Entity => {
                 // the only valid entity-typed attribute value is a UID literal
@@ -1781,6 +2475,10 @@ impl<'a> ExprGenerator<'a> {
                 if max_depth == 0 {
                     // no recursion allowed: just do the empty set
                     Ok(AttrValue::Set(vec![]))
+                } else if u.ratio::<u8>(1, 8)? {
+                    // make sure a present-but-empty set is common enough to be
+                    // distinguished from an absent attribute
+                    Ok(AttrValue::Set(vec![]))
                 } else {
                     let mut l = Vec::new();
                     u.arbitrary_loop(None, Some(self.settings.max_width as u32), |u| {
@@ -1903,13 +2601,11 @@ impl<'a> ExprGenerator<'a> {
             }
             Type::Long => {
                 // the only valid long-typed attribute value is an int literal
-                Ok(Value::from(self.constant_pool.arbitrary_int_constant(u)?))
+                Ok(Value::from(self.arbitrary_int_literal(u)?))
             }
             Type::String => {
                 // the only valid string-typed attribute value is a string literal
-                Ok(Value::from(
-                    self.constant_pool.arbitrary_string_constant(u)?,
-                ))
+                Ok(Value::from(self.arbitrary_string_literal(u)?))
             }
             Type::Entity => {
                 // the only valid entity-typed attribute value is a UID literal
@@ -2126,10 +2822,10 @@ impl<'a> ExprGenerator<'a> {
         gen!(u,
         11 => Ok(ast::Expr::val(u.arbitrary::<bool>()?)),
         10 => Ok(ast::Expr::val(
-            self.constant_pool.arbitrary_int_constant(u)?,
+            self.arbitrary_int_literal(u)?,
         )),
         10 => Ok(ast::Expr::val(
-            self.constant_pool.arbitrary_string_constant(u)?,
+            self.arbitrary_string_literal(u)?,
         )),
         20 => Ok(ast::Expr::val(self.generate_uid(u)?)),
         4 => Ok(ast::Expr::val(
@@ -2245,6 +2941,39 @@ impl<'a> ExprGenerator<'a> {
     }
 }
 
+/// Every way of combining the three boolean `operands`, in order, with two
+/// binary operators drawn from `&&` and `||`, grouped either way, and with
+/// each operand optionally negated, e.g., `(!a && b) || c` and
+/// `a || (b && !c)`. There are [`BOOL_OPERATOR_COMBINATIONS`] of them.
+pub fn bool_operator_combinations(operands: [ast::Expr; 3]) -> Vec<ast::Expr> {
+    let binops: [fn(ast::Expr, ast::Expr) -> ast::Expr; 2] = [ast::Expr::and, ast::Expr::or];
+    let mut combinations = Vec::with_capacity(BOOL_OPERATOR_COMBINATIONS);
+    for negated in 0..8 {
+        let [a, b, c] = [0, 1, 2].map(|i| {
+            if negated & (1 << i) == 0 {
+                operands[i].clone()
+            } else {
+                ast::Expr::not(operands[i].clone())
+            }
+        });
+        for op1 in binops {
+            for op2 in binops {
+                combinations.push(op2(op1(a.clone(), b.clone()), c.clone()));
+                combinations.push(op1(a.clone(), op2(b.clone(), c.clone())));
+            }
+        }
+    }
+    combinations
+}
+
+/// Number of expressions returned by [`bool_operator_combinations`]
+pub const BOOL_OPERATOR_COMBINATIONS: usize = 64;
+
+/// Names of the `Long` attributes that actions may have. Schemas can't declare
+/// action attributes, so these only exist in the entity data (see
+/// [`crate::schema::Schema::arbitrary_action_entities`]).
+pub const ACTION_ATTR_NAMES: [&str; 2] = ["riskLevel", "sensitivity"];
+
 /// internal helper function, get a [`json_schema::Type`] representing a Record
 /// with (at least) one attribute of the specified name and type.
 fn record_schematype_with_attr<N>(
@@ -23,10 +23,10 @@ macro_rules! gen_inner {
         $v
     };
     ($i:ident, [$w1:expr => $v1:expr] [$w2:expr => $v2:expr] $([$ws:expr => $vs:expr])*) => {
-        if $i < $w1 {
+        if $i < ($w1) as u32 {
             $v1
         } else {
-            gen_inner!($i, [$w1+$w2 => $v2] $([$ws => $vs])*)
+            gen_inner!($i, [($w1) as u32 + ($w2) as u32 => $v2] $([$ws => $vs])*)
         }
     };
 }
@@ -39,14 +39,19 @@ macro_rules! accum {
         0
     };
     ([$w1:expr => $v1:expr] $([$ws:expr => $vs:expr])*) => {
-        $w1 + accum!($([$ws => $vs])*)
+        ($w1) as u32 + accum!($([$ws => $vs])*)
     }
 }
 
 /// the top level language `u, w => v,+` where `u` is a `Unstructured`, `w` is the weight, and `v` is the value to generate
+/// weights are summed as `u32`, so they may be computed at runtime (e.g., from
+/// settings) without overflowing. `int_in_range` only consumes as many bytes
+/// as the total weight needs, so a total below 256 still takes a single byte.
+/// if all the weights are `0`, there's nothing to choose from, so it returns
+/// an `EmptyChoose` error.
 /// it desugars into something like
 /// ```ignore
-///      let x = u.int_in_range::<u8>(0..(w1+w2+...+wn-1))?;
+///      let x = u.int_in_range::<u32>(0..(w1+w2+...+wn-1))?;
 ///      if x < w1 { v1 } else {
 ///          if x < w1 + w2 { v2 } else {
 ///              if x < w1 + w2 + w3 { v3 } else {
@@ -59,7 +64,14 @@ macro_rules! accum {
 macro_rules! gen {
     ($u:expr, $($ws:expr => $vs:expr),+) => {
         {
-            let x = $u.int_in_range::<u8>(0..=(($crate::accum!($([$ws => $vs])+)-1)))?;
+            let total: u32 = $crate::accum!($([$ws => $vs])+);
+            if total == 0 {
+                return Err($crate::err::Error::EmptyChoose {
+                    doing_what: "choosing among alternatives with all-zero weights".into(),
+                }
+                .into());
+            }
+            let x = $u.int_in_range::<u32>(0..=(total - 1))?;
             $crate::gen_inner!(x, $([$ws => $vs])+)
         }
     };
@@ -71,7 +83,7 @@ macro_rules! gen {
 macro_rules! uniform {
     ($u:expr, $($es:expr),+) => {
         {
-            let x = $u.int_in_range::<u8>(0..=(($crate::accum!($([1 => $es])+)-1)))?;
+            let x = $u.int_in_range::<u32>(0..=(($crate::accum!($([1 => $es])+)-1)))?;
             $crate::gen_inner!(x, $([1 => $es])+)
         }
     };
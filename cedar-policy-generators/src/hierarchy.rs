@@ -14,17 +14,16 @@
  * limitations under the License.
  */
 
-use crate::abac::Type;
+use crate::abac::{arbitrary_unicode_string, AttrValue, Type, MAX_UNICODE_STRING_LEN};
 use crate::collections::{HashMap, HashSet};
 use crate::err::{while_doing, Error, Result};
-use crate::schema::{attrs_from_attrs_or_context, Schema};
+use crate::schema::{attrs_from_attrs_or_context, is_reference_to_entity_type, Schema};
 use crate::size_hint_utils::{size_hint_for_choose, size_hint_for_ratio};
 use arbitrary::{Arbitrary, Unstructured};
 use cedar_policy_core::ast::{self, Eid, Entity, EntityUID};
 use cedar_policy_core::entities::{Entities, NoEntitiesSchema, TCComputation};
 use cedar_policy_core::extensions::Extensions;
 use cedar_policy_validator::json_schema;
-use nanoid::nanoid;
 
 /// EntityUIDs with the mappings to their indices in the container.
 /// This is used to generate an entity that is lexicographically smaller/greater than the input entity.
@@ -148,6 +147,97 @@ impl Hierarchy {
         )
     }
 
+    /// Generate a UID of an entity that isn't in the hierarchy. Its type is
+    /// one of the entity types in the hierarchy, if there are any, so that it
+    /// looks like a reference to an entity that was left out.
+    pub fn arbitrary_missing_uid(&self, u: &mut Unstructured<'_>) -> Result<EntityUID> {
+        let ty = if self.entity_types.is_empty() {
+            u.arbitrary()?
+        } else {
+            u.choose(&self.entity_types)?.clone()
+        };
+        let mut eid: String = u.arbitrary()?;
+        loop {
+            let uid = EntityUID::from_components(ty.clone(), Eid::new(eid.as_str()), None);
+            if !self.entities.contains_key(&uid) {
+                return Ok(uid);
+            }
+            eid.push('_');
+        }
+    }
+
+    /// Generate an action UID for a policy's action scope. If the hierarchy has
+    /// `Action` entities (see `HierarchyGeneratorMode::Arbitrary`), this is
+    /// usually one of them, so that `action in` constraints can refer to
+    /// action groups. Otherwise, it's an arbitrary UID.
+    pub fn arbitrary_action_uid(&self, u: &mut Unstructured<'_>) -> Result<EntityUID> {
+        match self.uids_by_type.get(&action_entity_type()) {
+            Some(actions) if u.ratio::<u8>(9, 10)? => Ok(u.choose(actions.as_ref())?.clone()),
+            _ => self.arbitrary_uid(u, None),
+        }
+    }
+
+    /// Generate an action UID for a request. If the hierarchy has `Action`
+    /// entities that are members of action groups, this is one of them, so
+    /// that `action in` constraints aren't decided by equality alone.
+    /// Otherwise, it's an arbitrary UID.
+    pub fn arbitrary_member_action_uid(&self, u: &mut Unstructured<'_>) -> Result<EntityUID> {
+        let members: Vec<&EntityUID> = match self.uids_by_type.get(&action_entity_type()) {
+            Some(actions) => actions
+                .as_ref()
+                .iter()
+                .filter(|uid| {
+                    self.entities
+                        .get(*uid)
+                        .is_some_and(|entity| entity.ancestors().next().is_some())
+                })
+                .collect(),
+            None => vec![],
+        };
+        if members.is_empty() {
+            self.arbitrary_uid(u, Some(ast::Var::Action))
+        } else {
+            Ok((*u.choose(&members)?).clone())
+        }
+    }
+
+    /// Get the `Action` entities that have at least one member action, i.e.,
+    /// the action groups, in the order of `uids()`
+    pub fn action_group_uids(&self) -> Vec<&EntityUID> {
+        let groups: HashSet<&EntityUID> = self
+            .entities()
+            .filter(|entity| entity.uid().entity_type() == &action_entity_type())
+            .flat_map(|entity| entity.ancestors())
+            .collect();
+        self.uids_with_type(&action_entity_type())
+            .iter()
+            .filter(|uid| groups.contains(uid))
+            .collect()
+    }
+
+    /// Generate an action UID for a request that is a leaf of the action
+    /// hierarchy: a member of some action group, but not a group itself. If
+    /// there is no such action, this is `arbitrary_member_action_uid()`.
+    pub fn arbitrary_leaf_action_uid(&self, u: &mut Unstructured<'_>) -> Result<EntityUID> {
+        let groups = self.action_group_uids();
+        let leaves: Vec<&EntityUID> = self
+            .uids_with_type(&action_entity_type())
+            .iter()
+            .filter(|uid| {
+                !groups.contains(uid)
+                    && self
+                        .entities
+                        .get(*uid)
+                        .is_some_and(|entity| entity.ancestors().next().is_some())
+            })
+            .collect();
+        if leaves.is_empty() {
+            self.arbitrary_member_action_uid(u)
+        } else {
+            Ok((*u.choose(&leaves)?).clone())
+        }
+    }
+
     /// generate an arbitrary uid based on the hierarchy, with the given typename
     pub fn arbitrary_uid_with_type(
         &self,
@@ -232,6 +322,23 @@ impl Hierarchy {
         &self.uids
     }
 
+    /// Get the UIDs in the Hierarchy having the given type, as a slice
+    pub fn uids_with_type(&self, ty: &ast::EntityType) -> &[EntityUID] {
+        self.uids_by_type.get(ty).map_or(&[], AsRef::as_ref)
+    }
+
+    /// Iterate over the entities whose parents have at least two different
+    /// entity types, e.g., an entity in both `GroupA::"x"` and `GroupB::"y"`
+    pub fn multi_type_members(&self) -> impl Iterator<Item = &Entity> {
+        self.entities().filter(|entity| {
+            let parent_types: HashSet<&ast::EntityType> = entity
+                .ancestors()
+                .map(|parent| parent.entity_type())
+                .collect();
+            parent_types.len() >= 2
+        })
+    }
+
     /// Consume the Hierarchy and return a new one, which replaces the entities
     /// with a new set of entities.
     ///
@@ -315,6 +422,11 @@ pub struct HierarchyGenerator<'a, 'u> {
     pub uid_gen_mode: EntityUIDGenMode,
     /// How many entities to generate for the hierarchy
     pub num_entities: NumEntities,
+    /// If `Some(d)`, the longest chain of `memberOf` edges in the hierarchy
+    /// has at most `d` edges. Parents are then only ever chosen later in the
+    /// pool, so the hierarchy is acyclic even in schema-based mode, where the
+    /// entity types' `memberOfTypes` may form a cycle.
+    pub max_hierarchy_depth: Option<usize>,
     /// `Unstructured` used for making random choices
     pub u: &'a mut Unstructured<'u>,
     /// Extensions active for the attribute values in the hierarchy
@@ -335,8 +447,18 @@ impl<'a, 'u> std::fmt::Debug for HierarchyGenerator<'a, 'u> {
 pub enum EntityUIDGenMode {
     /// By calling `arbitrary`
     Arbitrary,
-    /// By calling `nanoid`
+    /// Nanoid-style ids of the given length, using nanoid's URL-safe
+    /// alphabet. The characters are drawn from the `Unstructured` rather than
+    /// from a global RNG, so identical inputs produce identical UIDs.
     Nanoid(usize),
+    /// Path-structured ids like `"org/team/user"` with between 1 and the given
+    /// number of segments. Segments mix ASCII with non-ASCII characters and
+    /// characters that must be escaped in Cedar string literals.
+    Hierarchical(usize),
+    /// Ids of between 1 and the given number of characters, drawn from
+    /// multi-byte characters, combining characters, and codepoints adjacent
+    /// to the surrogate range
+    Unicode(usize),
 }
 
 impl EntityUIDGenMode {
@@ -348,8 +470,22 @@ impl EntityUIDGenMode {
     pub fn default_nanoid_mode() -> Self {
         Self::Nanoid(Self::default_nanoid_len())
     }
+    /// Use hierarchical ids with at most 3 segments
+    pub fn default_hierarchical_mode() -> Self {
+        Self::Hierarchical(3)
+    }
+    /// Use Unicode ids with at most `MAX_UNICODE_STRING_LEN` characters
+    pub fn default_unicode_mode() -> Self {
+        Self::Unicode(MAX_UNICODE_STRING_LEN)
+    }
 }
 
+/// Characters used in the segments of `EntityUIDGenMode::Hierarchical` ids
+const HIERARCHICAL_UID_CHARS: [char; 24] = [
+    'a', 'b', 'x', 'y', 'z', 'A', 'Z', '0', '1', '9', '-', '_', '.', ' ', 'é', 'ü', 'ß', '日',
+    '本', '🦀', '"', '\\', '\n', '\t',
+];
+
 impl Default for EntityUIDGenMode {
     fn default() -> Self {
         Self::Arbitrary
@@ -368,6 +504,10 @@ pub enum HierarchyGeneratorMode<'a> {
     Arbitrary {
         /// Mode for generating attributes (or not)
         attributes_mode: AttributesMode,
+        /// If true, the hierarchy also contains `Action` entities, some of
+        /// which are members of others (action groups). Actions are only
+        /// members of other actions, and the action hierarchy is acyclic.
+        action_groups: bool,
     },
 }
 
@@ -376,10 +516,16 @@ impl HierarchyGeneratorMode<'_> {
     pub fn arbitrary_default() -> Self {
         Self::Arbitrary {
             attributes_mode: AttributesMode::NoAttributes,
+            action_groups: false,
         }
     }
 }
 
+/// The entity type of actions in hierarchies generated with action groups
+fn action_entity_type() -> ast::EntityType {
+    ast::EntityType::from_normalized_str("Action").expect("valid id")
+}
+
 /// Restrictions (or lack of) on the number of entities in the generated hierarchy
 #[derive(Debug)]
 pub enum NumEntities {
@@ -390,6 +536,11 @@ pub enum NumEntities {
     Exactly(usize),
     /// The hierarchy will contain exactly this many entities per entity type.
     ExactlyPerEntityType(usize),
+    /// The hierarchy will contain exactly this many entities in total, split
+    /// as evenly as possible across all entity types declared in the schema:
+    /// the per-type counts differ by at most one. Generation fails with
+    /// `Error::NotEnoughData` if there are no entity types.
+    ExactTotal(usize),
     /// The hierarchy will contain between some min and some max number of
     /// entities (inclusive) per entity type.
     RangePerEntityType(std::ops::RangeInclusive<usize>),
@@ -428,29 +579,70 @@ pub(crate) fn generate_uid_with_type(
     let eid: Eid = match mode {
         EntityUIDGenMode::Arbitrary => u.arbitrary()?,
         EntityUIDGenMode::Nanoid(n) => {
-            let n = *n;
-            Eid::new(nanoid!(n))
+            let id = (0..*n)
+                .map(|_| u.choose(&nanoid::alphabet::SAFE).copied())
+                .collect::<arbitrary::Result<String>>()?;
+            Eid::new(id)
         }
+        EntityUIDGenMode::Hierarchical(max_segments) => {
+            let num_segments = u.int_in_range(1..=(*max_segments).max(1))?;
+            let segments = (0..num_segments)
+                .map(|_| {
+                    let len = u.int_in_range(1..=4)?;
+                    (0..len)
+                        .map(|_| u.choose(&HIERARCHICAL_UID_CHARS).copied())
+                        .collect::<arbitrary::Result<String>>()
+                })
+                .collect::<arbitrary::Result<Vec<String>>>()?;
+            Eid::new(segments.join("/"))
+        }
+        EntityUIDGenMode::Unicode(max_len) => Eid::new(arbitrary_unicode_string(u, *max_len)?),
     };
     Ok(ast::EntityUID::from_components(ty, eid, None))
 }
 
+/// Generate exactly `count` distinct entity UIDs with the given type
+fn generate_distinct_uids_with_type(
+    ty: &ast::EntityType,
+    count: usize,
+    mode: &EntityUIDGenMode,
+    u: &mut Unstructured<'_>,
+) -> Result<HashSet<ast::EntityUID>> {
+    let mut uids = HashSet::new();
+    while uids.len() < count {
+        // If we run out of bytes in `u`, then `uid` will be the same on every
+        // subsequent iteration, so the size of `uids` won't increase.
+        if u.is_empty() {
+            return Err(Error::NotEnoughData);
+        }
+        uids.insert(generate_uid_with_type(ty.clone(), mode, u)?);
+    }
+    Ok(uids)
+}
+
 impl<'a, 'u> HierarchyGenerator<'a, 'u> {
     /// Generate a `Hierarchy` according to the specified parameters
     pub fn generate(&mut self) -> Result<Hierarchy> {
         let entity_types = match &self.mode {
             HierarchyGeneratorMode::SchemaBased { schema } => schema.entity_types.clone(),
-            HierarchyGeneratorMode::Arbitrary { .. } => {
+            HierarchyGeneratorMode::Arbitrary { action_groups, .. } => {
                 // generate a HashSet first to avoid duplicates
-                let entity_types: HashSet<ast::EntityType> = self.u.arbitrary()?;
+                let mut entity_types: HashSet<ast::EntityType> = self.u.arbitrary()?;
+                if *action_groups {
+                    entity_types.insert(action_entity_type());
+                }
                 // Collect into a vector
                 entity_types.into_iter().collect()
             }
         };
+        if matches!(self.num_entities, NumEntities::ExactTotal(_)) && entity_types.is_empty() {
+            return Err(Error::NotEnoughData);
+        }
         // For each entity type, generate entity UIDs of that type
         let uids_by_type: HashMap<ast::EntityType, HashSet<EntityUID>> = entity_types
             .iter()
-            .map(|name| {
+            .enumerate()
+            .map(|(i, name)| {
                 let name = match &self.mode {
                     HierarchyGeneratorMode::SchemaBased { schema } => {
                         name.qualify_with(schema.namespace())
@@ -485,18 +677,24 @@ impl<'a, 'u> HierarchyGenerator<'a, 'u> {
                     NumEntities::Exactly(num_entities) => {
                         // generate a fixed number of entity UIDs of this type
                         let num_entities_per_type = num_entities / entity_types.len();
-                        let mut uids = HashSet::new();
-                        while uids.len() < num_entities_per_type {
-                            // If we run out of bytes in `u`, then `uid` will be the same on every
-                            // subsequent iteration, so the size of `uids` won't increase.
-                            if self.u.is_empty() {
-                                return Err(Error::NotEnoughData);
-                            }
-                            let uid =
-                                generate_uid_with_type(name.clone(), &self.uid_gen_mode, self.u)?;
-                            uids.insert(uid);
-                        }
-                        uids
+                        generate_distinct_uids_with_type(
+                            &name,
+                            num_entities_per_type,
+                            &self.uid_gen_mode,
+                            self.u,
+                        )?
+                    }
+                    NumEntities::ExactTotal(total) => {
+                        // the first `total % n` types get one extra entity, so
+                        // that the counts add up to exactly `total`
+                        let num_entities_per_type = total / entity_types.len()
+                            + usize::from(i < total % entity_types.len());
+                        generate_distinct_uids_with_type(
+                            &name,
+                            num_entities_per_type,
+                            &self.uid_gen_mode,
+                            self.u,
+                        )?
                     }
                 };
                 Ok((name, uids))
@@ -522,10 +720,23 @@ impl<'a, 'u> HierarchyGenerator<'a, 'u> {
             ),
             HierarchyGeneratorMode::Arbitrary { .. } => None,
         };
+        // position of each uid in the pool, and (with `max_hierarchy_depth`)
+        // the length of the longest `memberOf` chain ending at each position.
+        // With `max_hierarchy_depth`, entities only get parents later in the
+        // pool. We visit entities in pool order, so all children of an entity
+        // have been visited, and its chain length is final, by the time we
+        // choose its parents.
+        let pool_idx: HashMap<&EntityUID, usize> = hierarchy_no_attrs
+            .uids()
+            .iter()
+            .enumerate()
+            .map(|(idx, uid)| (uid, idx))
+            .collect();
+        let mut chain_len: HashMap<usize, usize> = HashMap::new();
         // now create an entity hierarchy composed of those entity UIDs
         let entities = hierarchy_no_attrs
-            .entities()
-            .map(|e| e.uid())
+            .uids()
+            .iter()
             .map(|uid| {
                 let name = uid.entity_type();
                 // choose parents for this entity
@@ -559,7 +770,7 @@ impl<'a, 'u> HierarchyGenerator<'a, 'u> {
                             }
                         }
                     }
-                    HierarchyGeneratorMode::Arbitrary { .. } => {
+                    HierarchyGeneratorMode::Arbitrary { action_groups, .. } => {
                         // no schema data.
                         // for each uid in the pool, flip a weighted coin to decide whether
                         // to add it as a parent. We only consider uids appearing after the
@@ -570,7 +781,15 @@ impl<'a, 'u> HierarchyGenerator<'a, 'u> {
                             .iter()
                             .position(|x| x == uid)
                             .expect("uid should be in the pool");
+                        let is_action =
+                            |uid: &EntityUID| uid.entity_type() == &action_entity_type();
                         for pool_uid in &hierarchy_no_attrs.uids()[(this_idx + 1)..] {
+                            // with action groups, actions are only members of
+                            // other actions, and other entities aren't members
+                            // of actions
+                            if *action_groups && is_action(uid) != is_action(pool_uid) {
+                                continue;
+                            }
                             if self.u.ratio(1, 3)? {
                                 parents.insert(pool_uid.clone());
                             }
@@ -579,11 +798,25 @@ impl<'a, 'u> HierarchyGenerator<'a, 'u> {
                         assert!(!parents.contains(uid));
                     }
                 }
+                if let Some(max_hierarchy_depth) = self.max_hierarchy_depth {
+                    let this_idx = pool_idx[uid];
+                    let len = chain_len.get(&this_idx).copied().unwrap_or(0);
+                    if len >= max_hierarchy_depth {
+                        parents.clear();
+                    } else {
+                        parents.retain(|p| pool_idx[p] > this_idx);
+                    }
+                    for p in &parents {
+                        let p_len = chain_len.entry(pool_idx[p]).or_insert(0);
+                        *p_len = (*p_len).max(len + 1);
+                    }
+                }
                 // generate appropriate attributes for this entity
                 let mut attrs = HashMap::new();
                 match &self.mode {
                     HierarchyGeneratorMode::Arbitrary {
                         attributes_mode: AttributesMode::NoAttributes,
+                        ..
                     } => {
                         // don't add any attributes
                     }
@@ -599,8 +832,13 @@ impl<'a, 'u> HierarchyGenerator<'a, 'u> {
                                 .expect("typename should have an EntityType")
                                 .shape,
                         );
-                        if attributes.additional_attrs {
-                            // maybe add some additional attributes with arbitrary types
+                        if attributes.additional_attrs
+                            || schema.settings.enable_undeclared_entity_attrs
+                        {
+                            // maybe add some additional attributes with arbitrary types.
+                            // If the schema doesn't allow additional attributes, these
+                            // are undeclared attributes, so they must not shadow any
+                            // declared attribute.
                             self.u.arbitrary_loop(
                                 None,
                                 Some(schema.settings.max_width as u32),
@@ -611,6 +849,11 @@ impl<'a, 'u> HierarchyGenerator<'a, 'u> {
                                         Type::arbitrary_nonextension(u)?
                                     };
                                     let attr_name: String = u.arbitrary()?;
+                                    if !attributes.additional_attrs
+                                        && attributes.attrs.contains_key(attr_name.as_str())
+                                    {
+                                        return Ok(std::ops::ControlFlow::Continue(()));
+                                    }
                                     attrs.insert(
                                         attr_name.into(),
                                         schema
@@ -634,13 +877,37 @@ impl<'a, 'u> HierarchyGenerator<'a, 'u> {
                             // case we got a name collision between an explicitly specified
                             // attribute and one of the "additional" ones we added.
                             if ty.required || self.u.ratio::<u8>(1, 2)? {
-                                let attr_val = schema
-                                    .exprgenerator(Some(&hierarchy_no_attrs))
-                                    .generate_attr_value_for_schematype(
-                                        &ty.ty,
-                                        schema.settings.max_depth,
-                                        self.u,
-                                    )?;
+                                // For a recursive attribute (one whose type is this
+                                // entity's own type), only point to entities later in
+                                // the pool, like we do for parents. This ensures that
+                                // following the attribute always terminates. Generated
+                                // schemas make recursive attributes optional, so the
+                                // last entity of a chain can always leave it unset.
+                                let recursive = is_reference_to_entity_type(
+                                    &schema.schema,
+                                    schema.namespace(),
+                                    &ty.ty,
+                                    name,
+                                );
+                                let later_uids: Vec<&EntityUID> = if recursive {
+                                    hierarchy_no_attrs.uids_for_type(name, uid).collect()
+                                } else {
+                                    vec![]
+                                };
+                                let attr_val = if !later_uids.is_empty() {
+                                    AttrValue::UIDLit((*self.u.choose(&later_uids)?).clone())
+                                } else if recursive && !ty.required {
+                                    // end of the chain
+                                    continue;
+                                } else {
+                                    schema
+                                        .exprgenerator(Some(&hierarchy_no_attrs))
+                                        .generate_attr_value_for_schematype(
+                                            &ty.ty,
+                                            schema.settings.max_depth,
+                                            self.u,
+                                        )?
+                                };
                                 attrs.insert(
                                 attr.parse().expect(
                                     "all attribute names in the schema should be valid identifiers",
@@ -669,3 +936,283 @@ impl<'a, 'u> HierarchyGenerator<'a, 'u> {
         (0, None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AttributesMode, EntityUIDGenMode, Hierarchy, HierarchyGenerator, HierarchyGeneratorMode,
+        NumEntities,
+    };
+    use crate::collections::{HashMap, HashSet};
+    use crate::err::Error;
+    use crate::policy::{ActionConstraint, GeneratedPolicy, ScopeWeights};
+    use crate::schema::tests::{
+        for_each_schema, test_rng, GITHUB_SCHEMA_STR, ITERATION, RANDOM_BYTE_SIZE, TEST_SETTINGS,
+    };
+    use crate::schema::Schema;
+    use arbitrary::Unstructured;
+    use cedar_policy_core::ast;
+    use cedar_policy_core::entities::{
+        Entities, EntityJsonParser, NoEntitiesSchema, TCComputation,
+    };
+    use cedar_policy_core::extensions::Extensions;
+    use cedar_policy_validator::json_schema;
+    use rand::RngCore;
+
+    #[test]
+    fn hierarchical_uids() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        for_each_schema(&fragment, &TEST_SETTINGS, |schema, mut u| {
+            let Ok(hierarchy) = schema.arbitrary_hierarchy_with_uid_mode(
+                EntityUIDGenMode::default_hierarchical_mode(),
+                &mut u,
+            ) else {
+                return;
+            };
+            for uid in hierarchy.uids() {
+                let eid: &str = uid.eid().as_ref();
+                assert!(!eid.is_empty() && eid.split('/').count() <= 3, "{eid:?}");
+                // the UID is still a valid Cedar entity literal
+                let parsed: ast::EntityUID = uid.to_string().parse().expect("should parse");
+                assert_eq!(&parsed, uid);
+            }
+            // and round-trips through the entity JSON format
+            let entities = Entities::try_from(hierarchy.clone()).unwrap();
+            let json = entities.to_json_value().unwrap();
+            let parsed = EntityJsonParser::new(
+                None::<&NoEntitiesSchema>,
+                Extensions::all_available(),
+                TCComputation::ComputeNow,
+            )
+            .from_json_value(json)
+            .expect("should parse");
+            let uids: HashSet<_> = parsed.iter().map(|e| e.uid().clone()).collect();
+            assert_eq!(uids, hierarchy.uids().iter().cloned().collect());
+        });
+    }
+
+    #[test]
+    fn exact_total_entities() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut rng = test_rng();
+        for total in [0, 1, 7, 25] {
+            let mut bytes = vec![0; 1 << 16];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let schema = Schema::from_raw_schemafrag(fragment.clone(), TEST_SETTINGS, &mut u)
+                .expect("failed to generate schema!");
+            let hierarchy = HierarchyGenerator {
+                mode: HierarchyGeneratorMode::SchemaBased { schema: &schema },
+                uid_gen_mode: EntityUIDGenMode::default_nanoid_mode(),
+                num_entities: NumEntities::ExactTotal(total),
+                max_hierarchy_depth: None,
+                u: &mut u,
+                extensions: Extensions::all_available(),
+            }
+            .generate()
+            .expect("failed to generate hierarchy!");
+            assert_eq!(hierarchy.num_entities(), total);
+            // the budget is split as evenly as possible across entity types
+            let counts: Vec<usize> = schema
+                .entity_types()
+                .iter()
+                .map(|ty| {
+                    hierarchy
+                        .entities()
+                        .filter(|e| e.uid().entity_type() == ty)
+                        .count()
+                })
+                .collect();
+            let min = counts.iter().min().unwrap();
+            let max = counts.iter().max().unwrap();
+            assert!(
+                max - min <= 1,
+                "uneven split of {total} entities: {counts:?}"
+            );
+        }
+
+        // there are no entity types to put entities in
+        let mut u = Unstructured::new(&[]);
+        let res = HierarchyGenerator {
+            mode: HierarchyGeneratorMode::arbitrary_default(),
+            uid_gen_mode: EntityUIDGenMode::default_nanoid_mode(),
+            num_entities: NumEntities::ExactTotal(10),
+            max_hierarchy_depth: None,
+            u: &mut u,
+            extensions: Extensions::all_available(),
+        }
+        .generate();
+        assert!(matches!(res, Err(Error::NotEnoughData)));
+    }
+
+    #[test]
+    fn rbac_action_groups() {
+        let is_action = |uid: &ast::EntityUID| uid.entity_type().to_string() == "Action";
+        let mut rng = test_rng();
+        let mut found = false;
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let Ok(hierarchy) = HierarchyGenerator {
+                mode: HierarchyGeneratorMode::Arbitrary {
+                    attributes_mode: AttributesMode::NoAttributes,
+                    action_groups: true,
+                },
+                uid_gen_mode: EntityUIDGenMode::default(),
+                num_entities: NumEntities::RangePerEntityType(0..=4),
+                max_hierarchy_depth: None,
+                u: &mut u,
+                extensions: Extensions::all_available(),
+            }
+            .generate() else {
+                continue;
+            };
+            // actions are only members of other actions
+            for entity in hierarchy.entities() {
+                for parent in entity.ancestors() {
+                    assert_eq!(
+                        is_action(entity.uid()),
+                        is_action(parent),
+                        "{} is a member of {parent}",
+                        entity.uid()
+                    );
+                }
+            }
+            // computing the transitive closure fails on cycles
+            let entities =
+                Entities::try_from(hierarchy.clone()).expect("hierarchy should be acyclic");
+            for entity in entities.iter() {
+                assert!(!entity.is_descendant_of(entity.uid()));
+            }
+            let has_members = hierarchy
+                .entities()
+                .any(|e| is_action(e.uid()) && e.ancestors().next().is_some());
+            if has_members {
+                let action = hierarchy
+                    .arbitrary_member_action_uid(&mut u)
+                    .expect("failed to generate action");
+                let entity = hierarchy.entity(&action).expect("action should exist");
+                assert!(is_action(&action) && entity.ancestors().next().is_some());
+                found = true;
+            }
+        }
+        assert!(found, "no members of action groups were generated");
+    }
+
+    #[test]
+    fn rbac_leaf_actions() {
+        let mut rng = test_rng();
+        let mut found = false;
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let Ok(hierarchy) = HierarchyGenerator {
+                mode: HierarchyGeneratorMode::Arbitrary {
+                    attributes_mode: AttributesMode::NoAttributes,
+                    action_groups: true,
+                },
+                uid_gen_mode: EntityUIDGenMode::default(),
+                num_entities: NumEntities::RangePerEntityType(2..=8),
+                max_hierarchy_depth: None,
+                u: &mut u,
+                extensions: Extensions::all_available(),
+            }
+            .generate() else {
+                continue;
+            };
+            let groups = hierarchy.action_group_uids();
+            if groups.is_empty() {
+                continue;
+            }
+            let Ok(action) = hierarchy.arbitrary_leaf_action_uid(&mut u) else {
+                continue;
+            };
+            // the action is a real member of some group, and not a group
+            let entity = hierarchy.entity(&action).expect("action should exist");
+            assert!(entity.ancestors().any(|parent| groups.contains(&parent)));
+            assert!(!groups.contains(&&action));
+            let Ok(policy) = GeneratedPolicy::arbitrary_action_group_list_for_hierarchy(
+                None,
+                &hierarchy,
+                &ScopeWeights::DEFAULT,
+                ast::Expr::val(true),
+                &mut u,
+            ) else {
+                continue;
+            };
+            let ActionConstraint::InList(uids) = policy.action_constraint() else {
+                panic!("expected a list of action groups in {policy}");
+            };
+            assert!(!uids.is_empty());
+            assert!(uids.iter().all(|uid| groups.contains(&uid)));
+            found = true;
+        }
+        assert!(found, "no leaf actions were generated");
+    }
+
+    #[test]
+    fn bounded_hierarchy_depth() {
+        /// Length of the longest chain of `memberOf` edges starting at `uid`
+        fn chain_len(hierarchy: &Hierarchy, uid: &ast::EntityUID) -> usize {
+            hierarchy
+                .entity(uid)
+                .expect("parent should be in the hierarchy")
+                .ancestors()
+                .map(|parent| 1 + chain_len(hierarchy, parent))
+                .max()
+                .unwrap_or(0)
+        }
+        const MAX_HIERARCHY_DEPTH: usize = 2;
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut found = false;
+        let mut schema_based = false;
+        for_each_schema(&fragment, &TEST_SETTINGS, |schema, mut u| {
+            schema_based = !schema_based;
+            let mode = if schema_based {
+                HierarchyGeneratorMode::SchemaBased { schema: &schema }
+            } else {
+                HierarchyGeneratorMode::arbitrary_default()
+            };
+            let Ok(hierarchy) = HierarchyGenerator {
+                mode,
+                uid_gen_mode: EntityUIDGenMode::default(),
+                num_entities: NumEntities::RangePerEntityType(1..=4),
+                max_hierarchy_depth: Some(MAX_HIERARCHY_DEPTH),
+                u: &mut u,
+                extensions: Extensions::all_available(),
+            }
+            .generate() else {
+                return;
+            };
+            // computing the transitive closure fails on cycles
+            let entities =
+                Entities::try_from(hierarchy.clone()).expect("hierarchy should be acyclic");
+            let closed: HashMap<_, _> = entities.iter().map(|e| (e.uid(), e)).collect();
+            for entity in hierarchy.entities() {
+                let len = chain_len(&hierarchy, entity.uid());
+                assert!(
+                    len <= MAX_HIERARCHY_DEPTH,
+                    "{} starts a chain of length {len}",
+                    entity.uid()
+                );
+                // members of a parent are transitively members of its parents
+                for parent in entity.ancestors() {
+                    for grandparent in hierarchy.entity(parent).unwrap().ancestors() {
+                        found = true;
+                        assert!(
+                            closed[entity.uid()].is_descendant_of(grandparent),
+                            "{} should be in {grandparent}",
+                            entity.uid()
+                        );
+                    }
+                }
+            }
+        });
+        assert!(found, "no chains of length 2 were generated");
+    }
+}
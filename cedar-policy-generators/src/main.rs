@@ -60,8 +60,15 @@ pub struct HierarchyArgs {
     /// Maximum width
     #[arg(long, default_value_t = 4)]
     pub max_width: usize,
+    /// Maximum number of `when`/`unless` clauses per policy
+    #[arg(long, default_value_t = 4)]
+    pub max_conditions: usize,
     #[arg(long, default_value_t = EntityUIDGenMode::default_nanoid_len())]
     pub uid_length: usize,
+    /// Maximum length of a chain of `memberOf` edges
+    /// (if this is omitted, then the depth of the hierarchy is unbounded)
+    #[arg(long)]
+    pub max_hierarchy_depth: Option<usize>,
 }
 
 impl From<&HierarchyArgs> for ABACSettings {
@@ -69,15 +76,31 @@ impl From<&HierarchyArgs> for ABACSettings {
         Self {
             match_types: true,
             enable_extensions: true,
+            enable_ipaddr: false,
+            enable_invalid_extension_args: false,
             max_depth: value.max_depth,
             max_width: value.max_width,
+            max_conditions: value.max_conditions,
+            max_namespaces: 1,
             enable_additional_attributes: false,
+            enable_undeclared_entity_attrs: false,
             enable_like: true,
+            enable_unicode_strings: false,
             enable_action_groups_and_attrs: true,
             enable_arbitrary_func_call: false,
             enable_unknowns: false,
             enable_unspecified_apply_spec: true,
             enable_action_in_constraints: true,
+            enable_undeclared_actions: false,
+            enable_is_operator: true,
+            enable_guarded_attr_chains: false,
+            enable_unguarded_attr_chains: false,
+            record_set_nesting_weight: 0,
+            conditional_weight: 0,
+            prefer_context_conditions: false,
+            operator_weights: &[],
+            scope_weights: None,
+            enable_ownership_patterns: false,
         }
     }
 }
@@ -98,6 +121,7 @@ fn generate_hierarchy_from_schema(byte_length: usize, args: &HierarchyArgs) -> R
             Some(exact_num) => NumEntities::Exactly(exact_num),
             None => NumEntities::RangePerEntityType(1..=args.max_depth),
         },
+        max_hierarchy_depth: args.max_hierarchy_depth,
         u: &mut u,
         extensions: Extensions::all_available(),
     }
@@ -125,11 +149,19 @@ fn generate_hierarchy_from_schema(byte_length: usize, args: &HierarchyArgs) -> R
     )?)
 }
 
+/// Number of random bytes to generate a hierarchy from: `byte_length` for the
+/// schema and the entities' attributes and parents, plus a byte per character
+/// of each UID, since generating a UID fails once the bytes run out
+fn hierarchy_byte_length(byte_length: u16, args: &HierarchyArgs) -> usize {
+    byte_length as usize + args.num_entities.unwrap_or(0) * args.uid_length
+}
+
 fn main() {
     let cli = Cli::parse();
     match &cli.command {
         Commands::Hierarchy(args) => {
-            match generate_hierarchy_from_schema(cli.byte_length as usize, args) {
+            match generate_hierarchy_from_schema(hierarchy_byte_length(cli.byte_length, args), args)
+            {
                 Ok(h) => {
                     h.write_to_json(io::stdout())
                         .unwrap_or_else(|err| eprintln!("cannot convert entities to JSON: {err}"));
@@ -141,3 +173,44 @@ fn main() {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hierarchy_with_many_entities() {
+        let schema_file = std::env::temp_dir().join("cedar-policy-generators-many-entities.json");
+        std::fs::write(
+            &schema_file,
+            r#"{ "": {
+                "entityTypes": { "User": {} },
+                "actions": {
+                    "view": { "appliesTo": { "principalTypes": ["User"], "resourceTypes": ["User"] } }
+                }
+            } }"#,
+        )
+        .unwrap();
+        let cli = Cli::parse_from([
+            "cedar-policy-generators",
+            "hierarchy",
+            "--schema",
+            schema_file.to_str().unwrap(),
+            "--num-entities",
+            "10000",
+        ]);
+        let Commands::Hierarchy(args) = &cli.command;
+        // the UIDs alone need more bytes than fit in `byte_length`
+        assert!(args.num_entities.unwrap() * args.uid_length > u16::MAX as usize);
+        let entities =
+            generate_hierarchy_from_schema(hierarchy_byte_length(cli.byte_length, args), args)
+                .expect("should generate a hierarchy");
+        assert_eq!(
+            entities
+                .iter()
+                .filter(|e| e.uid().entity_type().to_string() == "User")
+                .count(),
+            10000
+        );
+    }
+}
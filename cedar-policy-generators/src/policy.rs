@@ -15,9 +15,9 @@
  */
 
 use crate::collections::HashMap;
-use crate::err::Result;
+use crate::err::{while_doing, Result};
 use crate::hierarchy::Hierarchy;
-use crate::size_hint_utils::size_hint_for_ratio;
+use crate::size_hint_utils::{size_hint_for_choose, size_hint_for_range, size_hint_for_ratio};
 use arbitrary::{Arbitrary, Unstructured};
 use cedar_policy_core::ast::{
     Annotation, Annotations, AnyId, Effect, EntityUID, Expr, Policy, PolicyID, PolicySet,
@@ -77,11 +77,14 @@ impl GeneratedPolicy {
         }
     }
 
-    /// Generate an arbitrary `GeneratedPolicy`
+    /// Generate an arbitrary `GeneratedPolicy`. Principal and resource
+    /// constraints without slots take a form chosen according to
+    /// `scope_weights`.
     pub fn arbitrary_for_hierarchy(
         fixed_id_opt: Option<PolicyID>,
         hierarchy: &Hierarchy,
         allow_slots: bool,
+        scope_weights: &ScopeWeights,
         abac_constraints: Expr,
         u: &mut Unstructured<'_>,
     ) -> arbitrary::Result<Self> {
@@ -92,11 +95,19 @@ impl GeneratedPolicy {
         };
         let annotations = u.arbitrary()?;
         let effect = u.arbitrary()?;
-        let principal_constraint =
-            PrincipalOrResourceConstraint::arbitrary_for_hierarchy(hierarchy, allow_slots, u)?;
+        let principal_constraint = PrincipalOrResourceConstraint::arbitrary_for_hierarchy(
+            hierarchy,
+            allow_slots,
+            scope_weights,
+            u,
+        )?;
         let action_constraint = ActionConstraint::arbitrary_for_hierarchy(hierarchy, u, Some(3))?;
-        let resource_constraint =
-            PrincipalOrResourceConstraint::arbitrary_for_hierarchy(hierarchy, allow_slots, u)?;
+        let resource_constraint = PrincipalOrResourceConstraint::arbitrary_for_hierarchy(
+            hierarchy,
+            allow_slots,
+            scope_weights,
+            u,
+        )?;
         Ok(Self {
             id,
             annotations,
@@ -108,6 +119,122 @@ impl GeneratedPolicy {
         })
     }
 
+    /// Generate an arbitrary template whose principal constraint is the
+    /// slotted membership `principal in ?principal`. The resource constraint
+    /// may also have a slot, or else takes a form chosen according to
+    /// `scope_weights`.
+    pub fn arbitrary_principal_in_slot_for_hierarchy(
+        fixed_id_opt: Option<PolicyID>,
+        hierarchy: &Hierarchy,
+        scope_weights: &ScopeWeights,
+        abac_constraints: Expr,
+        u: &mut Unstructured<'_>,
+    ) -> arbitrary::Result<Self> {
+        let id = if let Some(fixed_id) = fixed_id_opt {
+            fixed_id
+        } else {
+            u.arbitrary()?
+        };
+        let annotations = u.arbitrary()?;
+        let effect = u.arbitrary()?;
+        let action_constraint = ActionConstraint::arbitrary_for_hierarchy(hierarchy, u, Some(3))?;
+        let resource_constraint = PrincipalOrResourceConstraint::arbitrary_for_hierarchy(
+            hierarchy,
+            true,
+            scope_weights,
+            u,
+        )?;
+        Ok(Self {
+            id,
+            annotations,
+            effect,
+            principal_constraint: PrincipalOrResourceConstraint::InSlot,
+            action_constraint,
+            resource_constraint,
+            abac_constraints,
+        })
+    }
+
+    /// Generate an arbitrary policy whose action constraint is
+    /// `action in [...]`, naming action groups of the hierarchy (see
+    /// `Hierarchy::action_group_uids()`), so that whether a request's action
+    /// is in scope depends on the action hierarchy. Principal and resource
+    /// constraints don't have slots, and take a form chosen according to
+    /// `scope_weights`.
+    pub fn arbitrary_action_group_list_for_hierarchy(
+        fixed_id_opt: Option<PolicyID>,
+        hierarchy: &Hierarchy,
+        scope_weights: &ScopeWeights,
+        abac_constraints: Expr,
+        u: &mut Unstructured<'_>,
+    ) -> arbitrary::Result<Self> {
+        let id = if let Some(fixed_id) = fixed_id_opt {
+            fixed_id
+        } else {
+            u.arbitrary()?
+        };
+        let annotations = u.arbitrary()?;
+        let effect = u.arbitrary()?;
+        let principal_constraint = PrincipalOrResourceConstraint::arbitrary_for_hierarchy(
+            hierarchy,
+            false,
+            scope_weights,
+            u,
+        )?;
+        let action_constraint =
+            ActionConstraint::arbitrary_group_list_for_hierarchy(hierarchy, u, 3)?;
+        let resource_constraint = PrincipalOrResourceConstraint::arbitrary_for_hierarchy(
+            hierarchy,
+            false,
+            scope_weights,
+            u,
+        )?;
+        Ok(Self {
+            id,
+            annotations,
+            effect,
+            principal_constraint,
+            action_constraint,
+            resource_constraint,
+            abac_constraints,
+        })
+    }
+
+    /// size_hint for `arbitrary_action_group_list_for_hierarchy()`
+    pub fn arbitrary_action_group_list_size_hint(
+        have_fixed_id: bool,
+        depth: usize,
+    ) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            if have_fixed_id {
+                (0, Some(0))
+            } else {
+                <PolicyID as Arbitrary>::size_hint(depth)
+            },
+            <Effect as Arbitrary>::size_hint(depth),
+            PrincipalOrResourceConstraint::arbitrary_size_hint(false, depth),
+            ActionConstraint::arbitrary_group_list_size_hint(depth),
+            PrincipalOrResourceConstraint::arbitrary_size_hint(false, depth),
+        ])
+    }
+
+    /// size_hint for `arbitrary_principal_in_slot_for_hierarchy()`
+    pub fn arbitrary_principal_in_slot_size_hint(
+        have_fixed_id: bool,
+        depth: usize,
+    ) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            if have_fixed_id {
+                (0, Some(0))
+            } else {
+                <PolicyID as Arbitrary>::size_hint(depth)
+            },
+            <Effect as Arbitrary>::size_hint(depth),
+            ActionConstraint::arbitrary_size_hint(depth),
+            PrincipalOrResourceConstraint::arbitrary_size_hint(true, depth),
+        ])
+    }
+
     /// size_hint for `arbitrary_for_hierarchy()`
     pub fn arbitrary_for_hierarchy_size_hint(
         have_fixed_id: bool,
@@ -126,6 +253,41 @@ impl GeneratedPolicy {
             PrincipalOrResourceConstraint::arbitrary_size_hint(allow_slots, depth),
         ])
     }
+
+    /// Get the principal constraint of the policy
+    pub fn principal_constraint(&self) -> &PrincipalOrResourceConstraint {
+        &self.principal_constraint
+    }
+
+    /// Get the action constraint of the policy
+    pub fn action_constraint(&self) -> &ActionConstraint {
+        &self.action_constraint
+    }
+
+    /// Get the resource constraint of the policy
+    pub fn resource_constraint(&self) -> &PrincipalOrResourceConstraint {
+        &self.resource_constraint
+    }
+
+    /// Get the conditions of the policy
+    pub fn abac_constraints(&self) -> &Expr {
+        &self.abac_constraints
+    }
+
+    /// Get a policy with the same scope as this one, but with ID `id`, the
+    /// given effect and conditions, and no annotations
+    pub fn with_same_scope(&self, id: PolicyID, effect: Effect, abac_constraints: Expr) -> Self {
+        Self {
+            id,
+            annotations: HashMap::new(),
+            effect,
+            principal_constraint: self.principal_constraint.clone(),
+            action_constraint: self.action_constraint.clone(),
+            resource_constraint: self.resource_constraint.clone(),
+            abac_constraints,
+        }
+    }
+
     /// Does the policy have (a nonzero number of) slots
     pub fn has_slots(&self) -> bool {
         self.principal_constraint.has_slot() || self.resource_constraint.has_slot()
@@ -194,7 +356,7 @@ impl Display for GeneratedPolicy {
 }
 
 /// Represents the principal or resource constraint of the policy scope
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PrincipalOrResourceConstraint {
     /// No constraint, eg, `principal,` or `resource,`
     NoConstraint,
@@ -227,6 +389,16 @@ impl PrincipalOrResourceConstraint {
             PrincipalOrResourceConstraint::IsTypeInSlot(_) => true,
         }
     }
+
+    /// Fill the slot in this constraint, if it has one, with `uid`
+    fn fill_slot(&self, uid: Option<&EntityUID>) -> Self {
+        match (self, uid) {
+            (Self::EqSlot, Some(uid)) => Self::Eq(uid.clone()),
+            (Self::InSlot, Some(uid)) => Self::In(uid.clone()),
+            (Self::IsTypeInSlot(ty), Some(uid)) => Self::IsTypeIn(ty.clone(), uid.clone()),
+            _ => self.clone(),
+        }
+    }
 }
 
 impl From<PrincipalOrResourceConstraint> for ast::PrincipalConstraint {
@@ -297,71 +469,127 @@ impl std::fmt::Display for PrincipalOrResourceConstraint {
     }
 }
 
+/// Relative weights of the forms of a principal or resource constraint
+/// without slots. The weights must not all be `0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScopeWeights {
+    /// Weight of no constraint, e.g., `principal,`
+    pub no_constraint: u8,
+    /// Weight of e.g. `principal == User::"alice"`
+    pub eq: u8,
+    /// Weight of e.g. `principal in Group::"admins"`
+    pub is_in: u8,
+    /// Weight of e.g. `principal is User`
+    pub is_type: u8,
+    /// Weight of e.g. `principal is User in Group::"admins"`
+    pub is_type_in: u8,
+}
+
+impl ScopeWeights {
+    /// The weights the generators use unless configured otherwise: 20% no
+    /// constraint, 32% `==`, and 16% each of `in`, `is`, and `is .. in`
+    pub const DEFAULT: Self = Self {
+        no_constraint: 5,
+        eq: 8,
+        is_in: 4,
+        is_type: 4,
+        is_type_in: 4,
+    };
+}
+
+impl Default for ScopeWeights {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
 impl PrincipalOrResourceConstraint {
+    /// Generate a constraint without slots, in one of the forms chosen
+    /// according to `weights`. `uid` is the entity of `==`, `in`, and
+    /// `is .. in` constraints, and `ety` the entity type of `is` and
+    /// `is .. in` constraints.
+    pub fn arbitrary_with_weights(
+        weights: &ScopeWeights,
+        uid: EntityUID,
+        ety: ast::EntityType,
+        u: &mut Unstructured<'_>,
+    ) -> Result<Self> {
+        gen!(u,
+            weights.no_constraint => Ok(Self::NoConstraint),
+            weights.eq => Ok(Self::Eq(uid)),
+            weights.is_in => Ok(Self::In(uid)),
+            weights.is_type => Ok(Self::IsType(ety)),
+            weights.is_type_in => Ok(Self::IsTypeIn(ety, uid))
+        )
+    }
+
+    /// size hint for arbitrary_with_weights()
+    pub fn arbitrary_with_weights_size_hint(_depth: usize) -> (usize, Option<usize>) {
+        // `gen!` sums the weights as a `u32`, but the five `u8` weights sum to
+        // at most 1275, so the form takes at most two bytes
+        size_hint_for_range(0u16, u16::MAX)
+    }
+
     fn arbitrary_for_hierarchy(
         hierarchy: &Hierarchy,
         allow_slots: bool,
+        scope_weights: &ScopeWeights,
         u: &mut Unstructured<'_>,
     ) -> Result<Self> {
-        // 20% of the time, NoConstraint
-        if u.ratio(1, 5)? {
-            Ok(Self::NoConstraint)
+        // If slots are allowed, then generate a slot 50% of the time.
+        if allow_slots && u.ratio(1, 2)? {
+            // 40% Eq, 40% In or IsIn.
+            // Don't generate `Is` on its own because it can't have a slot.
+            gen!(u,
+                2 => Ok(Self::EqSlot),
+                1 => Ok(Self::InSlot),
+                1 => Ok(Self::IsTypeInSlot(hierarchy.arbitrary_entity_type(u)?))
+            )
         } else {
-            // If slots are allowed, then generate a slot 50% of the time.
-            if allow_slots && u.ratio(1, 2)? {
-                // 40% Eq, 40% In or IsIn.
-                // Don't generate `Is` on its own because it can't have a slot.
-                gen!(u,
-                    2 => Ok(Self::EqSlot),
-                    1 => Ok(Self::InSlot),
-                    1 => Ok(Self::IsTypeInSlot(hierarchy.arbitrary_entity_type(u)?))
-                )
+            // an entity that exists, so that authorization depends on its
+            // ancestors, unless there are none
+            let uid = if hierarchy.uids().is_empty() {
+                hierarchy.arbitrary_uid(u, None)?
             } else {
-                // 32% Eq, 16% In, 16% Is, 16% IsIn
-                let uid = hierarchy.arbitrary_uid(u, None)?;
-                gen!(u,
-                    2 => Ok(Self::Eq(uid)),
-                    1 => Ok(Self::In(uid)),
-                    1 => Ok(Self::IsType(hierarchy.arbitrary_entity_type(u)?)),
-                    1 => Ok(Self::IsTypeIn(hierarchy.arbitrary_entity_type(u)?, uid))
-                )
-            }
+                u.choose(hierarchy.uids())
+                    .map_err(|e| while_doing("choosing a uid for a scope constraint".into(), e))?
+                    .clone()
+            };
+            let ety = hierarchy.arbitrary_entity_type(u)?;
+            Self::arbitrary_with_weights(scope_weights, uid, ety, u)
         }
     }
 
     /// size hint for arbitrary_for_hierarchy()
     fn arbitrary_size_hint(allow_slots: bool, depth: usize) -> (usize, Option<usize>) {
-        arbitrary::size_hint::and(
-            size_hint_for_ratio(1, 5),
+        // No slot: need a UID, an entity type, and a form.
+        let no_slot = arbitrary::size_hint::and_all(&[
             arbitrary::size_hint::or(
-                // NoConstraint case
-                (0, Some(0)),
-                // Eq or In case
-                arbitrary::size_hint::and(
-                    size_hint_for_ratio(1, 2), // choose Eq or In
-                    if allow_slots {
-                        arbitrary::size_hint::and(
-                            // Decide whether to generate a slot.
-                            size_hint_for_ratio(1, 2),
-                            arbitrary::size_hint::or(
-                                // Slot: don't need a UID.
-                                (0, Some(0)),
-                                // No slot: need a UID.
-                                Hierarchy::arbitrary_uid_size_hint(depth),
-                            ),
-                        )
-                    } else {
-                        // Slot not allowed: need a UID.
-                        Hierarchy::arbitrary_uid_size_hint(depth)
-                    },
-                ),
+                size_hint_for_choose(None),
+                // empty hierarchy
+                Hierarchy::arbitrary_uid_size_hint(depth),
             ),
-        )
+            size_hint_for_choose(None),
+            Self::arbitrary_with_weights_size_hint(depth),
+        ]);
+        if allow_slots {
+            arbitrary::size_hint::and(
+                // Decide whether to generate a slot.
+                size_hint_for_ratio(1, 2),
+                arbitrary::size_hint::or(
+                    // Slot: don't need a UID.
+                    size_hint_for_choose(None),
+                    no_slot,
+                ),
+            )
+        } else {
+            no_slot
+        }
     }
 }
 
 /// Represents the action constraint of the policy scope
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ActionConstraint {
     /// No constraint, eg, `action,`
     NoConstraint,
@@ -415,19 +643,45 @@ impl ActionConstraint {
         if u.ratio(1, 10)? {
             Ok(Self::NoConstraint)
         } else if u.ratio(1, 3)? {
-            Ok(Self::Eq(hierarchy.arbitrary_uid(u, None)?))
+            Ok(Self::Eq(hierarchy.arbitrary_action_uid(u)?))
         } else if u.ratio(1, 2)? {
-            Ok(Self::In(hierarchy.arbitrary_uid(u, None)?))
+            Ok(Self::In(hierarchy.arbitrary_action_uid(u)?))
         } else {
             let mut uids = vec![];
             u.arbitrary_loop(Some(0), max_list_length, |u| {
-                uids.push(hierarchy.arbitrary_uid(u, None)?);
+                uids.push(hierarchy.arbitrary_action_uid(u)?);
                 Ok(std::ops::ControlFlow::Continue(()))
             })?;
             Ok(Self::InList(uids))
         }
     }
 
+    /// Generate `action in [...]` with one to `max_list_length` action groups
+    /// of the hierarchy. If the hierarchy has no action groups, this is
+    /// `arbitrary_for_hierarchy()`.
+    fn arbitrary_group_list_for_hierarchy(
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+        max_list_length: u32,
+    ) -> Result<Self> {
+        let groups = hierarchy.action_group_uids();
+        if groups.is_empty() {
+            return Self::arbitrary_for_hierarchy(hierarchy, u, Some(max_list_length));
+        }
+        let mut uids = vec![];
+        u.arbitrary_loop(Some(1), Some(max_list_length), |u| {
+            uids.push((*u.choose(&groups)?).clone());
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        Ok(Self::InList(uids))
+    }
+
+    /// size hint for arbitrary_group_list_for_hierarchy()
+    fn arbitrary_group_list_size_hint(_depth: usize) -> (usize, Option<usize>) {
+        // not sure how to account for arbitrary_loop(); this seems safe
+        (1, None)
+    }
+
     /// size hint for arbitrary_for_hierarchy()
     fn arbitrary_size_hint(depth: usize) -> (usize, Option<usize>) {
         arbitrary::size_hint::and(
@@ -482,6 +736,20 @@ impl GeneratedLinkedPolicy {
         }
     }
 
+    /// Like `arbitrary_slot_value()`, but half of the time the value is the
+    /// UID of an entity that isn't in `hierarchy`
+    fn arbitrary_missing_slot_value(
+        prc: &PrincipalOrResourceConstraint,
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<Option<EntityUID>> {
+        if prc.has_slot() && u.ratio(1, 2)? {
+            Ok(Some(hierarchy.arbitrary_missing_uid(u)?))
+        } else {
+            Self::arbitrary_slot_value(prc, hierarchy, u)
+        }
+    }
+
     /// Generate an arbitrary `GeneratedLinkedPolicy` from the given template
     pub fn arbitrary(
         id: PolicyID,
@@ -497,6 +765,86 @@ impl GeneratedLinkedPolicy {
         })
     }
 
+    /// Like `arbitrary()`, but each slot is filled with the UID of an entity
+    /// that isn't in `hierarchy` half of the time, so that the link refers to
+    /// a missing entity. Linking doesn't check that the entities exist.
+    pub fn arbitrary_with_missing_entities(
+        id: PolicyID,
+        template: &GeneratedPolicy,
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<Self> {
+        Ok(Self {
+            id,
+            template_id: template.id.clone(),
+            principal: Self::arbitrary_missing_slot_value(
+                &template.principal_constraint,
+                hierarchy,
+                u,
+            )?,
+            resource: Self::arbitrary_missing_slot_value(
+                &template.resource_constraint,
+                hierarchy,
+                u,
+            )?,
+        })
+    }
+
+    /// Create a new `GeneratedLinkedPolicy` linking `template` with the given
+    /// slot values. Values for slots the template doesn't have are dropped.
+    pub fn new(
+        id: PolicyID,
+        template: &GeneratedPolicy,
+        principal: EntityUID,
+        resource: EntityUID,
+    ) -> Self {
+        Self {
+            id,
+            template_id: template.id.clone(),
+            principal: Some(principal).filter(|_| template.principal_constraint.has_slot()),
+            resource: Some(resource).filter(|_| template.resource_constraint.has_slot()),
+        }
+    }
+
+    /// Get a copy of this `GeneratedLinkedPolicy` with the given `id`. The
+    /// copy links the same template with the same slot values, so the two
+    /// links have identical scopes.
+    pub fn relinked(&self, id: PolicyID) -> Self {
+        Self { id, ..self.clone() }
+    }
+
+    /// Get a copy of this `GeneratedLinkedPolicy` which fills the principal
+    /// slot with `principal` instead. Has no effect if the template has no
+    /// principal slot.
+    pub fn with_principal(&self, principal: EntityUID) -> Self {
+        Self {
+            principal: self.principal.as_ref().map(|_| principal),
+            ..self.clone()
+        }
+    }
+
+    /// Iterate over the values of the slots of this link
+    pub fn slot_values(&self) -> impl Iterator<Item = &EntityUID> {
+        self.principal.iter().chain(self.resource.iter())
+    }
+
+    /// Get the static policy that this link of `template` stands for: the
+    /// template with its slots filled in by hand, under the ID of the link.
+    /// This doesn't go through `PolicySet::link()`, so it can serve as the
+    /// expected result of linking.
+    pub fn instantiate(&self, template: &GeneratedPolicy) -> GeneratedPolicy {
+        GeneratedPolicy {
+            id: self.id.clone(),
+            principal_constraint: template
+                .principal_constraint
+                .fill_slot(self.principal.as_ref()),
+            resource_constraint: template
+                .resource_constraint
+                .fill_slot(self.resource.as_ref()),
+            ..template.clone()
+        }
+    }
+
     /// Add this `GeneratedLinkedPolicy` to the given `PolicySet`
     pub fn add_to_policyset(self, policyset: &mut PolicySet) {
         let mut vals = HashMap::new();
@@ -16,7 +16,7 @@
 
 use crate::collections::HashMap;
 use crate::hierarchy::Hierarchy;
-use crate::policy::GeneratedPolicy;
+use crate::policy::{GeneratedPolicy, ScopeWeights};
 use crate::request::Request;
 use arbitrary::{self, Unstructured};
 use ast::{Entity, Expr, PolicyID, StaticPolicy};
@@ -44,6 +44,47 @@ impl RBACHierarchy {
         s.push(']');
         s
     }
+
+    /// Express the `memberOf` edges of the hierarchy as a graphviz `digraph`,
+    /// for debugging, e.g., with `dot -Tsvg`. Each entity has an edge to each
+    /// of its ancestors. Actions and action groups are drawn as boxes in a
+    /// cluster of their own, apart from the other entities.
+    pub fn to_dot(&self) -> String {
+        let (actions, entities): (Vec<&Entity>, Vec<&Entity>) =
+            self.0.entities().partition(|e| e.uid().is_action());
+        let mut s = String::from("digraph hierarchy {\n");
+        for (cluster, label, shape, members) in [
+            ("cluster_entities", "entities", "ellipse", entities),
+            ("cluster_actions", "actions", "box", actions),
+        ] {
+            s.push_str(&format!(
+                "  subgraph {cluster} {{\n    label=\"{label}\";\n"
+            ));
+            for entity in &members {
+                s.push_str(&format!("    {} [shape={shape}];\n", dot_id(entity.uid())));
+            }
+            s.push_str("  }\n");
+        }
+        for entity in self.0.entities() {
+            for ancestor in entity.ancestors() {
+                s.push_str(&format!(
+                    "  {} -> {};\n",
+                    dot_id(entity.uid()),
+                    dot_id(ancestor)
+                ));
+            }
+        }
+        s.push('}');
+        s
+    }
+}
+
+/// The graphviz id of the node for `uid`: its `Display`, quoted and escaped
+fn dot_id(uid: &ast::EntityUID) -> String {
+    format!(
+        "\"{}\"",
+        uid.to_string().replace('\\', "\\\\").replace('"', "\\\"")
+    )
 }
 
 impl Deref for RBACHierarchy {
@@ -165,22 +206,79 @@ impl From<RBACPolicy> for StaticPolicy {
 }
 
 impl RBACPolicy {
-    /// Generate an arbitrary RBAC policy
+    /// Generate an arbitrary RBAC policy, with principal and resource
+    /// constraints in forms chosen according to `scope_weights`
     pub fn arbitrary_for_hierarchy(
         fixed_id_opt: Option<PolicyID>,
         hierarchy: &Hierarchy,
         allow_slots: bool,
+        scope_weights: &ScopeWeights,
         u: &mut Unstructured<'_>,
     ) -> arbitrary::Result<Self> {
         Ok(Self(GeneratedPolicy::arbitrary_for_hierarchy(
             fixed_id_opt,
             hierarchy,
             allow_slots,
+            scope_weights,
             Expr::val(true),
             u,
         )?))
     }
 
+    /// Generate an arbitrary RBAC template with the slotted principal scope
+    /// `principal in ?principal`
+    pub fn arbitrary_principal_in_slot_for_hierarchy(
+        fixed_id_opt: Option<PolicyID>,
+        hierarchy: &Hierarchy,
+        scope_weights: &ScopeWeights,
+        u: &mut Unstructured<'_>,
+    ) -> arbitrary::Result<Self> {
+        Ok(Self(
+            GeneratedPolicy::arbitrary_principal_in_slot_for_hierarchy(
+                fixed_id_opt,
+                hierarchy,
+                scope_weights,
+                Expr::val(true),
+                u,
+            )?,
+        ))
+    }
+
+    /// Generate an arbitrary RBAC policy with the action scope
+    /// `action in [...]`, naming action groups of the hierarchy
+    pub fn arbitrary_action_group_list_for_hierarchy(
+        fixed_id_opt: Option<PolicyID>,
+        hierarchy: &Hierarchy,
+        scope_weights: &ScopeWeights,
+        u: &mut Unstructured<'_>,
+    ) -> arbitrary::Result<Self> {
+        Ok(Self(
+            GeneratedPolicy::arbitrary_action_group_list_for_hierarchy(
+                fixed_id_opt,
+                hierarchy,
+                scope_weights,
+                Expr::val(true),
+                u,
+            )?,
+        ))
+    }
+
+    /// size hint for arbitrary_action_group_list_for_hierarchy()
+    pub fn arbitrary_action_group_list_size_hint(
+        have_fixed_id: bool,
+        depth: usize,
+    ) -> (usize, Option<usize>) {
+        GeneratedPolicy::arbitrary_action_group_list_size_hint(have_fixed_id, depth)
+    }
+
+    /// size hint for arbitrary_principal_in_slot_for_hierarchy()
+    pub fn arbitrary_principal_in_slot_size_hint(
+        have_fixed_id: bool,
+        depth: usize,
+    ) -> (usize, Option<usize>) {
+        GeneratedPolicy::arbitrary_principal_in_slot_size_hint(have_fixed_id, depth)
+    }
+
     /// size hint for arbitrary_for_hierarchy()
     pub fn arbitrary_size_hint(
         have_fixed_id: bool,
@@ -239,6 +337,42 @@ impl RBACRequest {
         )?))
     }
 
+    /// Generate an arbitrary RBAC request whose action is a member of an
+    /// action group, if the hierarchy has any (see
+    /// `Hierarchy::arbitrary_member_action_uid()`)
+    pub fn arbitrary_with_member_action_for_hierarchy(
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> arbitrary::Result<Self> {
+        let principal = hierarchy.arbitrary_uid(u, Some(ast::Var::Principal))?;
+        let action = hierarchy.arbitrary_member_action_uid(u)?;
+        let resource = hierarchy.arbitrary_uid(u, Some(ast::Var::Resource))?;
+        Ok(Self(Request {
+            principal,
+            action,
+            resource,
+            context: ast::Context::empty(),
+        }))
+    }
+
+    /// Generate an arbitrary RBAC request whose action is a leaf of the
+    /// action hierarchy, if the hierarchy has action groups (see
+    /// `Hierarchy::arbitrary_leaf_action_uid()`)
+    pub fn arbitrary_with_leaf_action_for_hierarchy(
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> arbitrary::Result<Self> {
+        let principal = hierarchy.arbitrary_uid(u, Some(ast::Var::Principal))?;
+        let action = hierarchy.arbitrary_leaf_action_uid(u)?;
+        let resource = hierarchy.arbitrary_uid(u, Some(ast::Var::Resource))?;
+        Ok(Self(Request {
+            principal,
+            action,
+            resource,
+            context: ast::Context::empty(),
+        }))
+    }
+
     /// size hint for arbitrary_for_hierarchy()
     pub fn arbitrary_size_hint(depth: usize) -> (usize, Option<usize>) {
         arbitrary::size_hint::and_all(&[
@@ -248,3 +382,33 @@ impl RBACRequest {
         ])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::RBACHierarchy;
+    use crate::schema::tests::{test_rng, GITHUB_SCHEMA_STR, RANDOM_BYTE_SIZE, TEST_SETTINGS};
+    use crate::schema::Schema;
+    use arbitrary::Unstructured;
+    use cedar_policy_validator::json_schema;
+    use rand::RngCore;
+
+    #[test]
+    fn hierarchy_to_dot() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut rng = test_rng();
+        let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+        rng.fill_bytes(&mut bytes);
+        let mut u = Unstructured::new(&bytes);
+        let schema = Schema::from_raw_schemafrag(fragment, TEST_SETTINGS, &mut u)
+            .expect("failed to generate schema!");
+        let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+            return;
+        };
+        let edges: usize = hierarchy.entities().map(|e| e.ancestors().count()).sum();
+        let dot = RBACHierarchy(hierarchy).to_dot();
+        assert!(dot.starts_with("digraph hierarchy {"));
+        assert!(dot.ends_with('}'));
+        assert_eq!(dot.matches(" -> ").count(), edges);
+    }
+}
@@ -53,6 +53,45 @@ impl Request {
     }
 }
 
+/// JSON numbers at and around the edges of what Cedar reads as a `Long`: the
+/// bounds of `i64` and one past each of them, and integral values written
+/// with a fraction or an exponent, which Cedar doesn't read as `Long`s
+pub const BOUNDARY_NUMBER_JSON: [&str; 12] = [
+    "0",
+    "-0",
+    "1",
+    "-1",
+    "9223372036854775807",
+    "-9223372036854775808",
+    "9223372036854775808",
+    "-9223372036854775809",
+    "18446744073709551616",
+    "1.5",
+    "3.0",
+    "1e3",
+];
+
+/// Generate the JSON for a context record with the given attributes, each of
+/// which is one of [`BOUNDARY_NUMBER_JSON`]. The JSON is written out directly
+/// rather than through `serde_json`, so that out-of-range and non-integral
+/// numbers appear exactly as chosen.
+pub fn arbitrary_boundary_number_context_json(
+    attrs: &[SmolStr],
+    u: &mut Unstructured<'_>,
+) -> arbitrary::Result<String> {
+    let fields = attrs
+        .iter()
+        .map(|attr| {
+            Ok(format!(
+                "{}: {}",
+                serde_json::Value::from(attr.as_str()),
+                u.choose(&BOUNDARY_NUMBER_JSON)?
+            ))
+        })
+        .collect::<arbitrary::Result<Vec<_>>>()?;
+    Ok(format!("{{{}}}", fields.join(", ")))
+}
+
 impl From<Request> for ast::Request {
     fn from(req: Request) -> ast::Request {
         ast::Request::new(
@@ -67,6 +106,89 @@ impl From<Request> for ast::Request {
     }
 }
 
+/// Data structure representing a partial authorization request, whose
+/// principal, resource, or context may be unknown (`None`).
+///
+/// Request validation only checks the parts of a request that are known: a
+/// known principal or resource must be of a type in the applies-to of the
+/// action, and a known context must match the action's context type. An
+/// unknown part is always valid, so any combination of unknowns is valid per
+/// the schema as long as the known parts are.
+#[derive(Debug, Clone)]
+pub struct PartialRequest {
+    /// Principal, if known
+    pub principal: Option<EntityUID>,
+    /// Action
+    pub action: EntityUID,
+    /// Resource, if known
+    pub resource: Option<EntityUID>,
+    /// Context, if known
+    pub context: Option<ast::Context>,
+}
+
+impl PartialRequest {
+    /// Make some parts of `request` unknown: each of the principal, resource,
+    /// and context independently with probability 1/3
+    pub fn arbitrary_from_request(
+        request: Request,
+        u: &mut Unstructured<'_>,
+    ) -> arbitrary::Result<Self> {
+        Ok(Self {
+            principal: (!u.ratio::<u8>(1, 3)?).then_some(request.principal),
+            action: request.action,
+            resource: (!u.ratio::<u8>(1, 3)?).then_some(request.resource),
+            context: (!u.ratio::<u8>(1, 3)?).then_some(request.context),
+        })
+    }
+}
+
+impl From<PartialRequest> for ast::Request {
+    fn from(req: PartialRequest) -> ast::Request {
+        let entry = |uid: Option<EntityUID>| match uid {
+            Some(uid) => ast::EntityUIDEntry::known(uid, None),
+            None => ast::EntityUIDEntry::unknown(),
+        };
+        ast::Request::new_with_unknowns(
+            entry(req.principal),
+            entry(Some(req.action)),
+            entry(req.resource),
+            req.context,
+            None::<&ast::RequestSchemaAllPass>,
+            Extensions::all_available(),
+        )
+        .expect("we aren't doing request validation here, so new_with_unknowns() can't fail")
+    }
+}
+
+impl std::fmt::Display for PartialRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let show = |uid: &Option<EntityUID>| match uid {
+            Some(uid) => uid.to_string(),
+            None => "unknown".to_string(),
+        };
+        write!(
+            f,
+            "(principal : {}, action: {}, resource: {})",
+            show(&self.principal),
+            self.action,
+            show(&self.resource)
+        )?;
+        let Some(context) = &self.context else {
+            return write!(f, "\nWith unknown context");
+        };
+        let mut context = context.clone().into_iter().peekable();
+        if context.peek().is_some() {
+            writeln!(f, "\nWith context: {{")?;
+            for (attr, val) in context {
+                writeln!(f, "{attr} : {val},")?;
+            }
+            write!(f, "}}")
+        } else {
+            Ok(())
+        }
+    }
+}
+
 impl std::fmt::Display for Request {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -15,25 +15,29 @@
  */
 
 use crate::abac::{
-    ABACPolicy, ABACRequest, AvailableExtensionFunctions, ConstantPool, Type, UnknownPool,
+    arbitrary_unicode_string, ABACPolicy, ABACRequest, AvailableExtensionFunctions, ConstantPool,
+    Type, UnknownPool, MAX_UNICODE_STRING_LEN,
 };
 use crate::collections::{HashMap, HashSet};
 use crate::err::{while_doing, Error, Result};
-use crate::expr::ExprGenerator;
+use crate::expr::{bool_operator_combinations, ExprGenerator, ACTION_ATTR_NAMES};
 use crate::hierarchy::{
     EntityUIDGenMode, Hierarchy, HierarchyGenerator, HierarchyGeneratorMode, NumEntities,
 };
-use crate::policy::{ActionConstraint, GeneratedPolicy, PrincipalOrResourceConstraint};
-use crate::request::Request;
-use crate::settings::ABACSettings;
+use crate::policy::{
+    ActionConstraint, GeneratedLinkedPolicy, GeneratedPolicy, PrincipalOrResourceConstraint,
+};
+use crate::request::{PartialRequest, Request};
+use crate::settings::{max_ast_nodes, ABACSettings};
 use crate::size_hint_utils::{size_hint_for_choose, size_hint_for_range, size_hint_for_ratio};
 use crate::{accum, gen, gen_inner, uniform};
 use arbitrary::{self, Arbitrary, Unstructured};
 use cedar_policy_core::ast::{self, Effect, PolicyID, UnreservedId};
 use cedar_policy_core::extensions::Extensions;
 use cedar_policy_validator::{json_schema, RawName, SchemaError, ValidatorSchema};
+use serde::Serialize;
 use smol_str::{SmolStr, ToSmolStr};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 /// Contains the schema, but also pools of constants etc
 #[derive(Debug, Clone)]
@@ -72,6 +76,26 @@ pub struct Schema {
     attributes_by_type: HashMap<Type, Vec<(ast::EntityType, SmolStr)>>,
 }
 
+/// Summary counts describing a [`Schema`], for tuning the generators and as
+/// coverage signals. See [`Schema::stats()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SchemaStats {
+    /// Number of actions
+    pub num_actions: usize,
+    /// Number of entity types
+    pub num_entity_types: usize,
+    /// Number of common types
+    pub num_common_types: usize,
+    /// Largest number of attributes declared on a single entity type
+    pub max_entity_attrs: usize,
+    /// Number of extension-typed attributes of entity types and action
+    /// contexts. Only top-level attributes are counted.
+    pub num_extension_attrs: usize,
+    /// Length of the longest chain of action-group memberships, e.g., 2 if
+    /// action `a` is in `b` and `b` is in `c`
+    pub action_group_depth: usize,
+}
+
 /// internal helper function, basically `impl Arbitrary for AttributesOrContext`
 fn arbitrary_attrspec<N: From<ast::Name>>(
     settings: &ABACSettings,
@@ -103,7 +127,12 @@ fn arbitrary_attrspec<N: From<ast::Name>>(
                             )?;
                         }
                     }
-                    Ok((AsRef::<str>::as_ref(&attr).into(), ty))
+                    let attr = if settings.enable_unicode_strings && u.ratio(1, 4)? {
+                        arbitrary_unicode_string(u, MAX_UNICODE_STRING_LEN)?
+                    } else {
+                        AsRef::<str>::as_ref(&attr).into()
+                    };
+                    Ok((attr, ty))
                 })
                 .collect::<Result<_>>()?,
             additional_attributes: if settings.enable_additional_attributes {
@@ -114,6 +143,78 @@ fn arbitrary_attrspec<N: From<ast::Name>>(
         }),
     )))
 }
+/// internal helper function, add an optional attribute to `attrspec` whose
+/// type is a reference to `etype` itself (e.g., `Person { manager?: Person }`).
+/// `attrspec` is expected to be a record type, as produced by
+/// `arbitrary_attrspec()`.
+fn add_recursive_attr<N: From<ast::Name>>(
+    attrspec: &mut json_schema::AttributesOrContext<N>,
+    etype: &ast::EntityType,
+    u: &mut Unstructured<'_>,
+) -> Result<()> {
+    if let json_schema::Type::Type(json_schema::TypeVariant::Record(record)) = &mut attrspec.0 {
+        let attr: ast::Id = u
+            .arbitrary()
+            .map_err(|e| while_doing("generating a recursive attribute name".into(), e))?;
+        record.attributes.insert(
+            AsRef::<str>::as_ref(&attr).into(),
+            json_schema::TypeOfAttribute {
+                ty: entity_type_name_to_schema_type(etype),
+                // optional, so that chains of entities linked through this
+                // attribute can terminate
+                required: false,
+            },
+        );
+    }
+    Ok(())
+}
+
+/// internal helper function, make every attribute in `attrspec` whose type is a
+/// reference to `etype` itself optional. A required one would force the last
+/// entity of a chain linked through it to point back into the chain.
+/// `attrspec` is expected to be a record type, as produced by
+/// `arbitrary_attrspec()`.
+fn make_recursive_attrs_optional<N: From<ast::Name> + PartialEq>(
+    attrspec: &mut json_schema::AttributesOrContext<N>,
+    etype: &ast::EntityType,
+) {
+    if let json_schema::Type::Type(json_schema::TypeVariant::Record(record)) = &mut attrspec.0 {
+        let self_type = entity_type_name_to_schema_type(etype);
+        for ty in record.attributes.values_mut() {
+            if ty.ty == self_type {
+                ty.required = false;
+            }
+        }
+    }
+}
+
+/// internal helper function, add a required attribute to `attrspec` whose type
+/// is a reference to one of `entity_types` (e.g., `context.device: Device`).
+/// `attrspec` is expected to be a record type, as produced by
+/// `arbitrary_attrspec()`.
+fn add_entity_attr<N: From<ast::Name>>(
+    attrspec: &mut json_schema::AttributesOrContext<N>,
+    entity_types: &[ast::EntityType],
+    u: &mut Unstructured<'_>,
+) -> Result<()> {
+    if let json_schema::Type::Type(json_schema::TypeVariant::Record(record)) = &mut attrspec.0 {
+        let attr: ast::Id = u
+            .arbitrary()
+            .map_err(|e| while_doing("generating an entity-typed attribute name".into(), e))?;
+        let etype = u
+            .choose(entity_types)
+            .map_err(|e| while_doing("choosing an entity type for an attribute".into(), e))?;
+        record.attributes.insert(
+            AsRef::<str>::as_ref(&attr).into(),
+            json_schema::TypeOfAttribute {
+                ty: entity_type_name_to_schema_type(etype),
+                required: true,
+            },
+        );
+    }
+    Ok(())
+}
+
 /// size hint for arbitrary_attrspec
 fn arbitrary_attrspec_size_hint(depth: usize) -> (usize, Option<usize>) {
     arbitrary::size_hint::recursion_guard(depth, |depth| {
@@ -174,68 +275,21 @@ pub fn arbitrary_schematype_with_bounded_depth<N: From<ast::Name>>(
     max_depth: usize,
     u: &mut Unstructured<'_>,
 ) -> Result<json_schema::Type<N>> {
+    let weight = settings.record_set_nesting_weight;
+    if max_depth > 0 && weight > 0 && u.ratio(weight, weight.saturating_add(6))? {
+        return Ok(json_schema::Type::Type(if u.arbitrary()? {
+            arbitrary_set_schematype_variant(settings, entity_types, max_depth, u)?
+        } else {
+            arbitrary_record_schematype_variant(settings, entity_types, max_depth, u)?
+        }));
+    }
     Ok(json_schema::Type::Type(uniform!(
         u,
         json_schema::TypeVariant::String,
         json_schema::TypeVariant::Long,
         json_schema::TypeVariant::Boolean,
-        {
-            if max_depth == 0 {
-                // can't recurse; we arbitrarily choose Set<Long> in this case
-                json_schema::TypeVariant::Set {
-                    element: Box::new(json_schema::Type::Type(json_schema::TypeVariant::Long)),
-                }
-            } else {
-                json_schema::TypeVariant::Set {
-                    element: Box::new(arbitrary_schematype_with_bounded_depth(
-                        settings,
-                        entity_types,
-                        max_depth - 1,
-                        u,
-                    )?),
-                }
-            }
-        },
-        {
-            if max_depth == 0 {
-                // can't recurse; use empty-record
-                json_schema::TypeVariant::Record(json_schema::RecordType {
-                    attributes: BTreeMap::new(),
-                    additional_attributes: if settings.enable_additional_attributes {
-                        u.arbitrary()?
-                    } else {
-                        false
-                    },
-                })
-            } else {
-                json_schema::TypeVariant::Record(json_schema::RecordType {
-                    attributes: {
-                        let attr_names: HashSet<String> = u
-                            .arbitrary()
-                            .map_err(|e| while_doing("generating attribute names".into(), e))?;
-                        attr_names
-                            .into_iter()
-                            .map(|attr_name| {
-                                Ok((
-                                    attr_name.into(),
-                                    arbitrary_typeofattribute_with_bounded_depth(
-                                        settings,
-                                        entity_types,
-                                        max_depth - 1,
-                                        u,
-                                    )?,
-                                ))
-                            })
-                            .collect::<Result<BTreeMap<_, _>>>()?
-                    },
-                    additional_attributes: if settings.enable_additional_attributes {
-                        u.arbitrary()?
-                    } else {
-                        false
-                    },
-                })
-            }
-        },
+        arbitrary_set_schematype_variant(settings, entity_types, max_depth, u)?,
+        arbitrary_record_schematype_variant(settings, entity_types, max_depth, u)?,
         entity_type_name_to_schema_type_variant::<N>(u.choose(entity_types)?),
         json_schema::TypeVariant::Extension {
             name: "ipaddr".parse().unwrap(),
@@ -246,6 +300,71 @@ pub fn arbitrary_schematype_with_bounded_depth<N: From<ast::Name>>(
     )))
 }
 
+/// internal helper function generating a `Set` type whose element type has
+/// depth at most `max_depth - 1`
+fn arbitrary_set_schematype_variant<N: From<ast::Name>>(
+    settings: &ABACSettings,
+    entity_types: &[ast::EntityType],
+    max_depth: usize,
+    u: &mut Unstructured<'_>,
+) -> Result<json_schema::TypeVariant<N>> {
+    if max_depth == 0 {
+        // can't recurse; we arbitrarily choose Set<Long> in this case
+        Ok(json_schema::TypeVariant::Set {
+            element: Box::new(json_schema::Type::Type(json_schema::TypeVariant::Long)),
+        })
+    } else {
+        Ok(json_schema::TypeVariant::Set {
+            element: Box::new(arbitrary_schematype_with_bounded_depth(
+                settings,
+                entity_types,
+                max_depth - 1,
+                u,
+            )?),
+        })
+    }
+}
+
+/// internal helper function generating a `Record` type whose attribute types
+/// have depth at most `max_depth - 1`
+fn arbitrary_record_schematype_variant<N: From<ast::Name>>(
+    settings: &ABACSettings,
+    entity_types: &[ast::EntityType],
+    max_depth: usize,
+    u: &mut Unstructured<'_>,
+) -> Result<json_schema::TypeVariant<N>> {
+    let attributes = if max_depth == 0 {
+        // can't recurse; use empty-record
+        BTreeMap::new()
+    } else {
+        let attr_names: HashSet<String> = u
+            .arbitrary()
+            .map_err(|e| while_doing("generating attribute names".into(), e))?;
+        attr_names
+            .into_iter()
+            .map(|attr_name| {
+                Ok((
+                    attr_name.into(),
+                    arbitrary_typeofattribute_with_bounded_depth(
+                        settings,
+                        entity_types,
+                        max_depth - 1,
+                        u,
+                    )?,
+                ))
+            })
+            .collect::<Result<BTreeMap<_, _>>>()?
+    };
+    Ok(json_schema::TypeVariant::Record(json_schema::RecordType {
+        attributes,
+        additional_attributes: if settings.enable_additional_attributes {
+            u.arbitrary()?
+        } else {
+            false
+        },
+    }))
+}
+
 /// Convert an [`ast::EntityType`] into the corresponding
 /// [`json_schema::TypeVariant`] for an entity reference with that entity type.
 pub fn entity_type_name_to_schema_type_variant<N: From<ast::Name>>(
@@ -270,6 +389,28 @@ fn arbitrary_schematype_size_hint(depth: usize) -> (usize, Option<usize>) {
     <json_schema::Type<RawName> as Arbitrary>::size_hint(depth)
 }
 
+/// Is `schematype` a reference to the entity type `etype`?
+/// Entity type names in `schematype` are qualified with `namespace` if they
+/// aren't already.
+pub(crate) fn is_reference_to_entity_type(
+    schema: &json_schema::NamespaceDefinition<ast::InternalName>,
+    namespace: Option<&ast::Name>,
+    schematype: &json_schema::Type<ast::InternalName>,
+    etype: &ast::EntityType,
+) -> bool {
+    let name = match schematype {
+        json_schema::Type::Type(json_schema::TypeVariant::Entity { name }) => name,
+        json_schema::Type::Type(json_schema::TypeVariant::EntityOrCommon { type_name })
+            if lookup_common_type(schema, type_name).is_none() =>
+        {
+            type_name
+        }
+        _ => return false,
+    };
+    ast::Name::try_from(name.qualify_with_name(namespace))
+        .is_ok_and(|name| &ast::EntityType::from(name) == etype)
+}
+
 /// internal helper function, get the [`ast::EntityUID`] corresponding to the given action
 pub fn uid_for_action_name(namespace: Option<&ast::Name>, action_name: ast::Eid) -> ast::EntityUID {
     let entity_type = ast::EntityType::from_normalized_str("Action")
@@ -330,12 +471,61 @@ fn schematype_to_type(
     }
 }
 
+/// internal helper function, collect the names of all extension types that
+/// occur in the given [`json_schema::Type`], including in nested set and
+/// record types
+fn extension_types_in_schematype<'a>(
+    schematype: &'a json_schema::Type<RawName>,
+    names: &mut Vec<&'a UnreservedId>,
+) {
+    match schematype {
+        json_schema::Type::Type(json_schema::TypeVariant::Extension { name }) => names.push(name),
+        json_schema::Type::Type(json_schema::TypeVariant::Set { element }) => {
+            extension_types_in_schematype(element, names)
+        }
+        json_schema::Type::Type(json_schema::TypeVariant::Record(json_schema::RecordType {
+            attributes,
+            ..
+        })) => {
+            for attr in attributes.values() {
+                extension_types_in_schematype(&attr.ty, names);
+            }
+        }
+        _ => (),
+    }
+}
+
 /// Get an arbitrary namespace for a schema. The namespace may be absent.
 fn arbitrary_namespace(u: &mut Unstructured<'_>) -> Result<Option<ast::Name>> {
     u.arbitrary()
         .map_err(|e| while_doing("generating namespace".into(), e))
 }
 
+/// Get an arbitrary namespace with between 1 and `max_segments` (inclusive)
+/// `::`-separated segments, for stress-testing parsers on deeply-segmented
+/// names like `A::B::C::...::Z`.
+pub fn arbitrary_deep_namespace(
+    max_segments: usize,
+    u: &mut Unstructured<'_>,
+) -> Result<ast::Name> {
+    let num_segments = u
+        .int_in_range(1..=max_segments.max(1))
+        .map_err(|e| while_doing("generating number of namespace segments".into(), e))?;
+    let segments = (0..num_segments)
+        .map(|_| {
+            u.arbitrary::<UnreservedId>()
+                .map(|id| id.to_string())
+                .map_err(|e| while_doing("generating namespace segment".into(), e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    segments
+        .join("::")
+        .parse()
+        .map_err(|_| Error::IncorrectFormat {
+            doing_what: "parsing generated namespace".into(),
+        })
+}
+
 /// Information about attributes from the schema
 pub(crate) struct Attributes<'a> {
     /// the actual attributes
@@ -443,6 +633,11 @@ fn build_attributes_by_type<'a>(
     hm
 }
 
+/// Maximum number of extra names bound to the same type by [`bind_type()`].
+/// A type with `n` names becomes a chain of `n - 1` common type aliases, e.g.,
+/// `type A = B; type B = C; type C = Long;`.
+const MAX_COMMON_TYPE_ALIASES: u8 = 3;
+
 // Common type bindings
 #[derive(Debug)]
 struct Bindings {
@@ -502,13 +697,7 @@ impl Bindings {
             }
             json_schema::Type::Type(json_schema::TypeVariant::Set { element }) => {
                 Ok(json_schema::Type::Type(json_schema::TypeVariant::Set {
-                    element: Box::new(if let Some(ids) = self.bindings.get(element) {
-                        json_schema::Type::CommonTypeRef {
-                            type_name: ast::Name::unqualified_name(u.choose(ids)?.clone()).into(),
-                        }
-                    } else {
-                        self.rewrite_type(u, element)?
-                    }),
+                    element: Box::new(self.ref_or_rewrite_type(u, element)?),
                 }))
             }
             json_schema::Type::Type(json_schema::TypeVariant::Record(
@@ -525,7 +714,7 @@ impl Bindings {
                                 Ok((
                                     attr.to_owned(),
                                     json_schema::TypeOfAttribute {
-                                        ty: self.rewrite_type(u, &attr_ty.ty)?,
+                                        ty: self.ref_or_rewrite_type(u, &attr_ty.ty)?,
                                         required: attr_ty.required.to_owned(),
                                     },
                                 ))
@@ -557,6 +746,17 @@ impl Bindings {
         &self,
         u: &mut Unstructured<'_>,
         ty: &json_schema::Type<ast::InternalName>,
+    ) -> Result<json_schema::Type<ast::InternalName>> {
+        self.ref_or_rewrite_type(u, ty)
+    }
+
+    // Replace a type with a reference to one of the common types bound to it,
+    // which may be any alias in its chain, or if it isn't bound, replace the
+    // types inside it
+    fn ref_or_rewrite_type(
+        &self,
+        u: &mut Unstructured<'_>,
+        ty: &json_schema::Type<ast::InternalName>,
     ) -> Result<json_schema::Type<ast::InternalName>> {
         let new_ty = if let Some(ids) = self.bindings.get(ty) {
             json_schema::Type::CommonTypeRef {
@@ -594,8 +794,8 @@ impl Bindings {
                             type_name: ast::Name::unqualified_name(ids[i + 1].clone()).into(),
                         },
                     );
-                    common_types.insert(ids[ids.len() - 1].clone(), self.rewrite_type(u, ty)?);
                 }
+                common_types.insert(ids[ids.len() - 1].clone(), self.rewrite_type(u, ty)?);
             }
         }
         Ok(common_types)
@@ -608,9 +808,14 @@ fn bind_type(
     u: &mut Unstructured<'_>,
     bindings: &mut Bindings,
 ) -> Result<()> {
-    // flip a coin to decide if we should create a binding for the top-level type
+    // flip a coin to decide if we should create a binding for the top-level
+    // type, and if so, bind up to `MAX_COMMON_TYPE_ALIASES` more names to it,
+    // which makes a chain of aliases
     if u.ratio(1, 2)? {
         bindings.add_binding((ty.clone(), u.arbitrary()?));
+        for _ in 0..u.int_in_range(0..=MAX_COMMON_TYPE_ALIASES)? {
+            bindings.add_binding((ty.clone(), u.arbitrary()?));
+        }
     }
     match ty {
         json_schema::Type::Type(json_schema::TypeVariant::Set { element }) => {
@@ -633,8 +838,96 @@ fn bind_type(
     Ok(())
 }
 
+/// Component of a request whose type can fall outside of the applies-to of
+/// the request's action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum AppliesToMismatch {
+    /// The principal's type isn't one of the action's principal types
+    Principal,
+    /// The resource's type isn't one of the action's resource types
+    Resource,
+}
+
+/// How the context of a request generated by
+/// [`Schema::arbitrary_invalid_context_request()`] violates the context type
+/// of the request's action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ContextViolation {
+    /// A required attribute is missing
+    MissingAttribute,
+    /// An attribute has a value of the wrong type
+    WrongType,
+}
+
+/// Describes how a common type added by [`Schema::with_shared_common_type()`]
+/// is used
+#[derive(Debug, Clone)]
+pub struct SharedCommonTypeUsage {
+    /// name of the common type
+    pub common_type: UnreservedId,
+    /// principal type with an attribute of the common type
+    pub principal_type: ast::EntityType,
+    /// attribute of `principal_type` with the common type
+    pub entity_attr: SmolStr,
+    /// action whose `context` has an attribute of the common type
+    pub action: ast::EntityUID,
+    /// attribute of the `context` of `action` with the common type
+    pub context_attr: SmolStr,
+}
+
+impl SharedCommonTypeUsage {
+    /// Get a policy touching both usages of the common type, i.e.,
+    /// `permit(principal is P, action == A, resource) when { principal has a && principal.a == context.c };`
+    pub fn policy(&self, id: PolicyID) -> ABACPolicy {
+        let entity_attr = ast::Expr::get_attr(
+            ast::Expr::var(ast::Var::Principal),
+            self.entity_attr.clone(),
+        );
+        let context_attr =
+            ast::Expr::get_attr(ast::Expr::var(ast::Var::Context), self.context_attr.clone());
+        ABACPolicy(GeneratedPolicy::new(
+            id,
+            [],
+            Effect::Permit,
+            PrincipalOrResourceConstraint::IsType(self.principal_type.clone()),
+            ActionConstraint::Eq(self.action.clone()),
+            PrincipalOrResourceConstraint::NoConstraint,
+            ast::Expr::and(
+                ast::Expr::has_attr(
+                    ast::Expr::var(ast::Var::Principal),
+                    self.entity_attr.clone(),
+                ),
+                ast::Expr::is_eq(entity_attr, context_attr),
+            ),
+        ))
+    }
+}
+
+/// Does `ty` contain a reference to a common type defined in `schema`?
+fn contains_common_type_ref(
+    schema: &json_schema::NamespaceDefinition<ast::InternalName>,
+    ty: &json_schema::Type<ast::InternalName>,
+) -> bool {
+    match ty {
+        json_schema::Type::CommonTypeRef { .. } => true,
+        json_schema::Type::Type(json_schema::TypeVariant::EntityOrCommon { type_name }) => {
+            lookup_common_type(schema, type_name).is_some()
+        }
+        json_schema::Type::Type(json_schema::TypeVariant::Set { element }) => {
+            contains_common_type_ref(schema, element)
+        }
+        json_schema::Type::Type(json_schema::TypeVariant::Record(record)) => record
+            .attributes
+            .values()
+            .any(|attr| contains_common_type_ref(schema, &attr.ty)),
+        json_schema::Type::Type(_) => false,
+    }
+}
+
 impl Schema {
-    /// Add common types to the existing schema and return a new schema
+    /// Add common types to the existing schema and return a new schema.
+    /// Some common types are aliases of others, declared before the type they
+    /// refer to, and entity attribute declarations refer to any of them.
     pub fn add_common_types(
         &self,
         u: &mut Unstructured<'_>,
@@ -685,6 +978,151 @@ impl Schema {
             actions: actions.into(),
         })
     }
+    /// Get a copy of this schema in which one common type is used both as the
+    /// type of an attribute of some principal type, and as the type of an
+    /// attribute of the `context` of an action applying to that principal type.
+    /// The common type is defined as the (existing) type of the principal's
+    /// attribute, and the `context` attribute is added.
+    pub fn with_shared_common_type(
+        &self,
+        u: &mut Unstructured<'_>,
+    ) -> Result<(Schema, SharedCommonTypeUsage)> {
+        // candidate (action, principal type, attribute name, attribute type)
+        let candidates: Vec<(&SmolStr, UnreservedId, &SmolStr, &json_schema::Type<_>)> = self
+            .schema
+            .actions
+            .iter()
+            .filter_map(|(name, action)| Some((name, action.applies_to.as_ref()?)))
+            .flat_map(|(name, applies_to)| {
+                applies_to.principal_types.iter().filter_map(move |ptype| {
+                    let id: UnreservedId = ptype.basename().clone().try_into().ok()?;
+                    Some((name, id))
+                })
+            })
+            .filter_map(|(name, id)| {
+                let et = self.schema.entity_types.get(&id)?;
+                Some((
+                    name,
+                    id,
+                    attrs_from_attrs_or_context(&self.schema, &et.shape),
+                ))
+            })
+            .flat_map(|(name, id, attributes)| {
+                attributes
+                    .attrs
+                    .iter()
+                    .filter(|(_, ty)| !contains_common_type_ref(&self.schema, &ty.ty))
+                    .map(|(attr, ty)| (name, id.clone(), attr, &ty.ty))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let (action, principal_type, entity_attr, attr_type) = u
+            .choose(&candidates)
+            .map_err(|e| while_doing("choosing an attribute to share".into(), e))?
+            .clone();
+        let attr_type = attr_type.clone();
+
+        let mut common_type = u
+            .arbitrary::<UnreservedId>()
+            .map_err(|e| while_doing("generating common type name".into(), e))?
+            .to_string();
+        while self
+            .schema
+            .entity_types
+            .keys()
+            .any(|id| id.to_string() == common_type)
+            || self
+                .schema
+                .common_types
+                .keys()
+                .any(|id| id.to_string() == common_type)
+            || [
+                "Bool",
+                "Boolean",
+                "Entity",
+                "Extension",
+                "Long",
+                "Record",
+                "Set",
+                "String",
+            ]
+            .contains(&common_type.as_str())
+        {
+            common_type.push('_');
+        }
+        let common_type: UnreservedId =
+            common_type.parse().map_err(|_| Error::IncorrectFormat {
+                doing_what: "parsing common type name".into(),
+            })?;
+        let type_ref = json_schema::Type::CommonTypeRef {
+            type_name: ast::Name::unqualified_name(common_type.clone()).into(),
+        };
+
+        let mut nsdef = self.schema.clone();
+        nsdef
+            .common_types
+            .insert(common_type.clone(), attr_type.clone());
+        let et = nsdef
+            .entity_types
+            .get_mut(&principal_type)
+            .expect("principal type should exist");
+        match &mut et.shape.0 {
+            json_schema::Type::Type(json_schema::TypeVariant::Record(record)) => {
+                if let Some(attr) = record.attributes.get_mut(entity_attr) {
+                    attr.ty = type_ref.clone();
+                }
+            }
+            _ => {
+                return Err(Error::IncorrectFormat {
+                    doing_what:
+                        "sharing a common type with an entity type with a common-type shape".into(),
+                })
+            }
+        }
+        let applies_to = nsdef
+            .actions
+            .get_mut(action)
+            .and_then(|action| action.applies_to.as_mut())
+            .expect("action should exist and apply to the principal type");
+        let context_attr = match &mut applies_to.context.0 {
+            json_schema::Type::Type(json_schema::TypeVariant::Record(record)) => {
+                let mut context_attr = entity_attr.to_string();
+                while record.attributes.contains_key(context_attr.as_str()) {
+                    context_attr.push('_');
+                }
+                let context_attr = SmolStr::from(context_attr);
+                record.attributes.insert(
+                    context_attr.clone(),
+                    json_schema::TypeOfAttribute {
+                        ty: type_ref,
+                        required: true,
+                    },
+                );
+                context_attr
+            }
+            _ => {
+                return Err(Error::IncorrectFormat {
+                    doing_what: "sharing a common type with a common-type context".into(),
+                })
+            }
+        };
+
+        let mut schema = self.clone();
+        schema.attributes.push((context_attr.clone(), attr_type));
+        schema.attributes_by_type =
+            build_attributes_by_type(&nsdef, nsdef.entity_types.iter(), self.namespace());
+        schema.schema = nsdef;
+        let usage = SharedCommonTypeUsage {
+            common_type,
+            principal_type: ast::EntityType::from(ast::Name::from(principal_type))
+                .qualify_with(self.namespace()),
+            entity_attr: entity_attr.clone(),
+            action: uid_for_action_name(self.namespace(), ast::Eid::new(action.clone())),
+            context_attr,
+        };
+        Ok((schema, usage))
+    }
+
     /// Get a slice of all of the entity types in this schema
     pub fn entity_types(&self) -> &[ast::EntityType] {
         &self.entity_types
@@ -843,6 +1281,69 @@ impl Schema {
         }
     }
 
+    /// Create an arbitrary `Schema` based on (compatible with) the schema
+    /// written in the Cedar schema syntax in `src`, so that we can fuzz
+    /// policies, entities, and requests against a fixed, hand-written schema.
+    ///
+    /// Unlike [`Self::from_raw_schemafrag()`], this doesn't panic on schemas
+    /// we can't handle. Returns [`Error::UnsupportedSchema`] if `src` doesn't
+    /// parse or isn't a valid schema, declares anything other than exactly
+    /// one namespace, or uses extension types we can't generate values of.
+    pub fn arbitrary_from_natural_source(
+        src: &str,
+        settings: ABACSettings,
+        u: &mut Unstructured<'_>,
+    ) -> Result<Schema> {
+        let (fragment, _) =
+            json_schema::Fragment::from_cedarschema_str(src, Extensions::all_available())
+                .map_err(|e| Error::UnsupportedSchema(format!("failed to parse schema: {e}")))?;
+        ValidatorSchema::try_from(fragment.clone())
+            .map_err(|e| Error::UnsupportedSchema(format!("invalid schema: {e}")))?;
+        let nsdef = match fragment.0.len() {
+            1 => fragment
+                .0
+                .values()
+                .next()
+                .expect("should have one namespace"),
+            n => {
+                return Err(Error::UnsupportedSchema(format!(
+                    "expected exactly one namespace, got {n}"
+                )))
+            }
+        };
+        let mut ext_types = Vec::new();
+        for schematype in nsdef
+            .common_types
+            .values()
+            .chain(nsdef.entity_types.values().map(|etype| &etype.shape.0))
+            .chain(
+                nsdef
+                    .actions
+                    .values()
+                    .filter_map(|action| action.applies_to.as_ref())
+                    .map(|applies_to| &applies_to.context.0),
+            )
+        {
+            extension_types_in_schematype(schematype, &mut ext_types);
+        }
+        if !ext_types.is_empty() && !settings.enable_extensions {
+            return Err(Error::ExtensionsDisabled);
+        }
+        if let Some(name) = ext_types
+            .iter()
+            .find(|name| !matches!(name.as_ref(), "ipaddr" | "decimal"))
+        {
+            return Err(Error::UnsupportedSchema(format!(
+                "unsupported extension type: {name}"
+            )));
+        }
+        let schema = Self::from_raw_schemafrag(fragment, settings, u)?;
+        if schema.principal_types.is_empty() || schema.resource_types.is_empty() {
+            return Err(Error::NoValidPrincipalOrResourceTypes);
+        }
+        Ok(schema)
+    }
+
     /// Get an `ExprGenerator` for generating expressions that conform to this `Schema`.
     ///
     /// If `hierarchy` is present, any literal UIDs included in generated `Expr`s will
@@ -855,7 +1356,18 @@ impl Schema {
             unknown_pool: &self.unknown_pool,
             ext_funcs: &self.ext_funcs,
             hierarchy,
-            uid_gen_mode: EntityUIDGenMode::default(),
+            uid_gen_mode: self.default_uid_gen_mode(),
+            action: None,
+        }
+    }
+
+    /// Mode for generating entity UIDs when the caller doesn't choose one:
+    /// Unicode ids if `enable_unicode_strings` is set in the settings
+    fn default_uid_gen_mode(&self) -> EntityUIDGenMode {
+        if self.settings.enable_unicode_strings {
+            EntityUIDGenMode::default_unicode_mode()
+        } else {
+            EntityUIDGenMode::default()
         }
     }
 
@@ -892,13 +1404,23 @@ impl Schema {
         let mut entity_types: Vec<(UnreservedId, json_schema::EntityType<ast::InternalName>)> =
             entity_type_ids
                 .iter()
-                .filter(|id| settings.enable_action_groups_and_attrs || id.to_string() != "Action")
-                .map(|id| {
+                .zip(entity_type_names.iter())
+                .filter(|(id, _)| {
+                    settings.enable_action_groups_and_attrs || id.to_string() != "Action"
+                })
+                .map(|(id, name)| {
+                    let mut shape = arbitrary_attrspec(&settings, &entity_type_names, u)?;
+                    make_recursive_attrs_optional(&mut shape, name);
+                    // sometimes make the entity type recursive, so that we
+                    // get chains of entities linked through an attribute
+                    if u.ratio::<u8>(1, 4)? {
+                        add_recursive_attr(&mut shape, name, u)?;
+                    }
                     Ok((
                         id.clone(),
                         json_schema::EntityType {
                             member_of_types: vec![],
-                            shape: arbitrary_attrspec(&settings, &entity_type_names, u)?,
+                            shape,
                         },
                     ))
                 })
@@ -964,6 +1486,14 @@ impl Schema {
                     .collect::<Vec<ast::InternalName>>(),
             )
         };
+        // entity types which are actually declared (e.g., excluding `Action`
+        // if action attributes are disabled), qualified with the namespace
+        let declared_entity_types: Vec<ast::EntityType> = entity_types
+            .iter()
+            .map(|(id, _)| {
+                ast::EntityType::from(ast::Name::from(id.clone())).qualify_with(namespace.as_ref())
+            })
+            .collect();
         let mut principal_and_resource_types_exist = false;
         // Ensure on the first pass we always generate a principal/resource
         // After that, flip a coin to optional delete the principal/resource type lists
@@ -991,7 +1521,18 @@ impl Schema {
                             Some(json_schema::ApplySpec {
                                 resource_types: picked_resource_types,
                                 principal_types: picked_principal_types,
-                                context: arbitrary_attrspec(&settings, &entity_type_names, u)?,
+                                context: {
+                                    let mut context =
+                                        arbitrary_attrspec(&settings, &entity_type_names, u)?;
+                                    // sometimes make sure the context has an
+                                    // entity reference, which requests must
+                                    // supply and which may be missing from the
+                                    // entity store
+                                    if u.ratio::<u8>(1, 4)? {
+                                        add_entity_attr(&mut context, &declared_entity_types, u)?;
+                                    }
+                                    context
+                                },
                             })
                         },
                         member_of: if settings.enable_action_groups_and_attrs {
@@ -1078,23 +1619,79 @@ impl Schema {
         arbitrary::size_hint::and_all(&[
             <HashSet<ast::Name> as Arbitrary>::size_hint(depth),
             arbitrary_attrspec_size_hint(depth), // actually we do one of these per Name that was generated
+            size_hint_for_ratio(1, 4),           // actually one of these per Name too
             size_hint_for_ratio(1, 2),           // actually many of these calls
             <HashSet<String> as Arbitrary>::size_hint(depth),
             size_hint_for_ratio(1, 8), // actually many of these calls
             size_hint_for_ratio(1, 4), // zero to many of these calls
             size_hint_for_ratio(1, 2), // zero to many of these calls
             arbitrary_attrspec_size_hint(depth),
+            size_hint_for_ratio(1, 4), // actually one of these per action
             size_hint_for_ratio(1, 2), // actually many of these calls
             <ConstantPool as Arbitrary>::size_hint(depth),
         ])
     }
 
+    /// Get an arbitrary schema fragment with between 1 and
+    /// `settings.max_namespaces` namespaces, each generated like
+    /// `Schema::arbitrary()`. Entity types may also be members of entity types
+    /// in namespaces generated after their own, which they reference by fully
+    /// qualified name, e.g., `Foo::User` may be a member of `Bar::Group`.
+    pub fn arbitrary_fragment(
+        settings: ABACSettings,
+        u: &mut Unstructured<'_>,
+    ) -> Result<json_schema::Fragment<ast::InternalName>> {
+        let mut namespaces: Vec<(
+            Option<ast::Name>,
+            json_schema::NamespaceDefinition<ast::InternalName>,
+        )> = Vec::new();
+        let max_namespaces = settings.max_namespaces.max(1) as u32;
+        u.arbitrary_loop(Some(1), Some(max_namespaces), |u| {
+            let schema = Schema::arbitrary(settings.clone(), u)?;
+            // namespaces must be distinct, so drop any repeats
+            if namespaces.iter().all(|(ns, _)| ns != &schema.namespace) {
+                namespaces.push((schema.namespace, schema.schema));
+            }
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        // WLOG we only make edges to namespaces later in the list, to ensure we
+        // get a DAG. Entity types in the empty namespace are skipped: their
+        // unqualified names could resolve to a type in the referencing
+        // namespace instead.
+        for i in 0..namespaces.len() {
+            let later: Vec<ast::InternalName> = namespaces[(i + 1)..]
+                .iter()
+                .filter_map(|(ns, nsdef)| Some((ns.as_ref()?, nsdef)))
+                .flat_map(|(ns, nsdef)| {
+                    nsdef.entity_types.keys().map(move |id| {
+                        ast::InternalName::from(ast::Name::from(id.clone()))
+                            .qualify_with_name(Some(ns))
+                    })
+                })
+                .collect();
+            for ety in namespaces[i].1.entity_types.values_mut() {
+                for name in &later {
+                    if u.ratio::<u8>(1, 4)? {
+                        ety.member_of_types.push(name.clone());
+                    }
+                }
+            }
+        }
+        Ok(json_schema::Fragment(namespaces.into_iter().collect()))
+    }
+
+    /// size hint for arbitrary_fragment()
+    pub fn arbitrary_fragment_size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and(Self::arbitrary_size_hint(depth), (1, None))
+    }
+
     /// Get an arbitrary Hierarchy conforming to the schema.
     pub fn arbitrary_hierarchy(&self, u: &mut Unstructured<'_>) -> Result<Hierarchy> {
         HierarchyGenerator {
             mode: HierarchyGeneratorMode::SchemaBased { schema: self },
-            uid_gen_mode: EntityUIDGenMode::default(),
+            uid_gen_mode: self.default_uid_gen_mode(),
             num_entities: NumEntities::RangePerEntityType(1..=self.settings.max_width),
+            max_hierarchy_depth: None,
             u,
             extensions: Extensions::all_available(),
         }
@@ -1106,18 +1703,28 @@ impl Schema {
         &self,
         nanoid_len: usize,
         u: &mut Unstructured<'_>,
+    ) -> Result<Hierarchy> {
+        self.arbitrary_hierarchy_with_uid_mode(EntityUIDGenMode::Nanoid(nanoid_len), u)
+    }
+
+    /// Get an arbitrary Hierarchy conforming to the schema, with uids
+    /// generated according to `uid_gen_mode`.
+    pub fn arbitrary_hierarchy_with_uid_mode(
+        &self,
+        uid_gen_mode: EntityUIDGenMode,
+        u: &mut Unstructured<'_>,
     ) -> Result<Hierarchy> {
         HierarchyGenerator {
             mode: HierarchyGeneratorMode::SchemaBased { schema: self },
-            uid_gen_mode: EntityUIDGenMode::Nanoid(nanoid_len),
+            uid_gen_mode,
             num_entities: NumEntities::RangePerEntityType(1..=self.settings.max_width),
+            max_hierarchy_depth: None,
             u,
             extensions: Extensions::all_available(),
         }
         .generate()
     }
 
-    #[allow(dead_code)]
     fn arbitrary_uid_with_etype(
         &self,
         ty_name: &ast::EntityType,
@@ -1242,186 +1849,926 @@ impl Schema {
         })
     }
 
-    /// get an arbitrary policy conforming to this schema
-    pub fn arbitrary_policy(
+    /// Get an entity type name and attribute name, such that entities with
+    /// that typename have a (possibly optional) attribute with some set type
+    pub fn arbitrary_set_attr(
         &self,
-        hierarchy: &Hierarchy,
         u: &mut Unstructured<'_>,
-    ) -> Result<ABACPolicy> {
-        let id = u.arbitrary()?;
-        let annotations: HashMap<ast::AnyId, SmolStr> = u.arbitrary()?;
-        let effect = u.arbitrary()?;
-        let principal_constraint = self.arbitrary_principal_constraint(hierarchy, u)?;
-        let action_constraint = self.arbitrary_action_constraint(u, Some(3))?;
-        let resource_constraint = self.arbitrary_resource_constraint(hierarchy, u)?;
-        let mut abac_constraints = Vec::new();
-        let mut exprgenerator = self.exprgenerator(Some(hierarchy));
-        u.arbitrary_loop(Some(0), Some(self.settings.max_depth as u32), |u| {
-            if self.settings.match_types {
-                abac_constraints.push(exprgenerator.generate_expr_for_type(
-                    &Type::bool(),
-                    self.settings.max_depth,
-                    u,
-                )?);
-            } else {
-                abac_constraints.push(exprgenerator.generate_expr(self.settings.max_depth, u)?);
-            }
-            Ok(std::ops::ControlFlow::Continue(()))
-        })?;
-        let mut conjunction = ast::Expr::val(true);
-        for constraint in abac_constraints {
-            conjunction = ast::Expr::and(conjunction, constraint);
-        }
-        Ok(ABACPolicy(GeneratedPolicy::new(
-            id,
-            annotations,
-            effect,
-            principal_constraint,
-            action_constraint,
-            resource_constraint,
-            conjunction,
-        )))
+    ) -> Result<(ast::EntityType, SmolStr)> {
+        let pairs: Vec<(ast::EntityType, SmolStr)> = self
+            .schema
+            .entity_types
+            .iter()
+            .map(|(name, et)| {
+                (
+                    ast::EntityType::from(ast::Name::from(name.clone()))
+                        .qualify_with(self.namespace()),
+                    attrs_from_attrs_or_context(&self.schema, &et.shape),
+                )
+            })
+            .flat_map(|(tyname, attributes)| {
+                attributes
+                    .attrs
+                    .iter()
+                    .filter(|(_, ty)| {
+                        matches!(
+                            ty.ty,
+                            json_schema::Type::Type(json_schema::TypeVariant::Set { .. })
+                        )
+                    })
+                    .map(move |(attr_name, _)| (tyname.clone(), attr_name.clone()))
+            })
+            .collect();
+        u.choose(&pairs)
+            .cloned()
+            .map_err(|e| while_doing("getting arbitrary set-typed attr".into(), e))
     }
 
-    /// size hint for arbitrary_policy()
-    pub fn arbitrary_policy_size_hint(
-        _settings: &ABACSettings,
-        depth: usize,
-    ) -> (usize, Option<usize>) {
-        arbitrary::size_hint::and_all(&[
-            <PolicyID as Arbitrary>::size_hint(depth),
-            <Effect as Arbitrary>::size_hint(depth),
-            Self::arbitrary_principal_constraint_size_hint(depth),
-            Self::arbitrary_action_constraint_size_hint(depth),
-            Self::arbitrary_resource_constraint_size_hint(depth),
-            (1, None), // not sure how to count the arbitrary_loop() call
-        ])
+    /// Get all (resource type, attribute name) pairs such that the attribute
+    /// is a reference to some principal type, e.g., `Document { owner: User }`
+    pub fn ownership_attributes(&self) -> Vec<(ast::EntityType, SmolStr)> {
+        self.schema
+            .entity_types
+            .iter()
+            .map(|(name, et)| {
+                (
+                    ast::EntityType::from(ast::Name::from(name.clone()))
+                        .qualify_with(self.namespace()),
+                    attrs_from_attrs_or_context(&self.schema, &et.shape),
+                )
+            })
+            .filter(|(tyname, _)| self.resource_types.contains(tyname))
+            .flat_map(|(tyname, attributes)| {
+                attributes
+                    .attrs
+                    .iter()
+                    .filter(|(_, ty)| {
+                        self.principal_types.iter().any(|ptype| {
+                            is_reference_to_entity_type(
+                                &self.schema,
+                                self.namespace(),
+                                &ty.ty,
+                                ptype,
+                            )
+                        })
+                    })
+                    .map(|(attr_name, _)| (tyname.clone(), attr_name.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
     }
 
-    fn arbitrary_principal_constraint(
+    /// Get the attributes of the `context` of each action that a policy for
+    /// `action` applies to: just `action`, or, without one, every action with
+    /// an `appliesTo`
+    fn scoped_contexts(
+        &self,
+        action: Option<&ast::EntityUID>,
+    ) -> Vec<Vec<(&SmolStr, &json_schema::TypeOfAttribute<ast::InternalName>)>> {
+        match action {
+            Some(action) => vec![self.context_attrs_of_action(action)],
+            None => self
+                .schema
+                .actions
+                .values()
+                .filter_map(|action| action.applies_to.as_ref())
+                .map(|applies_to| {
+                    attrs_from_attrs_or_context(&self.schema, &applies_to.context)
+                        .attrs
+                        .iter()
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Get the items that `items_of` finds among the required attributes of
+    /// the `context` of `action`, or, without one, those it finds in the
+    /// `context` of every action, so that accessing the attributes
+    /// type-checks in a policy for `action`
+    fn common_context_items<'s, T: Ord>(
+        &'s self,
+        action: Option<&ast::EntityUID>,
+        items_of: impl Fn(&'s SmolStr, &'s json_schema::TypeOfAttribute<ast::InternalName>) -> Vec<T>,
+    ) -> Vec<T> {
+        let mut contexts = self.scoped_contexts(action).into_iter().map(|attrs| {
+            attrs
+                .into_iter()
+                .filter(|(_, ty)| ty.required)
+                .flat_map(|(attr_name, ty)| items_of(attr_name, ty))
+                .collect::<BTreeSet<_>>()
+        });
+        let first = contexts.next().unwrap_or_default();
+        contexts
+            .fold(first, |common, items| {
+                common
+                    .into_iter()
+                    .filter(|item| items.contains(item))
+                    .collect()
+            })
+            .into_iter()
+            .collect()
+    }
+
+    /// Get the names of the `Long`-typed attributes of the `context` that a
+    /// policy for `action` can access (see [`Self::common_context_items`])
+    pub fn context_long_attributes(&self, action: Option<&ast::EntityUID>) -> Vec<SmolStr> {
+        self.common_context_items(action, |attr_name, ty| {
+            if schematype_to_type(&self.schema, &ty.ty) == Type::long() {
+                vec![attr_name.clone()]
+            } else {
+                vec![]
+            }
+        })
+    }
+
+    /// Get all (entity type, attribute name) pairs such that the attribute is
+    /// an attribute of the `context` that a policy for `action` can access
+    /// (see [`Self::common_context_items`]), and is a reference to the entity
+    /// type. Entity types are fully qualified.
+    pub fn context_entity_attributes(
+        &self,
+        action: Option<&ast::EntityUID>,
+    ) -> Vec<(ast::EntityType, SmolStr)> {
+        self.common_context_items(action, |attr_name, ty| {
+            self.entity_types
+                .iter()
+                .map(|etype| etype.clone().qualify_with(self.namespace()))
+                .filter(|etype| {
+                    is_reference_to_entity_type(&self.schema, self.namespace(), &ty.ty, etype)
+                })
+                .map(|etype| (etype, attr_name.clone()))
+                .collect()
+        })
+    }
+
+    /// Get all (entity type, attribute name) pairs such that the attribute
+    /// is a reference to the entity type itself (a recursive attribute)
+    pub fn recursive_attributes(&self) -> Vec<(ast::EntityType, SmolStr)> {
+        self.schema
+            .entity_types
+            .iter()
+            .map(|(name, et)| {
+                (
+                    ast::EntityType::from(ast::Name::from(name.clone()))
+                        .qualify_with(self.namespace()),
+                    attrs_from_attrs_or_context(&self.schema, &et.shape),
+                )
+            })
+            .flat_map(|(tyname, attributes)| {
+                attributes
+                    .attrs
+                    .iter()
+                    .filter(|(_, ty)| {
+                        is_reference_to_entity_type(&self.schema, self.namespace(), &ty.ty, &tyname)
+                    })
+                    .map(|(attr_name, _)| (tyname.clone(), attr_name.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// get an arbitrary policy whose scope allows several principal types, but
+    /// whose condition reads an attribute that only some of them have, e.g.,
+    /// `permit(principal, action == A, resource) when { principal.a == v };`
+    /// where `a` is declared on one principal type of `A` but not on another.
+    /// Strict validation should reject such policies.
+    pub fn arbitrary_union_principal_attr_policy(
         &self,
         hierarchy: &Hierarchy,
         u: &mut Unstructured<'_>,
-    ) -> Result<PrincipalOrResourceConstraint> {
-        // 20% of the time, NoConstraint
-        if u.ratio(1, 5)? {
-            Ok(PrincipalOrResourceConstraint::NoConstraint)
-        } else {
-            // 32% Eq, 16% In, 16% Is, 16% IsIn
-            let uid = self
-                .exprgenerator(Some(hierarchy))
-                .arbitrary_principal_uid(u)?;
-            let ety = u.choose(self.entity_types())?.clone();
-            gen!(u,
-                2 => Ok(PrincipalOrResourceConstraint::Eq(uid)),
-                1 => Ok(PrincipalOrResourceConstraint::In(uid)),
-                1 => Ok(PrincipalOrResourceConstraint::IsType(ety)),
-                1 => Ok(PrincipalOrResourceConstraint::IsTypeIn(ety, uid))
+    ) -> Result<ABACPolicy> {
+        let attrs_of = |ptype: &ast::InternalName| {
+            self.schema
+                .entity_types
+                .iter()
+                .find(|(id, _)| &ast::InternalName::from(ast::Name::from((*id).clone())) == ptype)
+                .map(|(_, et)| attrs_from_attrs_or_context(&self.schema, &et.shape))
+        };
+        // (action, attribute, attribute type) such that the attribute is
+        // declared on some, but not all, principal types of the action
+        let mut candidates = Vec::new();
+        for (action_name, action) in &self.schema.actions {
+            let Some(applies_to) = action.applies_to.as_ref() else {
+                continue;
+            };
+            let principal_attrs: Vec<_> = applies_to
+                .principal_types
+                .iter()
+                .filter_map(|ptype| attrs_of(ptype))
+                .collect();
+            for attrs in &principal_attrs {
+                for (attr_name, attr_type) in attrs.attrs {
+                    if principal_attrs
+                        .iter()
+                        .any(|other| !other.attrs.contains_key(attr_name))
+                    {
+                        candidates.push((action_name, attr_name, attr_type));
+                    }
+                }
+            }
+        }
+        let (action_name, attr_name, attr_type) = u.choose(&candidates).map_err(|e| {
+            while_doing(
+                "choosing an attribute declared on only some principal types".into(),
+                e,
             )
+        })?;
+        let value = self
+            .exprgenerator(Some(hierarchy))
+            .generate_expr_for_schematype(&attr_type.ty, self.settings.max_depth, u)?;
+        Ok(ABACPolicy(GeneratedPolicy::new(
+            u.arbitrary()?,
+            [],
+            u.arbitrary()?,
+            PrincipalOrResourceConstraint::NoConstraint,
+            ActionConstraint::Eq(uid_for_action_name(
+                self.namespace.as_ref(),
+                ast::Eid::new((*action_name).clone()),
+            )),
+            PrincipalOrResourceConstraint::NoConstraint,
+            ast::Expr::is_eq(
+                ast::Expr::get_attr(ast::Expr::var(ast::Var::Principal), (*attr_name).clone()),
+                value,
+            ),
+        )))
+    }
+
+    /// get an action entity for each action in the schema, with the action
+    /// groups it's declared a member of as parents, and with a random subset
+    /// of the [`ACTION_ATTR_NAMES`] as `Long` attributes. The attributes don't
+    /// appear in the schema, which can't declare action attributes.
+    pub fn arbitrary_action_entities(&self, u: &mut Unstructured<'_>) -> Result<Vec<ast::Entity>> {
+        if !self.settings.enable_action_groups_and_attrs {
+            return Err(Error::ActionAttrsDisabled);
+        }
+        let action_uid =
+            |name: &SmolStr| uid_for_action_name(self.namespace(), ast::Eid::new(name.clone()));
+        self.schema
+            .actions
+            .iter()
+            .map(|(name, action)| {
+                let parents: std::collections::HashSet<_> = action
+                    .member_of
+                    .iter()
+                    .flatten()
+                    .map(|parent| action_uid(&parent.id))
+                    .collect();
+                let mut attrs = std::collections::HashMap::new();
+                for attr in ACTION_ATTR_NAMES {
+                    // leave some attributes out, so that policies read absent ones
+                    if u.ratio(2, 3)? {
+                        attrs.insert(
+                            SmolStr::from(attr),
+                            ast::RestrictedExpr::val(self.constant_pool.arbitrary_int_constant(u)?),
+                        );
+                    }
+                }
+                ast::Entity::new(action_uid(name), attrs, parents, Extensions::none())
+                    .map_err(|e| Error::EntitiesError(e.to_string()))
+            })
+            .collect()
+    }
+
+    /// get an arbitrary policy whose condition compares an attribute of
+    /// `action` (see [`ExprGenerator::generate_action_attr_expr`]), e.g.,
+    /// `permit(principal, action in Action::"write", resource) when { action has riskLevel && action.riskLevel > 3 };`
+    /// Strict validation rejects such policies, because schemas can't declare
+    /// action attributes.
+    pub fn arbitrary_action_attr_policy(
+        &self,
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ABACPolicy> {
+        let condition = self
+            .exprgenerator(Some(hierarchy))
+            .generate_action_attr_expr(u)?;
+        Ok(ABACPolicy(GeneratedPolicy::new(
+            u.arbitrary()?,
+            [],
+            u.arbitrary()?,
+            PrincipalOrResourceConstraint::NoConstraint,
+            self.arbitrary_action_constraint(u, Some(3))?,
+            PrincipalOrResourceConstraint::NoConstraint,
+            condition,
+        )))
+    }
+
+    /// get an arbitrary policy whose condition is a conditional with an
+    /// extension-typed and a primitive-typed branch (see
+    /// [`ExprGenerator::generate_incompatible_ite_expr`]), e.g.,
+    /// `permit(principal, action, resource) when { (if 1 < 2 then ip("1.2.3.4") else "a") == ip("1.2.3.4") };`
+    /// Strict validation should reject such policies. Fails with
+    /// `Error::ExtensionsDisabled` if extensions are disabled.
+    pub fn arbitrary_incompatible_ite_policy(
+        &self,
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ABACPolicy> {
+        let condition = self
+            .exprgenerator(Some(hierarchy))
+            .generate_incompatible_ite_expr(self.settings.max_depth, u)?;
+        Ok(ABACPolicy(GeneratedPolicy::new(
+            u.arbitrary()?,
+            [],
+            u.arbitrary()?,
+            PrincipalOrResourceConstraint::NoConstraint,
+            ActionConstraint::NoConstraint,
+            PrincipalOrResourceConstraint::NoConstraint,
+            condition,
+        )))
+    }
+
+    /// get one policy for each of the [`bool_operator_combinations`] of a
+    /// `Bool` attribute of `context`, a `Bool` attribute of `principal`, and a
+    /// `Bool` attribute of `resource`, e.g.,
+    /// `permit(principal is P, action == A, resource is R) when { (context.c && !principal.p) || resource.r };`
+    /// All policies share the same scope and operands; only their conditions
+    /// and effects differ.
+    pub fn arbitrary_bool_operator_matrix(
+        &self,
+        u: &mut Unstructured<'_>,
+    ) -> Result<Vec<ABACPolicy>> {
+        let is_bool = |(_, ty): &(&SmolStr, &json_schema::TypeOfAttribute<ast::InternalName>)| {
+            ty.required && schematype_to_type(&self.schema, &ty.ty) == Type::bool()
+        };
+        // (entity type as named in `appliesTo`, qualified entity type, `Bool`
+        // attributes) for every entity type
+        let bool_attrs: Vec<(ast::InternalName, ast::EntityType, Vec<&SmolStr>)> = self
+            .schema
+            .entity_types
+            .iter()
+            .map(|(name, et)| {
+                (
+                    ast::InternalName::from(ast::Name::from(name.clone())),
+                    ast::EntityType::from(ast::Name::from(name.clone()))
+                        .qualify_with(self.namespace()),
+                    attrs_from_attrs_or_context(&self.schema, &et.shape)
+                        .attrs
+                        .iter()
+                        .filter(is_bool)
+                        .map(|(attr_name, _)| attr_name)
+                        .collect(),
+                )
+            })
+            .collect();
+        let bool_attrs_of = |ty: &ast::InternalName| {
+            bool_attrs
+                .iter()
+                .find(|(name, _, attrs)| name == ty && !attrs.is_empty())
+                .map(|(_, etype, attrs)| (etype.clone(), attrs.clone()))
+        };
+        // (action, principal type, resource type) such that the context, the
+        // principal type, and the resource type all have `Bool` attributes
+        let mut candidates = Vec::new();
+        for (action_name, action) in &self.schema.actions {
+            let Some(applies_to) = action.applies_to.as_ref() else {
+                continue;
+            };
+            let context_attrs: Vec<&SmolStr> =
+                attrs_from_attrs_or_context(&self.schema, &applies_to.context)
+                    .attrs
+                    .iter()
+                    .filter(is_bool)
+                    .map(|(attr_name, _)| attr_name)
+                    .collect();
+            if context_attrs.is_empty() {
+                continue;
+            }
+            for ptype in &applies_to.principal_types {
+                for rtype in &applies_to.resource_types {
+                    if let (Some((ptype, principal_attrs)), Some((rtype, resource_attrs))) =
+                        (bool_attrs_of(ptype), bool_attrs_of(rtype))
+                    {
+                        candidates.push((
+                            action_name,
+                            ptype,
+                            rtype,
+                            context_attrs.clone(),
+                            principal_attrs,
+                            resource_attrs,
+                        ));
+                    }
+                }
+            }
         }
+        let (action_name, ptype, rtype, context_attrs, principal_attrs, resource_attrs) =
+            u.choose(&candidates).map_err(|e| {
+                while_doing(
+                    "choosing an action whose context, principal, and resource have Bool attributes"
+                        .into(),
+                    e,
+                )
+            })?;
+        let operand =
+            |var: ast::Var, attrs: &[&SmolStr], u: &mut Unstructured<'_>| -> Result<ast::Expr> {
+                let attr_name = u
+                    .choose(attrs)
+                    .map_err(|e| while_doing("choosing a Bool attribute".into(), e))?;
+                Ok(ast::Expr::get_attr(
+                    ast::Expr::var(var),
+                    (*attr_name).clone(),
+                ))
+            };
+        let operands = [
+            operand(ast::Var::Context, context_attrs, u)?,
+            operand(ast::Var::Principal, principal_attrs, u)?,
+            operand(ast::Var::Resource, resource_attrs, u)?,
+        ];
+        bool_operator_combinations(operands)
+            .into_iter()
+            .enumerate()
+            .map(|(i, condition)| {
+                Ok(ABACPolicy(GeneratedPolicy::new(
+                    PolicyID::from_string(format!("combination{i}")),
+                    [],
+                    u.arbitrary()?,
+                    PrincipalOrResourceConstraint::IsType(ptype.clone()),
+                    ActionConstraint::Eq(uid_for_action_name(
+                        self.namespace.as_ref(),
+                        ast::Eid::new((*action_name).clone()),
+                    )),
+                    PrincipalOrResourceConstraint::IsType(rtype.clone()),
+                    condition,
+                )))
+            })
+            .collect()
     }
-    fn arbitrary_principal_constraint_size_hint(depth: usize) -> (usize, Option<usize>) {
-        arbitrary::size_hint::and(
-            size_hint_for_range(1, 10),
-            arbitrary::size_hint::or_all(&[
-                (0, Some(0)),
-                ExprGenerator::arbitrary_principal_uid_size_hint(depth),
-                ExprGenerator::arbitrary_principal_uid_size_hint(depth),
-            ]),
-        )
+
+    /// get an arbitrary policy conforming to this schema
+    pub fn arbitrary_policy(
+        &self,
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ABACPolicy> {
+        self.arbitrary_policy_with_max_nodes(hierarchy, max_ast_nodes(), u)
     }
 
-    fn arbitrary_resource_constraint(
+    /// get a permit and a forbid policy, with IDs `permit` and `forbid`, that
+    /// have the same (arbitrary) scope, to stress that forbid overrides permit.
+    /// The condition of the permit is `true`. The condition of the forbid is
+    /// `true` half of the time, in which case no request is allowed, and an
+    /// arbitrary condition otherwise, which may error, in which case the forbid
+    /// policy is not satisfied.
+    pub fn arbitrary_overlapping_policies(
         &self,
         hierarchy: &Hierarchy,
         u: &mut Unstructured<'_>,
-    ) -> Result<PrincipalOrResourceConstraint> {
-        // 20% of the time, NoConstraint
-        if u.ratio(1, 5)? {
-            Ok(PrincipalOrResourceConstraint::NoConstraint)
+    ) -> Result<(ABACPolicy, ABACPolicy)> {
+        let policy = self.arbitrary_policy(hierarchy, u)?;
+        let permit = policy.0.with_same_scope(
+            PolicyID::from_string("permit"),
+            Effect::Permit,
+            ast::Expr::val(true),
+        );
+        let forbid_condition = if u.ratio(1, 2)? {
+            ast::Expr::val(true)
         } else {
-            // 32% Eq, 16% In, 16% Is, 16% IsIn
-            let uid = self
-                .exprgenerator(Some(hierarchy))
-                .arbitrary_resource_uid(u)?;
-            let ety = u.choose(self.entity_types())?.clone();
-            gen!(u,
-                2 => Ok(PrincipalOrResourceConstraint::Eq(uid)),
-                1 => Ok(PrincipalOrResourceConstraint::In(uid)),
-                1 => Ok(PrincipalOrResourceConstraint::IsType(ety)),
-                1 => Ok(PrincipalOrResourceConstraint::IsTypeIn(ety, uid))
-            )
-        }
+            policy.0.abac_constraints().clone()
+        };
+        let forbid = policy.0.with_same_scope(
+            PolicyID::from_string("forbid"),
+            Effect::Forbid,
+            forbid_condition,
+        );
+        Ok((ABACPolicy(permit), ABACPolicy(forbid)))
     }
-    fn arbitrary_resource_constraint_size_hint(depth: usize) -> (usize, Option<usize>) {
+
+    /// size hint for arbitrary_overlapping_policies()
+    pub fn arbitrary_overlapping_policies_size_hint(
+        settings: &ABACSettings,
+        depth: usize,
+    ) -> (usize, Option<usize>) {
         arbitrary::size_hint::and(
-            size_hint_for_range(1, 10),
-            arbitrary::size_hint::or_all(&[
-                (0, Some(0)),
-                ExprGenerator::arbitrary_resource_uid_size_hint(depth),
-                ExprGenerator::arbitrary_resource_uid_size_hint(depth),
-            ]),
+            Self::arbitrary_policy_size_hint(settings, depth),
+            size_hint_for_ratio(1, 2),
         )
     }
 
-    fn arbitrary_action_constraint(
+    /// Like `arbitrary_policy()`, but fails with [`Error::TooLarge`] once the
+    /// conditions of the policy have more than `max_nodes` AST nodes in total
+    pub fn arbitrary_policy_with_max_nodes(
         &self,
+        hierarchy: &Hierarchy,
+        max_nodes: usize,
         u: &mut Unstructured<'_>,
-        max_list_length: Option<u32>,
-    ) -> Result<ActionConstraint> {
-        if !self.settings.enable_action_in_constraints {
-            // 25% of the time, NoConstraint; 75%, Eq
-            gen!(u,
-        1 => Ok(ActionConstraint::NoConstraint),
-        3 => Ok(ActionConstraint::Eq(self.exprgenerator(None).arbitrary_action_uid(u)?)))
-        } else {
-            // 10% of the time, NoConstraint; 30%, Eq; 30%, In; 30%, InList
-            gen!(u,
-            1 => Ok(ActionConstraint::NoConstraint),
-            3 => Ok(ActionConstraint::Eq(self.exprgenerator(None).arbitrary_action_uid(u)?)),
-            3 => Ok(ActionConstraint::In(self.exprgenerator(None).arbitrary_action_uid(u)?)),
-            3 => {
-                let mut uids = vec![];
-                let exprgenerator = self.exprgenerator(None);
-                u.arbitrary_loop(Some(0), max_list_length, |u| {
-                    uids.push(exprgenerator.arbitrary_action_uid(u)?);
-                    Ok(std::ops::ControlFlow::Continue(()))
-                })?;
-                Ok(ActionConstraint::InList(uids))
-            })
-        }
+    ) -> Result<ABACPolicy> {
+        self.arbitrary_policy_or_template(None, hierarchy, max_nodes, false, u)
     }
-    fn arbitrary_action_constraint_size_hint(depth: usize) -> (usize, Option<usize>) {
+
+    /// get an arbitrary template conforming to this schema, with ID `id`.
+    /// Its principal constraint always has a `?principal` slot, and its
+    /// resource constraint has a `?resource` slot 75% of the time.
+    pub fn arbitrary_template(
+        &self,
+        id: ast::PolicyID,
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ABACPolicy> {
+        self.arbitrary_policy_or_template(Some(id), hierarchy, max_ast_nodes(), true, u)
+    }
+
+    /// size hint for arbitrary_template()
+    pub fn arbitrary_template_size_hint(
+        settings: &ABACSettings,
+        depth: usize,
+    ) -> (usize, Option<usize>) {
         arbitrary::size_hint::and(
-            size_hint_for_range(1, 10),
-            arbitrary::size_hint::or_all(&[
-                (0, Some(0)),
-                ExprGenerator::arbitrary_action_uid_size_hint(depth),
-                ExprGenerator::arbitrary_action_uid_size_hint(depth),
-                (1, None), // not sure how to hint for arbitrary_loop()
-            ]),
+            Self::arbitrary_policy_size_hint(settings, depth),
+            size_hint_for_ratio(3, 4),
         )
     }
 
-    /// generate an arbitrary `ABACRequest` conforming to the schema
-    pub fn arbitrary_request(
+    /// get an arbitrary link of `template` with ID `id`. Slots are filled with
+    /// UIDs of principal (resp. resource) types that exist in `hierarchy`.
+    pub fn arbitrary_linked_policy(
         &self,
+        id: ast::PolicyID,
+        template: &ABACPolicy,
         hierarchy: &Hierarchy,
         u: &mut Unstructured<'_>,
-    ) -> Result<ABACRequest> {
-        // first pick one of the valid Actions
-        let applicable_actions: Vec<_> = self
-            .schema
-            .actions
-            .iter()
+    ) -> Result<GeneratedLinkedPolicy> {
+        let existing_uid = |types: &[ast::EntityType], u: &mut Unstructured<'_>| {
+            let uids: Vec<&ast::EntityUID> = hierarchy
+                .uids()
+                .iter()
+                .filter(|uid| types.contains(uid.entity_type()))
+                .collect();
+            u.choose(&uids)
+                .map(|uid| (*uid).clone())
+                .map_err(|e| while_doing("choosing an existing uid for a slot".into(), e))
+        };
+        let principal = existing_uid(&self.principal_types, u)?;
+        let resource = existing_uid(&self.resource_types, u)?;
+        Ok(GeneratedLinkedPolicy::new(
+            id, template, principal, resource,
+        ))
+    }
+
+    /// size hint for arbitrary_linked_policy()
+    pub fn arbitrary_linked_policy_size_hint(_depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and(size_hint_for_choose(None), size_hint_for_choose(None))
+    }
+
+    /// Shared implementation of `arbitrary_policy_with_max_nodes()` and
+    /// `arbitrary_template()`. If `allow_slots` is false, the result is a
+    /// static policy.
+    fn arbitrary_policy_or_template(
+        &self,
+        fixed_id_opt: Option<ast::PolicyID>,
+        hierarchy: &Hierarchy,
+        max_nodes: usize,
+        allow_slots: bool,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ABACPolicy> {
+        let id = match fixed_id_opt {
+            Some(fixed_id) => fixed_id,
+            None => u.arbitrary()?,
+        };
+        let annotations: HashMap<ast::AnyId, SmolStr> = u.arbitrary()?;
+        let effect = u.arbitrary()?;
+        let principal_constraint = if allow_slots {
+            self.arbitrary_slot_constraint(u)?
+        } else {
+            self.arbitrary_principal_constraint(hierarchy, u)?
+        };
+        let action_constraint = self.arbitrary_action_constraint(u, Some(3))?;
+        let resource_constraint = if allow_slots && u.ratio(3, 4)? {
+            self.arbitrary_slot_constraint(u)?
+        } else {
+            self.arbitrary_resource_constraint(hierarchy, u)?
+        };
+        // with `prefer_context_conditions`, most conditions of a policy for a
+        // single action are over the action's `context`
+        let context_action = match &action_constraint {
+            ActionConstraint::Eq(action)
+                if self.settings.prefer_context_conditions
+                    && !self.context_attrs_of_action(action).is_empty() =>
+            {
+                Some(action.clone())
+            }
+            _ => None,
+        };
+        let mut abac_constraints = Vec::new();
+        let mut exprgenerator = ExprGenerator {
+            action: match &action_constraint {
+                ActionConstraint::Eq(action) => Some(action),
+                _ => None,
+            },
+            ..self.exprgenerator(Some(hierarchy))
+        };
+        let mut nodes = 0;
+        let max_conditions = self.settings.max_conditions as u32;
+        u.arbitrary_loop(Some(max_conditions.min(1)), Some(max_conditions), |u| {
+            let condition = match &context_action {
+                Some(action) if u.ratio(2, 3)? => {
+                    self.arbitrary_context_condition(action, hierarchy, u)?
+                }
+                _ if self.settings.match_types => exprgenerator.generate_expr_for_type(
+                    &Type::bool(),
+                    self.settings.max_depth,
+                    u,
+                )?,
+                _ => exprgenerator.generate_expr(self.settings.max_depth, u)?,
+            };
+            // an `unless` clause is a `when` clause with the condition negated,
+            // which is how the parser desugars it
+            let constraint = if u.arbitrary()? {
+                ast::Expr::not(condition)
+            } else {
+                condition
+            };
+            nodes += constraint.subexpressions().count();
+            if nodes > max_nodes {
+                return Ok(std::ops::ControlFlow::Break(()));
+            }
+            abac_constraints.push(constraint);
+            Ok(std::ops::ControlFlow::Continue(()))
+        })?;
+        if nodes > max_nodes {
+            return Err(Error::TooLarge);
+        }
+        let mut conjunction = ast::Expr::val(true);
+        for constraint in abac_constraints {
+            conjunction = ast::Expr::and(conjunction, constraint);
+        }
+        Ok(ABACPolicy(GeneratedPolicy::new(
+            id,
+            annotations,
+            effect,
+            principal_constraint,
+            action_constraint,
+            resource_constraint,
+            conjunction,
+        )))
+    }
+
+    /// Get the attributes of the `context` that the action `action` declares,
+    /// or nothing if it isn't declared
+    fn context_attrs_of_action(
+        &self,
+        action: &ast::EntityUID,
+    ) -> Vec<(&SmolStr, &json_schema::TypeOfAttribute<ast::InternalName>)> {
+        self.schema
+            .actions
+            .iter()
+            .find(|(name, _)| {
+                uid_for_action_name(self.namespace(), ast::Eid::new((*name).clone())) == *action
+            })
+            .and_then(|(_, action)| action.applies_to.as_ref())
+            .map(|applies_to| {
+                attrs_from_attrs_or_context(&self.schema, &applies_to.context)
+                    .attrs
+                    .iter()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// get a condition over an attribute of the `context` of the action
+    /// `action`, which type-checks against the context type the action
+    /// declares: a `Bool` attribute itself, or an equality between the
+    /// attribute and an expression of its type, e.g., `context.count == 3`.
+    /// Optional attributes are guarded by `has`, e.g.,
+    /// `context has label && context.label == "a"`.
+    pub fn arbitrary_context_condition(
+        &self,
+        action: &ast::EntityUID,
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ast::Expr> {
+        let attrs = self.context_attrs_of_action(action);
+        let (attr_name, attr_type) = u
+            .choose(&attrs)
+            .map_err(|e| while_doing("choosing a context attribute of the action".into(), e))?;
+        let attr = ast::Expr::get_attr(ast::Expr::var(ast::Var::Context), (*attr_name).clone());
+        let condition =
+            if schematype_to_type(&self.schema, &attr_type.ty) == Type::bool() && u.ratio(1, 2)? {
+                attr
+            } else {
+                ast::Expr::is_eq(
+                    attr,
+                    self.exprgenerator(Some(hierarchy))
+                        .generate_expr_for_schematype(&attr_type.ty, self.settings.max_depth, u)?,
+                )
+            };
+        if attr_type.required {
+            Ok(condition)
+        } else {
+            Ok(ast::Expr::and(
+                ast::Expr::has_attr(ast::Expr::var(ast::Var::Context), (*attr_name).clone()),
+                condition,
+            ))
+        }
+    }
+
+    /// size hint for arbitrary_policy()
+    pub fn arbitrary_policy_size_hint(
+        _settings: &ABACSettings,
+        depth: usize,
+    ) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            <PolicyID as Arbitrary>::size_hint(depth),
+            <Effect as Arbitrary>::size_hint(depth),
+            Self::arbitrary_principal_constraint_size_hint(depth),
+            Self::arbitrary_action_constraint_size_hint(depth),
+            Self::arbitrary_resource_constraint_size_hint(depth),
+            (1, None), // not sure how to count the arbitrary_loop() call
+        ])
+    }
+
+    /// 50% `== ?slot`, 25% `in ?slot`, 25% `is T in ?slot`
+    fn arbitrary_slot_constraint(
+        &self,
+        u: &mut Unstructured<'_>,
+    ) -> Result<PrincipalOrResourceConstraint> {
+        gen!(u,
+            2 => Ok(PrincipalOrResourceConstraint::EqSlot),
+            1 => Ok(PrincipalOrResourceConstraint::InSlot),
+            1 => Ok(PrincipalOrResourceConstraint::IsTypeInSlot(
+                u.choose(self.entity_types())?.clone()
+            ))
+        )
+    }
+
+    /// get an arbitrary uid of one of the `types` for a principal or resource
+    /// constraint. The uid exists in `hierarchy` unless no entity of these
+    /// types does, so that `==` and `in` constraints can actually match.
+    fn arbitrary_scope_uid(
+        &self,
+        types: &[ast::EntityType],
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ast::EntityUID> {
+        let uids: Vec<&ast::EntityUID> = types
+            .iter()
+            .flat_map(|ty| hierarchy.uids_with_type(ty))
+            .collect();
+        if uids.is_empty() {
+            let ty = u
+                .choose(types)
+                .map_err(|e| while_doing("choosing a type for a scope constraint".into(), e))?;
+            self.exprgenerator(Some(hierarchy))
+                .arbitrary_uid_with_type(ty, u)
+        } else {
+            Ok((*u.choose(&uids)?).clone())
+        }
+    }
+    fn arbitrary_scope_uid_size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::or(
+            size_hint_for_choose(None),
+            arbitrary::size_hint::and(
+                size_hint_for_choose(None),
+                ExprGenerator::arbitrary_uid_with_type_size_hint(depth),
+            ),
+        )
+    }
+
+    /// get an arbitrary principal or resource constraint over the `types`,
+    /// in a form chosen according to the `scope_weights` setting
+    fn arbitrary_scope_constraint(
+        &self,
+        types: &[ast::EntityType],
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<PrincipalOrResourceConstraint> {
+        let uid = self.arbitrary_scope_uid(types, hierarchy, u)?;
+        let ety = u.choose(self.entity_types())?.clone();
+        PrincipalOrResourceConstraint::arbitrary_with_weights(
+            &self.settings.scope_weights.unwrap_or_default(),
+            uid,
+            ety,
+            u,
+        )
+    }
+    fn arbitrary_scope_constraint_size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Self::arbitrary_scope_uid_size_hint(depth),
+            size_hint_for_choose(None),
+            PrincipalOrResourceConstraint::arbitrary_with_weights_size_hint(depth),
+        ])
+    }
+
+    fn arbitrary_principal_constraint(
+        &self,
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<PrincipalOrResourceConstraint> {
+        self.arbitrary_scope_constraint(&self.principal_types, hierarchy, u)
+    }
+    fn arbitrary_principal_constraint_size_hint(depth: usize) -> (usize, Option<usize>) {
+        Self::arbitrary_scope_constraint_size_hint(depth)
+    }
+
+    fn arbitrary_resource_constraint(
+        &self,
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<PrincipalOrResourceConstraint> {
+        self.arbitrary_scope_constraint(&self.resource_types, hierarchy, u)
+    }
+    fn arbitrary_resource_constraint_size_hint(depth: usize) -> (usize, Option<usize>) {
+        Self::arbitrary_scope_constraint_size_hint(depth)
+    }
+
+    fn arbitrary_action_constraint(
+        &self,
+        u: &mut Unstructured<'_>,
+        max_list_length: Option<u32>,
+    ) -> Result<ActionConstraint> {
+        if !self.settings.enable_action_in_constraints {
+            // 25% of the time, NoConstraint; 75%, Eq
+            gen!(u,
+        1 => Ok(ActionConstraint::NoConstraint),
+        3 => Ok(ActionConstraint::Eq(self.exprgenerator(None).arbitrary_action_uid(u)?)))
+        } else {
+            // 10% of the time, NoConstraint; 30%, Eq; 30%, In; 30%, InList
+            gen!(u,
+            1 => Ok(ActionConstraint::NoConstraint),
+            3 => Ok(ActionConstraint::Eq(self.exprgenerator(None).arbitrary_action_uid(u)?)),
+            3 => Ok(ActionConstraint::In(self.exprgenerator(None).arbitrary_action_uid(u)?)),
+            3 => {
+                let mut uids = vec![];
+                let exprgenerator = self.exprgenerator(None);
+                u.arbitrary_loop(Some(0), max_list_length, |u| {
+                    uids.push(exprgenerator.arbitrary_action_uid(u)?);
+                    Ok(std::ops::ControlFlow::Continue(()))
+                })?;
+                if self.settings.enable_undeclared_actions && !uids.is_empty() && u.ratio(1, 4)? {
+                    // mix an undeclared action in with the declared ones
+                    let idx = u.choose_index(uids.len() + 1)?;
+                    uids.insert(idx, self.arbitrary_undeclared_action_uid(u)?);
+                }
+                Ok(ActionConstraint::InList(uids))
+            })
+        }
+    }
+
+    /// get the UID of an action that is not declared in the schema
+    fn arbitrary_undeclared_action_uid(&self, u: &mut Unstructured<'_>) -> Result<ast::EntityUID> {
+        let mut name = self.constant_pool.arbitrary_string_constant(u)?.to_string();
+        while self.actions_eids.contains(&ast::Eid::new(name.as_str())) {
+            name.push('_');
+        }
+        Ok(uid_for_action_name(self.namespace(), ast::Eid::new(name)))
+    }
+
+    fn arbitrary_action_constraint_size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and(
+            size_hint_for_range(1, 10),
+            arbitrary::size_hint::or_all(&[
+                (0, Some(0)),
+                ExprGenerator::arbitrary_action_uid_size_hint(depth),
+                ExprGenerator::arbitrary_action_uid_size_hint(depth),
+                (1, None), // not sure how to hint for arbitrary_loop()
+            ]),
+        )
+    }
+
+    /// generate an arbitrary `ABACRequest` conforming to the schema
+    pub fn arbitrary_request(
+        &self,
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ABACRequest> {
+        // first pick one of the valid Actions
+        let applicable_actions: Vec<_> = self
+            .schema
+            .actions
+            .iter()
             .filter(|(_, action)| action.applies_to.is_some())
             .collect();
         let (action_name, action) = applicable_actions[u.choose_index(applicable_actions.len())?];
-        // This is safe as we checked above
+        // now generate a valid request for that Action
+        self.arbitrary_request_for_action(action_name, action, hierarchy, u)
+    }
+
+    /// Generate an arbitrary partial request, where each of the principal,
+    /// resource, and context may be unknown. The parts that are known are
+    /// generated as in `arbitrary_request()`, so the request is valid for the
+    /// schema.
+    pub fn arbitrary_partial_request(
+        &self,
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<PartialRequest> {
+        let ABACRequest(request) = self.arbitrary_request(hierarchy, u)?;
+        Ok(PartialRequest::arbitrary_from_request(request, u)?)
+    }
+
+    /// internal helper function: generate an arbitrary `ABACRequest` for the
+    /// given action, which must have an `applies_to`
+    fn arbitrary_request_for_action(
+        &self,
+        action_name: &SmolStr,
+        action: &json_schema::ActionType<ast::InternalName>,
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ABACRequest> {
+        // This is safe as our callers check it
         let applies_to: &json_schema::ApplySpec<ast::InternalName> =
             action.applies_to.as_ref().unwrap();
-        // now generate a valid request for that Action
-        Ok(ABACRequest(Request {
+        let mut request = Request {
             principal: {
                 let types = &applies_to.principal_types;
                 let ty = u.choose(types).map_err(|e| {
@@ -1441,62 +2788,432 @@ impl Schema {
                 })?;
                 self.arbitrary_uid_with_etype_as_name(ty.try_into().unwrap(), Some(hierarchy), u)?
             },
-            context: {
-                let mut attributes: Vec<_> = action
-                    .applies_to
-                    .as_ref()
-                    .map(|a| attrs_from_attrs_or_context(&self.schema, &a.context))
-                    .iter()
-                    .flat_map(|attributes| attributes.attrs.iter())
-                    .collect();
-                attributes.sort();
-                let exprgenerator = self.exprgenerator(Some(hierarchy));
-                let attrs = attributes
-                    .iter()
-                    .map(|(attr_name, attr_type)| {
-                        Ok((
-                            attr_name.parse().expect("failed to parse attribute name"),
-                            exprgenerator
-                                .generate_attr_value_for_schematype(
-                                    &attr_type.ty,
-                                    self.settings.max_depth,
-                                    u,
-                                )?
-                                .into(),
-                        ))
-                    })
-                    .collect::<Result<HashMap<_, _>>>()?;
-                ast::Context::from_pairs(attrs, Extensions::all_available())
-                    .map_err(Error::ContextError)?
-            },
-        }))
-    }
-    /// size hint for arbitrary_request()
-    pub fn arbitrary_request_size_hint(_depth: usize) -> (usize, Option<usize>) {
-        arbitrary::size_hint::and(size_hint_for_choose(None), (1, None))
-    }
-
-    /// Get the namespace of this `Schema`, if any
-    pub fn namespace(&self) -> Option<&ast::Name> {
-        self.namespace.as_ref()
+            context: self.arbitrary_context_for_action(action, hierarchy, u)?,
+        };
+        if self.settings.enable_ownership_patterns && u.ratio::<u8>(1, 2)? {
+            // make the principal an owner of the resource, if it has one
+            let owners = self.owners_of(&request.resource, applies_to, hierarchy)?;
+            if !owners.is_empty() {
+                request.principal = u.choose(&owners)?.clone();
+            }
+        }
+        Ok(ABACRequest(request))
     }
 
-    /// Get the underlying schema file, as a `NamespaceDefinition`
-    pub fn schemafile(&self) -> &json_schema::NamespaceDefinition<ast::InternalName> {
-        &self.schema
+    /// internal helper function: generate a context conforming to the context
+    /// type the given action declares. Required attributes always get a
+    /// well-typed value; optional ones are included half of the time.
+    fn arbitrary_context_for_action(
+        &self,
+        action: &json_schema::ActionType<ast::InternalName>,
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ast::Context> {
+        let mut attributes: Vec<_> = action
+            .applies_to
+            .as_ref()
+            .map(|a| attrs_from_attrs_or_context(&self.schema, &a.context))
+            .iter()
+            .flat_map(|attributes| attributes.attrs.iter())
+            .collect();
+        attributes.sort();
+        let exprgenerator = self.exprgenerator(Some(hierarchy));
+        let mut attrs = HashMap::new();
+        for (attr_name, attr_type) in attributes {
+            // nested records get the same treatment of optional attributes
+            // from `generate_attr_value_for_schematype()`
+            if attr_type.required || u.ratio::<u8>(1, 2)? {
+                attrs.insert(
+                    attr_name.parse().expect("failed to parse attribute name"),
+                    exprgenerator
+                        .generate_attr_value_for_schematype(
+                            &attr_type.ty,
+                            self.settings.max_depth,
+                            u,
+                        )?
+                        .into(),
+                );
+            }
+        }
+        ast::Context::from_pairs(attrs, Extensions::all_available()).map_err(Error::ContextError)
     }
 
-    /// Get the underlying schema file, as a String containing JSON
-    pub fn schemafile_string(&self) -> String {
-        serde_json::to_string_pretty(&self.schema)
-            .expect("failed to serialize schema NamespaceDefinition")
+    /// generate an arbitrary `ABACRequest` which request validation accepts.
+    /// Unlike `arbitrary_request()`, this only picks
+    /// `(principal type, action, resource type)` triples allowed by the
+    /// schema's applies-to specs, and the principal and resource are always
+    /// entities in the `hierarchy`. The context has a well-typed value for
+    /// every attribute declared for the action.
+    pub fn arbitrary_valid_request(
+        &self,
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ABACRequest> {
+        let qualify = |ty: &ast::InternalName| -> ast::EntityType {
+            ast::Name::try_from(ty.qualify_with_name(self.namespace()))
+                .unwrap()
+                .into()
+        };
+        let triples: Vec<_> = self
+            .schema
+            .actions
+            .iter()
+            .filter_map(|(action_name, action)| {
+                Some((action_name, action, action.applies_to.as_ref()?))
+            })
+            .flat_map(|(action_name, action, applies_to)| {
+                applies_to.principal_types.iter().flat_map(move |p| {
+                    applies_to
+                        .resource_types
+                        .iter()
+                        .map(move |r| (action_name, action, qualify(p), qualify(r)))
+                })
+            })
+            .filter(|(_, _, p, r)| {
+                !hierarchy.uids_with_type(p).is_empty() && !hierarchy.uids_with_type(r).is_empty()
+            })
+            .collect();
+        let (action_name, action, principal_type, resource_type) =
+            u.choose(&triples).map_err(|e| {
+                while_doing(
+                    "choosing a principal type, action, and resource type allowed by the schema"
+                        .into(),
+                    e,
+                )
+            })?;
+        Ok(ABACRequest(Request {
+            principal: u.choose(hierarchy.uids_with_type(principal_type))?.clone(),
+            action: uid_for_action_name(
+                self.namespace.as_ref(),
+                ast::Eid::new((*action_name).clone()),
+            ),
+            resource: u.choose(hierarchy.uids_with_type(resource_type))?.clone(),
+            context: self.arbitrary_context_for_action(action, hierarchy, u)?,
+        }))
     }
-}
 
-impl From<Schema> for json_schema::Fragment<ast::InternalName> {
-    fn from(schema: Schema) -> json_schema::Fragment<ast::InternalName> {
-        json_schema::Fragment(HashMap::from_iter([(schema.namespace, schema.schema)]).into())
-    }
+    /// generate an arbitrary `ABACRequest` which conforms to the schema except
+    /// that the type of the principal or resource (as chosen by `mismatch`) is
+    /// a declared entity type that the action doesn't apply to. Request
+    /// validation should reject such requests.
+    pub fn arbitrary_applies_to_mismatched_request(
+        &self,
+        hierarchy: &Hierarchy,
+        mismatch: AppliesToMismatch,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ABACRequest> {
+        // pick one of the actions with at least one declared entity type
+        // outside of its applies-to
+        let candidates: Vec<_> = self
+            .schema
+            .actions
+            .iter()
+            .filter_map(|(action_name, action)| {
+                let applies_to = action.applies_to.as_ref()?;
+                let applicable_types: Vec<ast::EntityType> = match mismatch {
+                    AppliesToMismatch::Principal => &applies_to.principal_types,
+                    AppliesToMismatch::Resource => &applies_to.resource_types,
+                }
+                .iter()
+                .map(|ty| {
+                    ast::Name::try_from(ty.qualify_with_name(self.namespace()))
+                        .unwrap()
+                        .into()
+                })
+                .collect();
+                let other_types: Vec<ast::EntityType> = self
+                    .entity_types
+                    .iter()
+                    .filter(|ty| !applicable_types.contains(ty))
+                    .cloned()
+                    .collect();
+                (!other_types.is_empty()).then_some((action_name, action, other_types))
+            })
+            .collect();
+        let (action_name, action, other_types) = u.choose(&candidates).map_err(|e| {
+            while_doing(
+                "choosing an action with entity types outside its applies-to".into(),
+                e,
+            )
+        })?;
+        let mut request = self.arbitrary_request_for_action(action_name, action, hierarchy, u)?;
+        let ty = u.choose(other_types).map_err(|e| {
+            while_doing(
+                "choosing an entity type outside the action's applies-to".into(),
+                e,
+            )
+        })?;
+        let uid = self.arbitrary_uid_with_etype(ty, Some(hierarchy), u)?;
+        match mismatch {
+            AppliesToMismatch::Principal => request.principal = uid,
+            AppliesToMismatch::Resource => request.resource = uid,
+        }
+        Ok(request)
+    }
+
+    /// generate an arbitrary `ABACRequest` which conforms to the schema except
+    /// that its context violates the context type of its action, as chosen by
+    /// `violation`. Request validation should reject such requests.
+    pub fn arbitrary_invalid_context_request(
+        &self,
+        hierarchy: &Hierarchy,
+        violation: ContextViolation,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ABACRequest> {
+        let applicable_actions: Vec<_> = self
+            .schema
+            .actions
+            .iter()
+            .filter(|(_, action)| action.applies_to.is_some())
+            .collect();
+        let (action_name, action) = u
+            .choose(&applicable_actions)
+            .map_err(|e| while_doing("choosing an action with an applies-to".into(), e))?;
+        let mut request = self.arbitrary_request_for_action(action_name, action, hierarchy, u)?;
+        request.context =
+            self.arbitrary_invalid_context(&request.action, violation, hierarchy, u)?;
+        Ok(request)
+    }
+
+    /// generate a context which conforms to the context type the action
+    /// `action` declares except for one attribute, which is missing even
+    /// though it's required, or has a value of the wrong type, as chosen by
+    /// `violation`. Fails if the context type has no such attribute.
+    pub fn arbitrary_invalid_context(
+        &self,
+        action: &ast::EntityUID,
+        violation: ContextViolation,
+        hierarchy: &Hierarchy,
+        u: &mut Unstructured<'_>,
+    ) -> Result<ast::Context> {
+        let mut attributes = self.context_attrs_of_action(action);
+        attributes.sort();
+        let candidates: Vec<usize> = attributes
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, attr_type))| match violation {
+                ContextViolation::MissingAttribute => attr_type.required,
+                // we can't tell which type a common type or an ambiguous
+                // name stands for without resolving it, so avoid them
+                ContextViolation::WrongType => !matches!(
+                    attr_type.ty,
+                    json_schema::Type::CommonTypeRef { .. }
+                        | json_schema::Type::Type(json_schema::TypeVariant::EntityOrCommon { .. })
+                ),
+            })
+            .map(|(i, _)| i)
+            .collect();
+        let violated = *u.choose(&candidates).map_err(|e| {
+            while_doing(
+                format!("choosing a context attribute for a {violation:?} violation"),
+                e,
+            )
+        })?;
+        let exprgenerator = self.exprgenerator(Some(hierarchy));
+        let mut attrs = HashMap::new();
+        for (i, (attr_name, attr_type)) in attributes.into_iter().enumerate() {
+            let attr_name = attr_name.clone();
+            if i == violated {
+                if violation == ContextViolation::WrongType {
+                    // a `Long` never conforms to any other type, and a
+                    // `String` never conforms to `Long`
+                    let value = match attr_type.ty {
+                        json_schema::Type::Type(json_schema::TypeVariant::Long) => {
+                            ast::RestrictedExpr::val(u.arbitrary::<String>()?)
+                        }
+                        _ => ast::RestrictedExpr::val(u.arbitrary::<i64>()?),
+                    };
+                    attrs.insert(attr_name, value);
+                }
+            } else if attr_type.required || u.ratio::<u8>(1, 2)? {
+                attrs.insert(
+                    attr_name,
+                    exprgenerator
+                        .generate_attr_value_for_schematype(
+                            &attr_type.ty,
+                            self.settings.max_depth,
+                            u,
+                        )?
+                        .into(),
+                );
+            }
+        }
+        ast::Context::from_pairs(attrs, Extensions::all_available()).map_err(Error::ContextError)
+    }
+
+    /// internal helper function: the owners of `resource` (values of its
+    /// ownership attributes in the `hierarchy`) which are valid principals for
+    /// the given `applies_to`
+    fn owners_of(
+        &self,
+        resource: &ast::EntityUID,
+        applies_to: &json_schema::ApplySpec<ast::InternalName>,
+        hierarchy: &Hierarchy,
+    ) -> Result<Vec<ast::EntityUID>> {
+        let Some(entity) = hierarchy.entity(resource) else {
+            return Ok(vec![]);
+        };
+        let principal_types = applies_to
+            .principal_types
+            .iter()
+            .map(|ty| {
+                ast::Name::try_from(ty.qualify_with_name(self.namespace()))
+                    .map(ast::EntityType::from)
+                    .map_err(|e| Error::UnsupportedSchema(e.to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self
+            .ownership_attributes()
+            .into_iter()
+            .filter(|(ty, _)| ty == resource.entity_type())
+            .filter_map(|(_, attr)| match entity.get(&attr) {
+                Some(ast::PartialValue::Value(v)) => v.get_as_entity().ok().cloned(),
+                _ => None,
+            })
+            .filter(|owner| principal_types.contains(owner.entity_type()))
+            .collect())
+    }
+
+    /// size hint for arbitrary_request()
+    pub fn arbitrary_request_size_hint(_depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and(size_hint_for_choose(None), (1, None))
+    }
+
+    /// size hint for arbitrary_partial_request()
+    pub fn arbitrary_partial_request_size_hint(depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            Self::arbitrary_request_size_hint(depth),
+            size_hint_for_ratio(1, 3),
+            size_hint_for_ratio(1, 3),
+            size_hint_for_ratio(1, 3),
+        ])
+    }
+
+    /// size hint for arbitrary_valid_request()
+    pub fn arbitrary_valid_request_size_hint(_depth: usize) -> (usize, Option<usize>) {
+        arbitrary::size_hint::and_all(&[
+            size_hint_for_choose(None),
+            size_hint_for_choose(None),
+            size_hint_for_choose(None),
+            (1, None),
+        ])
+    }
+
+    /// Get the namespace of this `Schema`, if any
+    pub fn namespace(&self) -> Option<&ast::Name> {
+        self.namespace.as_ref()
+    }
+
+    /// Get the underlying schema file, as a `NamespaceDefinition`
+    pub fn schemafile(&self) -> &json_schema::NamespaceDefinition<ast::InternalName> {
+        &self.schema
+    }
+
+    /// Get the underlying schema file, as a String containing JSON
+    pub fn schemafile_string(&self) -> String {
+        serde_json::to_string_pretty(&self.schema)
+            .expect("failed to serialize schema NamespaceDefinition")
+    }
+
+    /// Get the attributes of entities in `hierarchy` that this `Schema`
+    /// doesn't declare for their entity types, as (entity, attribute) pairs.
+    /// Entity types whose shape allows additional attributes are skipped, so
+    /// these are exactly the attributes that make entities not conform to the
+    /// schema. The hierarchy generator only adds them with
+    /// `enable_undeclared_entity_attrs`.
+    pub fn undeclared_entity_attrs(&self, hierarchy: &Hierarchy) -> Vec<(ast::EntityUID, SmolStr)> {
+        let declared: HashMap<ast::EntityType, Attributes<'_>> = self
+            .schema
+            .entity_types
+            .iter()
+            .map(|(name, et)| {
+                (
+                    ast::EntityType::from(ast::Name::from(name.clone()))
+                        .qualify_with(self.namespace()),
+                    attrs_from_attrs_or_context(&self.schema, &et.shape),
+                )
+            })
+            .collect();
+        hierarchy
+            .entities()
+            .flat_map(|e| match declared.get(e.uid().entity_type()) {
+                Some(attributes) if !attributes.additional_attrs => e
+                    .attrs()
+                    .filter(|(attr, _)| !attributes.attrs.contains_key(*attr))
+                    .map(|(attr, _)| (e.uid().clone(), attr.clone()))
+                    .collect(),
+                // action entities aren't described by entity type shapes, and
+                // any attribute conforms to a shape with additional attributes
+                _ => vec![],
+            })
+            .collect()
+    }
+
+    /// Get summary counts for this `Schema`
+    pub fn stats(&self) -> SchemaStats {
+        let entity_attrs = self
+            .schema
+            .entity_types
+            .values()
+            .map(|et| attrs_from_attrs_or_context(&self.schema, &et.shape));
+        let context_attrs = self
+            .schema
+            .actions
+            .values()
+            .filter_map(|action| action.applies_to.as_ref())
+            .map(|applies_to| attrs_from_attrs_or_context(&self.schema, &applies_to.context));
+        let is_extension = |ty: &json_schema::TypeOfAttribute<ast::InternalName>| {
+            ty.ty.is_extension() == Some(true)
+        };
+        /// Length of the longest chain of memberships starting at `name`.
+        /// `fuel` bounds the recursion in case the action hierarchy is cyclic.
+        fn group_depth(
+            actions: &BTreeMap<SmolStr, json_schema::ActionType<ast::InternalName>>,
+            name: &SmolStr,
+            fuel: usize,
+        ) -> usize {
+            let Some(action) = actions.get(name) else {
+                return 0;
+            };
+            if fuel == 0 {
+                return 0;
+            }
+            action
+                .member_of
+                .iter()
+                .flatten()
+                .map(|parent| 1 + group_depth(actions, &parent.id, fuel - 1))
+                .max()
+                .unwrap_or(0)
+        }
+        SchemaStats {
+            num_actions: self.schema.actions.len(),
+            num_entity_types: self.schema.entity_types.len(),
+            num_common_types: self.schema.common_types.len(),
+            max_entity_attrs: entity_attrs
+                .clone()
+                .map(|attrs| attrs.attrs.len())
+                .max()
+                .unwrap_or(0),
+            num_extension_attrs: entity_attrs
+                .chain(context_attrs)
+                .map(|attrs| attrs.attrs.values().filter(|ty| is_extension(ty)).count())
+                .sum(),
+            action_group_depth: self
+                .schema
+                .actions
+                .keys()
+                .map(|name| group_depth(&self.schema.actions, name, self.schema.actions.len()))
+                .max()
+                .unwrap_or(0),
+        }
+    }
+}
+
+impl From<Schema> for json_schema::Fragment<ast::InternalName> {
+    fn from(schema: Schema) -> json_schema::Fragment<ast::InternalName> {
+        json_schema::Fragment(HashMap::from_iter([(schema.namespace, schema.schema)]).into())
+    }
 }
 
 impl From<Schema> for json_schema::Fragment<RawName> {
@@ -1692,33 +3409,96 @@ impl TryFrom<Schema> for ValidatorSchema {
 }
 
 #[cfg(test)]
-mod tests {
-    use super::Schema;
-    use crate::{hierarchy::EntityUIDGenMode, settings::ABACSettings};
+pub(crate) mod tests {
+    use super::{
+        arbitrary_schematype_with_bounded_depth, downgrade_frag_to_raw, uid_for_action_name,
+        AppliesToMismatch, ContextViolation, Schema, SchemaStats,
+    };
+    use crate::abac::Type;
+    use crate::collections::{HashMap, HashSet};
+    use crate::err::Error;
+    use crate::expr::{ExprGenerator, ACTION_ATTR_NAMES, BOOL_OPERATOR_COMBINATIONS};
+    use crate::hierarchy::EntityUIDGenMode;
+    use crate::policy::{
+        ActionConstraint, GeneratedPolicy, PrincipalOrResourceConstraint, ScopeWeights,
+    };
+    use crate::request::arbitrary_boundary_number_context_json;
+    use crate::settings::{ABACSettings, OperatorWeights};
     use arbitrary::Unstructured;
-    use cedar_policy_core::entities::Entities;
+    use cedar_policy_core::ast;
+    use cedar_policy_core::ast::RestrictedExpr;
+    use cedar_policy_core::entities::{
+        Entities, EntityJsonParser, NoEntitiesSchema, TCComputation,
+    };
+    use cedar_policy_core::evaluator::RestrictedEvaluator;
     use cedar_policy_core::extensions::Extensions;
-    use cedar_policy_validator::{json_schema, CoreSchema, RawName, ValidatorSchema};
-    use rand::{rngs::ThreadRng, thread_rng, RngCore};
+    use cedar_policy_validator::{
+        json_schema, CoreSchema, RawName, ValidationMode, Validator, ValidatorSchema,
+    };
+    use rand::{
+        rngs::{StdRng, ThreadRng},
+        thread_rng, RngCore, SeedableRng,
+    };
 
-    const RANDOM_BYTE_SIZE: u16 = 1024;
-    const ITERATION: u8 = 100;
+    pub(crate) const RANDOM_BYTE_SIZE: u16 = 1024;
+    pub(crate) const ITERATION: u8 = 100;
 
-    const TEST_SETTINGS: ABACSettings = ABACSettings {
+    pub(crate) const TEST_SETTINGS: ABACSettings = ABACSettings {
         match_types: false,
         enable_extensions: false,
+        enable_ipaddr: false,
+        enable_invalid_extension_args: false,
         max_depth: 4,
         max_width: 4,
+        max_conditions: 4,
+        max_namespaces: 1,
         enable_additional_attributes: false,
+        enable_undeclared_entity_attrs: false,
         enable_like: false,
+        enable_unicode_strings: false,
         enable_action_groups_and_attrs: true,
         enable_arbitrary_func_call: false,
         enable_unknowns: false,
         enable_unspecified_apply_spec: true,
         enable_action_in_constraints: true,
+        enable_undeclared_actions: false,
+        enable_is_operator: true,
+        enable_guarded_attr_chains: false,
+        enable_unguarded_attr_chains: false,
+        record_set_nesting_weight: 0,
+        conditional_weight: 0,
+        prefer_context_conditions: false,
+        operator_weights: &[],
+        scope_weights: None,
+        enable_ownership_patterns: false,
     };
 
-    const GITHUB_SCHEMA_STR: &str = r#"
+    /// Source of random bytes for tests. It's seeded, so tests that check
+    /// something is generated at least once don't fail intermittently.
+    pub(crate) fn test_rng() -> StdRng {
+        StdRng::seed_from_u64(0)
+    }
+
+    /// Call `f` `ITERATION` times, each time with a schema generated from
+    /// `fragment` and `settings` and the rest of the random bytes used to
+    /// generate it
+    pub(crate) fn for_each_schema(
+        fragment: &json_schema::Fragment<RawName>,
+        settings: &ABACSettings,
+        mut f: impl FnMut(Schema, Unstructured<'_>),
+    ) {
+        let mut rng = test_rng();
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let schema = Schema::from_raw_schemafrag(fragment.clone(), settings.clone(), &mut u)
+                .expect("failed to generate schema!");
+            f(schema, u);
+        }
+    }
+
+    pub(crate) const GITHUB_SCHEMA_STR: &str = r#"
     {
         "": {
             "entityTypes": {
@@ -2156,6 +3936,1956 @@ mod tests {
         }
     }
 
+    #[test]
+    fn nanoid_uids_are_reproducible() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut rng = test_rng();
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let generate_uids = || {
+                let mut u = Unstructured::new(&bytes);
+                let schema = Schema::from_raw_schemafrag(fragment.clone(), TEST_SETTINGS, &mut u)
+                    .expect("failed to generate schema!");
+                schema
+                    .arbitrary_hierarchy_with_nanoid_uids(
+                        EntityUIDGenMode::default_nanoid_len(),
+                        &mut u,
+                    )
+                    .map(|h| h.uids().to_vec())
+                    .ok()
+            };
+            assert_eq!(generate_uids(), generate_uids());
+        }
+    }
+
+    #[test]
+    fn undeclared_entity_attrs() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let settings = ABACSettings {
+            enable_undeclared_entity_attrs: true,
+            ..TEST_SETTINGS
+        };
+        let mut rng = test_rng();
+        let mut found = false;
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let Ok(schema) =
+                Schema::from_raw_schemafrag(fragment.clone(), settings.clone(), &mut u)
+            else {
+                continue;
+            };
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                continue;
+            };
+            let undeclared = schema.undeclared_entity_attrs(&hierarchy);
+            if undeclared.is_empty() {
+                continue;
+            }
+            found = true;
+            // the undeclared attributes survive conversion to `Entities` and
+            // a round trip through the entity JSON format
+            let entities = Entities::try_from(hierarchy.clone()).unwrap();
+            let json = entities.to_json_value().unwrap();
+            let parsed = EntityJsonParser::new(
+                None::<&NoEntitiesSchema>,
+                Extensions::all_available(),
+                TCComputation::ComputeNow,
+            )
+            .from_json_value(json)
+            .expect("should parse");
+            for (uid, attr) in &undeclared {
+                let entity = parsed
+                    .iter()
+                    .find(|e| e.uid() == uid)
+                    .expect("entity should survive the round trip");
+                assert!(entity.get(attr).is_some(), "{uid} lost attribute {attr:?}");
+            }
+            // but they don't conform to the schema
+            let vschema = ValidatorSchema::try_from(schema).unwrap();
+            assert!(Entities::from_entities(
+                hierarchy.entities().cloned(),
+                Some(&CoreSchema::new(&vschema)),
+                TCComputation::ComputeNow,
+                Extensions::all_available(),
+            )
+            .is_err());
+        }
+        assert!(found, "no undeclared attributes were generated");
+    }
+
+    #[test]
+    fn context_arith_cmp() {
+        let fragment = json_schema::Fragment::from_json_str(
+            r#"{
+                "": {
+                    "entityTypes": { "User": {} },
+                    "actions": {
+                        "view": {
+                            "appliesTo": {
+                                "principalTypes": ["User"],
+                                "resourceTypes": ["User"],
+                                "context": {
+                                    "type": "Record",
+                                    "attributes": {
+                                        "count": { "type": "Long" },
+                                        "limit": { "type": "Long" },
+                                        "name": { "type": "String" },
+                                        "offset": { "type": "Long", "required": false }
+                                    }
+                                }
+                            }
+                        },
+                        "edit": {
+                            "appliesTo": {
+                                "principalTypes": ["User"],
+                                "resourceTypes": ["User"],
+                                "context": {
+                                    "type": "Record",
+                                    "attributes": {
+                                        "count": { "type": "Long" },
+                                        "total": { "type": "Long" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .expect("schema str should be valid!");
+        let view = uid_for_action_name(None, ast::Eid::new("view"));
+        for_each_schema(&fragment, &TEST_SETTINGS, |schema, mut u| {
+            // optional attributes and attributes of other actions are left out
+            assert_eq!(
+                schema.context_long_attributes(Some(&view)),
+                vec!["count", "limit"]
+            );
+            assert_eq!(schema.context_long_attributes(None), vec!["count"]);
+            let generator = ExprGenerator {
+                action: Some(&view),
+                ..schema.exprgenerator(None)
+            };
+            if let Ok(expr) = generator.generate_context_arith_cmp_expr(&mut u) {
+                let src = expr.to_string();
+                assert!(src.contains("context"));
+                assert!(!src.contains("name") && !src.contains("offset") && !src.contains("total"));
+            }
+            if let Ok(expr) = schema
+                .exprgenerator(None)
+                .generate_context_arith_cmp_expr(&mut u)
+            {
+                let src = expr.to_string();
+                assert!(!src.contains("limit") && !src.contains("total"), "{src}");
+            }
+        });
+    }
+
+    #[test]
+    fn context_entity_eq() {
+        let fragment = json_schema::Fragment::from_json_str(
+            r#"{
+                "": {
+                    "entityTypes": { "User": {}, "Device": {} },
+                    "actions": {
+                        "view": {
+                            "appliesTo": {
+                                "principalTypes": ["User"],
+                                "resourceTypes": ["User"],
+                                "context": {
+                                    "type": "Record",
+                                    "attributes": {
+                                        "owner": { "type": "Entity", "name": "User" },
+                                        "viewer": { "type": "Entity", "name": "User" },
+                                        "device": { "type": "Entity", "name": "Device" },
+                                        "count": { "type": "Long" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .expect("schema str should be valid!");
+        for_each_schema(&fragment, &TEST_SETTINGS, |schema, mut u| {
+            let user = ast::EntityType::from_normalized_str("User").unwrap();
+            let device = ast::EntityType::from_normalized_str("Device").unwrap();
+            assert_eq!(
+                schema.context_entity_attributes(None),
+                vec![
+                    (device, "device".into()),
+                    (user.clone(), "owner".into()),
+                    (user, "viewer".into())
+                ]
+            );
+            if let Ok(expr) = schema
+                .exprgenerator(None)
+                .generate_context_entity_eq_expr(&mut u)
+            {
+                // both sides have the same entity type
+                let src = expr.to_string();
+                assert!(
+                    src.matches("device").count() == 2 || !src.contains("device"),
+                    "{src}"
+                );
+                assert!(!src.contains("count"));
+            }
+        });
+    }
+
+    #[test]
+    fn multi_type_membership() {
+        let fragment = json_schema::Fragment::from_json_str(
+            r#"{
+                "": {
+                    "entityTypes": {
+                        "User": { "memberOfTypes": ["GroupA", "GroupB"] },
+                        "GroupA": {},
+                        "GroupB": {}
+                    },
+                    "actions": {
+                        "view": {
+                            "appliesTo": {
+                                "principalTypes": ["User"],
+                                "resourceTypes": ["GroupA", "GroupB"]
+                            }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .expect("schema str should be valid!");
+        let mut found = false;
+        for_each_schema(&fragment, &TEST_SETTINGS, |schema, mut u| {
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                return;
+            };
+            let members: Vec<_> = hierarchy
+                .multi_type_members()
+                .map(|member| {
+                    let parents: Vec<_> = member.ancestors().cloned().collect();
+                    (member.uid().clone(), parents)
+                })
+                .collect();
+            let entities = Entities::try_from(hierarchy).unwrap();
+            for (uid, parents) in members {
+                found = true;
+                let member = entities.iter().find(|e| e.uid() == &uid).unwrap();
+                // a member of each of its parents, whatever their types
+                for parent in parents {
+                    assert!(member.is_descendant_of(&parent), "{uid} in {parent}");
+                }
+            }
+        });
+        assert!(found);
+    }
+
+    #[test]
+    fn is_operator() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut rng = test_rng();
+        for enable_is_operator in [true, false] {
+            let settings = ABACSettings {
+                match_types: true,
+                enable_is_operator,
+                ..TEST_SETTINGS
+            };
+            let mut found = false;
+            for _ in 0..ITERATION {
+                let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+                rng.fill_bytes(&mut bytes);
+                let mut u = Unstructured::new(&bytes);
+                let schema =
+                    Schema::from_raw_schemafrag(fragment.clone(), settings.clone(), &mut u)
+                        .expect("failed to generate schema!");
+                let Ok(expr) = schema.exprgenerator(None).generate_expr_for_type(
+                    &Type::bool(),
+                    settings.max_depth,
+                    &mut u,
+                ) else {
+                    continue;
+                };
+                for e in expr.subexpressions() {
+                    if let ast::ExprKind::Is { entity_type, .. } = e.expr_kind() {
+                        assert!(enable_is_operator, "{expr}");
+                        assert!(schema.entity_types.contains(entity_type), "{expr}");
+                        found = true;
+                    }
+                }
+            }
+            assert_eq!(found, enable_is_operator);
+        }
+    }
+
+    #[test]
+    fn bool_operator_matrix() {
+        let fragment = json_schema::Fragment::from_json_str(
+            r#"{
+                "": {
+                    "entityTypes": {
+                        "User": {
+                            "shape": {
+                                "type": "Record",
+                                "attributes": {
+                                    "admin": { "type": "Boolean" },
+                                    "name": { "type": "String" }
+                                }
+                            }
+                        },
+                        "Doc": {
+                            "shape": {
+                                "type": "Record",
+                                "attributes": {
+                                    "public": { "type": "Boolean" },
+                                    "draft": { "type": "Boolean", "required": false }
+                                }
+                            }
+                        }
+                    },
+                    "actions": {
+                        "view": {
+                            "appliesTo": {
+                                "principalTypes": ["User"],
+                                "resourceTypes": ["Doc"],
+                                "context": {
+                                    "type": "Record",
+                                    "attributes": {
+                                        "trusted": { "type": "Boolean" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .expect("schema str should be valid!");
+        for_each_schema(&fragment, &TEST_SETTINGS, |schema, mut u| {
+            let Ok(policies) = schema.arbitrary_bool_operator_matrix(&mut u) else {
+                return;
+            };
+            assert_eq!(policies.len(), BOOL_OPERATOR_COMBINATIONS);
+            let mut policyset = ast::PolicySet::new();
+            let mut conditions = HashSet::new();
+            for policy in policies {
+                let policy = ast::StaticPolicy::from(policy);
+                let condition = policy.non_scope_constraints().to_string();
+                // only the required `Bool` attributes are used as operands
+                assert!(condition.contains("context.trusted"), "{condition}");
+                assert!(condition.contains("principal.admin"), "{condition}");
+                assert!(condition.contains("resource.public"), "{condition}");
+                assert!(conditions.insert(condition));
+                policyset.add_static(policy).unwrap();
+            }
+            let vschema =
+                ValidatorSchema::try_from(schema.clone()).expect("should be a valid schema");
+            assert!(Validator::new(vschema)
+                .validate(&policyset, ValidationMode::Strict)
+                .validation_passed());
+        });
+    }
+
+    #[test]
+    fn multiple_namespaces() {
+        let settings = ABACSettings {
+            max_namespaces: 3,
+            ..TEST_SETTINGS
+        };
+        let mut rng = test_rng();
+        let mut cross_namespace = false;
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let Ok(fragment) = Schema::arbitrary_fragment(settings.clone(), &mut u) else {
+                continue;
+            };
+            assert!((1..=3).contains(&fragment.0.len()));
+            // memberships within a namespace use unqualified names
+            cross_namespace |= fragment.0.values().any(|nsdef| {
+                nsdef.entity_types.values().any(|ety| {
+                    ety.member_of_types
+                        .iter()
+                        .any(|name| name.to_string().contains("::"))
+                })
+            });
+            ValidatorSchema::try_from(downgrade_frag_to_raw(fragment.clone()))
+                .expect("should be a valid schema");
+            let src = fragment
+                .to_cedarschema()
+                .expect("should convert to a human readable schema");
+            json_schema::Fragment::from_cedarschema_str(&src, Extensions::all_available())
+                .expect("should parse the human readable schema");
+        }
+        assert!(
+            cross_namespace,
+            "no cross-namespace memberships were generated"
+        );
+    }
+
+    #[test]
+    fn policy_condition_counts() {
+        let settings = ABACSettings {
+            match_types: true,
+            max_conditions: 3,
+            ..TEST_SETTINGS
+        };
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut counts = HashSet::new();
+        for_each_schema(&fragment, &settings, |schema, mut u| {
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                return;
+            };
+            let Ok(policy) = schema.arbitrary_policy(&hierarchy, &mut u) else {
+                return;
+            };
+            let policy = ast::StaticPolicy::from(policy);
+            // the clauses are conjoined onto `true`, left to right
+            let mut condition = policy.non_scope_constraints();
+            let mut count = 0;
+            while let ast::ExprKind::And { left, .. } = condition.expr_kind() {
+                condition = left;
+                count += 1;
+            }
+            assert_eq!(condition, &ast::Expr::val(true));
+            assert!((1..=3).contains(&count), "{count} clauses in {policy}");
+            counts.insert(count);
+        });
+        assert!(
+            counts.len() > 1,
+            "only generated policies with {counts:?} clauses"
+        );
+    }
+
+    #[test]
+    fn oversized_policies_are_aborted() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut aborted = false;
+        for_each_schema(&fragment, &TEST_SETTINGS, |schema, mut u| {
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                return;
+            };
+            let mut unbounded = Unstructured::new(u.peek_bytes(u.len()).unwrap());
+            assert!(!matches!(
+                schema.arbitrary_policy_with_max_nodes(&hierarchy, usize::MAX, &mut unbounded),
+                Err(Error::TooLarge)
+            ));
+            if let Err(Error::TooLarge) =
+                schema.arbitrary_policy_with_max_nodes(&hierarchy, 0, &mut u)
+            {
+                aborted = true;
+            }
+        });
+        assert!(aborted);
+    }
+
+    #[test]
+    fn nested_ext_literals() {
+        let settings = ABACSettings {
+            enable_extensions: true,
+            ..TEST_SETTINGS
+        };
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut found = false;
+        for_each_schema(&fragment, &settings, |schema, mut u| {
+            let Ok(expr) = schema
+                .exprgenerator(None)
+                .generate_nested_ext_literal_expr(&mut u)
+            else {
+                return;
+            };
+            let src = expr.to_string();
+            assert!(src.starts_with("[{"), "{src}");
+            assert!(src.contains("ip(") || src.contains("decimal("), "{src}");
+            found = true;
+        });
+        assert!(found);
+
+        let mut rng = test_rng();
+        let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+        rng.fill_bytes(&mut bytes);
+        let mut u = Unstructured::new(&bytes);
+        let schema = Schema::from_raw_schemafrag(fragment, TEST_SETTINGS, &mut u)
+            .expect("failed to generate schema!");
+        assert!(schema
+            .exprgenerator(None)
+            .generate_nested_ext_literal_expr(&mut u)
+            .is_err());
+    }
+
+    #[test]
+    fn ipaddr_exprs() {
+        let settings = ABACSettings {
+            enable_extensions: true,
+            enable_ipaddr: true,
+            ..TEST_SETTINGS
+        };
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let (mut in_range, mut full_prefix, mut embedded) = (false, false, false);
+        for_each_schema(&fragment, &settings, |schema, mut u| {
+            let Ok(expr) = schema.exprgenerator(None).generate_ipaddr_expr(&mut u) else {
+                return;
+            };
+            let src = expr.to_string();
+            assert!(src.contains("ip(\""), "{src}");
+            in_range |= src.contains("isInRange");
+            full_prefix |= src.contains("/32\"") || src.contains("/128\"");
+            embedded |= src.contains("::ffff:");
+        });
+        assert!(in_range, "no `isInRange` was generated");
+        assert!(full_prefix, "no `/32` or `/128` prefix was generated");
+        assert!(embedded, "no IPv4-mapped address was generated");
+
+        // `enable_ipaddr` has no effect without `enable_extensions`
+        let mut rng = test_rng();
+        let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+        rng.fill_bytes(&mut bytes);
+        let mut u = Unstructured::new(&bytes);
+        let schema = Schema::from_raw_schemafrag(
+            fragment,
+            ABACSettings {
+                enable_ipaddr: true,
+                ..TEST_SETTINGS
+            },
+            &mut u,
+        )
+        .expect("failed to generate schema!");
+        assert!(schema
+            .exprgenerator(None)
+            .generate_ipaddr_expr(&mut u)
+            .is_err());
+    }
+
+    #[test]
+    fn invalid_extension_args() {
+        let settings = ABACSettings {
+            enable_extensions: true,
+            enable_invalid_extension_args: true,
+            ..TEST_SETTINGS
+        };
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let evaluator = RestrictedEvaluator::new(Extensions::all_available());
+        let mut rng = test_rng();
+        let mut checked = 0;
+        for i in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let schema = Schema::from_raw_schemafrag(fragment.clone(), settings.clone(), &mut u)
+                .expect("failed to generate schema!");
+            let ty = if i % 2 == 0 {
+                Type::ipaddr()
+            } else {
+                Type::decimal()
+            };
+            let Ok(expr) = schema
+                .exprgenerator(None)
+                .generate_invalid_ext_constructor_call(&ty, &mut u)
+            else {
+                continue;
+            };
+            let expr = RestrictedExpr::new(expr).expect("should be a restricted expression");
+            assert!(
+                evaluator.interpret(expr.as_borrowed()).is_err(),
+                "{expr} should error"
+            );
+            checked += 1;
+        }
+        assert!(checked > 0, "no invalid constructor calls were generated");
+    }
+
+    /// The type of `e` if it's evident from its outermost node, e.g., for a
+    /// literal or an operator that always returns a `Bool` or a `Long`
+    fn evident_type(e: &ast::Expr) -> Option<&'static str> {
+        use ast::{BinaryOp, ExprKind, Literal, UnaryOp};
+        match e.expr_kind() {
+            ExprKind::Lit(Literal::Bool(_))
+            | ExprKind::And { .. }
+            | ExprKind::Or { .. }
+            | ExprKind::HasAttr { .. }
+            | ExprKind::Like { .. }
+            | ExprKind::Is { .. }
+            | ExprKind::UnaryApp {
+                op: UnaryOp::Not, ..
+            }
+            | ExprKind::BinaryApp {
+                op:
+                    BinaryOp::Eq
+                    | BinaryOp::Less
+                    | BinaryOp::LessEq
+                    | BinaryOp::In
+                    | BinaryOp::Contains
+                    | BinaryOp::ContainsAll
+                    | BinaryOp::ContainsAny,
+                ..
+            } => Some("bool"),
+            ExprKind::Lit(Literal::Long(_))
+            | ExprKind::UnaryApp {
+                op: UnaryOp::Neg, ..
+            }
+            | ExprKind::BinaryApp {
+                op: BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul,
+                ..
+            } => Some("long"),
+            ExprKind::Lit(Literal::String(_)) => Some("string"),
+            ExprKind::Lit(Literal::EntityUID(_)) => Some("entity"),
+            ExprKind::Set(_) => Some("set"),
+            ExprKind::Record(_) => Some("record"),
+            ExprKind::If {
+                then_expr,
+                else_expr,
+                ..
+            } => match (evident_type(then_expr), evident_type(else_expr)) {
+                (Some(a), Some(b)) if a == b => Some(a),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn conditionals_have_matching_branches() {
+        let settings = ABACSettings {
+            match_types: true,
+            conditional_weight: 32,
+            ..TEST_SETTINGS
+        };
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut rng = test_rng();
+        let mut conditionals = 0;
+        for i in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let schema = Schema::from_raw_schemafrag(fragment.clone(), settings.clone(), &mut u)
+                .expect("failed to generate schema!");
+            let (ty, name) = match i % 3 {
+                0 => (Type::bool(), "bool"),
+                1 => (Type::long(), "long"),
+                _ => (Type::string(), "string"),
+            };
+            let Ok(expr) =
+                schema
+                    .exprgenerator(None)
+                    .generate_expr_for_type(&ty, settings.max_depth, &mut u)
+            else {
+                continue;
+            };
+            assert!(
+                matches!(evident_type(&expr), None | Some(t) if t == name),
+                "{expr} should have type {name}"
+            );
+            for e in expr.subexpressions() {
+                if let ast::ExprKind::If {
+                    test_expr,
+                    then_expr,
+                    else_expr,
+                } = e.expr_kind()
+                {
+                    conditionals += 1;
+                    assert!(
+                        matches!(evident_type(test_expr), None | Some("bool")),
+                        "the condition of {e} should be a boolean"
+                    );
+                    if let (Some(a), Some(b)) = (evident_type(then_expr), evident_type(else_expr)) {
+                        assert_eq!(a, b, "the branches of {e} should have the same type");
+                    }
+                }
+            }
+        }
+        assert!(conditionals > 0, "no conditionals were generated");
+    }
+
+    #[test]
+    fn operator_weight_zero_suppresses() {
+        let settings = ABACSettings {
+            operator_weights: &[("containsAll", 0), ("containsAny", 20)],
+            ..TEST_SETTINGS
+        };
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut rng = test_rng();
+        let mut contains_any = 0;
+        for i in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let schema = Schema::from_raw_schemafrag(fragment.clone(), settings.clone(), &mut u)
+                .expect("failed to generate schema!");
+            let mut generator = schema.exprgenerator(None);
+            let expr = if i % 2 == 0 {
+                generator.generate_expr_for_type(&Type::bool(), settings.max_depth, &mut u)
+            } else {
+                generator.generate_expr(settings.max_depth, &mut u)
+            };
+            let Ok(expr) = expr else {
+                continue;
+            };
+            for e in expr.subexpressions() {
+                if let ast::ExprKind::BinaryApp { op, .. } = e.expr_kind() {
+                    assert_ne!(
+                        *op,
+                        ast::BinaryOp::ContainsAll,
+                        "{e} uses a suppressed operator"
+                    );
+                    if *op == ast::BinaryOp::ContainsAny {
+                        contains_any += 1;
+                    }
+                }
+            }
+        }
+        assert!(contains_any > 0, "no `containsAny` was generated");
+    }
+
+    #[test]
+    fn operator_weight_raises_frequency() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        // number of `in` operators generated with the given weights, over the
+        // same random inputs each time
+        let count_in = |operator_weights: OperatorWeights| {
+            let settings = ABACSettings {
+                match_types: true,
+                operator_weights,
+                ..TEST_SETTINGS
+            };
+            let mut count = 0;
+            for_each_schema(&fragment, &settings, |schema, mut u| {
+                let Ok(expr) = schema.exprgenerator(None).generate_expr_for_type(
+                    &Type::bool(),
+                    settings.max_depth,
+                    &mut u,
+                ) else {
+                    return;
+                };
+                count += expr
+                    .subexpressions()
+                    .filter(|e| {
+                        matches!(
+                            e.expr_kind(),
+                            ast::ExprKind::BinaryApp {
+                                op: ast::BinaryOp::In,
+                                ..
+                            }
+                        )
+                    })
+                    .count();
+            });
+            count
+        };
+        // the default weight of `in` is 11, so this raises it
+        let default = count_in(&[]);
+        let raised = count_in(&[("in", 60)]);
+        assert!(
+            raised > default,
+            "raising the weight of `in` should generate more of them ({raised} <= {default})"
+        );
+    }
+
+    #[test]
+    fn set_op_operands() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut rng = test_rng();
+        let (mut both_empty, mut one_empty, mut overlapping) = (0, 0, 0);
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let schema =
+                Schema::from_raw_schemafrag(fragment.clone(), TEST_SETTINGS.clone(), &mut u)
+                    .expect("failed to generate schema!");
+            let Ok((receiver, arg)) = schema
+                .exprgenerator(None)
+                .generate_set_op_operands(TEST_SETTINGS.max_depth, &mut u)
+            else {
+                continue;
+            };
+            let (ast::ExprKind::Set(receiver), ast::ExprKind::Set(arg)) =
+                (receiver.expr_kind(), arg.expr_kind())
+            else {
+                panic!("operands should be set literals: {receiver}, {arg}");
+            };
+            match (receiver.is_empty(), arg.is_empty()) {
+                (true, true) => both_empty += 1,
+                (true, false) | (false, true) => one_empty += 1,
+                (false, false) => {
+                    if receiver.iter().any(|e| arg.contains(e)) {
+                        overlapping += 1;
+                    }
+                }
+            }
+        }
+        assert!(both_empty > 0, "no pair of empty sets was generated");
+        assert!(one_empty > 0, "no pair with one empty set was generated");
+        assert!(overlapping > 0, "no overlapping sets were generated");
+    }
+
+    #[test]
+    fn context_conditions_typecheck() {
+        let settings = ABACSettings {
+            match_types: true,
+            prefer_context_conditions: true,
+            ..TEST_SETTINGS
+        };
+        let fragment = json_schema::Fragment::from_json_str(
+            r#"
+            {
+                "": {
+                    "entityTypes": { "User": {}, "Photo": {} },
+                    "actions": {
+                        "view": {
+                            "appliesTo": {
+                                "principalTypes": ["User"],
+                                "resourceTypes": ["Photo"],
+                                "context": {
+                                    "type": "Record",
+                                    "attributes": {
+                                        "trusted": { "type": "Boolean" },
+                                        "count": { "type": "Long" },
+                                        "label": { "type": "String", "required": false },
+                                        "owner": { "type": "Entity", "name": "User" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .expect("schema str should be valid!");
+        let mut found = false;
+        for_each_schema(&fragment, &settings, |schema, mut u| {
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                return;
+            };
+            let view = uid_for_action_name(schema.namespace(), ast::Eid::new("view"));
+            let Ok(condition) = schema.arbitrary_context_condition(&view, &hierarchy, &mut u)
+            else {
+                return;
+            };
+            assert!(condition.to_string().contains("context."), "{condition}");
+            let policy = ast::StaticPolicy::from(GeneratedPolicy::new(
+                ast::PolicyID::from_string("policy0"),
+                [],
+                ast::Effect::Permit,
+                PrincipalOrResourceConstraint::NoConstraint,
+                ActionConstraint::Eq(view.clone()),
+                PrincipalOrResourceConstraint::NoConstraint,
+                condition,
+            ));
+            let mut policyset = ast::PolicySet::new();
+            policyset.add_static(policy).unwrap();
+            let vschema =
+                ValidatorSchema::try_from(schema.clone()).expect("should be a valid schema");
+            assert!(Validator::new(vschema)
+                .validate(&policyset, ValidationMode::Strict)
+                .validation_passed());
+            // generated policies for `view` mostly use its context
+            if let Ok(policy) = schema.arbitrary_policy(&hierarchy, &mut u) {
+                let policy = ast::StaticPolicy::from(policy);
+                found |= policy
+                    .non_scope_constraints()
+                    .to_string()
+                    .contains("context.");
+            }
+        });
+        assert!(found, "no generated policy referenced the context");
+    }
+
+    #[test]
+    fn common_type_alias_chains() {
+        let mut rng = test_rng();
+        let mut aliases = 0;
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let Ok(schema) = Schema::arbitrary(TEST_SETTINGS, &mut u) else {
+                continue;
+            };
+            let Ok(nsdef) = schema.add_common_types(&mut u) else {
+                continue;
+            };
+            aliases += nsdef
+                .common_types
+                .values()
+                .filter(|ty| matches!(ty, json_schema::Type::CommonTypeRef { .. }))
+                .count();
+            // the validator resolves the aliases, wherever they are used
+            let fragment = json_schema::Fragment(
+                HashMap::from_iter([(schema.namespace.clone(), nsdef)]).into(),
+            );
+            ValidatorSchema::try_from(downgrade_frag_to_raw(fragment))
+                .expect("common types should resolve");
+        }
+        assert!(aliases > 0, "no common type aliases were generated");
+    }
+
+    #[test]
+    fn scope_constraint_forms() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut forms = HashSet::new();
+        for_each_schema(&fragment, &TEST_SETTINGS, |schema, mut u| {
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                return;
+            };
+            let Ok(policy) = schema.arbitrary_policy(&hierarchy, &mut u) else {
+                return;
+            };
+            for (constraint, types) in [
+                (policy.0.principal_constraint(), &schema.principal_types),
+                (policy.0.resource_constraint(), &schema.resource_types),
+            ] {
+                let uid = match constraint {
+                    PrincipalOrResourceConstraint::NoConstraint => {
+                        forms.insert("none");
+                        None
+                    }
+                    PrincipalOrResourceConstraint::Eq(uid) => {
+                        forms.insert("==");
+                        Some(uid)
+                    }
+                    PrincipalOrResourceConstraint::In(uid) => {
+                        forms.insert("in");
+                        Some(uid)
+                    }
+                    PrincipalOrResourceConstraint::IsType(_) => {
+                        forms.insert("is");
+                        None
+                    }
+                    PrincipalOrResourceConstraint::IsTypeIn(_, uid) => {
+                        forms.insert("is in");
+                        Some(uid)
+                    }
+                    _ => panic!("unexpected slot in {policy}"),
+                };
+                let any_existing = types
+                    .iter()
+                    .any(|ty| !hierarchy.uids_with_type(ty).is_empty());
+                if let Some(uid) = uid.filter(|_| any_existing) {
+                    assert!(
+                        hierarchy.uids().contains(uid),
+                        "{uid} doesn't exist in the hierarchy"
+                    );
+                }
+            }
+        });
+        assert_eq!(forms.len(), 5, "only generated {forms:?}");
+
+        // with weights, only the chosen forms are generated
+        let settings = ABACSettings::builder()
+            .with_scope_weights(ScopeWeights {
+                no_constraint: 0,
+                eq: 0,
+                is_in: 0,
+                is_type: 0,
+                is_type_in: 1,
+            })
+            .build();
+        let mut generated = 0;
+        for_each_schema(&fragment, &settings, |schema, mut u| {
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                return;
+            };
+            let Ok(policy) = schema.arbitrary_policy(&hierarchy, &mut u) else {
+                return;
+            };
+            assert!(matches!(
+                policy.0.principal_constraint(),
+                PrincipalOrResourceConstraint::IsTypeIn(..)
+            ));
+            assert!(matches!(
+                policy.0.resource_constraint(),
+                PrincipalOrResourceConstraint::IsTypeIn(..)
+            ));
+            generated += 1;
+        });
+        assert!(generated > 0, "no policies were generated with weights");
+    }
+
+    #[test]
+    fn overlapping_policies() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        for_each_schema(&fragment, &TEST_SETTINGS, |schema, mut u| {
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                return;
+            };
+            let Ok((permit, forbid)) = schema.arbitrary_overlapping_policies(&hierarchy, &mut u)
+            else {
+                return;
+            };
+            assert_eq!(
+                permit.0.principal_constraint(),
+                forbid.0.principal_constraint()
+            );
+            assert_eq!(permit.0.action_constraint(), forbid.0.action_constraint());
+            assert_eq!(
+                permit.0.resource_constraint(),
+                forbid.0.resource_constraint()
+            );
+            assert_eq!(permit.0.abac_constraints(), &ast::Expr::val(true));
+            let permit = ast::StaticPolicy::from(permit);
+            let forbid = ast::StaticPolicy::from(forbid);
+            assert_eq!(permit.effect(), ast::Effect::Permit);
+            assert_eq!(forbid.effect(), ast::Effect::Forbid);
+            assert_ne!(permit.id(), forbid.id());
+        });
+    }
+
+    #[test]
+    fn from_natural_source() {
+        let src = r#"
+            entity Team;
+            entity User in [Team] { name: String, age: Long };
+            entity Photo { owner: User, tags: Set<String> };
+            action view appliesTo {
+                principal: [User],
+                resource: [Photo],
+                context: { authenticated: Bool }
+            };
+        "#;
+        let mut rng = test_rng();
+        let mut found = false;
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let schema = Schema::arbitrary_from_natural_source(src, TEST_SETTINGS, &mut u)
+                .expect("should accept the schema");
+            assert_eq!(schema.entity_types.len(), 3);
+            assert_eq!(schema.actions_eids, vec![ast::Eid::new("view")]);
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                continue;
+            };
+            let Ok(policy) = schema.arbitrary_policy(&hierarchy, &mut u) else {
+                continue;
+            };
+            let Ok(request) = schema.arbitrary_request(&hierarchy, &mut u) else {
+                continue;
+            };
+            assert_eq!(request.0.principal.entity_type().to_string(), "User");
+            assert_eq!(request.0.resource.entity_type().to_string(), "Photo");
+            let _ = ast::StaticPolicy::from(policy);
+            found = true;
+        }
+        assert!(found, "no policies and requests were generated");
+
+        let mut u = Unstructured::new(&[0; RANDOM_BYTE_SIZE as usize]);
+        for bad in [
+            "entity User",
+            "namespace A { entity User; } namespace B { entity User; }",
+            "entity User { friend: Stranger };",
+        ] {
+            assert!(matches!(
+                Schema::arbitrary_from_natural_source(bad, TEST_SETTINGS, &mut u),
+                Err(Error::UnsupportedSchema(_))
+            ));
+        }
+        assert!(matches!(
+            Schema::arbitrary_from_natural_source("entity User;", TEST_SETTINGS, &mut u),
+            Err(Error::NoValidPrincipalOrResourceTypes)
+        ));
+    }
+
+    #[test]
+    fn unicode_strings() {
+        let settings = ABACSettings {
+            enable_unicode_strings: true,
+            ..TEST_SETTINGS
+        };
+        let mut rng = test_rng();
+        let mut non_ascii_policies = 0;
+        let mut non_ascii_uids = 0;
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let Ok(schema) = Schema::arbitrary(settings.clone(), &mut u) else {
+                continue;
+            };
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                continue;
+            };
+            non_ascii_uids += hierarchy
+                .uids()
+                .iter()
+                .filter(|uid| !AsRef::<str>::as_ref(uid.eid()).is_ascii())
+                .count();
+            let Ok(policy) = schema.arbitrary_policy(&hierarchy, &mut u) else {
+                continue;
+            };
+            let text = ast::StaticPolicy::from(policy).to_string();
+            if !text.is_ascii() {
+                non_ascii_policies += 1;
+            }
+            let parsed = cedar_policy_core::parser::parse_policy(None, &text)
+                .unwrap_or_else(|err| panic!("failed to parse policy: {err:?}\n{text}"));
+            assert_eq!(parsed.to_string(), text);
+        }
+        assert!(non_ascii_uids > 0, "no non-ASCII entity ids were generated");
+        assert!(
+            non_ascii_policies > 0,
+            "no policies with non-ASCII text were generated"
+        );
+
+        // Cedar doesn't normalize strings: the precomposed (NFC) and
+        // decomposed (NFD) forms of `é` are different strings, and each
+        // survives printing and parsing unchanged
+        let nfc = ast::Expr::val("\u{e9}");
+        let nfd = ast::Expr::val("e\u{301}");
+        assert_ne!(nfc, nfd);
+        for expr in [nfc, nfd] {
+            let parsed = cedar_policy_core::parser::parse_expr(&expr.to_string())
+                .expect("should parse the printed string literal");
+            assert_eq!(parsed, expr);
+        }
+    }
+
+    #[test]
+    fn schema_stats() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut rng = test_rng();
+        let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+        rng.fill_bytes(&mut bytes);
+        let mut u = Unstructured::new(&bytes);
+        let schema = Schema::from_raw_schemafrag(fragment, TEST_SETTINGS, &mut u)
+            .expect("failed to generate schema!");
+        assert_eq!(
+            schema.stats(),
+            SchemaStats {
+                num_actions: 11,
+                num_entity_types: 6,
+                num_common_types: 0,
+                max_entity_attrs: 5,
+                num_extension_attrs: 0,
+                action_group_depth: 0,
+            }
+        );
+
+        let settings = ABACSettings {
+            enable_extensions: true,
+            enable_action_groups_and_attrs: true,
+            ..TEST_SETTINGS
+        };
+        for _ in 0..ITERATION {
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let Ok(schema) = Schema::arbitrary(settings.clone(), &mut u) else {
+                continue;
+            };
+            let stats = schema.stats();
+            assert_eq!(stats.num_entity_types, schema.entity_types().len());
+            // action groups are acyclic
+            assert!(stats.action_group_depth < stats.num_actions.max(1));
+        }
+    }
+
+    #[test]
+    fn attr_chains() {
+        let mut rng = test_rng();
+        let mut found = false;
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let Ok(schema) = Schema::arbitrary(TEST_SETTINGS, &mut u) else {
+                continue;
+            };
+            let guards_data = u.take_rest();
+            // the guards don't consume any randomness, so given the same
+            // data, the guarded chain is the unguarded one behind the guards
+            let Ok(mut guarded) = schema.exprgenerator(None).generate_attr_chain_expr(
+                true,
+                2,
+                &mut Unstructured::new(guards_data),
+            ) else {
+                continue;
+            };
+            let unguarded = schema
+                .exprgenerator(None)
+                .generate_attr_chain_expr(false, 2, &mut Unstructured::new(guards_data))
+                .expect("the unguarded chain should succeed if the guarded one does");
+            while let ast::ExprKind::And { left, right } = guarded.expr_kind() {
+                assert!(
+                    matches!(left.expr_kind(), ast::ExprKind::HasAttr { .. }),
+                    "expected a `has` guard, got {left}"
+                );
+                found = true;
+                guarded = right.as_ref().clone();
+            }
+            assert!(
+                guarded.eq_shape(&unguarded),
+                "{guarded} should be {unguarded}"
+            );
+        }
+        assert!(found, "no `has` guards were generated");
+    }
+
+    #[test]
+    fn entity_typed_context_attributes() {
+        let mut rng = test_rng();
+        let mut found = false;
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let Ok(schema) = Schema::arbitrary(TEST_SETTINGS, &mut u) else {
+                continue;
+            };
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                continue;
+            };
+            let Ok(request) = schema.arbitrary_request(&hierarchy, &mut u) else {
+                continue;
+            };
+            let attrs = schema.context_entity_attributes(Some(&request.action));
+            for (etype, _) in &attrs {
+                // every referenced entity type is declared
+                assert!(schema
+                    .entity_types
+                    .iter()
+                    .any(|declared| &declared.clone().qualify_with(schema.namespace()) == etype));
+            }
+            // the request supplies an entity of the declared type
+            found |= request
+                .context
+                .clone()
+                .into_iter()
+                .any(|(attr, val)| match val {
+                    ast::PartialValue::Value(v) => v.get_as_entity().is_ok_and(|uid| {
+                        attrs.contains(&(uid.entity_type().clone(), attr.clone()))
+                    }),
+                    _ => false,
+                });
+        }
+        assert!(
+            found,
+            "no request supplied an entity-typed context attribute"
+        );
+    }
+
+    #[test]
+    fn union_principal_attr_policies() {
+        let fragment = json_schema::Fragment::from_json_str(
+            r#"{
+                "": {
+                    "entityTypes": {
+                        "User": {
+                            "shape": {
+                                "type": "Record",
+                                "attributes": { "name": { "type": "String" } }
+                            }
+                        },
+                        "Robot": {},
+                        "Photo": {}
+                    },
+                    "actions": {
+                        "view": {
+                            "appliesTo": {
+                                "principalTypes": ["User", "Robot"],
+                                "resourceTypes": ["Photo"]
+                            }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .expect("schema str should be valid!");
+        for_each_schema(&fragment, &TEST_SETTINGS, |schema, mut u| {
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                return;
+            };
+            let Ok(policy) = schema.arbitrary_union_principal_attr_policy(&hierarchy, &mut u)
+            else {
+                return;
+            };
+            let policy: ast::StaticPolicy = policy.into();
+            assert!(policy.to_string().contains("name"));
+            let mut policies = ast::PolicySet::new();
+            policies.add_static(policy).unwrap();
+            let vschema =
+                ValidatorSchema::try_from(schema.clone()).expect("should be a valid schema");
+            assert!(!Validator::new(vschema)
+                .validate(&policies, ValidationMode::Strict)
+                .validation_passed());
+        });
+    }
+
+    #[test]
+    fn incompatible_ite_policies() {
+        let settings = ABACSettings {
+            enable_extensions: true,
+            ..TEST_SETTINGS
+        };
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        for_each_schema(&fragment, &settings, |schema, mut u| {
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                return;
+            };
+            let Ok(policy) = schema.arbitrary_incompatible_ite_policy(&hierarchy, &mut u) else {
+                return;
+            };
+            let mut policies = ast::PolicySet::new();
+            policies.add_static(policy.into()).unwrap();
+            let vschema =
+                ValidatorSchema::try_from(schema.clone()).expect("should be a valid schema");
+            assert!(!Validator::new(vschema)
+                .validate(&policies, ValidationMode::Strict)
+                .validation_passed());
+        });
+
+        // gated on extensions
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut rng = test_rng();
+        let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+        rng.fill_bytes(&mut bytes);
+        let mut u = Unstructured::new(&bytes);
+        let schema = Schema::from_raw_schemafrag(fragment, TEST_SETTINGS, &mut u)
+            .expect("failed to generate schema!");
+        if let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) {
+            assert!(matches!(
+                schema.arbitrary_incompatible_ite_policy(&hierarchy, &mut u),
+                Err(Error::ExtensionsDisabled)
+            ));
+        }
+    }
+
+    #[test]
+    fn template_links() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        for_each_schema(&fragment, &TEST_SETTINGS, |schema, mut u| {
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                return;
+            };
+            let Ok(template) =
+                schema.arbitrary_template(ast::PolicyID::from_string("t"), &hierarchy, &mut u)
+            else {
+                return;
+            };
+            assert!(template.has_slots());
+            let Ok(link) = schema.arbitrary_linked_policy(
+                ast::PolicyID::from_string("t_l0"),
+                &template,
+                &hierarchy,
+                &mut u,
+            ) else {
+                return;
+            };
+            let mut policyset = ast::PolicySet::new();
+            template.0.add_to_policyset(&mut policyset);
+            link.add_to_policyset(&mut policyset);
+            let linked = policyset
+                .get(&ast::PolicyID::from_string("t_l0"))
+                .expect("link should be in the policy set");
+            assert!(!linked.env().is_empty());
+            for uid in linked.env().values() {
+                assert!(
+                    hierarchy.entity(uid).is_some(),
+                    "{uid} isn't in the hierarchy"
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn action_attrs() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        for_each_schema(&fragment, &TEST_SETTINGS, |schema, mut u| {
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                return;
+            };
+            let Ok(actions) = schema.arbitrary_action_entities(&mut u) else {
+                return;
+            };
+            assert_eq!(actions.len(), schema.actions_eids.len());
+            for action in &actions {
+                assert!(schema.actions_eids.contains(action.uid().eid()));
+                for (attr, _) in action.attrs() {
+                    assert!(ACTION_ATTR_NAMES.contains(&attr.as_str()));
+                }
+            }
+            let Ok(policy) = schema.arbitrary_action_attr_policy(&hierarchy, &mut u) else {
+                return;
+            };
+            let policy: ast::StaticPolicy = policy.into();
+            assert!(policy.condition().subexpressions().any(
+                |e| matches!(e.expr_kind(), ast::ExprKind::GetAttr { expr, attr }
+                    if matches!(expr.expr_kind(), ast::ExprKind::Var(ast::Var::Action))
+                        && ACTION_ATTR_NAMES.contains(&attr.as_str()))
+            ));
+        });
+
+        // gated on action groups and attributes
+        let settings = ABACSettings {
+            enable_action_groups_and_attrs: false,
+            ..TEST_SETTINGS
+        };
+        let mut rng = test_rng();
+        let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+        rng.fill_bytes(&mut bytes);
+        let mut u = Unstructured::new(&bytes);
+        let schema = Schema::from_raw_schemafrag(fragment, settings, &mut u)
+            .expect("failed to generate schema!");
+        assert!(matches!(
+            schema.arbitrary_action_entities(&mut u),
+            Err(Error::ActionAttrsDisabled)
+        ));
+    }
+
+    #[test]
+    fn boundary_number_context_json() {
+        let attrs = ["x".into(), "y \"quoted\"".into()];
+        let mut rng = test_rng();
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let json = arbitrary_boundary_number_context_json(&attrs, &mut u)
+                .expect("failed to generate context JSON!");
+            let record: serde_json::Map<String, serde_json::Value> =
+                serde_json::from_str(&json).expect("should be a JSON record");
+            assert_eq!(record.len(), attrs.len());
+            for attr in &attrs {
+                assert!(record[attr.as_str()].is_number());
+            }
+        }
+    }
+
+    /// Depth of a schema type, counting each enclosing `Set` or `Record`
+    fn schematype_depth(ty: &json_schema::Type<RawName>) -> usize {
+        match ty {
+            json_schema::Type::Type(json_schema::TypeVariant::Set { element }) => {
+                1 + schematype_depth(element)
+            }
+            json_schema::Type::Type(json_schema::TypeVariant::Record(record)) => {
+                1 + record
+                    .attributes
+                    .values()
+                    .map(|attr| schematype_depth(&attr.ty))
+                    .max()
+                    .unwrap_or(0)
+            }
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn nested_schematypes_respect_max_depth() {
+        let mut rng = test_rng();
+        for max_depth in 0..4 {
+            let settings = ABACSettings {
+                max_depth,
+                record_set_nesting_weight: 100,
+                ..TEST_SETTINGS
+            };
+            let entity_types = [ast::EntityType::from_normalized_str("E").unwrap()];
+            let mut deepest = 0;
+            for _ in 0..ITERATION {
+                let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+                rng.fill_bytes(&mut bytes);
+                let mut u = Unstructured::new(&bytes);
+                if let Ok(ty) = arbitrary_schematype_with_bounded_depth::<RawName>(
+                    &settings,
+                    &entity_types,
+                    max_depth,
+                    &mut u,
+                ) {
+                    // at `max_depth` 0 we may still generate `Set<Long>` or an
+                    // empty record, which contain no further nesting
+                    let depth = schematype_depth(&ty);
+                    assert!(depth <= max_depth + 1, "{ty:?} exceeds depth {max_depth}");
+                    deepest = deepest.max(depth);
+                }
+            }
+            assert!(
+                deepest > max_depth,
+                "never generated a type nested {max_depth} deep"
+            );
+        }
+    }
+
+    #[test]
+    fn undeclared_actions_in_action_lists() {
+        let settings = ABACSettings {
+            enable_undeclared_actions: true,
+            enable_is_operator: true,
+            ..TEST_SETTINGS
+        };
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut found = false;
+        for_each_schema(&fragment, &settings, |schema, mut u| {
+            let Ok(ActionConstraint::InList(uids)) =
+                schema.arbitrary_action_constraint(&mut u, Some(3))
+            else {
+                return;
+            };
+            let undeclared = uids
+                .iter()
+                .filter(|uid| !schema.actions_eids.contains(uid.eid()))
+                .count();
+            // at most one undeclared action, and only alongside declared ones
+            assert!(undeclared <= 1);
+            if undeclared == 1 {
+                assert!(uids.len() >= 2);
+                found = true;
+            }
+        });
+        assert!(found, "no undeclared actions were generated");
+    }
+
+    #[test]
+    fn shared_common_types() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        for_each_schema(&fragment, &TEST_SETTINGS, |schema, mut u| {
+            let Ok((schema, usage)) = schema.with_shared_common_type(&mut u) else {
+                return;
+            };
+            assert!(schema.schema.common_types.contains_key(&usage.common_type));
+            let schemafile = schema.schemafile_string();
+            assert!(schemafile.contains(usage.context_attr.as_str()));
+            let vschema =
+                ValidatorSchema::try_from(schema.clone()).expect("should be a valid schema");
+            let mut policies = ast::PolicySet::new();
+            policies
+                .add_static(usage.policy(ast::PolicyID::from_string("shared")).into())
+                .unwrap();
+            assert!(Validator::new(vschema)
+                .validate(&policies, ValidationMode::Strict)
+                .validation_passed());
+        });
+    }
+
+    #[test]
+    fn applies_to_mismatched_requests() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut rng = test_rng();
+        for mismatch in [AppliesToMismatch::Principal, AppliesToMismatch::Resource] {
+            let mut generated = 0;
+            for _ in 0..ITERATION {
+                let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+                rng.fill_bytes(&mut bytes);
+                let mut u = Unstructured::new(&bytes);
+                let schema = Schema::from_raw_schemafrag(fragment.clone(), TEST_SETTINGS, &mut u)
+                    .expect("failed to generate schema!");
+                let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                    continue;
+                };
+                let Ok(request) =
+                    schema.arbitrary_applies_to_mismatched_request(&hierarchy, mismatch, &mut u)
+                else {
+                    continue;
+                };
+                let vschema =
+                    ValidatorSchema::try_from(schema.clone()).expect("should be a valid schema");
+                let request = request.0;
+                assert!(
+                    ast::Request::new(
+                        (request.principal, None),
+                        (request.action, None),
+                        (request.resource, None),
+                        request.context,
+                        Some(&vschema),
+                        Extensions::all_available(),
+                    )
+                    .is_err(),
+                    "request validation should fail for a {mismatch:?} mismatch"
+                );
+                generated += 1;
+            }
+            assert!(generated > 0, "no {mismatch:?} mismatches were generated");
+        }
+    }
+
+    #[test]
+    fn applies_to_mismatched_requests_namespaced() {
+        // the applies-to types are unqualified in the schema file, but the
+        // entity types they're compared against are qualified
+        let src = r#"
+            namespace Photos {
+                entity User;
+                entity Album;
+                entity Photo in [Album];
+                action view appliesTo { principal: [User], resource: [Photo] };
+            }
+        "#;
+        let mut rng = test_rng();
+        for mismatch in [AppliesToMismatch::Principal, AppliesToMismatch::Resource] {
+            let mut generated = 0;
+            for _ in 0..ITERATION {
+                let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+                rng.fill_bytes(&mut bytes);
+                let mut u = Unstructured::new(&bytes);
+                let schema = Schema::arbitrary_from_natural_source(src, TEST_SETTINGS, &mut u)
+                    .expect("should accept the schema");
+                let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                    continue;
+                };
+                let Ok(request) =
+                    schema.arbitrary_applies_to_mismatched_request(&hierarchy, mismatch, &mut u)
+                else {
+                    continue;
+                };
+                let request = request.0;
+                let (mismatched, expected) = match mismatch {
+                    AppliesToMismatch::Principal => (&request.principal, "Photos::User"),
+                    AppliesToMismatch::Resource => (&request.resource, "Photos::Photo"),
+                };
+                assert_ne!(
+                    mismatched.entity_type().to_string(),
+                    expected,
+                    "{mismatched} is in the applies-to of `view`"
+                );
+                generated += 1;
+            }
+            assert!(generated > 0, "no {mismatch:?} mismatches were generated");
+        }
+    }
+
+    #[test]
+    fn invalid_context_requests() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut rng = test_rng();
+        for violation in [
+            ContextViolation::MissingAttribute,
+            ContextViolation::WrongType,
+        ] {
+            let mut generated = 0;
+            for _ in 0..ITERATION {
+                let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+                rng.fill_bytes(&mut bytes);
+                let mut u = Unstructured::new(&bytes);
+                let schema = Schema::from_raw_schemafrag(fragment.clone(), TEST_SETTINGS, &mut u)
+                    .expect("failed to generate schema!");
+                let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                    continue;
+                };
+                let Ok(request) =
+                    schema.arbitrary_invalid_context_request(&hierarchy, violation, &mut u)
+                else {
+                    continue;
+                };
+                let vschema =
+                    ValidatorSchema::try_from(schema.clone()).expect("should be a valid schema");
+                let request = request.0;
+                assert!(
+                    ast::Request::new(
+                        (request.principal, None),
+                        (request.action, None),
+                        (request.resource, None),
+                        request.context,
+                        Some(&vschema),
+                        Extensions::all_available(),
+                    )
+                    .is_err(),
+                    "request validation should fail for a {violation:?} violation"
+                );
+                generated += 1;
+            }
+            assert!(generated > 0, "no {violation:?} violations were generated");
+        }
+    }
+
+    #[test]
+    fn contexts_conform_to_action_context_type() {
+        let fragment = json_schema::Fragment::from_json_str(
+            r#"
+            {
+                "": {
+                    "entityTypes": { "User": {}, "Photo": {} },
+                    "actions": {
+                        "view": {
+                            "appliesTo": {
+                                "principalTypes": ["User"],
+                                "resourceTypes": ["Photo"],
+                                "context": {
+                                    "type": "Record",
+                                    "attributes": {
+                                        "maybeNested": {
+                                            "type": "Record",
+                                            "required": false,
+                                            "attributes": {
+                                                "depth": { "type": "Long" },
+                                                "label": { "type": "String", "required": false }
+                                            }
+                                        },
+                                        "labels": {
+                                            "type": "Set",
+                                            "element": { "type": "String" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .expect("schema str should be valid!");
+        let (mut with_optional, mut without_optional) = (false, false);
+        for_each_schema(&fragment, &TEST_SETTINGS, |schema, mut u| {
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                return;
+            };
+            let Ok(request) = schema.arbitrary_request(&hierarchy, &mut u) else {
+                return;
+            };
+            let vschema =
+                ValidatorSchema::try_from(schema.clone()).expect("should be a valid schema");
+            let request = request.0;
+            if request.context.to_string().contains("maybeNested") {
+                with_optional = true;
+            } else {
+                without_optional = true;
+            }
+            let res = ast::Request::new(
+                (request.principal, None),
+                (request.action, None),
+                (request.resource, None),
+                request.context,
+                Some(&vschema),
+                Extensions::all_available(),
+            );
+            assert!(res.is_ok(), "context doesn't conform: {res:?}");
+        });
+        assert!(
+            with_optional,
+            "optional context attribute was never included"
+        );
+        assert!(
+            without_optional,
+            "optional context attribute was never omitted"
+        );
+    }
+
+    #[test]
+    fn like_patterns_exercise_escaping() {
+        use crate::abac::ConstantPool;
+        let mut rng = test_rng();
+        let (mut literal_star, mut wildcard, mut backslash) = (false, false, false);
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let pool: ConstantPool = u.arbitrary().expect("failed to generate constant pool");
+            for _ in 0..10 {
+                let Ok(pattern) = pool.arbitrary_pattern_literal(&mut u) else {
+                    continue;
+                };
+                for elem in pattern {
+                    match elem {
+                        ast::PatternElem::Char('*') => literal_star = true,
+                        ast::PatternElem::Char('\\') => backslash = true,
+                        ast::PatternElem::Wildcard => wildcard = true,
+                        ast::PatternElem::Char(_) => (),
+                    }
+                }
+            }
+        }
+        assert!(literal_star, "no pattern had an escaped `*`");
+        assert!(backslash, "no pattern had a `\\`");
+        assert!(wildcard, "no pattern had a wildcard");
+    }
+
+    #[test]
+    fn valid_requests() {
+        let fragment = json_schema::Fragment::from_json_file(GITHUB_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let mut generated = 0;
+        for_each_schema(&fragment, &TEST_SETTINGS, |schema, mut u| {
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                return;
+            };
+            let Ok(request) = schema.arbitrary_valid_request(&hierarchy, &mut u) else {
+                return;
+            };
+            let vschema =
+                ValidatorSchema::try_from(schema.clone()).expect("should be a valid schema");
+            let request = request.0;
+            assert!(hierarchy.entity(&request.principal).is_some());
+            assert!(hierarchy.entity(&request.resource).is_some());
+            let res = ast::Request::new(
+                (request.principal, None),
+                (request.action, None),
+                (request.resource, None),
+                request.context,
+                Some(&vschema),
+                Extensions::all_available(),
+            );
+            assert!(res.is_ok(), "request validation failed: {res:?}");
+            generated += 1;
+        });
+        assert!(generated > 0, "no valid requests were generated");
+    }
+
+    #[test]
+    fn ownership_patterns() {
+        let settings = ABACSettings {
+            enable_ownership_patterns: true,
+            ..TEST_SETTINGS
+        };
+        let fragment = json_schema::Fragment::from_json_file(DOCUMENT_CLOUD_SCHEMA_STR.as_bytes())
+            .expect("schema str should be valid!");
+        let (mut owner, mut non_owner) = (false, false);
+        for_each_schema(&fragment, &settings, |schema, mut u| {
+            assert!(!schema.ownership_attributes().is_empty());
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                return;
+            };
+            let expr = schema
+                .exprgenerator(Some(&hierarchy))
+                .generate_ownership_expr(&mut u)
+                .expect("should generate an ownership pattern");
+            // the access is guarded by the type of the resource
+            assert!(expr.to_string().contains("resource is "), "{expr}");
+            assert!(expr.to_string().contains("principal"));
+            let Ok(request) = schema.arbitrary_request(&hierarchy, &mut u) else {
+                return;
+            };
+            let is_owner = schema
+                .ownership_attributes()
+                .into_iter()
+                .filter_map(
+                    |(_, attr)| match hierarchy.entity(&request.resource)?.get(&attr)? {
+                        ast::PartialValue::Value(v) => v.get_as_entity().ok().cloned(),
+                        ast::PartialValue::Residual(_) => None,
+                    },
+                )
+                .any(|o| o == request.principal);
+            if is_owner {
+                owner = true;
+            } else {
+                non_owner = true;
+            }
+        });
+        assert!(owner, "no requests where the principal owns the resource");
+        assert!(
+            non_owner,
+            "no requests where the principal doesn't own the resource"
+        );
+    }
+
+    #[test]
+    fn deep_namespaces() {
+        let mut rng = test_rng();
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            if let Ok(name) = super::arbitrary_deep_namespace(64, &mut u) {
+                let segments = name.to_string().split("::").count();
+                assert!((1..=64).contains(&segments));
+                let reparsed: ast::Name = name.to_string().parse().unwrap();
+                assert_eq!(reparsed, name);
+            }
+        }
+    }
+
+    #[test]
+    fn recursive_attributes() {
+        use cedar_policy_core::ast::PartialValue;
+        let mut rng = test_rng();
+        let mut found = false;
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let Ok(schema) = Schema::arbitrary(TEST_SETTINGS, &mut u) else {
+                continue;
+            };
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                continue;
+            };
+            for (etype, attr) in schema.recursive_attributes() {
+                found = true;
+                for entity in hierarchy
+                    .entities()
+                    .filter(|e| e.uid().entity_type() == &etype)
+                {
+                    // following `attr` should stay within `etype` and terminate
+                    let mut current = entity.uid().clone();
+                    let mut steps = 0;
+                    while let Some(PartialValue::Value(v)) =
+                        hierarchy.entity(&current).and_then(|e| e.get(&attr))
+                    {
+                        let next = v
+                            .get_as_entity()
+                            .expect("recursive attribute should be an entity reference");
+                        assert_eq!(next.entity_type(), &etype);
+                        current = next.clone();
+                        steps += 1;
+                        assert!(
+                            steps <= hierarchy.num_entities(),
+                            "cycle through recursive attribute `{attr}` of `{etype}`"
+                        );
+                    }
+                }
+            }
+        }
+        assert!(found, "no recursive attributes were generated");
+    }
+
+    #[test]
+    fn nested_bool_generation() {
+        use cedar_policy_core::ast::{Expr, ExprKind};
+        fn nesting(e: &Expr) -> usize {
+            match e.expr_kind() {
+                ExprKind::And { left, right } | ExprKind::Or { left, right } => {
+                    1 + nesting(left).max(nesting(right))
+                }
+                _ => 0,
+            }
+        }
+        const NESTING: usize = 16;
+        let mut rng = test_rng();
+        let mut found = false;
+        for _ in 0..ITERATION {
+            let mut bytes = [0; RANDOM_BYTE_SIZE as usize];
+            rng.fill_bytes(&mut bytes);
+            let mut u = Unstructured::new(&bytes);
+            let Ok(schema) = Schema::arbitrary(TEST_SETTINGS, &mut u) else {
+                continue;
+            };
+            let Ok(hierarchy) = schema.arbitrary_hierarchy(&mut u) else {
+                continue;
+            };
+            if let Ok(expr) = schema
+                .exprgenerator(Some(&hierarchy))
+                .generate_nested_bool_expr(NESTING, 1, &mut u)
+            {
+                assert!(
+                    nesting(&expr) >= NESTING,
+                    "not nested deeply enough: {expr}"
+                );
+                found = true;
+            }
+        }
+        assert!(found, "no nested boolean expressions were generated");
+    }
+
     fn generate_hierarchy_from_schema(
         rng: &mut ThreadRng,
         fragment: json_schema::Fragment<RawName>,
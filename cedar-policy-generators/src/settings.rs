@@ -14,9 +14,44 @@
  * limitations under the License.
  */
 
+use crate::policy::ScopeWeights;
+use std::sync::OnceLock;
+
 /// Maximum length of a pattern string
 pub const MAX_PATTERN_LEN: usize = 6;
 
+/// Environment variable overriding [`DEFAULT_MAX_AST_NODES`]
+pub const MAX_AST_NODES_ENV_VAR: &str = "MAX_AST_NODES";
+
+/// Default maximum number of AST nodes in the conditions of a generated policy
+pub const DEFAULT_MAX_AST_NODES: usize = 10_000;
+
+/// Maximum number of AST nodes in the conditions of a generated policy.
+/// Generation aborts with [`crate::err::Error::TooLarge`] once this is
+/// exceeded, so that adversarial inputs can't make the fuzzer run out of
+/// memory. Set by [`MAX_AST_NODES_ENV_VAR`], defaulting to
+/// [`DEFAULT_MAX_AST_NODES`]. The variable is read once, on first use.
+pub fn max_ast_nodes() -> usize {
+    static MAX_AST_NODES: OnceLock<usize> = OnceLock::new();
+    *MAX_AST_NODES.get_or_init(|| {
+        std::env::var(MAX_AST_NODES_ENV_VAR)
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(DEFAULT_MAX_AST_NODES)
+    })
+}
+
+/// Relative weights of operators in generated expressions, as a map from
+/// operator name to weight. Operators are named as in the JSON policy format,
+/// e.g., `"containsAll"`, `"&&"`, `"."` (attribute access), or `"has"`.
+/// Operators missing from the map keep the generator's default weight, so the
+/// empty map `&[]` leaves the distribution unchanged. A weight of `0`
+/// suppresses an operator entirely, except for the comparisons `==` between
+/// expressions of different types, whose weight is fixed. If the weights of
+/// all the alternatives at some point of generation are `0`, generation there
+/// fails with an `EmptyChoose` error.
+pub type OperatorWeights = &'static [(&'static str, u8)];
+
 /// Settings controlling the generation of ABAC hierarchies/policies/requests
 #[derive(Debug, Clone)]
 pub struct ABACSettings {
@@ -30,6 +65,15 @@ pub struct ABACSettings {
     /// If true, may generate extension function calls in policies and/or
     /// attribute values.
     pub enable_extensions: bool,
+    /// If true (and `enable_extensions` is true), also generate dedicated
+    /// `ipaddr` expressions: chains of `isIpv4()`, `isInRange()`, etc. on
+    /// `ip(..)` literals, including boundary CIDR prefixes and IPv4 addresses
+    /// embedded in IPv6.
+    pub enable_ipaddr: bool,
+    /// If true (and `enable_extensions` is true), sometimes call extension
+    /// constructors with arguments they reject, e.g., `decimal("1.23456")` or
+    /// `ip("999.999.999.999")`, so that evaluating the call errors.
+    pub enable_invalid_extension_args: bool,
     /// Maximum depth of an expression or type. E.g., maximum nesting of sets.
     ///
     /// This is used in the following places:
@@ -43,7 +87,6 @@ pub struct ABACSettings {
     /// - Maximum depth of expressions in attribute values in the hierarchy,
     ///     and also of attributes of `context` in each request
     /// - Maximum depth of a type specified in a generated schema
-    /// - Maximum number of when/unless clauses in a policy
     pub max_depth: usize,
     /// Maximum width of an expression or type. E.g., maximum number of elements
     /// in a set.
@@ -59,6 +102,14 @@ pub struct ABACSettings {
     /// - Maximum number of "additional attributes" on any entity in the
     ///     hierarchy
     pub max_width: usize,
+    /// Maximum number of `when`/`unless` clauses in a generated policy. Each
+    /// policy gets between 1 and `max_conditions` clauses (0 if this is 0),
+    /// each of which is either a `when` or an `unless`.
+    pub max_conditions: usize,
+    /// Maximum number of namespaces in a schema fragment generated by
+    /// `Schema::arbitrary_fragment()`. A `Schema` itself always has a single
+    /// namespace.
+    pub max_namespaces: usize,
     /// Whether to enable the `additional_attributes` flag in generated schemas.
     /// If this option is `false`, `additional_attributes` will always be false
     /// in all generated schemas.
@@ -68,9 +119,20 @@ pub struct ABACSettings {
     /// `additional_attributes` in the schema: it may add additional attributes,
     /// but only if `additional_attributes` is true.
     pub enable_additional_attributes: bool,
+    /// Whether generated entities may carry attributes that the schema doesn't
+    /// declare, even for entity types whose `additional_attributes` is false.
+    /// Such entities don't conform to the schema; this is for testing how
+    /// undeclared attributes are handled.
+    pub enable_undeclared_entity_attrs: bool,
     /// Flag to globally enable/disable generation of expressions containing the
     /// `like` operator.
     pub enable_like: bool,
+    /// If true, string literals, entity ids, and attribute names are sometimes
+    /// drawn from multi-byte characters, combining characters, and codepoints
+    /// adjacent to the surrogate range, to exercise printing and parsing of
+    /// non-ASCII text. Identifiers (e.g., entity type names) stay ASCII, as
+    /// Cedar requires.
+    pub enable_unicode_strings: bool,
     /// Flag to enable/disable generating actions in groups and declaring
     /// attributes on entity types.
     pub enable_action_groups_and_attrs: bool,
@@ -89,4 +151,334 @@ pub struct ABACSettings {
 
     /// Flag to enable/disable action constraints in forms of `in` operations
     pub enable_action_in_constraints: bool,
+
+    /// Flag to bias generation towards the ownership pattern
+    /// `resource.owner == principal`, where `owner` is an entity-typed
+    /// attribute of a resource type. When enabled, generated requests also
+    /// sometimes pick the resource's owner as the principal, so that both the
+    /// owner and non-owner cases are covered.
+    pub enable_ownership_patterns: bool,
+
+    /// Flag to enable/disable generating `action in [...]` constraints that
+    /// mix declared actions with an action not declared in the schema, which
+    /// validation should reject
+    pub enable_undeclared_actions: bool,
+
+    /// Flag to enable/disable generating `is` entity-type tests, e.g.,
+    /// `principal is User` and `principal is User in Group::"admins"`
+    pub enable_is_operator: bool,
+
+    /// Flag to enable/disable generating accesses to (possibly nested)
+    /// attributes where every optional attribute on the path is guarded by
+    /// `has`, e.g., `principal has a && principal.a has b && principal.a.b == 3`.
+    /// These accesses can't error because an attribute is missing.
+    pub enable_guarded_attr_chains: bool,
+
+    /// Flag to enable/disable generating the same accesses as
+    /// `enable_guarded_attr_chains`, but without the `has` guards, so that they
+    /// error whenever an optional attribute on the path is missing
+    pub enable_unguarded_attr_chains: bool,
+
+    /// Weight biasing generated schema types towards nested `Set` and `Record`
+    /// types, so that attribute access chains like `resource.a.b.c` are
+    /// exercised more often. While `max_depth` still allows nesting, a `Set` or
+    /// `Record` is chosen with probability `w / (w + 6)`, and otherwise all
+    /// type variants are chosen uniformly. `0` disables the bias.
+    /// Types never exceed `max_depth` regardless of this weight.
+    pub record_set_nesting_weight: u32,
+
+    /// Weight of `if-then-else` among the expressions generated for a type, in
+    /// place of the default weights. The condition is always a `Bool` and,
+    /// with `match_types`, both branches have the target type, so raising this
+    /// exercises the short-circuiting of errors in the untaken branch.
+    /// `0` keeps the default weights.
+    pub conditional_weight: u8,
+
+    /// Flag to bias the conditions of policies for a single action (i.e.,
+    /// with an `action == ..` constraint) towards conditions over attributes
+    /// of that action's `context`, e.g., `context.count == 3`. These
+    /// conditions type-check against the context type the action declares.
+    pub prefer_context_conditions: bool,
+
+    /// Weights of operators in generated expressions, overriding the default
+    /// weights, e.g., `&[("containsAny", 6), ("like", 0)]`. See
+    /// [`OperatorWeights`].
+    pub operator_weights: OperatorWeights,
+    /// Weights of the forms of principal and resource constraints (`==`,
+    /// `in`, `is`, `is .. in`, or none). If `None`, use
+    /// [`ScopeWeights::DEFAULT`].
+    pub scope_weights: Option<ScopeWeights>,
+}
+
+impl ABACSettings {
+    /// Get a builder for `ABACSettings`, starting from the configuration most
+    /// fuzz targets use: well-typed generation with extensions, `like`, `is`,
+    /// action groups and attributes, and `action in` constraints, a
+    /// `max_depth`, `max_width`, and `max_conditions` of 3, and a single
+    /// namespace. Everything else is disabled.
+    ///
+    /// The builder is usable in `const` items, so a fuzz target can write
+    /// `const SETTINGS: ABACSettings = ABACSettings::builder().with_max_depth(7).build();`
+    /// and keep compiling when new settings are added.
+    pub const fn builder() -> ABACSettingsBuilder {
+        ABACSettingsBuilder(ABACSettings {
+            match_types: true,
+            enable_extensions: true,
+            enable_ipaddr: false,
+            enable_invalid_extension_args: false,
+            max_depth: 3,
+            max_width: 3,
+            max_conditions: 3,
+            max_namespaces: 1,
+            enable_additional_attributes: false,
+            enable_undeclared_entity_attrs: false,
+            enable_like: true,
+            enable_unicode_strings: false,
+            enable_action_groups_and_attrs: true,
+            enable_arbitrary_func_call: true,
+            enable_unknowns: false,
+            enable_unspecified_apply_spec: true,
+            enable_action_in_constraints: true,
+            enable_ownership_patterns: false,
+            enable_undeclared_actions: false,
+            enable_is_operator: true,
+            enable_guarded_attr_chains: false,
+            enable_unguarded_attr_chains: false,
+            record_set_nesting_weight: 0,
+            conditional_weight: 0,
+            prefer_context_conditions: false,
+            operator_weights: &[],
+            scope_weights: None,
+        })
+    }
+}
+
+/// Builder for [`ABACSettings`], see [`ABACSettings::builder()`]
+#[derive(Debug, Clone)]
+pub struct ABACSettingsBuilder(ABACSettings);
+
+impl ABACSettingsBuilder {
+    /// Set whether to generate well-typed hierarchies/policies/requests
+    pub const fn with_match_types(self, match_types: bool) -> Self {
+        Self(ABACSettings {
+            match_types,
+            ..self.0
+        })
+    }
+
+    /// Set whether to generate extension function calls
+    pub const fn with_extensions(self, enable_extensions: bool) -> Self {
+        Self(ABACSettings {
+            enable_extensions,
+            ..self.0
+        })
+    }
+
+    /// Set whether to generate dedicated `ipaddr` expressions
+    pub const fn with_ipaddr(self, enable_ipaddr: bool) -> Self {
+        Self(ABACSettings {
+            enable_ipaddr,
+            ..self.0
+        })
+    }
+
+    /// Set whether to generate extension constructor calls with invalid
+    /// arguments
+    pub const fn with_invalid_extension_args(self, enable_invalid_extension_args: bool) -> Self {
+        Self(ABACSettings {
+            enable_invalid_extension_args,
+            ..self.0
+        })
+    }
+
+    /// Set the maximum depth of an expression or type
+    pub const fn with_max_depth(self, max_depth: usize) -> Self {
+        Self(ABACSettings {
+            max_depth,
+            ..self.0
+        })
+    }
+
+    /// Set the maximum width of an expression or type
+    pub const fn with_max_width(self, max_width: usize) -> Self {
+        Self(ABACSettings {
+            max_width,
+            ..self.0
+        })
+    }
+
+    /// Set the maximum number of `when`/`unless` clauses in a policy
+    pub const fn with_max_conditions(self, max_conditions: usize) -> Self {
+        Self(ABACSettings {
+            max_conditions,
+            ..self.0
+        })
+    }
+
+    /// Set the maximum number of namespaces in a generated schema fragment
+    pub const fn with_max_namespaces(self, max_namespaces: usize) -> Self {
+        Self(ABACSettings {
+            max_namespaces,
+            ..self.0
+        })
+    }
+
+    /// Set whether generated schemas may have `additional_attributes`
+    pub const fn with_additional_attributes(self, enable_additional_attributes: bool) -> Self {
+        Self(ABACSettings {
+            enable_additional_attributes,
+            ..self.0
+        })
+    }
+
+    /// Set whether generated entities may have attributes not declared in the
+    /// schema
+    pub const fn with_undeclared_entity_attrs(self, enable_undeclared_entity_attrs: bool) -> Self {
+        Self(ABACSettings {
+            enable_undeclared_entity_attrs,
+            ..self.0
+        })
+    }
+
+    /// Set whether to generate `like` expressions
+    pub const fn with_like(self, enable_like: bool) -> Self {
+        Self(ABACSettings {
+            enable_like,
+            ..self.0
+        })
+    }
+
+    /// Set whether to generate Unicode-heavy strings, entity ids, and
+    /// attribute names
+    pub const fn with_unicode_strings(self, enable_unicode_strings: bool) -> Self {
+        Self(ABACSettings {
+            enable_unicode_strings,
+            ..self.0
+        })
+    }
+
+    /// Set whether to generate action groups and entity attributes
+    pub const fn with_action_groups_and_attrs(self, enable_action_groups_and_attrs: bool) -> Self {
+        Self(ABACSettings {
+            enable_action_groups_and_attrs,
+            ..self.0
+        })
+    }
+
+    /// Set whether to generate arbitrary extension function calls
+    pub const fn with_arbitrary_func_call(self, enable_arbitrary_func_call: bool) -> Self {
+        Self(ABACSettings {
+            enable_arbitrary_func_call,
+            ..self.0
+        })
+    }
+
+    /// Set whether to generate unknowns
+    pub const fn with_unknowns(self, enable_unknowns: bool) -> Self {
+        Self(ABACSettings {
+            enable_unknowns,
+            ..self.0
+        })
+    }
+
+    /// Set whether actions may leave their principal and resource types unspecified
+    pub const fn with_unspecified_apply_spec(self, enable_unspecified_apply_spec: bool) -> Self {
+        Self(ABACSettings {
+            enable_unspecified_apply_spec,
+            ..self.0
+        })
+    }
+
+    /// Set whether to generate `action in` constraints
+    pub const fn with_action_in_constraints(self, enable_action_in_constraints: bool) -> Self {
+        Self(ABACSettings {
+            enable_action_in_constraints,
+            ..self.0
+        })
+    }
+
+    /// Set whether to bias generation towards ownership patterns
+    pub const fn with_ownership_patterns(self, enable_ownership_patterns: bool) -> Self {
+        Self(ABACSettings {
+            enable_ownership_patterns,
+            ..self.0
+        })
+    }
+
+    /// Set whether to generate `action in` constraints mentioning undeclared actions
+    pub const fn with_undeclared_actions(self, enable_undeclared_actions: bool) -> Self {
+        Self(ABACSettings {
+            enable_undeclared_actions,
+            ..self.0
+        })
+    }
+
+    /// Set whether to generate `is` entity-type tests
+    pub const fn with_is_operator(self, enable_is_operator: bool) -> Self {
+        Self(ABACSettings {
+            enable_is_operator,
+            ..self.0
+        })
+    }
+
+    /// Set whether to generate `has`-guarded accesses to optional attributes
+    pub const fn with_guarded_attr_chains(self, enable_guarded_attr_chains: bool) -> Self {
+        Self(ABACSettings {
+            enable_guarded_attr_chains,
+            ..self.0
+        })
+    }
+
+    /// Set whether to generate unguarded accesses to optional attributes
+    pub const fn with_unguarded_attr_chains(self, enable_unguarded_attr_chains: bool) -> Self {
+        Self(ABACSettings {
+            enable_unguarded_attr_chains,
+            ..self.0
+        })
+    }
+
+    /// Set the weight biasing schema types towards nested sets and records
+    pub const fn with_record_set_nesting_weight(self, record_set_nesting_weight: u32) -> Self {
+        Self(ABACSettings {
+            record_set_nesting_weight,
+            ..self.0
+        })
+    }
+
+    /// Set the weight of `if-then-else` expressions
+    pub const fn with_conditional_weight(self, conditional_weight: u8) -> Self {
+        Self(ABACSettings {
+            conditional_weight,
+            ..self.0
+        })
+    }
+
+    /// Set whether to prefer conditions over the action's `context`
+    pub const fn with_prefer_context_conditions(self, prefer_context_conditions: bool) -> Self {
+        Self(ABACSettings {
+            prefer_context_conditions,
+            ..self.0
+        })
+    }
+
+    /// Set the weights of operators
+    pub const fn with_operator_weights(self, operator_weights: OperatorWeights) -> Self {
+        Self(ABACSettings {
+            operator_weights,
+            ..self.0
+        })
+    }
+
+    /// Set the weights of the forms of principal and resource constraints
+    pub const fn with_scope_weights(self, scope_weights: ScopeWeights) -> Self {
+        Self(ABACSettings {
+            scope_weights: Some(scope_weights),
+            ..self.0
+        })
+    }
+
+    /// Get the `ABACSettings`
+    pub const fn build(self) -> ABACSettings {
+        self.0
+    }
 }